@@ -0,0 +1,31 @@
+(module
+  (memory (export "memory") 1)
+  (func $add (export "add") (param $a i32) (param $b i32) (result i32)
+    local.get $a
+    local.get $b
+    i32.add
+  )
+  (func $mul (export "mul") (param $a i64) (param $b i64) (result i64)
+    local.get $a
+    local.get $b
+    i64.mul
+  )
+  (func $div (export "div") (param $a f64) (param $b f64) (result f64)
+    local.get $a
+    local.get $b
+    f64.div
+  )
+  (func $neg (export "neg") (param $a f32) (result f32)
+    local.get $a
+    f32.neg
+  )
+  (func $grow (export "grow") (param $pages i32) (result i32)
+    local.get $pages
+    memory.grow
+  )
+  (func $spin (export "spin")
+    loop $again
+      br $again
+    end
+  )
+)
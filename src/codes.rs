@@ -15,6 +15,19 @@ pub mod section {
     pub const ELEMENT: u8 = 9;
     pub const CODE: u8 = 10;
     pub const DATA: u8 = 11;
+    pub const DATA_COUNT: u8 = 12;
+
+    pub const ORDER: [u8; 12] = [
+        TYPE, IMPORT, FUNCTION, TABLE, MEMORY, GLOBAL, EXPORT, START, ELEMENT, DATA_COUNT, CODE,
+        DATA,
+    ];
+}
+
+// Name Subsection Codes
+pub mod name {
+    pub const MODULE: u8 = 0;
+    pub const FUNCTIONS: u8 = 1;
+    pub const LOCALS: u8 = 2;
 }
 
 pub mod types {
@@ -23,6 +36,7 @@ pub mod types {
     pub const F32: u8 = 0x7D;
     pub const F64: u8 = 0x7C;
     pub const FUNCREF: u8 = 0x70;
+    pub const EXTERNREF: u8 = 0x6F;
     pub const FUNCTION: u8 = 0x60;
     pub const RESULT: u8 = 0x40;
     pub const CONST: u8 = 0x00;
@@ -31,6 +45,15 @@ pub mod types {
     pub const LIMIT_MAX: u8 = 0x01;
 }
 
+pub mod segment {
+    pub const ACTIVE: u32 = 0x00;
+    pub const PASSIVE: u32 = 0x01;
+    pub const ACTIVE_EXPLICIT: u32 = 0x02;
+    pub const DECLARATIVE: u32 = 0x03;
+    pub const EXPRESSIONS: u32 = 0x04;
+    pub const ELEM_KIND_FUNCREF: u8 = 0x00;
+}
+
 pub mod im_export_desc {
     pub const TYPE: u8 = 0x00;
     pub const TABLE: u8 = 0x01;
@@ -54,11 +77,14 @@ pub mod instr {
     pub const CALL_INDIRECT: u8 = 0x11;
     pub const DROP: u8 = 0x1A;
     pub const SELECT: u8 = 0x1B;
+    pub const SELECT_TYPED: u8 = 0x1C;
     pub const LOCAL_GET: u8 = 0x20;
     pub const LOCAL_SET: u8 = 0x21;
     pub const LOCAL_TEE: u8 = 0x22;
     pub const GLOBAL_GET: u8 = 0x23;
     pub const GLOBAL_SET: u8 = 0x24;
+    pub const TABLE_GET: u8 = 0x25;
+    pub const TABLE_SET: u8 = 0x26;
     pub const I32_LOAD: u8 = 0x28;
     pub const I64_LOAD: u8 = 0x29;
     pub const F32_LOAD: u8 = 0x2A;
@@ -211,4 +237,34 @@ pub mod instr {
     pub const I64_REINTERPRET_F64: u8 = 0xBD;
     pub const F32_REINTERPRET_I32: u8 = 0xBE;
     pub const F64_REINTERPRET_I64: u8 = 0xBF;
+    pub const I32_EXTEND8_S: u8 = 0xC0;
+    pub const I32_EXTEND16_S: u8 = 0xC1;
+    pub const I64_EXTEND8_S: u8 = 0xC2;
+    pub const I64_EXTEND16_S: u8 = 0xC3;
+    pub const I64_EXTEND32_S: u8 = 0xC4;
+    pub const REF_NULL: u8 = 0xD0;
+    pub const REF_IS_NULL: u8 = 0xD1;
+    pub const REF_FUNC: u8 = 0xD2;
+}
+
+pub mod misc {
+    pub const PREFIX: u8 = 0xFC;
+    pub const I32_TRUNC_SAT_F32_S: u32 = 0;
+    pub const I32_TRUNC_SAT_F32_U: u32 = 1;
+    pub const I32_TRUNC_SAT_F64_S: u32 = 2;
+    pub const I32_TRUNC_SAT_F64_U: u32 = 3;
+    pub const I64_TRUNC_SAT_F32_S: u32 = 4;
+    pub const I64_TRUNC_SAT_F32_U: u32 = 5;
+    pub const I64_TRUNC_SAT_F64_S: u32 = 6;
+    pub const I64_TRUNC_SAT_F64_U: u32 = 7;
+    pub const MEMORY_INIT: u32 = 8;
+    pub const DATA_DROP: u32 = 9;
+    pub const MEMORY_COPY: u32 = 10;
+    pub const MEMORY_FILL: u32 = 11;
+    pub const TABLE_INIT: u32 = 12;
+    pub const ELEM_DROP: u32 = 13;
+    pub const TABLE_COPY: u32 = 14;
+    pub const TABLE_GROW: u32 = 15;
+    pub const TABLE_SIZE: u32 = 16;
+    pub const TABLE_FILL: u32 = 17;
 }
@@ -1,16 +1,113 @@
+use std::env;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    Validate,
+    Inspect,
+    Invoke,
+    Print,
+}
+
 pub struct Config {
+    pub command: Command,
     pub binary_path: String,
+    pub invoke: Option<String>,
+    pub args: Vec<String>,
+    pub invoke_args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub dirs: Vec<(String, String)>,
+    pub trace: bool,
+    pub fuel: Option<u64>,
+    pub max_memory: Option<u32>,
 }
 
 impl Config {
-    pub fn build(args: &[String]) -> Result<Self, &'static str> {
-        if args.len() > 2 {
-            return Err("Too many params");
+    pub fn build(args: &[String]) -> Result<Self, String> {
+        let mut command = None;
+        let mut invoke = None;
+        let mut env = Vec::new();
+        let mut dirs = Vec::new();
+        let mut trace = false;
+        let mut fuel = None;
+        let mut max_memory = None;
+        let mut args = args.iter().skip(1);
+        let binary_path = loop {
+            let Some(arg) = args.next() else {
+                return Err(String::from("Not enough params"));
+            };
+            match arg.as_str() {
+                "--invoke" => invoke = Some(Self::value(&mut args, arg)?.clone()),
+                "--dir" => {
+                    let dir = Self::value(&mut args, arg)?;
+                    dirs.push(match dir.split_once("::") {
+                        Some((host, guest)) => (host.to_string(), guest.to_string()),
+                        None => (dir.clone(), dir.clone()),
+                    });
+                }
+                "--env" => {
+                    let var = Self::value(&mut args, arg)?;
+                    env.push(match var.split_once('=') {
+                        Some((name, value)) => (name.to_string(), value.to_string()),
+                        None => (var.clone(), env::var(var).unwrap_or_default()),
+                    });
+                }
+                "--trace" => trace = true,
+                "--fuel" => fuel = Some(Self::number(Self::value(&mut args, arg)?, arg)?),
+                "--max-memory" => {
+                    max_memory = Some(Self::number(Self::value(&mut args, arg)?, arg)?)
+                }
+                flag if flag.starts_with("--") => return Err(format!("Unknown option {flag}")),
+                "run" if command.is_none() => command = Some(Command::Run),
+                "validate" if command.is_none() => command = Some(Command::Validate),
+                "inspect" if command.is_none() => command = Some(Command::Inspect),
+                "invoke" if command.is_none() => command = Some(Command::Invoke),
+                "print" if command.is_none() => command = Some(Command::Print),
+                _ => break arg.clone(),
+            }
+        };
+        let command = command.unwrap_or(Command::Run);
+        let mut rest = args.cloned();
+        let mut invoke_args = Vec::new();
+        match command {
+            Command::Run => {}
+            Command::Invoke => {
+                let name = rest.next().ok_or("Missing export name to invoke")?;
+                invoke = Some(name);
+                invoke_args.extend(rest.by_ref());
+            }
+            Command::Validate | Command::Inspect | Command::Print => {
+                if let Some(arg) = rest.next() {
+                    return Err(format!("Unexpected argument {arg}"));
+                }
+            }
         }
-        if args.len() < 2 {
-            return Err("Not enough params");
-        }
-        let binary_path = args[1].clone();
-        Ok(Self { binary_path })
+        let args = std::iter::once(binary_path.clone()).chain(rest).collect();
+        Ok(Self {
+            command,
+            binary_path,
+            invoke,
+            args,
+            invoke_args,
+            env,
+            dirs,
+            trace,
+            fuel,
+            max_memory,
+        })
     }
-}
\ No newline at end of file
+
+    fn number<T: std::str::FromStr>(value: &str, flag: &str) -> Result<T, String> {
+        value
+            .parse()
+            .map_err(|_| format!("Invalid value {value} for {flag}"))
+    }
+
+    fn value<'a>(
+        args: &mut impl Iterator<Item = &'a String>,
+        flag: &str,
+    ) -> Result<&'a String, String> {
+        args.next()
+            .ok_or_else(|| format!("Missing value for {flag}"))
+    }
+}
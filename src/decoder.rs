@@ -1,115 +1,139 @@
 use crate::codes;
 use crate::codes::*;
+use crate::error::{DecodeError, DecodeErrorKind, Error, ValidationError};
+use crate::leb128::{self, LebResult};
 use crate::module::*;
-use std::io::{Cursor, Read};
+use crate::validator::Validator;
+use std::collections::HashMap;
+use std::io::{self, Cursor, Read, Write};
+use std::mem;
+
+const MAX_LOCALS: u64 = 50_000;
 
 pub struct Decoder;
 
+#[derive(Default)]
+pub struct StreamingDecoder {
+    buffer: Vec<u8>,
+    consumed: usize,
+    position: usize,
+    header_decoded: bool,
+    module: Module,
+    function_section: Option<FunctionSection>,
+    last_section: Option<u8>,
+}
+
+// A block whose `end` hasn't been reached yet, along with the instructions
+// of the enclosing body that it will be appended to.
+struct OpenBlock {
+    opcode: u8,
+    block_type: BlockType,
+    outer: Vec<Instr>,
+    then_instrs: Option<Vec<Instr>>,
+}
+
 type FunctionSection = Vec<TypeIdx>;
 type Code = (Vec<ValType>, Expr);
 type CodeSection = Vec<Code>;
 
 impl Decoder {
-    pub fn decode(byte_code: &[u8]) -> Result<Module, String> {
+    pub fn decode(byte_code: &[u8]) -> Result<Module, DecodeError> {
+        let mut decoder = StreamingDecoder::new();
+        decoder.feed(byte_code)?;
+        decoder.finish_decoding()
+    }
+
+    pub(crate) fn section_sizes(byte_code: &[u8]) -> Result<Vec<(u8, u32)>, DecodeError> {
         let mut cursor = Cursor::new(byte_code);
         Self::check_magic_number(&mut cursor)?;
-        let version = Self::get_version(&mut cursor)?;
-        if version != 1 {
-            return Err(Self::gen_error_msg(
-                &cursor,
-                format!(
-                    "Version {version} unsupported. Currently there is only version 1 supported"
-                ),
-            ));
-        };
-        let mut function_section = None;
-        let mut code_section = None;
-        let mut module = Module::default();
+        Self::get_version(&mut cursor)?;
+        let mut sections = Vec::new();
         while let Ok(section_code) = cursor.read_byte() {
-            let _section_size = Self::decode_uint(&mut cursor, 32);
-            match section_code {
-                codes::section::CUSTOM => {
-                    return Err(Self::gen_error_msg(
-                        &cursor,
-                        String::from("Custom are not supported"),
-                    ))
-                }
-                codes::section::TYPE => module.types = Self::decode_type_section(&mut cursor)?,
-                codes::section::IMPORT => {
-                    module.imports = Self::decode_import_section(&mut cursor)?
-                }
-                codes::section::CODE => {
-                    code_section = Some(Self::decode_code_section(&mut cursor)?)
-                }
-                codes::section::TABLE => module.table = Self::decode_table_section(&mut cursor)?,
-                codes::section::MEMORY => module.memory = Self::decode_memory_section(&mut cursor)?,
-                codes::section::GLOBAL => {
-                    module.globals = Self::decode_global_section(&mut cursor)?
-                }
-                codes::section::EXPORT => {
-                    module.exports = Self::decode_export_section(&mut cursor)?
-                }
-                codes::section::START => module.start = Self::decode_start_section(&mut cursor)?,
-                codes::section::ELEMENT => module.elem = Self::decode_elem_section(&mut cursor)?,
-                codes::section::FUNCTION => {
-                    function_section = Some(Self::decode_function_section(&mut cursor)?)
-                }
-                codes::section::DATA => module.data = Self::decode_data_section(&mut cursor)?,
-                _ => {
-                    return Err(Self::gen_error_msg(
-                        &cursor,
-                        String::from("Unsupported section type"),
-                    ))
-                }
-            };
-        }
-        if let (Some(fs), Some(cs)) = (function_section, code_section) {
-            module.funcs = Self::build_functions_component(fs, cs)?;
+            let size = Self::decode_u32(&mut cursor)?;
+            cursor.set_position(cursor.position() + size as u64);
+            sections.push((section_code, size));
         }
-        Ok(module)
+        Ok(sections)
     }
 
-    fn check_magic_number(cursor: &mut Cursor<&[u8]>) -> Result<(), String> {
+    fn check_magic_number(cursor: &mut Cursor<&[u8]>) -> Result<(), DecodeError> {
         let mut magic_buffer = [0; 4];
         cursor.read_exact_custom(&mut magic_buffer)?;
         if magic_buffer != MAGIC {
-            return Err(Self::gen_error_msg(
-                cursor,
-                String::from("Wrong binary magic"),
-            ));
+            return Err(Self::error(cursor, DecodeErrorKind::BadMagic));
         }
         Ok(())
     }
 
-    fn get_version(cursor: &mut Cursor<&[u8]>) -> Result<u32, String> {
+    fn get_version(cursor: &mut Cursor<&[u8]>) -> Result<u32, DecodeError> {
         let version = cursor.read_le_i32()?;
         if version != 1 {
-            return Err(Self::gen_error_msg(
+            return Err(Self::error(
                 cursor,
-                String::from(
-                    "Unsupported version {version}. Currently only version 1 is supported",
-                ),
+                DecodeErrorKind::UnsupportedVersion(version),
             ));
         }
         Ok(version)
     }
 
-    fn decode_type_section(cursor: &mut Cursor<&[u8]>) -> Result<TypesComponent, String> {
+    fn decode_custom_section(
+        cursor: &mut Cursor<&[u8]>,
+        after_section: Option<u8>,
+    ) -> Result<Custom, DecodeError> {
+        let name = Self::decode_string(cursor)?;
+        let bytes = *cursor.get_ref();
+        let start = cursor.position() as usize;
+        cursor.set_position(bytes.len() as u64);
+        Ok(Custom {
+            name,
+            bytes: bytes[start..].to_vec(),
+            after_section,
+        })
+    }
+
+    fn decode_name_section(bytes: &[u8]) -> Result<Names, DecodeError> {
+        let mut cursor = Cursor::new(bytes);
+        let mut names = Names::default();
+        while let Ok(id) = cursor.read_byte() {
+            let size = Self::decode_u32(&mut cursor)?;
+            let end = cursor.position() + size as u64;
+            match id {
+                codes::name::MODULE => names.module = Some(Self::decode_string(&mut cursor)?),
+                codes::name::FUNCTIONS => names.funcs = Self::decode_name_map(&mut cursor)?,
+                codes::name::LOCALS => {
+                    names.locals = Self::process_vector(&mut cursor, |cursor| {
+                        Ok((Self::decode_u32(cursor)?, Self::decode_name_map(cursor)?))
+                    })?
+                    .into_iter()
+                    .collect()
+                }
+                _ => {}
+            }
+            cursor.set_position(end);
+        }
+        Ok(names)
+    }
+
+    fn decode_name_map(cursor: &mut Cursor<&[u8]>) -> Result<HashMap<u32, String>, DecodeError> {
+        let entries = Self::process_vector(cursor, |cursor| {
+            Ok((Self::decode_u32(cursor)?, Self::decode_string(cursor)?))
+        })?;
+        Ok(entries.into_iter().collect())
+    }
+
+    fn decode_type_section(cursor: &mut Cursor<&[u8]>) -> Result<TypesComponent, DecodeError> {
         Self::process_vector(cursor, |cursor| {
             let typ = cursor.read_byte()?;
             if typ != codes::types::FUNCTION {
-                return Err(Self::gen_error_msg(
-                    cursor,
-                    String::from("Wrong elements stored in section type"),
-                ));
+                return Err(Self::error(cursor, DecodeErrorKind::InvalidFuncType(typ)));
             }
-            let params = Self::process_vector(cursor, |cursor| Self::decode_val_type(cursor))?;
-            let results = Self::process_vector(cursor, |cursor| Self::decode_val_type(cursor))?;
+            let params = Self::process_vector(cursor, Self::decode_val_type)?;
+            let results = Self::process_vector(cursor, Self::decode_val_type)?;
             Ok(FuncType { params, results })
         })
     }
 
-    fn decode_import_section(cursor: &mut Cursor<&[u8]>) -> Result<ImportsComponent, String> {
+    fn decode_import_section(cursor: &mut Cursor<&[u8]>) -> Result<ImportsComponent, DecodeError> {
         Self::process_vector(cursor, |cursor| {
             let module = Self::decode_string(cursor)?;
             let name = Self::decode_string(cursor)?;
@@ -118,55 +142,47 @@ impl Decoder {
         })
     }
 
-    fn decode_table_section(cursor: &mut Cursor<&[u8]>) -> Result<TableComponent, String> {
-        let table_component = Self::process_vector(cursor, |cursor| {
+    fn decode_table_section(cursor: &mut Cursor<&[u8]>) -> Result<TableComponent, DecodeError> {
+        Self::process_vector(cursor, |cursor| {
             Ok(Table {
                 typ: Self::decode_table_type(cursor)?,
             })
-        })?;
-        if table_component.len() > 1 {
-            return Err(Self::gen_error_msg(
-                cursor,
-                String::from("Only one table allowed per module in version 1.0"),
-            ));
-        }
-        Ok(table_component)
+        })
     }
 
-    fn decode_memory_section(cursor: &mut Cursor<&[u8]>) -> Result<MemoryComponent, String> {
+    fn decode_memory_section(cursor: &mut Cursor<&[u8]>) -> Result<MemoryComponent, DecodeError> {
         let memory_component = Self::process_vector(cursor, |cursor| {
             Ok(Mem {
                 typ: Self::decode_mem_type(cursor)?,
             })
         })?;
         if memory_component.len() > 1 {
-            return Err(Self::gen_error_msg(
-                cursor,
-                String::from("Only one memory allowed per module in version 1.0"),
-            ));
+            return Err(Self::error(cursor, DecodeErrorKind::MultipleMemories));
         }
         Ok(memory_component)
     }
 
-    fn decode_global_section(cursor: &mut Cursor<&[u8]>) -> Result<GlobalsComponent, String> {
+    fn decode_global_section(cursor: &mut Cursor<&[u8]>) -> Result<GlobalsComponent, DecodeError> {
         Self::process_vector(cursor, |cursor| {
-            let init = Self::decode_expression(cursor)?;
+            let typ = Self::decode_global_type(cursor)?;
             Ok(Global {
-                typ: Self::decode_global_type(cursor)?,
-                init,
+                typ,
+                init: Self::decode_expression(cursor)?,
             })
         })
     }
 
-    fn decode_export_section(cursor: &mut Cursor<&[u8]>) -> Result<ExportsComponent, String> {
+    fn decode_export_section(cursor: &mut Cursor<&[u8]>) -> Result<ExportsComponent, DecodeError> {
         Self::process_vector(cursor, |cursor| {
             let name = Self::decode_string(cursor)?;
-            let desc = Self::decode_imp_export_description(cursor)?;
+            let desc = Self::decode_export_description(cursor)?;
             Ok(Export { name, desc })
         })
     }
 
-    fn decode_imp_export_description(cursor: &mut Cursor<&[u8]>) -> Result<ImpExportDesc, String> {
+    fn decode_imp_export_description(
+        cursor: &mut Cursor<&[u8]>,
+    ) -> Result<ImpExportDesc, DecodeError> {
         match cursor.read_byte()? {
             codes::im_export_desc::TYPE => {
                 Ok(ImpExportDesc::Func(TypeIdx(Self::decode_u32(cursor)?)))
@@ -178,85 +194,156 @@ impl Decoder {
             codes::im_export_desc::GLOBAL => {
                 Ok(ImpExportDesc::Global(Self::decode_global_type(cursor)?))
             }
-            _ => Err(Self::gen_error_msg(
+            kind => Err(Self::error(
+                cursor,
+                DecodeErrorKind::InvalidImportDesc(kind),
+            )),
+        }
+    }
+
+    fn decode_export_description(cursor: &mut Cursor<&[u8]>) -> Result<ExportDesc, DecodeError> {
+        let kind = cursor.read_byte()?;
+        let idx = Self::decode_u32(cursor)?;
+        match kind {
+            codes::im_export_desc::TYPE => Ok(ExportDesc::Func(FuncIdx(idx))),
+            codes::im_export_desc::TABLE => Ok(ExportDesc::Table(TableIdx(idx))),
+            codes::im_export_desc::MEM => Ok(ExportDesc::Mem(MemIdx(idx))),
+            codes::im_export_desc::GLOBAL => Ok(ExportDesc::Global(GlobalIdx(idx))),
+            _ => Err(Self::error(
                 cursor,
-                String::from("Malicious import description"),
+                DecodeErrorKind::InvalidExportDesc(kind),
             )),
         }
     }
 
-    fn decode_start_section(cursor: &mut Cursor<&[u8]>) -> Result<StartComponent, String> {
+    fn decode_start_section(cursor: &mut Cursor<&[u8]>) -> Result<StartComponent, DecodeError> {
         Ok(Some(Start {
             func: FuncIdx(Self::decode_u32(cursor)?),
         }))
     }
 
-    fn decode_elem_section(cursor: &mut Cursor<&[u8]>) -> Result<ElemComponent, String> {
+    fn decode_elem_section(cursor: &mut Cursor<&[u8]>) -> Result<ElemComponent, DecodeError> {
         Self::process_vector(cursor, |cursor| {
-            let table = TableIdx(Self::decode_u32(cursor)?);
-            let offset = Self::decode_expression(cursor)?;
-            let init =
-                Self::process_vector(cursor, |cursor| Ok(FuncIdx(Self::decode_u32(cursor)?)))?;
-            Ok(Elem {
-                table,
-                offset,
-                init,
-            })
+            let flags = Self::decode_u32(cursor)?;
+            if flags > (codes::segment::DECLARATIVE | codes::segment::EXPRESSIONS) {
+                return Err(Self::error(
+                    cursor,
+                    DecodeErrorKind::InvalidSegmentFlags(flags),
+                ));
+            }
+            let exprs = flags & codes::segment::EXPRESSIONS != 0;
+            let kind = flags & !codes::segment::EXPRESSIONS;
+            let mode = match kind {
+                codes::segment::ACTIVE => ElemMode::Active {
+                    table: TableIdx(0),
+                    offset: Self::decode_expression(cursor)?,
+                },
+                codes::segment::PASSIVE => ElemMode::Passive,
+                codes::segment::ACTIVE_EXPLICIT => {
+                    let table = TableIdx(Self::decode_u32(cursor)?);
+                    let offset = Self::decode_expression(cursor)?;
+                    ElemMode::Active { table, offset }
+                }
+                _ => ElemMode::Declarative,
+            };
+            // Segments for table 0 leave the element type implicit.
+            let typ = match (kind, exprs) {
+                (codes::segment::ACTIVE, _) => ElemType::FuncRef,
+                (_, false) => Self::decode_elem_kind(cursor)?,
+                (_, true) => Self::decode_ref_type(cursor)?,
+            };
+            let init = if exprs {
+                Self::process_vector(cursor, Self::decode_expression)?
+            } else {
+                Self::process_vector(cursor, |cursor| {
+                    let func = FuncIdx(Self::decode_u32(cursor)?);
+                    Ok(Expr(vec![Instr::RefFunc(func)], End))
+                })?
+            };
+            Ok(Elem { typ, mode, init })
         })
     }
 
-    fn decode_data_section(cursor: &mut Cursor<&[u8]>) -> Result<DataComponent, String> {
+    fn decode_elem_kind(cursor: &mut Cursor<&[u8]>) -> Result<ElemType, DecodeError> {
+        match cursor.read_byte()? {
+            codes::segment::ELEM_KIND_FUNCREF => Ok(ElemType::FuncRef),
+            byte => Err(Self::error(cursor, DecodeErrorKind::InvalidElemType(byte))),
+        }
+    }
+
+    fn decode_data_section(cursor: &mut Cursor<&[u8]>) -> Result<DataComponent, DecodeError> {
         Self::process_vector(cursor, |cursor| {
-            let data = MemIdx(Self::decode_u32(cursor)?);
-            let offset = Self::decode_expression(cursor)?;
+            let mode = match Self::decode_u32(cursor)? {
+                codes::segment::ACTIVE => DataMode::Active {
+                    memory: MemIdx(0),
+                    offset: Self::decode_expression(cursor)?,
+                },
+                codes::segment::PASSIVE => DataMode::Passive,
+                codes::segment::ACTIVE_EXPLICIT => DataMode::Active {
+                    memory: MemIdx(Self::decode_u32(cursor)?),
+                    offset: Self::decode_expression(cursor)?,
+                },
+                flags => {
+                    return Err(Self::error(
+                        cursor,
+                        DecodeErrorKind::InvalidSegmentFlags(flags),
+                    ))
+                }
+            };
             let init = Self::process_vector(cursor, |cursor| cursor.read_byte())?;
-            Ok(Data { data, offset, init })
+            Ok(Data { mode, init })
         })
     }
 
-    fn decode_function_section(cursor: &mut Cursor<&[u8]>) -> Result<FunctionSection, String> {
+    fn decode_data_count_section(cursor: &mut Cursor<&[u8]>) -> Result<Option<u32>, DecodeError> {
+        Ok(Some(Self::decode_u32(cursor)?))
+    }
+
+    fn decode_function_section(cursor: &mut Cursor<&[u8]>) -> Result<FunctionSection, DecodeError> {
         let function_section =
             Self::process_vector(cursor, |cursor| Ok(TypeIdx(Self::decode_u32(cursor)?)))?;
         Ok(function_section)
     }
 
-    fn decode_code_section(cursor: &mut Cursor<&[u8]>) -> Result<CodeSection, String> {
+    fn decode_code_section(cursor: &mut Cursor<&[u8]>) -> Result<CodeSection, DecodeError> {
         let code_section = Self::process_vector(cursor, |cursor| {
-            let _size = Self::decode_u32(cursor)?;
-            let code = Self::decode_code(cursor)?;
+            let size = Self::decode_u32(cursor)?;
+            let mut body = Self::bounded(cursor, size)?;
+            let code = Self::decode_code(&mut body)?;
+            Self::check_consumed(&body)?;
+            cursor.set_position(body.position());
             Ok(code)
         })?;
         Ok(code_section)
     }
 
-    fn decode_code(cursor: &mut Cursor<&[u8]>) -> Result<Code, String> {
-        let locals = Self::process_vector(cursor, |cursor| Self::decode_local(cursor))?
+    fn decode_code(cursor: &mut Cursor<&[u8]>) -> Result<Code, DecodeError> {
+        let groups = Self::process_vector(cursor, |cursor| {
+            Ok((Self::decode_u32(cursor)?, Self::decode_val_type(cursor)?))
+        })?;
+        // The counts are checked before anything is allocated, as a handful
+        // of bytes can declare billions of locals.
+        let count = groups.iter().map(|(n, _)| u64::from(*n)).sum::<u64>();
+        if count > MAX_LOCALS {
+            return Err(Self::error(cursor, DecodeErrorKind::TooManyLocals));
+        }
+        let locals = groups
             .into_iter()
-            .flat_map(|v| v)
+            .flat_map(|(n, val_type)| std::iter::repeat_n(val_type, n as usize))
             .collect();
         let expr = Self::decode_expression(cursor)?;
         Ok((locals, expr))
     }
 
-    fn decode_local(cursor: &mut Cursor<&[u8]>) -> Result<Vec<ValType>, String> {
-        let n = Self::decode_u32(cursor)?;
-        let mut locals = Vec::new();
-        if n > 0 {
-            let val_type = Self::decode_val_type(cursor)?;
-            for _ in 0..n {
-                locals.push(val_type.clone())
-            }
-        }
-        Ok(locals)
-    }
-
     fn build_functions_component(
+        cursor: &Cursor<&[u8]>,
         function_section: FunctionSection,
         code_section: CodeSection,
-    ) -> Result<FuncsComponent, String> {
+    ) -> Result<FuncsComponent, DecodeError> {
         if function_section.len() != code_section.len() {
-            return Err(String::from(
-                "Function section and code section dont have the same number of elements",
+            return Err(Self::error(
+                cursor,
+                DecodeErrorKind::FunctionCodeCountMismatch,
             ));
         }
         let function_component = function_section
@@ -271,39 +358,55 @@ impl Decoder {
         Ok(function_component)
     }
 
-    fn decode_table_type(cursor: &mut Cursor<&[u8]>) -> Result<TableType, String> {
-        let elem_type = if cursor.read_byte()? != codes::types::FUNCREF {
-            ElemType::FuncRef
-        } else {
-            return Err(Self::gen_error_msg(
-                cursor,
-                String::from("This element type is not supported"),
-            ));
-        };
+    fn uses_data_index(instrs: &[Instr]) -> bool {
+        let mut pending = vec![instrs];
+        while let Some(instrs) = pending.pop() {
+            for instr in instrs {
+                match instr {
+                    Instr::MemoryInit(_) | Instr::DataDrop(_) => return true,
+                    Instr::Block(_, body, _) | Instr::Loop(_, body, _) => pending.push(body),
+                    Instr::If(_, then, _, els, _) => pending.extend([&then[..], els]),
+                    _ => {}
+                }
+            }
+        }
+        false
+    }
+
+    fn decode_table_type(cursor: &mut Cursor<&[u8]>) -> Result<TableType, DecodeError> {
+        let elem_type = Self::decode_ref_type(cursor)?;
         let limits = Self::decode_limits(cursor)?;
         Ok(TableType(limits, elem_type))
     }
 
-    fn decode_mem_type(cursor: &mut Cursor<&[u8]>) -> Result<MemType, String> {
+    fn decode_ref_type(cursor: &mut Cursor<&[u8]>) -> Result<ElemType, DecodeError> {
+        match cursor.read_byte()? {
+            codes::types::FUNCREF => Ok(ElemType::FuncRef),
+            codes::types::EXTERNREF => Ok(ElemType::ExternRef),
+            byte => Err(Self::error(cursor, DecodeErrorKind::InvalidElemType(byte))),
+        }
+    }
+
+    fn decode_mem_type(cursor: &mut Cursor<&[u8]>) -> Result<MemType, DecodeError> {
         Ok(MemType(Self::decode_limits(cursor)?))
     }
 
-    fn decode_global_type(cursor: &mut Cursor<&[u8]>) -> Result<GlobalType, String> {
+    fn decode_global_type(cursor: &mut Cursor<&[u8]>) -> Result<GlobalType, DecodeError> {
         let val_type = Self::decode_val_type(cursor)?;
         let mutablity = match cursor.read_byte()? {
             codes::types::CONST => Mut::Const,
             codes::types::VAR => Mut::Var,
-            _ => {
-                return Err(Self::gen_error_msg(
+            byte => {
+                return Err(Self::error(
                     cursor,
-                    String::from("Invalid mutability modifier for global"),
+                    DecodeErrorKind::InvalidMutability(byte),
                 ))
             }
         };
         Ok(GlobalType(mutablity, val_type))
     }
 
-    fn decode_limits(cursor: &mut Cursor<&[u8]>) -> Result<Limits, String> {
+    fn decode_limits(cursor: &mut Cursor<&[u8]>) -> Result<Limits, DecodeError> {
         match cursor.read_byte()? {
             codes::types::LIMIT_NO_MAX => Ok(Limits {
                 min: Self::decode_u32(cursor)?,
@@ -313,87 +416,95 @@ impl Decoder {
                 min: Self::decode_u32(cursor)?,
                 max: Some(Self::decode_u32(cursor)?),
             }),
-            _ => {
-                return Err(Self::gen_error_msg(
-                    cursor,
-                    String::from("Invalid Limits type"),
-                ))
-            }
+            byte => Err(Self::error(cursor, DecodeErrorKind::InvalidLimits(byte))),
         }
     }
 
-    fn decode_val_type(cursor: &mut Cursor<&[u8]>) -> Result<ValType, String> {
+    fn decode_val_type(cursor: &mut Cursor<&[u8]>) -> Result<ValType, DecodeError> {
         match cursor.read_byte()? {
             codes::types::I32 => Ok(ValType::I32),
             codes::types::I64 => Ok(ValType::I64),
             codes::types::F32 => Ok(ValType::F32),
             codes::types::F64 => Ok(ValType::F64),
-            code => Err(Self::gen_error_msg(
-                cursor,
-                format!("Could not derive a ValType from code {code:x}"),
-            )),
+            codes::types::FUNCREF => Ok(ValType::FuncRef),
+            codes::types::EXTERNREF => Ok(ValType::ExternRef),
+            byte => Err(Self::error(cursor, DecodeErrorKind::InvalidValType(byte))),
         }
     }
 
-    fn decode_expression(cursor: &mut Cursor<&[u8]>) -> Result<Expr, String> {
-        Ok(Expr(Self::decode_instr_until_end(cursor)?, End))
-    }
-
-    fn decode_block_type(cursor: &mut Cursor<&[u8]>) -> Result<ResultType, String> {
-        match cursor.read_byte()? {
-            codes::types::RESULT => Ok(ResultType(None)),
-            codes::types::I32 => Ok(ResultType(Some(ValType::I32))),
-            codes::types::I64 => Ok(ResultType(Some(ValType::I64))),
-            codes::types::F32 => Ok(ResultType(Some(ValType::F32))),
-            codes::types::F64 => Ok(ResultType(Some(ValType::F64))),
-            _ => Err(Self::gen_error_msg(
-                cursor,
-                String::from("Invalid blocktype"),
-            )),
-        }
-    }
-
-    fn decode_instr_until_end(cursor: &mut Cursor<&[u8]>) -> Result<Vec<Instr>, String> {
-        let mut instructions = Vec::new();
-        let mut opcode = cursor.read_byte()?;
-        while opcode != codes::instr::END {
-            instructions.push(Self::decode_instruction(cursor, opcode)?);
-            opcode = cursor.read_byte()?;
+    // Nested blocks are decoded with an explicit stack rather than by
+    // recursion, so the depth of a body is bounded by MAX_NESTING_DEPTH
+    // instead of by the native stack.
+    fn decode_expression(cursor: &mut Cursor<&[u8]>) -> Result<Expr, DecodeError> {
+        let mut instrs = Vec::new();
+        let mut open: Vec<OpenBlock> = Vec::new();
+        loop {
+            let opcode = cursor.read_byte()?;
+            match opcode {
+                codes::instr::BLOCK | codes::instr::LOOP | codes::instr::IF => {
+                    if open.len() >= MAX_NESTING_DEPTH {
+                        return Err(Self::error(cursor, DecodeErrorKind::NestingTooDeep));
+                    }
+                    open.push(OpenBlock {
+                        opcode,
+                        block_type: Self::decode_block_type(cursor)?,
+                        outer: mem::take(&mut instrs),
+                        then_instrs: None,
+                    });
+                }
+                codes::instr::ELSE => match open.last_mut() {
+                    Some(block)
+                        if block.opcode == codes::instr::IF && block.then_instrs.is_none() =>
+                    {
+                        block.then_instrs = Some(mem::take(&mut instrs));
+                    }
+                    _ => return Err(Self::error(cursor, DecodeErrorKind::UnknownOpcode(opcode))),
+                },
+                codes::instr::END => {
+                    let Some(block) = open.pop() else {
+                        return Ok(Expr(instrs, End));
+                    };
+                    let body = mem::replace(&mut instrs, block.outer);
+                    instrs.push(match (block.opcode, block.then_instrs) {
+                        (codes::instr::BLOCK, _) => Instr::Block(block.block_type, body, End),
+                        (codes::instr::LOOP, _) => Instr::Loop(block.block_type, body, End),
+                        (_, Some(then_instrs)) => {
+                            Instr::If(block.block_type, then_instrs, Else, body, End)
+                        }
+                        (_, None) => Instr::If(block.block_type, body, Else, Vec::new(), End),
+                    });
+                }
+                _ => instrs.push(Self::decode_instruction(cursor, opcode)?),
+            }
         }
-        Ok(instructions)
     }
 
-    fn decode_instr_until_else(cursor: &mut Cursor<&[u8]>) -> Result<Vec<Instr>, String> {
-        let mut instructions = Vec::new();
-        let mut opcode = cursor.read_byte()?;
-        while opcode != codes::instr::ELSE {
-            instructions.push(Self::decode_instruction(cursor, opcode)?);
-            opcode = cursor.read_byte()?;
+    fn decode_block_type(cursor: &mut Cursor<&[u8]>) -> Result<BlockType, DecodeError> {
+        let position = cursor.position();
+        match cursor.read_byte()? {
+            codes::types::RESULT => Ok(BlockType::Empty),
+            codes::types::I32 => Ok(BlockType::Value(ValType::I32)),
+            codes::types::I64 => Ok(BlockType::Value(ValType::I64)),
+            codes::types::F32 => Ok(BlockType::Value(ValType::F32)),
+            codes::types::F64 => Ok(BlockType::Value(ValType::F64)),
+            codes::types::FUNCREF => Ok(BlockType::Value(ValType::FuncRef)),
+            codes::types::EXTERNREF => Ok(BlockType::Value(ValType::ExternRef)),
+            // Type indices are encoded as positive s33 values so they cannot
+            // collide with the single byte value types above.
+            byte => {
+                cursor.set_position(position);
+                let idx = Self::decode_leb128(cursor, |bytes| leb128::read_signed(bytes, 33))?;
+                u32::try_from(idx)
+                    .map(|idx| BlockType::Type(TypeIdx(idx)))
+                    .map_err(|_| Self::error(cursor, DecodeErrorKind::InvalidBlockType(byte)))
+            }
         }
-        Ok(instructions)
     }
 
-    fn decode_instruction(cursor: &mut Cursor<&[u8]>, opcode: u8) -> Result<Instr, String> {
+    fn decode_instruction(cursor: &mut Cursor<&[u8]>, opcode: u8) -> Result<Instr, DecodeError> {
         let instr = match opcode {
             codes::instr::UNREACHABLE => Instr::Unreachable,
             codes::instr::NOP => Instr::Nop,
-            codes::instr::BLOCK => Instr::Block(
-                Self::decode_block_type(cursor)?,
-                Self::decode_instr_until_end(cursor)?,
-                End,
-            ),
-            codes::instr::LOOP => Instr::Loop(
-                Self::decode_block_type(cursor)?,
-                Self::decode_instr_until_end(cursor)?,
-                End,
-            ),
-            codes::instr::IF => Instr::If(
-                Self::decode_block_type(cursor)?,
-                Self::decode_instr_until_else(cursor)?,
-                Else,
-                Self::decode_instr_until_end(cursor)?,
-                End,
-            ),
             codes::instr::BR => Instr::Br(LabelIdx(Self::decode_u32(cursor)?)),
             codes::instr::BR_IF => Instr::BrIf(LabelIdx(Self::decode_u32(cursor)?)),
             codes::instr::BR_TABLE => Instr::BrTable(
@@ -402,108 +513,53 @@ impl Decoder {
             ),
             codes::instr::RETURN => Instr::Return,
             codes::instr::CALL => Instr::Call(FuncIdx(Self::decode_u32(cursor)?)),
-            codes::instr::CALL_INDIRECT => Instr::CallIndirect(TypeIdx(Self::decode_u32(cursor)?)),
+            codes::instr::CALL_INDIRECT => {
+                let typ = TypeIdx(Self::decode_u32(cursor)?);
+                Instr::CallIndirect(typ, TableIdx(Self::decode_u32(cursor)?))
+            }
             codes::instr::DROP => Instr::Drop,
             codes::instr::SELECT => Instr::Select,
+            codes::instr::SELECT_TYPED => {
+                Instr::TypedSelect(Self::process_vector(cursor, Self::decode_val_type)?)
+            }
             codes::instr::LOCAL_GET => Instr::LocalGet(LocalIdx(Self::decode_u32(cursor)?)),
             codes::instr::LOCAL_SET => Instr::LocalSet(LocalIdx(Self::decode_u32(cursor)?)),
             codes::instr::LOCAL_TEE => Instr::LocalTee(LocalIdx(Self::decode_u32(cursor)?)),
             codes::instr::GLOBAL_GET => Instr::GlobalGet(GlobalIdx(Self::decode_u32(cursor)?)),
             codes::instr::GLOBAL_SET => Instr::GlobalSet(GlobalIdx(Self::decode_u32(cursor)?)),
-            codes::instr::I32_LOAD => Instr::I32Load(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I64_LOAD => Instr::I64Load(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::F32_LOAD => Instr::F32Load(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::F64_LOAD => Instr::F64Load(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I32_LOAD8_S => Instr::I32Load8S(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I32_LOAD8_U => Instr::I32Load8U(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I32_LOAD16_S => Instr::I32Load16S(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I32_LOAD16_U => Instr::I32Load16U(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I64_LOAD8_S => Instr::I64Load8S(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I64_LOAD8_U => Instr::I64Load8U(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I64_LOAD16_S => Instr::I64Load16S(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I64_LOAD16_U => Instr::I64Load16U(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I64_LOAD32_S => Instr::I64Load32S(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I64_LOAD32_U => Instr::I64Load32U(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I32_STORE => Instr::I32Store(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I64_STORE => Instr::I64Store(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::F32_STORE => Instr::F32Store(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::F64_STORE => Instr::F64Store(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I32_STORE8 => Instr::I32Store8(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I32_STORE16 => Instr::I32Store16(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I64_STORE8 => Instr::I64Store8(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I64_STORE16 => Instr::I64Store16(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::I64_STORE32 => Instr::I64Store32(MemArg {
-                offset: Self::decode_u32(cursor)?,
-                align: Self::decode_u32(cursor)?,
-            }),
-            codes::instr::MEMORY_SIZE => Instr::MemorySize,
-            codes::instr::MEMORY_GROW => Instr::MemoryGrow,
+            codes::instr::TABLE_GET => Instr::TableGet(TableIdx(Self::decode_u32(cursor)?)),
+            codes::instr::TABLE_SET => Instr::TableSet(TableIdx(Self::decode_u32(cursor)?)),
+            codes::instr::I32_LOAD => Instr::I32Load(Self::decode_mem_arg(cursor)?),
+            codes::instr::I64_LOAD => Instr::I64Load(Self::decode_mem_arg(cursor)?),
+            codes::instr::F32_LOAD => Instr::F32Load(Self::decode_mem_arg(cursor)?),
+            codes::instr::F64_LOAD => Instr::F64Load(Self::decode_mem_arg(cursor)?),
+            codes::instr::I32_LOAD8_S => Instr::I32Load8S(Self::decode_mem_arg(cursor)?),
+            codes::instr::I32_LOAD8_U => Instr::I32Load8U(Self::decode_mem_arg(cursor)?),
+            codes::instr::I32_LOAD16_S => Instr::I32Load16S(Self::decode_mem_arg(cursor)?),
+            codes::instr::I32_LOAD16_U => Instr::I32Load16U(Self::decode_mem_arg(cursor)?),
+            codes::instr::I64_LOAD8_S => Instr::I64Load8S(Self::decode_mem_arg(cursor)?),
+            codes::instr::I64_LOAD8_U => Instr::I64Load8U(Self::decode_mem_arg(cursor)?),
+            codes::instr::I64_LOAD16_S => Instr::I64Load16S(Self::decode_mem_arg(cursor)?),
+            codes::instr::I64_LOAD16_U => Instr::I64Load16U(Self::decode_mem_arg(cursor)?),
+            codes::instr::I64_LOAD32_S => Instr::I64Load32S(Self::decode_mem_arg(cursor)?),
+            codes::instr::I64_LOAD32_U => Instr::I64Load32U(Self::decode_mem_arg(cursor)?),
+            codes::instr::I32_STORE => Instr::I32Store(Self::decode_mem_arg(cursor)?),
+            codes::instr::I64_STORE => Instr::I64Store(Self::decode_mem_arg(cursor)?),
+            codes::instr::F32_STORE => Instr::F32Store(Self::decode_mem_arg(cursor)?),
+            codes::instr::F64_STORE => Instr::F64Store(Self::decode_mem_arg(cursor)?),
+            codes::instr::I32_STORE8 => Instr::I32Store8(Self::decode_mem_arg(cursor)?),
+            codes::instr::I32_STORE16 => Instr::I32Store16(Self::decode_mem_arg(cursor)?),
+            codes::instr::I64_STORE8 => Instr::I64Store8(Self::decode_mem_arg(cursor)?),
+            codes::instr::I64_STORE16 => Instr::I64Store16(Self::decode_mem_arg(cursor)?),
+            codes::instr::I64_STORE32 => Instr::I64Store32(Self::decode_mem_arg(cursor)?),
+            codes::instr::MEMORY_SIZE => {
+                Self::decode_reserved_byte(cursor)?;
+                Instr::MemorySize
+            }
+            codes::instr::MEMORY_GROW => {
+                Self::decode_reserved_byte(cursor)?;
+                Instr::MemoryGrow
+            }
             codes::instr::I32_CONST => Instr::I32Const(Self::decode_i32(cursor)?),
             codes::instr::I64_CONST => Instr::I64Const(Self::decode_i64(cursor)?),
             codes::instr::F32_CONST => Instr::F32Const(cursor.read_le_f32()?),
@@ -629,128 +685,428 @@ impl Decoder {
             codes::instr::F64_PROMOTE_F32 => Instr::F64PromoteF32,
             codes::instr::F32_REINTERPRET_I32 => Instr::F32ReinterpretI32,
             codes::instr::F64_REINTERPRET_I64 => Instr::F64ReinterpretI64,
+            codes::instr::I32_EXTEND8_S => Instr::I32Extend8S,
+            codes::instr::I32_EXTEND16_S => Instr::I32Extend16S,
+            codes::instr::I64_EXTEND8_S => Instr::I64Extend8S,
+            codes::instr::I64_EXTEND16_S => Instr::I64Extend16S,
+            codes::instr::I64_EXTEND32_S => Instr::I64Extend32S,
             codes::instr::I32_REINTERPRET_F32 => Instr::I32ReinterpretF32,
             codes::instr::I64_REINTERPRET_F64 => Instr::I64ReinterpretF64,
-            _ => {
-                return Err(Self::gen_error_msg(
-                    cursor,
-                    String::from("The instruction with opcode {opcode} is currently not supported"),
-                ))
+            codes::instr::REF_NULL => Instr::RefNull(Self::decode_ref_type(cursor)?),
+            codes::instr::REF_IS_NULL => Instr::RefIsNull,
+            codes::instr::REF_FUNC => Instr::RefFunc(FuncIdx(Self::decode_u32(cursor)?)),
+            codes::misc::PREFIX => Self::decode_misc_instruction(cursor)?,
+            _ => return Err(Self::error(cursor, DecodeErrorKind::UnknownOpcode(opcode))),
+        };
+        Ok(instr)
+    }
+
+    fn decode_misc_instruction(cursor: &mut Cursor<&[u8]>) -> Result<Instr, DecodeError> {
+        let instr = match Self::decode_u32(cursor)? {
+            codes::misc::I32_TRUNC_SAT_F32_S => Instr::I32TruncSatF32S,
+            codes::misc::I32_TRUNC_SAT_F32_U => Instr::I32TruncSatF32U,
+            codes::misc::I32_TRUNC_SAT_F64_S => Instr::I32TruncSatF64S,
+            codes::misc::I32_TRUNC_SAT_F64_U => Instr::I32TruncSatF64U,
+            codes::misc::I64_TRUNC_SAT_F32_S => Instr::I64TruncSatF32S,
+            codes::misc::I64_TRUNC_SAT_F32_U => Instr::I64TruncSatF32U,
+            codes::misc::I64_TRUNC_SAT_F64_S => Instr::I64TruncSatF64S,
+            codes::misc::I64_TRUNC_SAT_F64_U => Instr::I64TruncSatF64U,
+            codes::misc::MEMORY_INIT => {
+                let data = DataIdx(Self::decode_u32(cursor)?);
+                Self::decode_reserved_byte(cursor)?;
+                Instr::MemoryInit(data)
+            }
+            codes::misc::DATA_DROP => Instr::DataDrop(DataIdx(Self::decode_u32(cursor)?)),
+            codes::misc::MEMORY_COPY => {
+                Self::decode_reserved_byte(cursor)?;
+                Self::decode_reserved_byte(cursor)?;
+                Instr::MemoryCopy
             }
+            codes::misc::MEMORY_FILL => {
+                Self::decode_reserved_byte(cursor)?;
+                Instr::MemoryFill
+            }
+            codes::misc::TABLE_INIT => Instr::TableInit(
+                ElemIdx(Self::decode_u32(cursor)?),
+                TableIdx(Self::decode_u32(cursor)?),
+            ),
+            codes::misc::ELEM_DROP => Instr::ElemDrop(ElemIdx(Self::decode_u32(cursor)?)),
+            codes::misc::TABLE_COPY => Instr::TableCopy(
+                TableIdx(Self::decode_u32(cursor)?),
+                TableIdx(Self::decode_u32(cursor)?),
+            ),
+            codes::misc::TABLE_GROW => Instr::TableGrow(TableIdx(Self::decode_u32(cursor)?)),
+            codes::misc::TABLE_SIZE => Instr::TableSize(TableIdx(Self::decode_u32(cursor)?)),
+            codes::misc::TABLE_FILL => Instr::TableFill(TableIdx(Self::decode_u32(cursor)?)),
+            op => return Err(Self::error(cursor, DecodeErrorKind::UnknownMiscOpcode(op))),
         };
         Ok(instr)
     }
 
-    fn decode_u32(cursor: &mut Cursor<&[u8]>) -> Result<u32, String> {
-        Self::decode_uint(cursor, 32).map(|x| x as u32)
+    fn decode_mem_arg(cursor: &mut Cursor<&[u8]>) -> Result<MemArg, DecodeError> {
+        let align = Self::decode_u32(cursor)?;
+        let offset = Self::decode_u32(cursor)?;
+        Ok(MemArg { offset, align })
     }
 
-    fn decode_uint(cursor: &mut Cursor<&[u8]>, size: u32) -> Result<u64, String> {
-        let n: u64 = cursor.read_byte()?.into();
-        if n < 128 && n < 2_u64.pow(size) as u64 {
-            Ok(n)
-        } else if n >= 128 && size > 7 {
-            Ok(128 * Self::decode_uint(cursor, size - 7)? + (n - 128))
-        } else {
-            Err(Self::gen_error_msg(
-                cursor,
-                String::from("Error decoding uint"),
-            ))
+    fn decode_reserved_byte(cursor: &mut Cursor<&[u8]>) -> Result<(), DecodeError> {
+        if cursor.read_byte()? != 0x00 {
+            return Err(Self::error(cursor, DecodeErrorKind::ExpectedZeroByte));
         }
+        Ok(())
     }
 
-    fn decode_i32(cursor: &mut Cursor<&[u8]>) -> Result<i32, String> {
-        Self::decode_int(cursor, 32).map(|x| x as i32)
+    fn bounded<'a>(cursor: &Cursor<&'a [u8]>, size: u32) -> Result<Cursor<&'a [u8]>, DecodeError> {
+        let bytes = *cursor.get_ref();
+        let start = cursor.position() as usize;
+        let end = start + size as usize;
+        if end > bytes.len() {
+            return Err(DecodeError::new(
+                bytes.len(),
+                DecodeErrorKind::UnexpectedEnd,
+            ));
+        }
+        let mut bounded = Cursor::new(&bytes[..end]);
+        bounded.set_position(start as u64);
+        Ok(bounded)
     }
 
-    fn decode_i64(cursor: &mut Cursor<&[u8]>) -> Result<i64, String> {
-        Self::decode_int(cursor, 64).map(|x| x as i64)
+    fn check_consumed(cursor: &Cursor<&[u8]>) -> Result<(), DecodeError> {
+        let position = cursor.position() as usize;
+        if position != cursor.get_ref().len() {
+            return Err(DecodeError::new(
+                position,
+                DecodeErrorKind::SectionSizeMismatch,
+            ));
+        }
+        Ok(())
     }
 
-    fn decode_int(cursor: &mut Cursor<&[u8]>, size: u32) -> Result<i64, String> {
-        let n: i64 = cursor.read_byte()?.into();
-        if n < 64 && n < 2_u64.pow(size - 1) as i64 {
-            Ok(n)
-        } else if 64 <= n && n <= 128 && n >= 128 - 2_u64.pow(size - 1) as i64 {
-            Ok(n - 128)
-        } else if n >= 128 && size > 7 {
-            Ok(128 * Self::decode_int(cursor, size - 7)? + (n - 128))
-        } else {
-            Err(Self::gen_error_msg(
-                cursor,
-                String::from("Error decoding int"),
-            ))
-        }
+    fn decode_u32(cursor: &mut Cursor<&[u8]>) -> Result<u32, DecodeError> {
+        Self::decode_leb128(cursor, |bytes| leb128::read_unsigned(bytes, 32)).map(|x| x as u32)
+    }
+
+    fn decode_i32(cursor: &mut Cursor<&[u8]>) -> Result<i32, DecodeError> {
+        Self::decode_leb128(cursor, |bytes| leb128::read_signed(bytes, 32)).map(|x| x as i32)
+    }
+
+    fn decode_i64(cursor: &mut Cursor<&[u8]>) -> Result<i64, DecodeError> {
+        Self::decode_leb128(cursor, |bytes| leb128::read_signed(bytes, 64))
+    }
+
+    fn decode_leb128<T>(
+        cursor: &mut Cursor<&[u8]>,
+        read: impl Fn(&[u8]) -> LebResult<T>,
+    ) -> Result<T, DecodeError> {
+        let position = cursor.position() as usize;
+        let bytes = cursor.get_ref().get(position..).unwrap_or_default();
+        let (value, len) =
+            read(bytes).map_err(|(offset, kind)| DecodeError::new(position + offset, kind))?;
+        cursor.set_position((position + len) as u64);
+        Ok(value)
     }
 
-    fn process_vector<F, R>(cursor: &mut Cursor<&[u8]>, f: F) -> Result<Vec<R>, String>
+    fn process_vector<F, R>(cursor: &mut Cursor<&[u8]>, f: F) -> Result<Vec<R>, DecodeError>
     where
-        F: Fn(&mut Cursor<&[u8]>) -> Result<R, String>,
+        F: Fn(&mut Cursor<&[u8]>) -> Result<R, DecodeError>,
     {
         let length = Self::decode_u32(cursor)?;
         let mut vec = Vec::default();
         for _ in 0..length {
-            match f(cursor) {
-                Ok(r) => vec.push(r),
-                Err(err) => {
-                    return Err(Self::gen_error_msg(
-                        cursor,
-                        format!("Error processing Vector: {err}"),
-                    ))
-                }
-            }
+            vec.push(f(cursor)?);
         }
         Ok(vec)
     }
 
-    fn decode_string(cursor: &mut Cursor<&[u8]>) -> Result<String, String> {
-        let length = Self::decode_u32(cursor)?;
-        let mut string = String::default();
-        for _ in 0..length {
-            string.push(cursor.read_byte()? as char);
+    fn decode_string(cursor: &mut Cursor<&[u8]>) -> Result<String, DecodeError> {
+        let length = Self::decode_u32(cursor)? as usize;
+        let start = cursor.position() as usize;
+        let bytes = cursor
+            .get_ref()
+            .get(start..start.saturating_add(length))
+            .ok_or_else(|| {
+                DecodeError::new(cursor.get_ref().len(), DecodeErrorKind::UnexpectedEnd)
+            })?;
+        let string = std::str::from_utf8(bytes).map_err(|err| {
+            DecodeError::new(start + err.valid_up_to(), DecodeErrorKind::MalformedUtf8)
+        })?;
+        cursor.set_position((start + length) as u64);
+        Ok(string.to_owned())
+    }
+
+    fn error(cursor: &Cursor<&[u8]>, kind: DecodeErrorKind) -> DecodeError {
+        DecodeError::new(cursor.position().saturating_sub(1) as usize, kind)
+    }
+}
+
+impl StreamingDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn decode_from(mut reader: impl Read) -> io::Result<Module> {
+        let mut decoder = Self::new();
+        io::copy(&mut reader, &mut decoder)?;
+        decoder
+            .finish()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<usize, DecodeError> {
+        self.buffer.extend_from_slice(bytes);
+        let sections = self.decode_sections().map_err(|err| self.absolute(err))?;
+        // Only the bytes of a section that hasn't fully arrived are kept.
+        self.buffer.drain(..self.position);
+        self.consumed += self.position;
+        self.position = 0;
+        Ok(sections)
+    }
+
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let Some(function_section) = &self.function_section else {
+            return Validator::validate(&self.module);
+        };
+        // Until the code section arrives each declared function stands in
+        // with an `unreachable` body, which is valid for every type.
+        let mut module = self.module.clone();
+        module.funcs = function_section
+            .iter()
+            .map(|typ| Function {
+                typ: typ.clone(),
+                locals: Vec::new(),
+                body: Expr(vec![Instr::Unreachable], End),
+            })
+            .collect();
+        Validator::validate(&module)
+    }
+
+    // Like `Module::from_bytes`, the finished module is validated: it can go
+    // straight to instantiation.
+    pub fn finish(self) -> Result<Module, Error> {
+        let module = self.finish_decoding()?;
+        Validator::validate(&module)?;
+        Ok(module)
+    }
+
+    pub(crate) fn finish_decoding(mut self) -> Result<Module, DecodeError> {
+        if !self.header_decoded {
+            self.decode_header().map_err(|err| self.absolute(err))?;
+        }
+        let end = self.consumed + self.buffer.len();
+        if !self.buffer.is_empty() {
+            return Err(DecodeError::new(end, DecodeErrorKind::UnexpectedEnd));
+        }
+        if self
+            .function_section
+            .is_some_and(|section| !section.is_empty())
+        {
+            return Err(DecodeError::new(
+                end,
+                DecodeErrorKind::FunctionCodeCountMismatch,
+            ));
+        }
+        // A name section that decodes lives on in `names`, which the encoder
+        // rebuilds it from; a malformed one is kept as an opaque custom section.
+        let customs = &mut self.module.customs;
+        if let Some(idx) = customs.iter().position(|custom| custom.name == "name") {
+            if let Ok(names) = Decoder::decode_name_section(&customs[idx].bytes) {
+                self.module.names = names;
+                customs.remove(idx);
+            }
+        }
+        if let Some(count) = self.module.data_count {
+            if count as usize != self.module.data.len() {
+                return Err(DecodeError::new(end, DecodeErrorKind::DataCountMismatch));
+            }
+        }
+        Ok(self.module)
+    }
+
+    fn decode_sections(&mut self) -> Result<usize, DecodeError> {
+        if !self.header_decoded {
+            if self.buffer.len() < 8 {
+                return Ok(0);
+            }
+            self.decode_header()?;
+        }
+        let mut sections = 0;
+        while let Some((section_code, size)) = self.next_section()? {
+            self.decode_section(section_code, size)?;
+            sections += 1;
+        }
+        Ok(sections)
+    }
+
+    fn absolute(&self, err: DecodeError) -> DecodeError {
+        DecodeError::new(self.consumed + err.offset, err.kind)
+    }
+
+    fn decode_header(&mut self) -> Result<(), DecodeError> {
+        let mut cursor = Cursor::new(self.buffer.as_slice());
+        Decoder::check_magic_number(&mut cursor)?;
+        self.module.version = Decoder::get_version(&mut cursor)? as i32;
+        self.position = cursor.position() as usize;
+        self.header_decoded = true;
+        Ok(())
+    }
+
+    fn next_section(&self) -> Result<Option<(u8, u32)>, DecodeError> {
+        let mut cursor = Cursor::new(self.buffer.as_slice());
+        cursor.set_position(self.position as u64);
+        let Ok(section_code) = cursor.read_byte() else {
+            return Ok(None);
+        };
+        let size = match Decoder::decode_u32(&mut cursor) {
+            Ok(size) => size,
+            Err(DecodeError {
+                kind: DecodeErrorKind::UnexpectedEnd,
+                ..
+            }) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        if cursor.position() as usize + size as usize > self.buffer.len() {
+            return Ok(None);
         }
-        Ok(string)
+        Ok(Some((section_code, size)))
     }
 
-    fn gen_error_msg(cursor: &Cursor<&[u8]>, msg: String) -> String {
-        format!("Byte address: {:x}, {}", cursor.position() - 1, msg)
+    fn check_order(&self, section_code: u8) -> Result<(), DecodeError> {
+        if section_code == codes::section::CUSTOM {
+            return Ok(());
+        }
+        let rank = |code| {
+            codes::section::ORDER
+                .iter()
+                .position(|section| *section == code)
+        };
+        let Some(current) = rank(section_code) else {
+            return Err(DecodeError::new(
+                self.position,
+                DecodeErrorKind::UnsupportedSection(section_code),
+            ));
+        };
+        match self.last_section.and_then(rank) {
+            Some(last) if last == current => Err(DecodeError::new(
+                self.position,
+                DecodeErrorKind::DuplicateSection(section_code),
+            )),
+            Some(last) if last > current => Err(DecodeError::new(
+                self.position,
+                DecodeErrorKind::SectionOutOfOrder(section_code),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn decode_section(&mut self, section_code: u8, size: u32) -> Result<(), DecodeError> {
+        let mut cursor = Cursor::new(self.buffer.as_slice());
+        cursor.set_position(self.position as u64);
+        cursor.read_byte()?;
+        Decoder::decode_u32(&mut cursor)?;
+        let mut cursor = Decoder::bounded(&cursor, size)?;
+        self.check_order(section_code)?;
+        let module = &mut self.module;
+        match section_code {
+            codes::section::CUSTOM => module.customs.push(Decoder::decode_custom_section(
+                &mut cursor,
+                self.last_section,
+            )?),
+            codes::section::TYPE => module.types = Decoder::decode_type_section(&mut cursor)?,
+            codes::section::IMPORT => module.imports = Decoder::decode_import_section(&mut cursor)?,
+            codes::section::FUNCTION => {
+                self.function_section = Some(Decoder::decode_function_section(&mut cursor)?)
+            }
+            codes::section::TABLE => module.table = Decoder::decode_table_section(&mut cursor)?,
+            codes::section::MEMORY => module.memory = Decoder::decode_memory_section(&mut cursor)?,
+            codes::section::GLOBAL => module.globals = Decoder::decode_global_section(&mut cursor)?,
+            codes::section::EXPORT => module.exports = Decoder::decode_export_section(&mut cursor)?,
+            codes::section::START => module.start = Decoder::decode_start_section(&mut cursor)?,
+            codes::section::ELEMENT => module.elem = Decoder::decode_elem_section(&mut cursor)?,
+            codes::section::DATA_COUNT => {
+                module.data_count = Decoder::decode_data_count_section(&mut cursor)?
+            }
+            codes::section::CODE => {
+                let code_section = Decoder::decode_code_section(&mut cursor)?;
+                // The data count section comes before the code, so a body
+                // naming a data segment without one can be refused right here.
+                if module.data_count.is_none()
+                    && code_section
+                        .iter()
+                        .any(|(_, body)| Decoder::uses_data_index(&body.0))
+                {
+                    return Err(DecodeError::new(
+                        self.position,
+                        DecodeErrorKind::DataCountRequired,
+                    ));
+                }
+                module.funcs = Decoder::build_functions_component(
+                    &cursor,
+                    self.function_section.take().unwrap_or_default(),
+                    code_section,
+                )?
+            }
+            codes::section::DATA => module.data = Decoder::decode_data_section(&mut cursor)?,
+            _ => unreachable!("section order checked above"),
+        };
+        Decoder::check_consumed(&cursor)?;
+        if section_code != codes::section::CUSTOM {
+            self.last_section = Some(section_code);
+        }
+        self.position = cursor.position() as usize;
+        Ok(())
+    }
+}
+
+impl Write for StreamingDecoder {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.feed(bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, Error::Decode(err)))?;
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
 
 trait ReadExt: Read {
-    fn read_exact_custom(&mut self, buf: &mut [u8]) -> Result<(), String>;
-    fn read_byte(&mut self) -> Result<u8, String>;
-    fn read_le_i32(&mut self) -> Result<u32, String>;
-    fn read_le_f32(&mut self) -> Result<f32, String>;
-    fn read_le_f64(&mut self) -> Result<f64, String>;
+    fn read_exact_custom(&mut self, buf: &mut [u8]) -> Result<(), DecodeError>;
+    fn read_byte(&mut self) -> Result<u8, DecodeError>;
+    fn read_le_i32(&mut self) -> Result<u32, DecodeError>;
+    fn read_le_f32(&mut self) -> Result<f32, DecodeError>;
+    fn read_le_f64(&mut self) -> Result<f64, DecodeError>;
 }
 impl ReadExt for Cursor<&[u8]> {
-    fn read_exact_custom(&mut self, buf: &mut [u8]) -> Result<(), String> {
+    fn read_exact_custom(&mut self, buf: &mut [u8]) -> Result<(), DecodeError> {
         self.read_exact(buf)
-            .map_err(|_| String::from("Buffer read error"))
+            .map_err(|_| DecodeError::new(self.position() as usize, DecodeErrorKind::UnexpectedEnd))
     }
 
-    fn read_byte(&mut self) -> Result<u8, String> {
+    fn read_byte(&mut self) -> Result<u8, DecodeError> {
         let mut byte_buf = [0; 1];
-        let _ = self.read_exact_custom(&mut byte_buf)?;
+        self.read_exact_custom(&mut byte_buf)?;
         Ok(u8::from_le_bytes(byte_buf))
     }
 
-    fn read_le_i32(&mut self) -> Result<u32, String> {
+    fn read_le_i32(&mut self) -> Result<u32, DecodeError> {
         let mut int_buf = [0; 4];
-        let _ = self.read_exact_custom(&mut int_buf)?;
+        self.read_exact_custom(&mut int_buf)?;
         Ok(u32::from_le_bytes(int_buf))
     }
 
-    fn read_le_f32(&mut self) -> Result<f32, String> {
+    fn read_le_f32(&mut self) -> Result<f32, DecodeError> {
         let mut float_buf = [0; 4];
-        let _ = self.read_exact_custom(&mut float_buf)?;
+        self.read_exact_custom(&mut float_buf)?;
         Ok(f32::from_le_bytes(float_buf))
     }
 
-    fn read_le_f64(&mut self) -> Result<f64, String> {
+    fn read_le_f64(&mut self) -> Result<f64, DecodeError> {
         let mut float_buf = [0; 8];
-        let _ = self.read_exact_custom(&mut float_buf)?;
+        self.read_exact_custom(&mut float_buf)?;
         Ok(f64::from_le_bytes(float_buf))
     }
 }
@@ -0,0 +1,655 @@
+use crate::codes::{self, MAGIC};
+use crate::leb128;
+use crate::module::*;
+use std::collections::HashMap;
+
+pub struct Encoder;
+
+impl Encoder {
+    pub fn encode(module: &Module) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&1_u32.to_le_bytes());
+        Self::encode_custom_sections(module, None, &mut bytes);
+        for section_code in codes::section::ORDER {
+            let count = match section_code {
+                codes::section::TYPE => module.types.len(),
+                codes::section::IMPORT => module.imports.len(),
+                codes::section::FUNCTION | codes::section::CODE => module.funcs.len(),
+                codes::section::TABLE => module.table.len(),
+                codes::section::MEMORY => module.memory.len(),
+                codes::section::GLOBAL => module.globals.len(),
+                codes::section::EXPORT => module.exports.len(),
+                codes::section::START => usize::from(module.start.is_some()),
+                codes::section::ELEMENT => module.elem.len(),
+                codes::section::DATA_COUNT => usize::from(module.data_count.is_some()),
+                _ => module.data.len(),
+            };
+            // An empty section is kept when a custom section is anchored
+            // after it, so that decoding restores the same placement. The
+            // start and data count sections have no empty form.
+            let anchored = module
+                .customs
+                .iter()
+                .any(|custom| custom.after_section == Some(section_code));
+            let singular = matches!(
+                section_code,
+                codes::section::START | codes::section::DATA_COUNT
+            );
+            if count == 0 && (!anchored || singular) {
+                continue;
+            }
+            let mut contents = Vec::new();
+            Self::encode_section(module, section_code, &mut contents);
+            Self::encode_raw_section(section_code, &contents, &mut bytes);
+            Self::encode_custom_sections(module, Some(section_code), &mut bytes);
+        }
+        Self::encode_names(&module.names, &mut bytes);
+        bytes
+    }
+
+    fn encode_names(names: &Names, out: &mut Vec<u8>) {
+        if *names == Names::default() {
+            return;
+        }
+        let mut contents = Vec::new();
+        Self::encode_string("name", &mut contents);
+        if let Some(module) = &names.module {
+            let mut subsection = Vec::new();
+            Self::encode_string(module, &mut subsection);
+            Self::encode_raw_section(codes::name::MODULE, &subsection, &mut contents);
+        }
+        if !names.funcs.is_empty() {
+            let mut subsection = Vec::new();
+            Self::encode_name_map(&names.funcs, &mut subsection);
+            Self::encode_raw_section(codes::name::FUNCTIONS, &subsection, &mut contents);
+        }
+        if !names.locals.is_empty() {
+            let mut locals = names.locals.iter().collect::<Vec<_>>();
+            locals.sort_by_key(|(func, _)| **func);
+            let mut subsection = Vec::new();
+            Self::encode_vector(&locals, &mut subsection, |(func, map), out| {
+                Self::encode_u32(**func, out);
+                Self::encode_name_map(map, out);
+            });
+            Self::encode_raw_section(codes::name::LOCALS, &subsection, &mut contents);
+        }
+        Self::encode_raw_section(codes::section::CUSTOM, &contents, out);
+    }
+
+    // Name maps have to be sorted by index.
+    fn encode_name_map(map: &HashMap<u32, String>, out: &mut Vec<u8>) {
+        let mut entries = map.iter().collect::<Vec<_>>();
+        entries.sort();
+        Self::encode_vector(&entries, out, |(idx, name), out| {
+            Self::encode_u32(**idx, out);
+            Self::encode_string(name, out);
+        });
+    }
+
+    fn encode_custom_sections(module: &Module, after_section: Option<u8>, out: &mut Vec<u8>) {
+        for custom in &module.customs {
+            if custom.after_section == after_section {
+                let mut contents = Vec::new();
+                Self::encode_string(&custom.name, &mut contents);
+                contents.extend_from_slice(&custom.bytes);
+                Self::encode_raw_section(codes::section::CUSTOM, &contents, out);
+            }
+        }
+    }
+
+    fn encode_raw_section(section_code: u8, contents: &[u8], out: &mut Vec<u8>) {
+        out.push(section_code);
+        Self::encode_len(contents.len(), out);
+        out.extend_from_slice(contents);
+    }
+
+    fn encode_section(module: &Module, section_code: u8, out: &mut Vec<u8>) {
+        match section_code {
+            codes::section::TYPE => Self::encode_vector(&module.types, out, Self::encode_func_type),
+            codes::section::IMPORT => {
+                Self::encode_vector(&module.imports, out, Self::encode_import)
+            }
+            codes::section::FUNCTION => Self::encode_vector(&module.funcs, out, |func, out| {
+                Self::encode_u32(func.typ.0, out)
+            }),
+            codes::section::TABLE => Self::encode_vector(&module.table, out, |table, out| {
+                Self::encode_table_type(&table.typ, out)
+            }),
+            codes::section::MEMORY => Self::encode_vector(&module.memory, out, |mem, out| {
+                Self::encode_limits(&mem.typ.0, out)
+            }),
+            codes::section::GLOBAL => Self::encode_vector(&module.globals, out, |global, out| {
+                Self::encode_global_type(&global.typ, out);
+                Self::encode_expression(&global.init, out);
+            }),
+            codes::section::EXPORT => {
+                Self::encode_vector(&module.exports, out, Self::encode_export)
+            }
+            codes::section::START => {
+                if let Some(start) = &module.start {
+                    Self::encode_u32(start.func.0, out);
+                }
+            }
+            codes::section::ELEMENT => Self::encode_vector(&module.elem, out, Self::encode_elem),
+            codes::section::DATA_COUNT => {
+                if let Some(count) = module.data_count {
+                    Self::encode_u32(count, out);
+                }
+            }
+            codes::section::CODE => Self::encode_vector(&module.funcs, out, |func, out| {
+                let mut body = Vec::new();
+                Self::encode_code(func, &mut body);
+                Self::encode_len(body.len(), out);
+                out.extend_from_slice(&body);
+            }),
+            _ => Self::encode_vector(&module.data, out, Self::encode_data),
+        }
+    }
+
+    fn encode_elem(elem: &Elem, out: &mut Vec<u8>) {
+        // Segments that only list functions use the compact index encoding.
+        let funcs = elem.func_indices();
+        let exprs = match funcs {
+            Some(_) => 0,
+            None => codes::segment::EXPRESSIONS,
+        };
+        let encode_kind = |out: &mut Vec<u8>| match funcs {
+            Some(_) => out.push(codes::segment::ELEM_KIND_FUNCREF),
+            None => Self::encode_ref_type(elem.typ, out),
+        };
+        match &elem.mode {
+            ElemMode::Active {
+                table: TableIdx(0),
+                offset,
+            } if elem.typ == ElemType::FuncRef => {
+                Self::encode_u32(codes::segment::ACTIVE | exprs, out);
+                Self::encode_expression(offset, out);
+            }
+            ElemMode::Active { table, offset } => {
+                Self::encode_u32(codes::segment::ACTIVE_EXPLICIT | exprs, out);
+                Self::encode_u32(table.0, out);
+                Self::encode_expression(offset, out);
+                encode_kind(out);
+            }
+            ElemMode::Passive => {
+                Self::encode_u32(codes::segment::PASSIVE | exprs, out);
+                encode_kind(out);
+            }
+            ElemMode::Declarative => {
+                Self::encode_u32(codes::segment::DECLARATIVE | exprs, out);
+                encode_kind(out);
+            }
+        }
+        match &funcs {
+            Some(funcs) => Self::encode_vector(funcs, out, |idx, out| Self::encode_u32(*idx, out)),
+            None => Self::encode_vector(&elem.init, out, Self::encode_expression),
+        }
+    }
+
+    fn encode_data(data: &Data, out: &mut Vec<u8>) {
+        match &data.mode {
+            DataMode::Active {
+                memory: MemIdx(0),
+                offset,
+            } => {
+                Self::encode_u32(codes::segment::ACTIVE, out);
+                Self::encode_expression(offset, out);
+            }
+            DataMode::Active { memory, offset } => {
+                Self::encode_u32(codes::segment::ACTIVE_EXPLICIT, out);
+                Self::encode_u32(memory.0, out);
+                Self::encode_expression(offset, out);
+            }
+            DataMode::Passive => Self::encode_u32(codes::segment::PASSIVE, out),
+        }
+        Self::encode_len(data.init.len(), out);
+        out.extend_from_slice(&data.init);
+    }
+
+    fn encode_func_type(typ: &FuncType, out: &mut Vec<u8>) {
+        out.push(codes::types::FUNCTION);
+        Self::encode_vector(&typ.params, out, Self::encode_val_type);
+        Self::encode_vector(&typ.results, out, Self::encode_val_type);
+    }
+
+    fn encode_import(import: &Import, out: &mut Vec<u8>) {
+        Self::encode_string(&import.module, out);
+        Self::encode_string(&import.name, out);
+        match &import.desc {
+            ImpExportDesc::Func(typ) => {
+                out.push(codes::im_export_desc::TYPE);
+                Self::encode_u32(typ.0, out);
+            }
+            ImpExportDesc::Table(typ) => {
+                out.push(codes::im_export_desc::TABLE);
+                Self::encode_table_type(typ, out);
+            }
+            ImpExportDesc::Mem(typ) => {
+                out.push(codes::im_export_desc::MEM);
+                Self::encode_limits(&typ.0, out);
+            }
+            ImpExportDesc::Global(typ) => {
+                out.push(codes::im_export_desc::GLOBAL);
+                Self::encode_global_type(typ, out);
+            }
+        }
+    }
+
+    fn encode_export(export: &Export, out: &mut Vec<u8>) {
+        Self::encode_string(&export.name, out);
+        let (kind, idx) = match &export.desc {
+            ExportDesc::Func(idx) => (codes::im_export_desc::TYPE, idx.0),
+            ExportDesc::Table(idx) => (codes::im_export_desc::TABLE, idx.0),
+            ExportDesc::Mem(idx) => (codes::im_export_desc::MEM, idx.0),
+            ExportDesc::Global(idx) => (codes::im_export_desc::GLOBAL, idx.0),
+        };
+        out.push(kind);
+        Self::encode_u32(idx, out);
+    }
+
+    fn encode_code(func: &Function, out: &mut Vec<u8>) {
+        let mut runs: Vec<(u32, ValType)> = Vec::new();
+        for local in &func.locals {
+            match runs.last_mut() {
+                Some((count, typ)) if typ == local => *count += 1,
+                _ => runs.push((1, *local)),
+            }
+        }
+        Self::encode_vector(&runs, out, |(count, typ), out| {
+            Self::encode_u32(*count, out);
+            Self::encode_val_type(typ, out);
+        });
+        Self::encode_expression(&func.body, out);
+    }
+
+    fn encode_table_type(typ: &TableType, out: &mut Vec<u8>) {
+        Self::encode_ref_type(typ.1, out);
+        Self::encode_limits(&typ.0, out);
+    }
+
+    fn encode_ref_type(typ: ElemType, out: &mut Vec<u8>) {
+        Self::encode_val_type(&typ.into(), out);
+    }
+
+    fn encode_global_type(typ: &GlobalType, out: &mut Vec<u8>) {
+        Self::encode_val_type(&typ.1, out);
+        out.push(match typ.0 {
+            Mut::Const => codes::types::CONST,
+            Mut::Var => codes::types::VAR,
+        });
+    }
+
+    fn encode_limits(limits: &Limits, out: &mut Vec<u8>) {
+        match limits.max {
+            None => {
+                out.push(codes::types::LIMIT_NO_MAX);
+                Self::encode_u32(limits.min, out);
+            }
+            Some(max) => {
+                out.push(codes::types::LIMIT_MAX);
+                Self::encode_u32(limits.min, out);
+                Self::encode_u32(max, out);
+            }
+        }
+    }
+
+    fn encode_val_type(typ: &ValType, out: &mut Vec<u8>) {
+        out.push(match typ {
+            ValType::I32 => codes::types::I32,
+            ValType::I64 => codes::types::I64,
+            ValType::F32 => codes::types::F32,
+            ValType::F64 => codes::types::F64,
+            ValType::FuncRef => codes::types::FUNCREF,
+            ValType::ExternRef => codes::types::EXTERNREF,
+        });
+    }
+
+    fn encode_expression(expr: &Expr, out: &mut Vec<u8>) {
+        Self::encode_instrs(&expr.0, out);
+        out.push(codes::instr::END);
+    }
+
+    fn encode_block_type(typ: &BlockType, out: &mut Vec<u8>) {
+        match typ {
+            BlockType::Empty => out.push(codes::types::RESULT),
+            BlockType::Value(typ) => Self::encode_val_type(typ, out),
+            BlockType::Type(TypeIdx(idx)) => leb128::write_signed(out, (*idx).into()),
+        }
+    }
+
+    fn encode_instrs(instrs: &[Instr], out: &mut Vec<u8>) {
+        for instr in instrs {
+            Self::encode_instruction(instr, out);
+        }
+    }
+
+    fn encode_instruction(instr: &Instr, out: &mut Vec<u8>) {
+        use codes::instr::*;
+        match instr {
+            Instr::Block(typ, instrs, End) | Instr::Loop(typ, instrs, End) => {
+                out.push(match instr {
+                    Instr::Block(..) => BLOCK,
+                    _ => LOOP,
+                });
+                Self::encode_block_type(typ, out);
+                Self::encode_instrs(instrs, out);
+                out.push(END);
+            }
+            Instr::If(typ, if_instrs, Else, else_instrs, End) => {
+                out.push(IF);
+                Self::encode_block_type(typ, out);
+                Self::encode_instrs(if_instrs, out);
+                if !else_instrs.is_empty() {
+                    out.push(ELSE);
+                    Self::encode_instrs(else_instrs, out);
+                }
+                out.push(END);
+            }
+            Instr::Br(label) => Self::encode_with_u32(BR, label.0, out),
+            Instr::BrIf(label) => Self::encode_with_u32(BR_IF, label.0, out),
+            Instr::BrTable(labels, default) => {
+                out.push(BR_TABLE);
+                Self::encode_vector(labels, out, |label, out| Self::encode_u32(label.0, out));
+                Self::encode_u32(default.0, out);
+            }
+            Instr::Call(func) => Self::encode_with_u32(CALL, func.0, out),
+            Instr::CallIndirect(typ, table) => {
+                Self::encode_with_u32(CALL_INDIRECT, typ.0, out);
+                Self::encode_u32(table.0, out);
+            }
+            Instr::TypedSelect(types) => {
+                out.push(SELECT_TYPED);
+                Self::encode_vector(types, out, Self::encode_val_type);
+            }
+            Instr::LocalGet(local) => Self::encode_with_u32(LOCAL_GET, local.0, out),
+            Instr::LocalSet(local) => Self::encode_with_u32(LOCAL_SET, local.0, out),
+            Instr::LocalTee(local) => Self::encode_with_u32(LOCAL_TEE, local.0, out),
+            Instr::GlobalGet(global) => Self::encode_with_u32(GLOBAL_GET, global.0, out),
+            Instr::GlobalSet(global) => Self::encode_with_u32(GLOBAL_SET, global.0, out),
+            Instr::TableGet(table) => Self::encode_with_u32(TABLE_GET, table.0, out),
+            Instr::TableSet(table) => Self::encode_with_u32(TABLE_SET, table.0, out),
+            Instr::MemorySize => out.extend_from_slice(&[MEMORY_SIZE, 0x00]),
+            Instr::MemoryGrow => out.extend_from_slice(&[MEMORY_GROW, 0x00]),
+            Instr::I32TruncSatF32S => Self::encode_misc(codes::misc::I32_TRUNC_SAT_F32_S, out),
+            Instr::I32TruncSatF32U => Self::encode_misc(codes::misc::I32_TRUNC_SAT_F32_U, out),
+            Instr::I32TruncSatF64S => Self::encode_misc(codes::misc::I32_TRUNC_SAT_F64_S, out),
+            Instr::I32TruncSatF64U => Self::encode_misc(codes::misc::I32_TRUNC_SAT_F64_U, out),
+            Instr::I64TruncSatF32S => Self::encode_misc(codes::misc::I64_TRUNC_SAT_F32_S, out),
+            Instr::I64TruncSatF32U => Self::encode_misc(codes::misc::I64_TRUNC_SAT_F32_U, out),
+            Instr::I64TruncSatF64S => Self::encode_misc(codes::misc::I64_TRUNC_SAT_F64_S, out),
+            Instr::I64TruncSatF64U => Self::encode_misc(codes::misc::I64_TRUNC_SAT_F64_U, out),
+            Instr::MemoryInit(data) => {
+                Self::encode_misc(codes::misc::MEMORY_INIT, out);
+                Self::encode_u32(data.0, out);
+                out.push(0x00);
+            }
+            Instr::DataDrop(data) => {
+                Self::encode_misc(codes::misc::DATA_DROP, out);
+                Self::encode_u32(data.0, out);
+            }
+            Instr::MemoryCopy => {
+                Self::encode_misc(codes::misc::MEMORY_COPY, out);
+                out.extend_from_slice(&[0x00, 0x00]);
+            }
+            Instr::MemoryFill => {
+                Self::encode_misc(codes::misc::MEMORY_FILL, out);
+                out.push(0x00);
+            }
+            Instr::TableInit(elem, table) => {
+                Self::encode_misc(codes::misc::TABLE_INIT, out);
+                Self::encode_u32(elem.0, out);
+                Self::encode_u32(table.0, out);
+            }
+            Instr::ElemDrop(elem) => {
+                Self::encode_misc(codes::misc::ELEM_DROP, out);
+                Self::encode_u32(elem.0, out);
+            }
+            Instr::TableCopy(dst, src) => {
+                Self::encode_misc(codes::misc::TABLE_COPY, out);
+                Self::encode_u32(dst.0, out);
+                Self::encode_u32(src.0, out);
+            }
+            Instr::TableGrow(table) => {
+                Self::encode_misc(codes::misc::TABLE_GROW, out);
+                Self::encode_u32(table.0, out);
+            }
+            Instr::TableSize(table) => {
+                Self::encode_misc(codes::misc::TABLE_SIZE, out);
+                Self::encode_u32(table.0, out);
+            }
+            Instr::TableFill(table) => {
+                Self::encode_misc(codes::misc::TABLE_FILL, out);
+                Self::encode_u32(table.0, out);
+            }
+            Instr::RefNull(typ) => {
+                out.push(REF_NULL);
+                Self::encode_ref_type(*typ, out);
+            }
+            Instr::RefFunc(func) => Self::encode_with_u32(REF_FUNC, func.0, out),
+            Instr::I32Const(value) => {
+                out.push(I32_CONST);
+                leb128::write_signed(out, (*value).into());
+            }
+            Instr::I64Const(value) => {
+                out.push(I64_CONST);
+                leb128::write_signed(out, *value);
+            }
+            Instr::F32Const(value) => {
+                out.push(F32_CONST);
+                out.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+            Instr::F64Const(value) => {
+                out.push(F64_CONST);
+                out.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+            _ => match Self::memory_instruction(instr) {
+                Some((opcode, mem_arg)) => {
+                    out.push(opcode);
+                    Self::encode_u32(mem_arg.align, out);
+                    Self::encode_u32(mem_arg.offset, out);
+                }
+                None => out.push(Self::opcode(instr)),
+            },
+        }
+    }
+
+    fn memory_instruction(instr: &Instr) -> Option<(u8, &MemArg)> {
+        use codes::instr::*;
+        Some(match instr {
+            Instr::I32Load(mem_arg) => (I32_LOAD, mem_arg),
+            Instr::I64Load(mem_arg) => (I64_LOAD, mem_arg),
+            Instr::F32Load(mem_arg) => (F32_LOAD, mem_arg),
+            Instr::F64Load(mem_arg) => (F64_LOAD, mem_arg),
+            Instr::I32Load8S(mem_arg) => (I32_LOAD8_S, mem_arg),
+            Instr::I32Load8U(mem_arg) => (I32_LOAD8_U, mem_arg),
+            Instr::I32Load16S(mem_arg) => (I32_LOAD16_S, mem_arg),
+            Instr::I32Load16U(mem_arg) => (I32_LOAD16_U, mem_arg),
+            Instr::I64Load8S(mem_arg) => (I64_LOAD8_S, mem_arg),
+            Instr::I64Load8U(mem_arg) => (I64_LOAD8_U, mem_arg),
+            Instr::I64Load16S(mem_arg) => (I64_LOAD16_S, mem_arg),
+            Instr::I64Load16U(mem_arg) => (I64_LOAD16_U, mem_arg),
+            Instr::I64Load32S(mem_arg) => (I64_LOAD32_S, mem_arg),
+            Instr::I64Load32U(mem_arg) => (I64_LOAD32_U, mem_arg),
+            Instr::I32Store(mem_arg) => (I32_STORE, mem_arg),
+            Instr::I64Store(mem_arg) => (I64_STORE, mem_arg),
+            Instr::F32Store(mem_arg) => (F32_STORE, mem_arg),
+            Instr::F64Store(mem_arg) => (F64_STORE, mem_arg),
+            Instr::I32Store8(mem_arg) => (I32_STORE8, mem_arg),
+            Instr::I32Store16(mem_arg) => (I32_STORE16, mem_arg),
+            Instr::I64Store8(mem_arg) => (I64_STORE8, mem_arg),
+            Instr::I64Store16(mem_arg) => (I64_STORE16, mem_arg),
+            Instr::I64Store32(mem_arg) => (I64_STORE32, mem_arg),
+            _ => return None,
+        })
+    }
+
+    fn opcode(instr: &Instr) -> u8 {
+        use codes::instr::*;
+        match instr {
+            Instr::Unreachable => UNREACHABLE,
+            Instr::Nop => NOP,
+            Instr::Return => RETURN,
+            Instr::Drop => DROP,
+            Instr::Select => SELECT,
+            Instr::RefIsNull => REF_IS_NULL,
+            Instr::I32Eqz => I32_EQZ,
+            Instr::I32Eq => I32_EQ,
+            Instr::I32Ne => I32_NE,
+            Instr::I32LtS => I32_LT_S,
+            Instr::I32LtU => I32_LT_U,
+            Instr::I32GtS => I32_GT_S,
+            Instr::I32GtU => I32_GT_U,
+            Instr::I32LeS => I32_LE_S,
+            Instr::I32LeU => I32_LE_U,
+            Instr::I32GeS => I32_GE_S,
+            Instr::I32GeU => I32_GE_U,
+            Instr::I64Eqz => I64_EQZ,
+            Instr::I64Eq => I64_EQ,
+            Instr::I64Ne => I64_NE,
+            Instr::I64LtS => I64_LT_S,
+            Instr::I64LtU => I64_LT_U,
+            Instr::I64GtS => I64_GT_S,
+            Instr::I64GtU => I64_GT_U,
+            Instr::I64LeS => I64_LE_S,
+            Instr::I64LeU => I64_LE_U,
+            Instr::I64GeS => I64_GE_S,
+            Instr::I64GeU => I64_GE_U,
+            Instr::F32Eq => F32_EQ,
+            Instr::F32Ne => F32_NE,
+            Instr::F32Lt => F32_LT,
+            Instr::F32Gt => F32_GT,
+            Instr::F32Le => F32_LE,
+            Instr::F32Ge => F32_GE,
+            Instr::F64Eq => F64_EQ,
+            Instr::F64Ne => F64_NE,
+            Instr::F64Lt => F64_LT,
+            Instr::F64Gt => F64_GT,
+            Instr::F64Le => F64_LE,
+            Instr::F64Ge => F64_GE,
+            Instr::I32Clz => I32_CLZ,
+            Instr::I32Ctz => I32_CTZ,
+            Instr::I32Popcnt => I32_POPCNT,
+            Instr::I32Add => I32_ADD,
+            Instr::I32Sub => I32_SUB,
+            Instr::I32Mul => I32_MUL,
+            Instr::I32DivS => I32_DIV_S,
+            Instr::I32DivU => I32_DIV_U,
+            Instr::I32RemS => I32_REM_S,
+            Instr::I32RemU => I32_REM_U,
+            Instr::I32And => I32_AND,
+            Instr::I32Or => I32_OR,
+            Instr::I32Xor => I32_XOR,
+            Instr::I32Shl => I32_SHL,
+            Instr::I32ShrS => I32_SHR_S,
+            Instr::I32ShrU => I32_SHR_U,
+            Instr::I32Rotl => I32_ROTL,
+            Instr::I32Rotr => I32_ROTR,
+            Instr::I64Clz => I64_CLZ,
+            Instr::I64Ctz => I64_CTZ,
+            Instr::I64Popcnt => I64_POPCNT,
+            Instr::I64Add => I64_ADD,
+            Instr::I64Sub => I64_SUB,
+            Instr::I64Mul => I64_MUL,
+            Instr::I64DivS => I64_DIV_S,
+            Instr::I64DivU => I64_DIV_U,
+            Instr::I64RemS => I64_REM_S,
+            Instr::I64RemU => I64_REM_U,
+            Instr::I64And => I64_AND,
+            Instr::I64Or => I64_OR,
+            Instr::I64Xor => I64_XOR,
+            Instr::I64Shl => I64_SHL,
+            Instr::I64ShrS => I64_SHR_S,
+            Instr::I64ShrU => I64_SHR_U,
+            Instr::I64Rotl => I64_ROTL,
+            Instr::I64Rotr => I64_ROTR,
+            Instr::F32Abs => F32_ABS,
+            Instr::F32Neg => F32_NEG,
+            Instr::F32Ceil => F32_CEIL,
+            Instr::F32Floor => F32_FLOOR,
+            Instr::F32Trunc => F32_TRUNC,
+            Instr::F32Nearest => F32_NEAREST,
+            Instr::F32Sqrt => F32_SQRT,
+            Instr::F32Add => F32_ADD,
+            Instr::F32Sub => F32_SUB,
+            Instr::F32Mul => F32_MUL,
+            Instr::F32Div => F32_DIV,
+            Instr::F32Min => F32_MIN,
+            Instr::F32Max => F32_MAX,
+            Instr::F32Copysign => F32_COPYSIGN,
+            Instr::F64Abs => F64_ABS,
+            Instr::F64Neg => F64_NEG,
+            Instr::F64Ceil => F64_CEIL,
+            Instr::F64Floor => F64_FLOOR,
+            Instr::F64Trunc => F64_TRUNC,
+            Instr::F64Nearest => F64_NEAREST,
+            Instr::F64Sqrt => F64_SQRT,
+            Instr::F64Add => F64_ADD,
+            Instr::F64Sub => F64_SUB,
+            Instr::F64Mul => F64_MUL,
+            Instr::F64Div => F64_DIV,
+            Instr::F64Min => F64_MIN,
+            Instr::F64Max => F64_MAX,
+            Instr::F64Copysign => F64_COPYSIGN,
+            Instr::I32WrapI64 => I32_WRAP_I64,
+            Instr::I32TruncF32S => I32_TRUNC_F32_S,
+            Instr::I32TruncF32U => I32_TRUNC_F32_U,
+            Instr::I32TruncF64S => I32_TRUNC_F64_S,
+            Instr::I32TruncF64U => I32_TRUNC_F64_U,
+            Instr::I64ExtendI32S => I64_EXTEND_I32_S,
+            Instr::I64ExtendI32U => I64_EXTEND_I32_U,
+            Instr::I64TruncF32S => I64_TRUNC_F32_S,
+            Instr::I64TruncF32U => I64_TRUNC_F32_U,
+            Instr::I64TruncF64S => I64_TRUNC_F64_S,
+            Instr::I64TruncF64U => I64_TRUNC_F64_U,
+            Instr::F32DemoteF64 => F32_DEMOTE_F64_S,
+            Instr::F32ConvertI32S => F32_CONVERT_I32_S,
+            Instr::F32ConvertI32U => F32_CONVERT_I32_U,
+            Instr::F32ConvertI64S => F32_CONVERT_I64_S,
+            Instr::F32ConvertI64U => F32_CONVERT_I64_U,
+            Instr::F64ConvertI32S => F64_CONVERT_I32_S,
+            Instr::F64ConvertI32U => F64_CONVERT_I32_U,
+            Instr::F64ConvertI64S => F64_CONVERT_I64_S,
+            Instr::F64ConvertI64U => F64_CONVERT_I64_U,
+            Instr::F64PromoteF32 => F64_PROMOTE_F32,
+            Instr::F32ReinterpretI32 => F32_REINTERPRET_I32,
+            Instr::F64ReinterpretI64 => F64_REINTERPRET_I64,
+            Instr::I32Extend8S => I32_EXTEND8_S,
+            Instr::I32Extend16S => I32_EXTEND16_S,
+            Instr::I64Extend8S => I64_EXTEND8_S,
+            Instr::I64Extend16S => I64_EXTEND16_S,
+            Instr::I64Extend32S => I64_EXTEND32_S,
+            Instr::I32ReinterpretF32 => I32_REINTERPRET_F32,
+            Instr::I64ReinterpretF64 => I64_REINTERPRET_F64,
+            _ => unreachable!("instructions with immediates are encoded separately"),
+        }
+    }
+
+    fn encode_misc(op: u32, out: &mut Vec<u8>) {
+        Self::encode_with_u32(codes::misc::PREFIX, op, out);
+    }
+
+    fn encode_with_u32(opcode: u8, value: u32, out: &mut Vec<u8>) {
+        out.push(opcode);
+        Self::encode_u32(value, out);
+    }
+
+    fn encode_vector<T>(items: &[T], out: &mut Vec<u8>, f: impl Fn(&T, &mut Vec<u8>)) {
+        Self::encode_len(items.len(), out);
+        for item in items {
+            f(item, out);
+        }
+    }
+
+    fn encode_string(string: &str, out: &mut Vec<u8>) {
+        Self::encode_len(string.len(), out);
+        out.extend_from_slice(string.as_bytes());
+    }
+
+    fn encode_len(len: usize, out: &mut Vec<u8>) {
+        leb128::write_unsigned(out, len as u64);
+    }
+
+    fn encode_u32(value: u32, out: &mut Vec<u8>) {
+        leb128::write_unsigned(out, value.into());
+    }
+}
@@ -0,0 +1,507 @@
+use crate::module::ValType;
+use crate::trap::Trap;
+use std::{error, fmt, io};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub offset: usize,
+    pub kind: DecodeErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    UnexpectedEnd,
+    BadMagic,
+    UnsupportedVersion(u32),
+    UnsupportedSection(u8),
+    DuplicateSection(u8),
+    SectionOutOfOrder(u8),
+    InvalidFuncType(u8),
+    InvalidImportDesc(u8),
+    InvalidExportDesc(u8),
+    InvalidElemType(u8),
+    InvalidMutability(u8),
+    InvalidLimits(u8),
+    InvalidValType(u8),
+    InvalidBlockType(u8),
+    UnknownOpcode(u8),
+    UnknownMiscOpcode(u32),
+    InvalidSegmentFlags(u32),
+    ExpectedZeroByte,
+    IntegerTooLong,
+    IntegerTooLarge,
+    MalformedUtf8,
+    MultipleMemories,
+    FunctionCodeCountMismatch,
+    DataCountMismatch,
+    DataCountRequired,
+    SectionSizeMismatch,
+    TooManyLocals,
+    NestingTooDeep,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    UnexpectedToken {
+        expected: &'static str,
+        found: String,
+    },
+    UnterminatedComment,
+    UnterminatedString,
+    InvalidEscape,
+    EmptyId,
+    UnknownField(String),
+    UnknownInstruction(String),
+    UnknownId(&'static str, String),
+    DuplicateId(&'static str, String),
+    MismatchedLabel(String),
+    InvalidNumber(String),
+    ConstantOutOfRange(String),
+    InvalidAlignment(u32),
+    MalformedUtf8,
+    ImportAfterDefinition(&'static str),
+    InlineTypeMismatch,
+    MultipleStart,
+    NestingTooDeep,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub func_idx: Option<u32>,
+    pub func_name: Option<String>,
+    pub instr_offset: Option<usize>,
+    pub kind: ValidationErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    TypeMismatch { expected: ValType, actual: ValType },
+    StackUnderflow,
+    ValuesRemaining,
+    BrTableArityMismatch,
+    UnknownType(u32),
+    UnknownFunction(u32),
+    UnknownTable(u32),
+    UnknownMemory(u32),
+    UnknownGlobal(u32),
+    UnknownLocal(u32),
+    UnknownLabel(u32),
+    UnknownElem(u32),
+    UnknownData(u32),
+    DataCountRequired,
+    SelectRequiresType(ValType),
+    InvalidResultArity,
+    ExpectedReference(ValType),
+    UndeclaredFunctionReference(u32),
+    ImmutableGlobal(u32),
+    ConstExprRequired,
+    ConstExprMutableGlobal(u32),
+    LimitsMinTooLarge(u32),
+    LimitsMaxTooLarge(u32),
+    LimitsMinExceedsMax,
+    AlignmentTooLarge,
+    MultipleMemories,
+    InvalidStartFunction,
+    DuplicateExport(String),
+    Global(u32, Box<ValidationErrorKind>),
+    ElementSegment(u32, Box<ValidationErrorKind>),
+    DataSegment(u32, Box<ValidationErrorKind>),
+    Export(String, Box<ValidationErrorKind>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkError {
+    InvalidModule(ValidationError),
+    UnknownImport {
+        module: String,
+        name: String,
+    },
+    ImportCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    IncompatibleImport {
+        module: String,
+        name: String,
+        reason: String,
+    },
+    InvalidConstExpr,
+    ElementSegmentDoesNotFit(u32),
+    DataSegmentDoesNotFit(u32),
+    MemoryLimitExceeded {
+        pages: u32,
+        limit: u32,
+    },
+    TableLimitExceeded {
+        elements: u32,
+        limit: u32,
+    },
+    StartTrap(Trap),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvokeError {
+    UnknownExport(String),
+    NotAFunction(String),
+    ArgumentCount { expected: usize, actual: usize },
+    InvalidArgument { arg: String, typ: ValType },
+    NoEntryPoint,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io { path: String, source: io::Error },
+    Decode(DecodeError),
+    Parse(ParseError),
+    Validation(ValidationError),
+    Link(LinkError),
+    Trap(Trap),
+    Invoke(InvokeError),
+}
+
+impl DecodeError {
+    pub(crate) fn new(offset: usize, kind: DecodeErrorKind) -> Self {
+        Self { offset, kind }
+    }
+}
+
+impl ParseError {
+    pub(crate) fn new(source: &str, offset: usize, kind: ParseErrorKind) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rfind('\n')
+            .map_or(before.len(), |nl| before.len() - nl - 1)
+            + 1;
+        Self { line, column, kind }
+    }
+}
+
+impl From<ValidationErrorKind> for ValidationError {
+    fn from(kind: ValidationErrorKind) -> Self {
+        Self {
+            func_idx: None,
+            func_name: None,
+            instr_offset: None,
+            kind,
+        }
+    }
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Io { .. } => 2,
+            Self::Decode(_) | Self::Parse(_) => 3,
+            Self::Validation(_) => 4,
+            Self::Link(LinkError::StartTrap(_)) | Self::Trap(_) => 6,
+            Self::Link(_) => 5,
+            Self::Invoke(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Byte address: {:x}, {}", self.offset, self.kind)
+    }
+}
+
+impl fmt::Display for DecodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of input"),
+            Self::BadMagic => write!(f, "wrong binary magic"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "version {version} unsupported, currently only version 1 is supported"
+            ),
+            Self::UnsupportedSection(id) => write!(f, "unsupported section id {id}"),
+            Self::DuplicateSection(id) => write!(f, "duplicate section id {id}"),
+            Self::SectionOutOfOrder(id) => write!(f, "section id {id} out of order"),
+            Self::InvalidFuncType(byte) => write!(f, "invalid function type form {byte:#x}"),
+            Self::InvalidImportDesc(byte) => write!(f, "invalid import description {byte:#x}"),
+            Self::InvalidExportDesc(byte) => write!(f, "invalid export description {byte:#x}"),
+            Self::InvalidElemType(byte) => write!(f, "invalid element type {byte:#x}"),
+            Self::InvalidMutability(byte) => write!(f, "invalid global mutability {byte:#x}"),
+            Self::InvalidLimits(byte) => write!(f, "invalid limits flag {byte:#x}"),
+            Self::InvalidValType(byte) => write!(f, "invalid value type {byte:#x}"),
+            Self::InvalidBlockType(byte) => write!(f, "invalid block type {byte:#x}"),
+            Self::UnknownOpcode(byte) => write!(f, "unknown opcode {byte:#x}"),
+            Self::UnknownMiscOpcode(op) => write!(f, "unknown opcode 0xfc {op}"),
+            Self::InvalidSegmentFlags(flags) => write!(f, "invalid segment flags {flags}"),
+            Self::ExpectedZeroByte => write!(f, "expected zero byte"),
+            Self::IntegerTooLong => write!(f, "integer representation too long"),
+            Self::IntegerTooLarge => write!(f, "integer too large"),
+            Self::MalformedUtf8 => write!(f, "malformed UTF-8 encoding"),
+            Self::MultipleMemories => write!(f, "only one memory is allowed per module"),
+            Self::FunctionCodeCountMismatch => {
+                write!(f, "function and code section have inconsistent lengths")
+            }
+            Self::DataCountMismatch => {
+                write!(f, "data count and data section have inconsistent lengths")
+            }
+            Self::DataCountRequired => write!(f, "data count section required"),
+            Self::SectionSizeMismatch => write!(f, "section size mismatch"),
+            Self::TooManyLocals => write!(f, "too many locals"),
+            Self::NestingTooDeep => write!(f, "blocks nested too deeply"),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedChar(c) => write!(f, "unexpected character {c:?}"),
+            Self::UnexpectedEnd => write!(f, "unexpected end of input"),
+            Self::UnexpectedToken { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            Self::UnterminatedComment => write!(f, "unterminated block comment"),
+            Self::UnterminatedString => write!(f, "unterminated string"),
+            Self::InvalidEscape => write!(f, "invalid escape sequence"),
+            Self::EmptyId => write!(f, "empty identifier"),
+            Self::UnknownField(field) => write!(f, "unknown module field {field}"),
+            Self::UnknownInstruction(name) => write!(f, "unknown instruction {name}"),
+            Self::UnknownId(space, id) => write!(f, "unknown {space} ${id}"),
+            Self::DuplicateId(space, id) => write!(f, "duplicate {space} ${id}"),
+            Self::MismatchedLabel(id) => write!(f, "mismatching label ${id}"),
+            Self::InvalidNumber(text) => write!(f, "invalid number {text}"),
+            Self::ConstantOutOfRange(text) => write!(f, "constant out of range {text}"),
+            Self::InvalidAlignment(align) => {
+                write!(f, "alignment {align} must be a power of two")
+            }
+            Self::MalformedUtf8 => write!(f, "malformed UTF-8 encoding"),
+            Self::ImportAfterDefinition(kind) => write!(f, "import after {kind}"),
+            Self::InlineTypeMismatch => write!(f, "inline function type does not match type use"),
+            Self::MultipleStart => write!(f, "multiple start sections"),
+            Self::NestingTooDeep => write!(f, "instructions nested too deeply"),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.func_name, self.func_idx) {
+            (Some(name), _) => write!(f, "in ${name}, ")?,
+            (None, Some(func_idx)) => write!(f, "in func {func_idx}, ")?,
+            (None, None) => {}
+        }
+        if let Some(instr_offset) = self.instr_offset {
+            write!(f, "instruction {instr_offset}: ")?;
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl fmt::Display for ValidationErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TypeMismatch { expected, actual } => {
+                write!(f, "type mismatch: expected {expected:?}, found {actual:?}")
+            }
+            Self::StackUnderflow => write!(f, "type mismatch: operand stack underflow"),
+            Self::ValuesRemaining => write!(
+                f,
+                "type mismatch: values remaining on the stack at the end of a block"
+            ),
+            Self::BrTableArityMismatch => {
+                write!(
+                    f,
+                    "type mismatch: br_table targets have inconsistent arities"
+                )
+            }
+            Self::UnknownType(idx) => write!(f, "unknown type {idx}"),
+            Self::UnknownFunction(idx) => write!(f, "unknown function {idx}"),
+            Self::UnknownTable(idx) => write!(f, "unknown table {idx}"),
+            Self::UnknownMemory(idx) => write!(f, "unknown memory {idx}"),
+            Self::UnknownGlobal(idx) => write!(f, "unknown global {idx}"),
+            Self::UnknownLocal(idx) => write!(f, "unknown local {idx}"),
+            Self::UnknownLabel(idx) => write!(f, "unknown label {idx}"),
+            Self::UnknownElem(idx) => write!(f, "unknown elem segment {idx}"),
+            Self::UnknownData(idx) => write!(f, "unknown data segment {idx}"),
+            Self::DataCountRequired => write!(f, "data count section required"),
+            Self::SelectRequiresType(typ) => {
+                write!(
+                    f,
+                    "type mismatch: select on {typ} requires a type annotation"
+                )
+            }
+            Self::InvalidResultArity => write!(f, "invalid result arity"),
+            Self::ExpectedReference(typ) => {
+                write!(f, "type mismatch: expected a reference, found {typ}")
+            }
+            Self::UndeclaredFunctionReference(idx) => {
+                write!(f, "undeclared function reference {idx}")
+            }
+            Self::ImmutableGlobal(idx) => write!(f, "global {idx} is immutable"),
+            Self::ConstExprRequired => write!(f, "constant expression required"),
+            Self::ConstExprMutableGlobal(idx) => {
+                write!(f, "constant expression must not read mutable global {idx}")
+            }
+            Self::LimitsMinTooLarge(range) => {
+                write!(f, "limits minimum must not be larger than {range}")
+            }
+            Self::LimitsMaxTooLarge(range) => {
+                write!(f, "limits maximum must not be larger than {range}")
+            }
+            Self::LimitsMinExceedsMax => {
+                write!(f, "limits minimum must not be larger than maximum")
+            }
+            Self::AlignmentTooLarge => write!(f, "alignment must not be larger than natural"),
+            Self::MultipleMemories => write!(f, "multiple memories are not allowed"),
+            Self::InvalidStartFunction => write!(f, "start function must have type [] -> []"),
+            Self::DuplicateExport(name) => write!(f, "duplicate export name {name}"),
+            Self::Global(idx, kind) => write!(f, "global {idx}: {kind}"),
+            Self::ElementSegment(idx, kind) => write!(f, "element segment {idx}: {kind}"),
+            Self::DataSegment(idx, kind) => write!(f, "data segment {idx}: {kind}"),
+            Self::Export(name, kind) => write!(f, "export {name}: {kind}"),
+        }
+    }
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidModule(err) => write!(f, "invalid module: {err}"),
+            Self::UnknownImport { module, name } => write!(f, "unknown import {module}.{name}"),
+            Self::ImportCountMismatch { expected, actual } => write!(
+                f,
+                "module requires {expected} imports but {actual} were provided"
+            ),
+            Self::IncompatibleImport {
+                module,
+                name,
+                reason,
+            } => write!(f, "incompatible import type for {module}.{name}: {reason}"),
+            Self::InvalidConstExpr => write!(f, "invalid constant expression"),
+            Self::ElementSegmentDoesNotFit(idx) => {
+                write!(f, "element segment {idx} does not fit")
+            }
+            Self::DataSegmentDoesNotFit(idx) => write!(f, "data segment {idx} does not fit"),
+            Self::MemoryLimitExceeded { pages, limit } => write!(
+                f,
+                "memory requires {pages} pages but the limit is {limit} pages"
+            ),
+            Self::TableLimitExceeded { elements, limit } => write!(
+                f,
+                "table requires {elements} elements but the limit is {limit} elements"
+            ),
+            Self::StartTrap(trap) => write!(f, "start function trapped: {trap}"),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "Error reading file {path}: {source}"),
+            Self::Decode(err) => write!(f, "Error decoding binary: {err}"),
+            Self::Parse(err) => write!(f, "Error parsing text: {err}"),
+            Self::Validation(err) => write!(f, "Error validating module: {err}"),
+            Self::Link(err) => write!(f, "Error linking module: {err}"),
+            Self::Trap(trap) => write!(f, "Error interpreting binary: {trap}"),
+            Self::Invoke(err) => write!(f, "Error invoking function: {err}"),
+        }
+    }
+}
+
+impl fmt::Display for InvokeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownExport(name) => write!(f, "unknown export {name}"),
+            Self::NotAFunction(name) => write!(f, "export {name} is not a function"),
+            Self::ArgumentCount { expected, actual } => {
+                write!(f, "expected {expected} arguments but got {actual}")
+            }
+            Self::InvalidArgument { arg, typ } => write!(f, "invalid {typ} argument {arg}"),
+            Self::NoEntryPoint => write!(f, "module has no _start export, use --invoke"),
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+impl error::Error for ParseError {}
+
+impl error::Error for InvokeError {}
+
+impl error::Error for ValidationError {}
+
+impl error::Error for LinkError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::InvalidModule(err) => Some(err),
+            Self::StartTrap(trap) => Some(trap),
+            _ => None,
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Decode(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::Validation(err) => Some(err),
+            Self::Link(err) => Some(err),
+            Self::Trap(trap) => Some(trap),
+            Self::Invoke(err) => Some(err),
+        }
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(err: DecodeError) -> Self {
+        Error::Decode(err)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<ValidationError> for Error {
+    fn from(err: ValidationError) -> Self {
+        Error::Validation(err)
+    }
+}
+
+impl From<LinkError> for Error {
+    fn from(err: LinkError) -> Self {
+        match err {
+            LinkError::InvalidModule(err) => Error::Validation(err),
+            err => Error::Link(err),
+        }
+    }
+}
+
+impl From<InvokeError> for Error {
+    fn from(err: InvokeError) -> Self {
+        Error::Invoke(err)
+    }
+}
+
+impl From<Trap> for Error {
+    fn from(trap: Trap) -> Self {
+        Error::Trap(trap)
+    }
+}
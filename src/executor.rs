@@ -0,0 +1,931 @@
+use crate::instance::{Caller, Func};
+use crate::module::*;
+use crate::printer::Printer;
+use crate::store::*;
+use crate::trap::{FrameInfo, Trap, TrapKind};
+use crate::value::Value;
+
+pub const MAX_CALL_DEPTH: usize = 16384;
+
+pub(crate) struct Executor<'a> {
+    funcs: &'a [FuncInst],
+    instances: &'a [ModuleInst],
+    tables: &'a mut [TableInst],
+    mems: &'a mut [MemInst],
+    globals: &'a mut [GlobalInst],
+    elems: &'a mut [ElemInst],
+    datas: &'a mut [DataInst],
+    fuel: &'a mut Option<u64>,
+    trace: bool,
+    stack: Vec<Value>,
+    frames: Vec<Frame<'a>>,
+}
+
+struct Frame<'a> {
+    func: FuncAddr,
+    instance: &'a ModuleInst,
+    locals: Vec<Value>,
+    arity: usize,
+    height: usize,
+    labels: Vec<Label<'a>>,
+}
+
+struct Label<'a> {
+    instrs: &'a [Instr],
+    pc: usize,
+    arity: usize,
+    height: usize,
+    is_loop: bool,
+}
+
+trait Operand {
+    fn from_value(value: Value) -> Self;
+}
+
+impl Operand for i32 {
+    fn from_value(value: Value) -> Self {
+        match value {
+            Value::I32(v) => v,
+            v => panic!("Expected i32 on the stack but found {v:?}"),
+        }
+    }
+}
+
+impl Operand for i64 {
+    fn from_value(value: Value) -> Self {
+        match value {
+            Value::I64(v) => v,
+            v => panic!("Expected i64 on the stack but found {v:?}"),
+        }
+    }
+}
+
+impl Operand for f32 {
+    fn from_value(value: Value) -> Self {
+        match value {
+            Value::F32(v) => v,
+            v => panic!("Expected f32 on the stack but found {v:?}"),
+        }
+    }
+}
+
+impl Operand for f64 {
+    fn from_value(value: Value) -> Self {
+        match value {
+            Value::F64(v) => v,
+            v => panic!("Expected f64 on the stack but found {v:?}"),
+        }
+    }
+}
+
+impl<'a> Executor<'a> {
+    pub fn new(store: &'a mut Store) -> Self {
+        Self {
+            funcs: &store.funcs,
+            instances: &store.instances,
+            tables: &mut store.tables,
+            mems: &mut store.mems,
+            globals: &mut store.globals,
+            elems: &mut store.elems,
+            datas: &mut store.datas,
+            fuel: &mut store.fuel,
+            trace: store.trace,
+            stack: Vec::new(),
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn invoke(mut self, addr: FuncAddr, args: Vec<Value>) -> Result<Vec<Value>, Trap> {
+        let arity = self.funcs[addr].typ().results.len();
+        self.stack.extend(args);
+        if let Err(mut trap) = self.call(addr).and_then(|_| self.run()) {
+            for frame in self.frames.iter().rev() {
+                trap.push_frame(self.frame_info(frame.func));
+            }
+            return Err(trap);
+        }
+        Ok(self.stack.split_off(self.stack.len() - arity))
+    }
+
+    fn frame_info(&self, addr: FuncAddr) -> FrameInfo {
+        let FuncInst::Wasm { module, code, .. } = &self.funcs[addr] else {
+            unreachable!("host functions have no frames");
+        };
+        let imported_funcs = module
+            .imports
+            .iter()
+            .filter(|import| matches!(import.desc, ImpExportDesc::Func(_)))
+            .count();
+        let func_idx = (imported_funcs + code) as u32;
+        FrameInfo {
+            func_idx,
+            func_name: module.names.func(func_idx).map(String::from),
+        }
+    }
+
+    fn run(&mut self) -> Result<(), Trap> {
+        while let Some(frame) = self.frames.last_mut() {
+            let Some(label) = frame.labels.last_mut() else {
+                self.return_from_function();
+                continue;
+            };
+            let instrs = label.instrs;
+            match instrs.get(label.pc) {
+                Some(instr) => {
+                    label.pc += 1;
+                    self.consume_fuel()?;
+                    if self.trace {
+                        self.trace(instr);
+                    }
+                    self.execute(instr)?;
+                }
+                None => {
+                    frame.labels.pop();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn consume_fuel(&mut self) -> Result<(), Trap> {
+        match self.fuel {
+            Some(0) => Err(Trap::from(TrapKind::OutOfFuel)),
+            Some(fuel) => {
+                *fuel -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn trace(&self, instr: &Instr) {
+        let frame = self.frames.last().expect("no active frame");
+        let FuncInst::Wasm { module, .. } = &self.funcs[frame.func] else {
+            unreachable!("host functions have no frames");
+        };
+        let text = Printer::instr(module, instr);
+        eprintln!("{}: {text}", self.frame_info(frame.func));
+    }
+
+    fn call(&mut self, addr: FuncAddr) -> Result<(), Trap> {
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(Trap::from(TrapKind::CallStackExhausted));
+        }
+        let (typ, instance, function) = match &self.funcs[addr] {
+            FuncInst::Wasm {
+                typ,
+                instance,
+                module,
+                code,
+            } => (typ, *instance, &module.funcs[*code]),
+            FuncInst::Host { typ, func } => return self.call_host(typ, func),
+        };
+        let mut locals = self.stack.split_off(self.stack.len() - typ.params.len());
+        locals.extend(function.locals.iter().map(|typ| Value::default_for(*typ)));
+        let arity = typ.results.len();
+        let height = self.stack.len();
+        self.frames.push(Frame {
+            func: addr,
+            instance: &self.instances[instance],
+            locals,
+            arity,
+            height,
+            labels: vec![Label {
+                instrs: &function.body.0,
+                pc: 0,
+                arity,
+                height,
+                is_loop: false,
+            }],
+        });
+        Ok(())
+    }
+
+    fn call_host(&mut self, typ: &FuncType, func: &HostFunc) -> Result<(), Trap> {
+        let args = self.stack.split_off(self.stack.len() - typ.params.len());
+        let mut caller = Caller {
+            instance: self.frames.last().map(|frame| frame.instance),
+            mems: self.mems,
+            globals: self.globals,
+        };
+        let results = func(&mut caller, &args)?;
+        if results.len() != typ.results.len()
+            || results.iter().zip(&typ.results).any(|(v, t)| v.typ() != *t)
+        {
+            return Err(Trap::from(TrapKind::ResultMismatch {
+                expected: typ.results.clone(),
+                actual: results.iter().map(Value::typ).collect(),
+            }));
+        }
+        self.stack.extend(results);
+        Ok(())
+    }
+
+    fn return_from_function(&mut self) {
+        let frame = self.frames.pop().expect("return outside of a function");
+        let results = self.stack.split_off(self.stack.len() - frame.arity);
+        self.stack.truncate(frame.height);
+        self.stack.extend(results);
+    }
+
+    fn branch(&mut self, LabelIdx(depth): &LabelIdx) {
+        let frame = self
+            .frames
+            .last_mut()
+            .expect("branch outside of a function");
+        let target = frame.labels.len() - 1 - *depth as usize;
+        let label = &mut frame.labels[target];
+        let results = self.stack.split_off(self.stack.len() - label.arity);
+        self.stack.truncate(label.height);
+        self.stack.extend(results);
+        if label.is_loop {
+            label.pc = 0;
+            frame.labels.truncate(target + 1);
+        } else {
+            frame.labels.truncate(target);
+        }
+    }
+
+    fn enter_block(&mut self, block_type: &BlockType, instrs: &'a [Instr], is_loop: bool) {
+        let (params, results) = match block_type {
+            BlockType::Empty => (0, 0),
+            BlockType::Value(_) => (0, 1),
+            BlockType::Type(TypeIdx(idx)) => {
+                let typ = &self.instance().types[*idx as usize];
+                (typ.params.len(), typ.results.len())
+            }
+        };
+        // A branch to a loop restarts it and so carries the block's parameters.
+        let arity = if is_loop { params } else { results };
+        let height = self.stack.len() - params;
+        self.frame().labels.push(Label {
+            instrs,
+            pc: 0,
+            arity,
+            height,
+            is_loop,
+        });
+    }
+
+    fn frame(&mut self) -> &mut Frame<'a> {
+        self.frames.last_mut().expect("no active frame")
+    }
+
+    fn instance(&self) -> &'a ModuleInst {
+        self.frames.last().expect("no active frame").instance
+    }
+
+    fn memory(&mut self) -> &mut MemInst {
+        let addr = self.instance().mem_addrs[0];
+        &mut self.mems[addr]
+    }
+
+    fn table(&mut self, TableIdx(idx): &TableIdx) -> &mut TableInst {
+        let addr = self.instance().table_addrs[*idx as usize];
+        &mut self.tables[addr]
+    }
+
+    fn push(&mut self, value: impl Into<Value>) {
+        self.stack.push(value.into());
+    }
+
+    fn pop<T: Operand>(&mut self) -> T {
+        T::from_value(self.stack.pop().expect("operand stack underflow"))
+    }
+
+    fn unop<T: Operand, R: Into<Value>>(&mut self, f: impl FnOnce(T) -> R) {
+        let a = self.pop();
+        self.push(f(a));
+    }
+
+    fn binop<T: Operand, R: Into<Value>>(&mut self, f: impl FnOnce(T, T) -> R) {
+        let b = self.pop();
+        let a = self.pop();
+        self.push(f(a, b));
+    }
+
+    fn try_unop<T: Operand, R: Into<Value>>(
+        &mut self,
+        f: impl FnOnce(T) -> Result<R, Trap>,
+    ) -> Result<(), Trap> {
+        let a = self.pop();
+        self.push(f(a)?);
+        Ok(())
+    }
+
+    fn try_binop<T: Operand, R: Into<Value>>(
+        &mut self,
+        f: impl FnOnce(T, T) -> Result<R, Trap>,
+    ) -> Result<(), Trap> {
+        let b = self.pop();
+        let a = self.pop();
+        self.push(f(a, b)?);
+        Ok(())
+    }
+
+    fn load<const N: usize>(&mut self, mem_arg: &MemArg) -> Result<[u8; N], Trap> {
+        let base = self.pop::<i32>() as u32;
+        let memory = self.memory();
+        let start = base as usize + mem_arg.offset as usize;
+        let bytes = memory
+            .data
+            .get(start..start + N)
+            .ok_or_else(|| Trap::from(TrapKind::OutOfBoundsMemoryAccess))?;
+        Ok(bytes.try_into().expect("slice has exactly N bytes"))
+    }
+
+    fn store<const N: usize>(&mut self, mem_arg: &MemArg, bytes: [u8; N]) -> Result<(), Trap> {
+        let base = self.pop::<i32>() as u32;
+        let memory = self.memory();
+        let start = base as usize + mem_arg.offset as usize;
+        memory
+            .data
+            .get_mut(start..start + N)
+            .ok_or_else(|| Trap::from(TrapKind::OutOfBoundsMemoryAccess))?
+            .copy_from_slice(&bytes);
+        Ok(())
+    }
+
+    // Pops the destination, source and length operands shared by the bulk
+    // instructions, in that order.
+    fn bulk_operands(&mut self) -> (u32, u32, u32) {
+        let n = self.pop::<i32>() as u32;
+        let s = self.pop::<i32>() as u32;
+        let d = self.pop::<i32>() as u32;
+        (d, s, n)
+    }
+
+    fn memory_init(&mut self, DataIdx(idx): &DataIdx) -> Result<(), Trap> {
+        let (d, s, n) = self.bulk_operands();
+        let data = &self.datas[self.instance().data_addrs[*idx as usize]].data;
+        let memory = &mut self.mems[self.instance().mem_addrs[0]];
+        let out_of_bounds = || Trap::from(TrapKind::OutOfBoundsMemoryAccess);
+        let src = range(s, n, data.len()).ok_or_else(out_of_bounds)?;
+        let dst = range(d, n, memory.data.len()).ok_or_else(out_of_bounds)?;
+        memory.data[dst].copy_from_slice(&data[src]);
+        Ok(())
+    }
+
+    fn memory_copy(&mut self) -> Result<(), Trap> {
+        let (d, s, n) = self.bulk_operands();
+        let memory = self.memory();
+        let out_of_bounds = || Trap::from(TrapKind::OutOfBoundsMemoryAccess);
+        let src = range(s, n, memory.data.len()).ok_or_else(out_of_bounds)?;
+        range(d, n, memory.data.len()).ok_or_else(out_of_bounds)?;
+        memory.data.copy_within(src, d as usize);
+        Ok(())
+    }
+
+    fn memory_fill(&mut self) -> Result<(), Trap> {
+        let n = self.pop::<i32>() as u32;
+        let value = self.pop::<i32>() as u8;
+        let d = self.pop::<i32>() as u32;
+        let memory = self.memory();
+        let dst = range(d, n, memory.data.len())
+            .ok_or_else(|| Trap::from(TrapKind::OutOfBoundsMemoryAccess))?;
+        memory.data[dst].fill(value);
+        Ok(())
+    }
+
+    fn table_init(
+        &mut self,
+        ElemIdx(elem): &ElemIdx,
+        TableIdx(table): &TableIdx,
+    ) -> Result<(), Trap> {
+        let (d, s, n) = self.bulk_operands();
+        let elements = &self.elems[self.instance().elem_addrs[*elem as usize]].elements;
+        let table = &mut self.tables[self.instance().table_addrs[*table as usize]];
+        let out_of_bounds = || Trap::from(TrapKind::OutOfBoundsTableAccess);
+        let src = range(s, n, elements.len()).ok_or_else(out_of_bounds)?;
+        let dst = range(d, n, table.elements.len()).ok_or_else(out_of_bounds)?;
+        table.elements[dst].copy_from_slice(&elements[src]);
+        Ok(())
+    }
+
+    fn table_copy(
+        &mut self,
+        TableIdx(dst): &TableIdx,
+        TableIdx(src): &TableIdx,
+    ) -> Result<(), Trap> {
+        let (d, s, n) = self.bulk_operands();
+        let instance = self.instance();
+        let src_table = &self.tables[instance.table_addrs[*src as usize]];
+        let out_of_bounds = || Trap::from(TrapKind::OutOfBoundsTableAccess);
+        let src = range(s, n, src_table.elements.len()).ok_or_else(out_of_bounds)?;
+        // Copying through a buffer handles overlapping ranges within a table.
+        let elements = src_table.elements[src].to_vec();
+        let dst_table = &mut self.tables[instance.table_addrs[*dst as usize]];
+        let dst = range(d, n, dst_table.elements.len()).ok_or_else(out_of_bounds)?;
+        dst_table.elements[dst].copy_from_slice(&elements);
+        Ok(())
+    }
+
+    fn table_fill(&mut self, table: &TableIdx) -> Result<(), Trap> {
+        let n = self.pop::<i32>() as u32;
+        let value = self.stack.pop().expect("operand stack underflow");
+        let d = self.pop::<i32>() as u32;
+        let table = self.table(table);
+        let dst = range(d, n, table.elements.len())
+            .ok_or_else(|| Trap::from(TrapKind::OutOfBoundsTableAccess))?;
+        table.elements[dst].fill(value);
+        Ok(())
+    }
+
+    fn call_indirect(&mut self, TypeIdx(idx): &TypeIdx, table: &TableIdx) -> Result<(), Trap> {
+        let instance = self.instance();
+        let elem_idx = self.pop::<i32>() as u32 as usize;
+        let element = self
+            .table(table)
+            .elements
+            .get(elem_idx)
+            .ok_or_else(|| Trap::from(TrapKind::UndefinedElement))?;
+        let Value::FuncRef(Some(Func(addr))) = *element else {
+            return Err(Trap::from(TrapKind::UninitializedElement));
+        };
+        if *self.funcs[addr].typ() != instance.types[*idx as usize] {
+            return Err(Trap::from(TrapKind::IndirectCallTypeMismatch));
+        }
+        self.call(addr)
+    }
+
+    fn execute(&mut self, instr: &'a Instr) -> Result<(), Trap> {
+        match instr {
+            Instr::Unreachable => return Err(Trap::from(TrapKind::Unreachable)),
+            Instr::Nop => {}
+            Instr::Block(block_type, instrs, _) => self.enter_block(block_type, instrs, false),
+            Instr::Loop(block_type, instrs, _) => self.enter_block(block_type, instrs, true),
+            Instr::If(block_type, if_instrs, _, else_instrs, _) => {
+                if self.pop::<i32>() != 0 {
+                    self.enter_block(block_type, if_instrs, false);
+                } else {
+                    self.enter_block(block_type, else_instrs, false);
+                }
+            }
+            Instr::Br(label) => self.branch(label),
+            Instr::BrIf(label) => {
+                if self.pop::<i32>() != 0 {
+                    self.branch(label);
+                }
+            }
+            Instr::BrTable(labels, default) => {
+                let idx = self.pop::<i32>() as u32 as usize;
+                self.branch(labels.get(idx).unwrap_or(default));
+            }
+            Instr::Return => self.return_from_function(),
+            Instr::Call(FuncIdx(idx)) => {
+                let addr = self.instance().func_addrs[*idx as usize];
+                self.call(addr)?;
+            }
+            Instr::CallIndirect(typ, table) => self.call_indirect(typ, table)?,
+            Instr::Drop => {
+                self.stack.pop();
+            }
+            Instr::Select | Instr::TypedSelect(_) => {
+                let condition = self.pop::<i32>();
+                let b = self.stack.pop().expect("operand stack underflow");
+                let a = self.stack.pop().expect("operand stack underflow");
+                self.push(if condition != 0 { a } else { b });
+            }
+            Instr::LocalGet(LocalIdx(idx)) => {
+                let value = self.frame().locals[*idx as usize];
+                self.push(value);
+            }
+            Instr::LocalSet(LocalIdx(idx)) => {
+                let value = self.stack.pop().expect("operand stack underflow");
+                self.frame().locals[*idx as usize] = value;
+            }
+            Instr::LocalTee(LocalIdx(idx)) => {
+                let value = *self.stack.last().expect("operand stack underflow");
+                self.frame().locals[*idx as usize] = value;
+            }
+            Instr::GlobalGet(GlobalIdx(idx)) => {
+                let addr = self.instance().global_addrs[*idx as usize];
+                self.push(self.globals[addr].value);
+            }
+            Instr::GlobalSet(GlobalIdx(idx)) => {
+                let addr = self.instance().global_addrs[*idx as usize];
+                self.globals[addr].value = self.stack.pop().expect("operand stack underflow");
+            }
+            Instr::TableGet(table) => {
+                let idx = self.pop::<i32>() as u32 as usize;
+                let value = *self
+                    .table(table)
+                    .elements
+                    .get(idx)
+                    .ok_or_else(|| Trap::from(TrapKind::OutOfBoundsTableAccess))?;
+                self.push(value);
+            }
+            Instr::TableSet(table) => {
+                let value = self.stack.pop().expect("operand stack underflow");
+                let idx = self.pop::<i32>() as u32 as usize;
+                *self
+                    .table(table)
+                    .elements
+                    .get_mut(idx)
+                    .ok_or_else(|| Trap::from(TrapKind::OutOfBoundsTableAccess))? = value;
+            }
+            Instr::I32Load(m) => {
+                let bytes = self.load(m)?;
+                self.push(i32::from_le_bytes(bytes));
+            }
+            Instr::I64Load(m) => {
+                let bytes = self.load(m)?;
+                self.push(i64::from_le_bytes(bytes));
+            }
+            Instr::F32Load(m) => {
+                let bytes = self.load(m)?;
+                self.push(f32::from_le_bytes(bytes));
+            }
+            Instr::F64Load(m) => {
+                let bytes = self.load(m)?;
+                self.push(f64::from_le_bytes(bytes));
+            }
+            Instr::I32Load8S(m) => {
+                let bytes = self.load(m)?;
+                self.push(i8::from_le_bytes(bytes) as i32);
+            }
+            Instr::I32Load8U(m) => {
+                let bytes = self.load(m)?;
+                self.push(u8::from_le_bytes(bytes) as i32);
+            }
+            Instr::I32Load16S(m) => {
+                let bytes = self.load(m)?;
+                self.push(i16::from_le_bytes(bytes) as i32);
+            }
+            Instr::I32Load16U(m) => {
+                let bytes = self.load(m)?;
+                self.push(u16::from_le_bytes(bytes) as i32);
+            }
+            Instr::I64Load8S(m) => {
+                let bytes = self.load(m)?;
+                self.push(i8::from_le_bytes(bytes) as i64);
+            }
+            Instr::I64Load8U(m) => {
+                let bytes = self.load(m)?;
+                self.push(u8::from_le_bytes(bytes) as i64);
+            }
+            Instr::I64Load16S(m) => {
+                let bytes = self.load(m)?;
+                self.push(i16::from_le_bytes(bytes) as i64);
+            }
+            Instr::I64Load16U(m) => {
+                let bytes = self.load(m)?;
+                self.push(u16::from_le_bytes(bytes) as i64);
+            }
+            Instr::I64Load32S(m) => {
+                let bytes = self.load(m)?;
+                self.push(i32::from_le_bytes(bytes) as i64);
+            }
+            Instr::I64Load32U(m) => {
+                let bytes = self.load(m)?;
+                self.push(u32::from_le_bytes(bytes) as i64);
+            }
+            Instr::I32Store(m) => {
+                let value = self.pop::<i32>();
+                self.store(m, value.to_le_bytes())?;
+            }
+            Instr::I64Store(m) => {
+                let value = self.pop::<i64>();
+                self.store(m, value.to_le_bytes())?;
+            }
+            Instr::F32Store(m) => {
+                let value = self.pop::<f32>();
+                self.store(m, value.to_le_bytes())?;
+            }
+            Instr::F64Store(m) => {
+                let value = self.pop::<f64>();
+                self.store(m, value.to_le_bytes())?;
+            }
+            Instr::I32Store8(m) => {
+                let value = self.pop::<i32>();
+                self.store(m, (value as i8).to_le_bytes())?;
+            }
+            Instr::I32Store16(m) => {
+                let value = self.pop::<i32>();
+                self.store(m, (value as i16).to_le_bytes())?;
+            }
+            Instr::I64Store8(m) => {
+                let value = self.pop::<i64>();
+                self.store(m, (value as i8).to_le_bytes())?;
+            }
+            Instr::I64Store16(m) => {
+                let value = self.pop::<i64>();
+                self.store(m, (value as i16).to_le_bytes())?;
+            }
+            Instr::I64Store32(m) => {
+                let value = self.pop::<i64>();
+                self.store(m, (value as i32).to_le_bytes())?;
+            }
+            Instr::MemorySize => {
+                let size = self.memory().size();
+                self.push(size as i32);
+            }
+            Instr::MemoryGrow => {
+                let delta = self.pop::<i32>() as u32;
+                let result = self.memory().grow(delta).map_or(-1, |old| old as i32);
+                self.push(result);
+            }
+            Instr::MemoryInit(data) => self.memory_init(data)?,
+            Instr::DataDrop(DataIdx(idx)) => {
+                let addr = self.instance().data_addrs[*idx as usize];
+                self.datas[addr].data = Vec::new();
+            }
+            Instr::MemoryCopy => self.memory_copy()?,
+            Instr::MemoryFill => self.memory_fill()?,
+            Instr::TableInit(elem, table) => self.table_init(elem, table)?,
+            Instr::ElemDrop(ElemIdx(idx)) => {
+                let addr = self.instance().elem_addrs[*idx as usize];
+                self.elems[addr].elements = Vec::new();
+            }
+            Instr::TableCopy(dst, src) => self.table_copy(dst, src)?,
+            Instr::TableGrow(table) => {
+                let delta = self.pop::<i32>() as u32;
+                let init = self.stack.pop().expect("operand stack underflow");
+                let old_size = self.table(table).grow(delta, init);
+                self.push(old_size.map_or(-1, |size| size as i32));
+            }
+            Instr::TableSize(table) => {
+                let size = self.table(table).elements.len() as i32;
+                self.push(size);
+            }
+            Instr::TableFill(table) => self.table_fill(table)?,
+            Instr::RefNull(typ) => self.push(Value::default_for((*typ).into())),
+            Instr::RefIsNull => {
+                let value = self.stack.pop().expect("operand stack underflow");
+                self.push(value.is_null());
+            }
+            Instr::RefFunc(FuncIdx(idx)) => {
+                let addr = self.instance().func_addrs[*idx as usize];
+                self.push(Func(addr));
+            }
+            Instr::I32Const(v) => self.push(*v),
+            Instr::I64Const(v) => self.push(*v),
+            Instr::F32Const(v) => self.push(*v),
+            Instr::F64Const(v) => self.push(*v),
+            Instr::I32Eqz => self.unop(|a: i32| a == 0),
+            Instr::I32Eq => self.binop(|a: i32, b| a == b),
+            Instr::I32Ne => self.binop(|a: i32, b| a != b),
+            Instr::I32LtS => self.binop(|a: i32, b| a < b),
+            Instr::I32LtU => self.binop(|a: i32, b| (a as u32) < (b as u32)),
+            Instr::I32GtS => self.binop(|a: i32, b| a > b),
+            Instr::I32GtU => self.binop(|a: i32, b| (a as u32) > (b as u32)),
+            Instr::I32LeS => self.binop(|a: i32, b| a <= b),
+            Instr::I32LeU => self.binop(|a: i32, b| (a as u32) <= (b as u32)),
+            Instr::I32GeS => self.binop(|a: i32, b| a >= b),
+            Instr::I32GeU => self.binop(|a: i32, b| (a as u32) >= (b as u32)),
+            Instr::I64Eqz => self.unop(|a: i64| a == 0),
+            Instr::I64Eq => self.binop(|a: i64, b| a == b),
+            Instr::I64Ne => self.binop(|a: i64, b| a != b),
+            Instr::I64LtS => self.binop(|a: i64, b| a < b),
+            Instr::I64LtU => self.binop(|a: i64, b| (a as u64) < (b as u64)),
+            Instr::I64GtS => self.binop(|a: i64, b| a > b),
+            Instr::I64GtU => self.binop(|a: i64, b| (a as u64) > (b as u64)),
+            Instr::I64LeS => self.binop(|a: i64, b| a <= b),
+            Instr::I64LeU => self.binop(|a: i64, b| (a as u64) <= (b as u64)),
+            Instr::I64GeS => self.binop(|a: i64, b| a >= b),
+            Instr::I64GeU => self.binop(|a: i64, b| (a as u64) >= (b as u64)),
+            Instr::F32Eq => self.binop(|a: f32, b| a == b),
+            Instr::F32Ne => self.binop(|a: f32, b| a != b),
+            Instr::F32Lt => self.binop(|a: f32, b| a < b),
+            Instr::F32Gt => self.binop(|a: f32, b| a > b),
+            Instr::F32Le => self.binop(|a: f32, b| a <= b),
+            Instr::F32Ge => self.binop(|a: f32, b| a >= b),
+            Instr::F64Eq => self.binop(|a: f64, b| a == b),
+            Instr::F64Ne => self.binop(|a: f64, b| a != b),
+            Instr::F64Lt => self.binop(|a: f64, b| a < b),
+            Instr::F64Gt => self.binop(|a: f64, b| a > b),
+            Instr::F64Le => self.binop(|a: f64, b| a <= b),
+            Instr::F64Ge => self.binop(|a: f64, b| a >= b),
+            Instr::I32Clz => self.unop(|a: i32| a.leading_zeros() as i32),
+            Instr::I32Ctz => self.unop(|a: i32| a.trailing_zeros() as i32),
+            Instr::I32Popcnt => self.unop(|a: i32| a.count_ones() as i32),
+            Instr::I32Add => self.binop(|a: i32, b| a.wrapping_add(b)),
+            Instr::I32Sub => self.binop(|a: i32, b| a.wrapping_sub(b)),
+            Instr::I32Mul => self.binop(|a: i32, b| a.wrapping_mul(b)),
+            Instr::I32DivS => self.try_binop(|a: i32, b| {
+                if b == 0 {
+                    return Err(Trap::from(TrapKind::IntegerDivideByZero));
+                }
+                a.checked_div(b)
+                    .ok_or_else(|| Trap::from(TrapKind::IntegerOverflow))
+            })?,
+            Instr::I32DivU => self.try_binop(|a: i32, b| {
+                (a as u32)
+                    .checked_div(b as u32)
+                    .map(|r| r as i32)
+                    .ok_or_else(|| Trap::from(TrapKind::IntegerDivideByZero))
+            })?,
+            Instr::I32RemS => self.try_binop(|a: i32, b| {
+                if b == 0 {
+                    return Err(Trap::from(TrapKind::IntegerDivideByZero));
+                }
+                Ok(a.wrapping_rem(b))
+            })?,
+            Instr::I32RemU => self.try_binop(|a: i32, b| {
+                (a as u32)
+                    .checked_rem(b as u32)
+                    .map(|r| r as i32)
+                    .ok_or_else(|| Trap::from(TrapKind::IntegerDivideByZero))
+            })?,
+            Instr::I32And => self.binop(|a: i32, b| a & b),
+            Instr::I32Or => self.binop(|a: i32, b| a | b),
+            Instr::I32Xor => self.binop(|a: i32, b| a ^ b),
+            Instr::I32Shl => self.binop(|a: i32, b| a.wrapping_shl(b as u32)),
+            Instr::I32ShrS => self.binop(|a: i32, b| a.wrapping_shr(b as u32)),
+            Instr::I32ShrU => self.binop(|a: i32, b| (a as u32).wrapping_shr(b as u32) as i32),
+            Instr::I32Rotl => self.binop(|a: i32, b| a.rotate_left(b as u32)),
+            Instr::I32Rotr => self.binop(|a: i32, b| a.rotate_right(b as u32)),
+            Instr::I64Clz => self.unop(|a: i64| a.leading_zeros() as i64),
+            Instr::I64Ctz => self.unop(|a: i64| a.trailing_zeros() as i64),
+            Instr::I64Popcnt => self.unop(|a: i64| a.count_ones() as i64),
+            Instr::I64Add => self.binop(|a: i64, b| a.wrapping_add(b)),
+            Instr::I64Sub => self.binop(|a: i64, b| a.wrapping_sub(b)),
+            Instr::I64Mul => self.binop(|a: i64, b| a.wrapping_mul(b)),
+            Instr::I64DivS => self.try_binop(|a: i64, b| {
+                if b == 0 {
+                    return Err(Trap::from(TrapKind::IntegerDivideByZero));
+                }
+                a.checked_div(b)
+                    .ok_or_else(|| Trap::from(TrapKind::IntegerOverflow))
+            })?,
+            Instr::I64DivU => self.try_binop(|a: i64, b| {
+                (a as u64)
+                    .checked_div(b as u64)
+                    .map(|r| r as i64)
+                    .ok_or_else(|| Trap::from(TrapKind::IntegerDivideByZero))
+            })?,
+            Instr::I64RemS => self.try_binop(|a: i64, b| {
+                if b == 0 {
+                    return Err(Trap::from(TrapKind::IntegerDivideByZero));
+                }
+                Ok(a.wrapping_rem(b))
+            })?,
+            Instr::I64RemU => self.try_binop(|a: i64, b| {
+                (a as u64)
+                    .checked_rem(b as u64)
+                    .map(|r| r as i64)
+                    .ok_or_else(|| Trap::from(TrapKind::IntegerDivideByZero))
+            })?,
+            Instr::I64And => self.binop(|a: i64, b| a & b),
+            Instr::I64Or => self.binop(|a: i64, b| a | b),
+            Instr::I64Xor => self.binop(|a: i64, b| a ^ b),
+            Instr::I64Shl => self.binop(|a: i64, b| a.wrapping_shl(b as u32)),
+            Instr::I64ShrS => self.binop(|a: i64, b| a.wrapping_shr(b as u32)),
+            Instr::I64ShrU => self.binop(|a: i64, b| (a as u64).wrapping_shr(b as u32) as i64),
+            Instr::I64Rotl => self.binop(|a: i64, b| a.rotate_left(b as u32)),
+            Instr::I64Rotr => self.binop(|a: i64, b| a.rotate_right(b as u32)),
+            Instr::F32Abs => self.unop(|a: f32| a.abs()),
+            Instr::F32Neg => self.unop(|a: f32| -a),
+            Instr::F32Ceil => self.unop(|a: f32| a.ceil()),
+            Instr::F32Floor => self.unop(|a: f32| a.floor()),
+            Instr::F32Trunc => self.unop(|a: f32| a.trunc()),
+            Instr::F32Nearest => self.unop(|a: f32| a.round_ties_even()),
+            Instr::F32Sqrt => self.unop(|a: f32| a.sqrt()),
+            Instr::F32Add => self.binop(|a: f32, b| a + b),
+            Instr::F32Sub => self.binop(|a: f32, b| a - b),
+            Instr::F32Mul => self.binop(|a: f32, b| a * b),
+            Instr::F32Div => self.binop(|a: f32, b| a / b),
+            Instr::F32Min => self.binop(f32_min),
+            Instr::F32Max => self.binop(f32_max),
+            Instr::F32Copysign => self.binop(|a: f32, b| a.copysign(b)),
+            Instr::F64Abs => self.unop(|a: f64| a.abs()),
+            Instr::F64Neg => self.unop(|a: f64| -a),
+            Instr::F64Ceil => self.unop(|a: f64| a.ceil()),
+            Instr::F64Floor => self.unop(|a: f64| a.floor()),
+            Instr::F64Trunc => self.unop(|a: f64| a.trunc()),
+            Instr::F64Nearest => self.unop(|a: f64| a.round_ties_even()),
+            Instr::F64Sqrt => self.unop(|a: f64| a.sqrt()),
+            Instr::F64Add => self.binop(|a: f64, b| a + b),
+            Instr::F64Sub => self.binop(|a: f64, b| a - b),
+            Instr::F64Mul => self.binop(|a: f64, b| a * b),
+            Instr::F64Div => self.binop(|a: f64, b| a / b),
+            Instr::F64Min => self.binop(f64_min),
+            Instr::F64Max => self.binop(f64_max),
+            Instr::F64Copysign => self.binop(|a: f64, b| a.copysign(b)),
+            Instr::I32WrapI64 => self.unop(|a: i64| a as i32),
+            Instr::I32TruncF32S => self.try_unop(|a: f32| {
+                trunc(a as f64, -2147483649.0, 2147483648.0).map(|t| t as i32)
+            })?,
+            Instr::I32TruncF32U => self
+                .try_unop(|a: f32| trunc(a as f64, -1.0, 4294967296.0).map(|t| t as u32 as i32))?,
+            Instr::I32TruncF64S => {
+                self.try_unop(|a: f64| trunc(a, -2147483649.0, 2147483648.0).map(|t| t as i32))?
+            }
+            Instr::I32TruncF64U => {
+                self.try_unop(|a: f64| trunc(a, -1.0, 4294967296.0).map(|t| t as u32 as i32))?
+            }
+            Instr::I64ExtendI32S => self.unop(|a: i32| a as i64),
+            Instr::I64ExtendI32U => self.unop(|a: i32| a as u32 as i64),
+            Instr::I64TruncF32S => self.try_unop(|a: f32| {
+                trunc(a as f64, -9223372036854777856.0, 9223372036854775808.0).map(|t| t as i64)
+            })?,
+            Instr::I64TruncF32U => self.try_unop(|a: f32| {
+                trunc(a as f64, -1.0, 18446744073709551616.0).map(|t| t as u64 as i64)
+            })?,
+            Instr::I64TruncF64S => self.try_unop(|a: f64| {
+                trunc(a, -9223372036854777856.0, 9223372036854775808.0).map(|t| t as i64)
+            })?,
+            Instr::I64TruncF64U => self.try_unop(|a: f64| {
+                trunc(a, -1.0, 18446744073709551616.0).map(|t| t as u64 as i64)
+            })?,
+            Instr::F32ConvertI32S => self.unop(|a: i32| a as f32),
+            Instr::F32ConvertI32U => self.unop(|a: i32| a as u32 as f32),
+            Instr::F32ConvertI64S => self.unop(|a: i64| a as f32),
+            Instr::F32ConvertI64U => self.unop(|a: i64| a as u64 as f32),
+            Instr::F32DemoteF64 => self.unop(|a: f64| a as f32),
+            Instr::F64ConvertI32S => self.unop(|a: i32| a as f64),
+            Instr::F64ConvertI32U => self.unop(|a: i32| a as u32 as f64),
+            Instr::F64ConvertI64S => self.unop(|a: i64| a as f64),
+            Instr::F64ConvertI64U => self.unop(|a: i64| a as u64 as f64),
+            Instr::F64PromoteF32 => self.unop(|a: f32| a as f64),
+            Instr::I32ReinterpretF32 => self.unop(|a: f32| a.to_bits() as i32),
+            Instr::I64ReinterpretF64 => self.unop(|a: f64| a.to_bits() as i64),
+            Instr::F32ReinterpretI32 => self.unop(|a: i32| f32::from_bits(a as u32)),
+            Instr::F64ReinterpretI64 => self.unop(|a: i64| f64::from_bits(a as u64)),
+            Instr::I32Extend8S => self.unop(|a: i32| a as i8 as i32),
+            Instr::I32Extend16S => self.unop(|a: i32| a as i16 as i32),
+            Instr::I64Extend8S => self.unop(|a: i64| a as i8 as i64),
+            Instr::I64Extend16S => self.unop(|a: i64| a as i16 as i64),
+            Instr::I64Extend32S => self.unop(|a: i64| a as i32 as i64),
+            // Float-to-int `as` casts saturate and map NaN to zero, as trunc_sat requires.
+            Instr::I32TruncSatF32S => self.unop(|a: f32| a as i32),
+            Instr::I32TruncSatF32U => self.unop(|a: f32| a as u32 as i32),
+            Instr::I32TruncSatF64S => self.unop(|a: f64| a as i32),
+            Instr::I32TruncSatF64U => self.unop(|a: f64| a as u32 as i32),
+            Instr::I64TruncSatF32S => self.unop(|a: f32| a as i64),
+            Instr::I64TruncSatF32U => self.unop(|a: f32| a as u64 as i64),
+            Instr::I64TruncSatF64S => self.unop(|a: f64| a as i64),
+            Instr::I64TruncSatF64U => self.unop(|a: f64| a as u64 as i64),
+        }
+        Ok(())
+    }
+}
+
+// Truncates towards zero and checks that the result lies strictly between the
+// exclusive bounds `min` and `max`.
+fn trunc(a: f64, min: f64, max: f64) -> Result<f64, Trap> {
+    if a.is_nan() {
+        return Err(Trap::from(TrapKind::InvalidConversionToInteger));
+    }
+    let t = a.trunc();
+    if t <= min || t >= max {
+        return Err(Trap::from(TrapKind::IntegerOverflow));
+    }
+    Ok(t)
+}
+
+fn f32_min(a: f32, b: f32) -> f32 {
+    if a.is_nan() || b.is_nan() {
+        a + b
+    } else if a == b {
+        if a.is_sign_negative() {
+            a
+        } else {
+            b
+        }
+    } else {
+        a.min(b)
+    }
+}
+
+fn f32_max(a: f32, b: f32) -> f32 {
+    if a.is_nan() || b.is_nan() {
+        a + b
+    } else if a == b {
+        if a.is_sign_positive() {
+            a
+        } else {
+            b
+        }
+    } else {
+        a.max(b)
+    }
+}
+
+fn f64_min(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        a + b
+    } else if a == b {
+        if a.is_sign_negative() {
+            a
+        } else {
+            b
+        }
+    } else {
+        a.min(b)
+    }
+}
+
+fn f64_max(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        a + b
+    } else if a == b {
+        if a.is_sign_positive() {
+            a
+        } else {
+            b
+        }
+    } else {
+        a.max(b)
+    }
+}
@@ -0,0 +1,246 @@
+use crate::error::LinkError;
+use crate::module::{FuncType, GlobalType, MemType, Module, Mut, TableType};
+use crate::store::*;
+use crate::trap::{Trap, TrapKind};
+use crate::value::Value;
+use std::any::Any;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instance(pub(crate) ModuleAddr);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Func(pub(crate) FuncAddr);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Table(pub(crate) TableAddr);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Memory(pub(crate) MemAddr);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Global(pub(crate) GlobalAddr);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExternRef(pub(crate) ExternAddr);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extern {
+    Func(Func),
+    Table(Table),
+    Memory(Memory),
+    Global(Global),
+}
+
+impl Instance {
+    pub fn new(store: &mut Store, module: &Module, imports: &[Extern]) -> Result<Self, LinkError> {
+        store.instantiate(module, imports).map(Instance)
+    }
+
+    pub fn get_export(&self, store: &Store, name: &str) -> Option<Extern> {
+        store.instances[self.0].export(name)
+    }
+
+    pub fn get_func(&self, store: &Store, name: &str) -> Option<Func> {
+        match self.get_export(store, name)? {
+            Extern::Func(func) => Some(func),
+            _ => None,
+        }
+    }
+
+    pub fn invoke(
+        &self,
+        store: &mut Store,
+        name: &str,
+        args: &[Value],
+    ) -> Result<Vec<Value>, Trap> {
+        let func = self
+            .get_func(store, name)
+            .ok_or_else(|| Trap::from(TrapKind::UnknownExport(name.to_string())))?;
+        func.call(store, args)
+    }
+}
+
+pub struct Caller<'a> {
+    pub(crate) instance: Option<&'a ModuleInst>,
+    pub(crate) mems: &'a mut [MemInst],
+    pub(crate) globals: &'a mut [GlobalInst],
+}
+
+impl Caller<'_> {
+    pub fn get_export(&self, name: &str) -> Option<Extern> {
+        self.instance?.export(name)
+    }
+
+    pub fn memory_data(&self, memory: Memory) -> &[u8] {
+        &self.mems[memory.0].data
+    }
+
+    pub fn memory_data_mut(&mut self, memory: Memory) -> &mut [u8] {
+        &mut self.mems[memory.0].data
+    }
+
+    pub fn global_get(&self, global: Global) -> Value {
+        self.globals[global.0].value
+    }
+}
+
+impl Func {
+    pub fn new(
+        store: &mut Store,
+        typ: FuncType,
+        func: impl Fn(&mut Caller, &[Value]) -> Result<Vec<Value>, Trap> + 'static,
+    ) -> Self {
+        store.funcs.push(FuncInst::Host {
+            typ,
+            func: Box::new(func),
+        });
+        Func(store.funcs.len() - 1)
+    }
+
+    pub fn typ<'a>(&self, store: &'a Store) -> &'a FuncType {
+        store.funcs[self.0].typ()
+    }
+
+    pub fn call(&self, store: &mut Store, args: &[Value]) -> Result<Vec<Value>, Trap> {
+        store.invoke(self.0, args.to_vec())
+    }
+}
+
+impl Table {
+    pub fn new(store: &mut Store, typ: TableType) -> Self {
+        store.tables.push(TableInst::new(&typ));
+        Table(store.tables.len() - 1)
+    }
+
+    pub fn size(&self, store: &Store) -> u32 {
+        store.tables[self.0].elements.len() as u32
+    }
+
+    pub fn grow(&self, store: &mut Store, delta: u32, init: Value) -> Result<u32, String> {
+        let table = &mut store.tables[self.0];
+        Self::check_element(table, &init)?;
+        let size = table.elements.len();
+        table
+            .grow(delta, init)
+            .ok_or_else(|| format!("Cannot grow table of size {size} by {delta}"))
+    }
+
+    pub fn get(&self, store: &Store, idx: u32) -> Option<Value> {
+        store.tables[self.0].elements.get(idx as usize).copied()
+    }
+
+    pub fn set(&self, store: &mut Store, idx: u32, value: Value) -> Result<(), String> {
+        let table = &mut store.tables[self.0];
+        Self::check_element(table, &value)?;
+        let size = table.elements.len();
+        let element = table
+            .elements
+            .get_mut(idx as usize)
+            .ok_or_else(|| format!("Table index {idx} is out of bounds for size {size}"))?;
+        *element = value;
+        Ok(())
+    }
+
+    fn check_element(table: &TableInst, value: &Value) -> Result<(), String> {
+        if value.typ() != table.typ.into() {
+            return Err(format!(
+                "Table has element type {} but got {}",
+                table.typ,
+                value.typ()
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Memory {
+    pub fn new(store: &mut Store, typ: MemType) -> Self {
+        store.mems.push(MemInst::new(&typ, store.memory_limit));
+        Memory(store.mems.len() - 1)
+    }
+
+    pub fn size(&self, store: &Store) -> u32 {
+        store.mems[self.0].size()
+    }
+
+    pub fn grow(&self, store: &mut Store, delta: u32) -> Option<u32> {
+        store.mems[self.0].grow(delta)
+    }
+
+    pub fn data<'a>(&self, store: &'a Store) -> &'a [u8] {
+        &store.mems[self.0].data
+    }
+
+    pub fn data_mut<'a>(&self, store: &'a mut Store) -> &'a mut [u8] {
+        &mut store.mems[self.0].data
+    }
+}
+
+impl Global {
+    pub fn new(store: &mut Store, typ: GlobalType, value: Value) -> Result<Self, String> {
+        let GlobalType(mutability, val_type) = typ;
+        if value.typ() != val_type {
+            return Err(format!(
+                "Global has type {val_type:?} but got {:?}",
+                value.typ()
+            ));
+        }
+        store.globals.push(GlobalInst { value, mutability });
+        Ok(Global(store.globals.len() - 1))
+    }
+
+    pub fn get(&self, store: &Store) -> Value {
+        store.globals[self.0].value
+    }
+
+    pub fn set(&self, store: &mut Store, value: Value) -> Result<(), String> {
+        let global = &mut store.globals[self.0];
+        if global.mutability == Mut::Const {
+            return Err(String::from("Cannot set an immutable global"));
+        }
+        if global.value.typ() != value.typ() {
+            return Err(format!(
+                "Global has type {:?} but got {:?}",
+                global.value.typ(),
+                value.typ()
+            ));
+        }
+        global.value = value;
+        Ok(())
+    }
+}
+
+impl ExternRef {
+    pub fn new(store: &mut Store, data: impl Any) -> Self {
+        store.externs.push(Box::new(data));
+        ExternRef(store.externs.len() - 1)
+    }
+
+    pub fn data<'a>(&self, store: &'a Store) -> &'a dyn Any {
+        store.externs[self.0].as_ref()
+    }
+}
+
+impl From<Func> for Extern {
+    fn from(func: Func) -> Self {
+        Extern::Func(func)
+    }
+}
+
+impl From<Table> for Extern {
+    fn from(table: Table) -> Self {
+        Extern::Table(table)
+    }
+}
+
+impl From<Memory> for Extern {
+    fn from(memory: Memory) -> Self {
+        Extern::Memory(memory)
+    }
+}
+
+impl From<Global> for Extern {
+    fn from(global: Global) -> Self {
+        Extern::Global(global)
+    }
+}
@@ -1,31 +1,170 @@
-use crate::{config::Config, decoder::Decoder, module::Module, validator::Validator};
-use std::{fs, io::Read};
+use crate::{
+    codes,
+    config::{Command, Config},
+    decoder::Decoder,
+    error::{Error, InvokeError, LinkError},
+    linker::Linker,
+    module::{ExportDesc, FuncIdx, Module, ValType},
+    parser::{self, Parser},
+    printer::{self, Printer},
+    store::Store,
+    validator::Validator,
+    value::Value,
+    wasi::WasiCtx,
+};
+use std::fs;
 
 pub struct Interpreter;
 
 impl Interpreter {
-    pub fn run(config: Config) -> Result<(), String> {
-        let mut wasm_file = fs::File::open(&config.binary_path).unwrap_or_else(|_| {
-            panic!("Error reading file {}", config.binary_path);
-        });
-        let mut byte_code = Vec::new();
-        wasm_file.read_to_end(&mut byte_code).unwrap_or_else(|_| {
-            panic!("Error reading bytes from file {}", config.binary_path);
-        });
-        let module = Decoder::decode(&byte_code).unwrap_or_else(|err| {
-            panic!("Error decoding binary: {}", err);
-        });
-        Validator::validate(&module).unwrap_or_else(|err| {
-            panic!("Error validating module: {}", err);
-        });
-        Self.interpret(module).unwrap_or_else(|err| {
-            panic!("Error interpreting binary: {}", err);
-        });
+    pub fn run(config: Config) -> Result<i32, Error> {
+        let io_error = |source| Error::Io {
+            path: config.binary_path.clone(),
+            source,
+        };
+        let (module, byte_code) = if config.binary_path.ends_with(".wat") {
+            let text = fs::read_to_string(&config.binary_path).map_err(io_error)?;
+            (Parser::parse(&text)?, None)
+        } else {
+            let byte_code = fs::read(&config.binary_path).map_err(io_error)?;
+            (Decoder::decode(&byte_code)?, Some(byte_code))
+        };
+        match config.command {
+            Command::Print => {
+                print!("{}", module.to_wat());
+                Ok(0)
+            }
+            Command::Inspect => {
+                Validator::validate(&module)?;
+                let byte_code = byte_code.unwrap_or_else(|| module.to_bytes());
+                Self::inspect(&module, &byte_code)?;
+                Ok(0)
+            }
+            Command::Validate => {
+                Validator::validate(&module)?;
+                println!("{}: valid", config.binary_path);
+                Ok(0)
+            }
+            Command::Run | Command::Invoke => {
+                Validator::validate(&module)?;
+                Self.interpret(module, &config)
+            }
+        }
+    }
+
+    fn inspect(module: &Module, byte_code: &[u8]) -> Result<(), Error> {
+        println!("version {}, {} bytes", module.version, byte_code.len());
+        println!("sections:");
+        let mut customs = module.customs.iter();
+        for (section_code, size) in Decoder::section_sizes(byte_code)? {
+            let (name, entries) = match section_code {
+                codes::section::CUSTOM => {
+                    let name = customs.next().map_or("", |custom| custom.name.as_str());
+                    println!("  {:<10}{size:>8} bytes  {name:?}", "custom");
+                    continue;
+                }
+                codes::section::TYPE => ("type", module.types.len()),
+                codes::section::IMPORT => ("import", module.imports.len()),
+                codes::section::FUNCTION => ("function", module.funcs.len()),
+                codes::section::TABLE => ("table", module.table.len()),
+                codes::section::MEMORY => ("memory", module.memory.len()),
+                codes::section::GLOBAL => ("global", module.globals.len()),
+                codes::section::EXPORT => ("export", module.exports.len()),
+                codes::section::START => ("start", 1),
+                codes::section::ELEMENT => ("element", module.elem.len()),
+                codes::section::DATA_COUNT => ("datacount", 1),
+                codes::section::CODE => ("code", module.funcs.len()),
+                codes::section::DATA => ("data", module.data.len()),
+                _ => ("unknown", 0),
+            };
+            println!("  {name:<10}{size:>8} bytes  {entries} entries");
+        }
+        println!("imports:");
+        for import in &module.imports {
+            let desc = Printer::import_desc(module, &import.desc);
+            println!("  {}.{}: {desc}", import.module, import.name);
+        }
+        println!("exports:");
+        for export in &module.exports {
+            let desc = match &export.desc {
+                ExportDesc::Func(idx) => {
+                    let typ = module.func_type(idx.0).expect("validated function index");
+                    format!("func {}{}", idx.0, printer::signature(typ, &[]))
+                }
+                ExportDesc::Table(idx) => format!("table {}", idx.0),
+                ExportDesc::Mem(idx) => format!("memory {}", idx.0),
+                ExportDesc::Global(idx) => format!("global {}", idx.0),
+            };
+            println!("  {}: {desc}", export.name);
+        }
         Ok(())
     }
 
-    fn interpret(&self, module: Module) -> Result<(), String> {
-        dbg!(module);
-        todo!()
+    fn interpret(&self, module: Module, config: &Config) -> Result<i32, Error> {
+        let mut store = Store::default();
+        store.set_fuel(config.fuel);
+        store.set_trace(config.trace);
+        store.set_memory_limit(config.max_memory);
+        let mut linker = Linker::new();
+        let mut wasi = WasiCtx::new(config.args.clone(), config.env.clone());
+        for (host, guest) in &config.dirs {
+            wasi.preopen_dir(host, guest);
+        }
+        wasi.add_to_linker(&mut linker, &mut store);
+        WasiCtx::add_stubs(&mut linker, &mut store, &module);
+        let instance = match linker.instantiate(&mut store, &module) {
+            Ok(instance) => instance,
+            Err(LinkError::StartTrap(trap)) => {
+                return trap
+                    .exit_code()
+                    .ok_or(Error::Link(LinkError::StartTrap(trap)))
+            }
+            Err(err) => return Err(err.into()),
+        };
+        // A module whose start function is all there is to run is done
+        // once it has been instantiated, anything else needs an export.
+        let name = match config.invoke.as_deref() {
+            Some(name) => name,
+            None if module.exports.iter().any(|export| export.name == "_start") => "_start",
+            None if module.start.is_some() => return Ok(0),
+            None => return Err(InvokeError::NoEntryPoint.into()),
+        };
+        let export = module
+            .exports
+            .iter()
+            .find(|export| export.name == name)
+            .ok_or_else(|| InvokeError::UnknownExport(name.to_owned()))?;
+        let ExportDesc::Func(FuncIdx(idx)) = export.desc else {
+            return Err(InvokeError::NotAFunction(name.to_owned()).into());
+        };
+        let typ = module.func_type(idx).expect("validated function index");
+        let args = Self::arguments(&typ.params, &config.invoke_args)?;
+        let results = match instance.invoke(&mut store, name, &args) {
+            Ok(results) => results,
+            Err(trap) => return trap.exit_code().ok_or(Error::Trap(trap)),
+        };
+        for result in results {
+            println!("{result}");
+        }
+        Ok(0)
+    }
+
+    fn arguments(params: &[ValType], args: &[String]) -> Result<Vec<Value>, InvokeError> {
+        if params.len() != args.len() {
+            return Err(InvokeError::ArgumentCount {
+                expected: params.len(),
+                actual: args.len(),
+            });
+        }
+        params
+            .iter()
+            .zip(args)
+            .map(|(typ, arg)| {
+                parser::value(arg, *typ).ok_or_else(|| InvokeError::InvalidArgument {
+                    arg: arg.clone(),
+                    typ: *typ,
+                })
+            })
+            .collect()
     }
 }
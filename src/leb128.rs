@@ -0,0 +1,247 @@
+use crate::error::DecodeErrorKind;
+
+pub(crate) type LebResult<T> = Result<(T, usize), (usize, DecodeErrorKind)>;
+
+pub(crate) fn read_unsigned(bytes: &[u8], bits: u32) -> LebResult<u64> {
+    let max_len = bits.div_ceil(7) as usize;
+    let mut result = 0_u64;
+    for i in 0..max_len {
+        let byte = *bytes.get(i).ok_or((i, DecodeErrorKind::UnexpectedEnd))?;
+        let shift = 7 * i as u32;
+        let payload = u64::from(byte & 0x7f);
+        if i == max_len - 1 {
+            if byte & 0x80 != 0 {
+                return Err((i, DecodeErrorKind::IntegerTooLong));
+            }
+            let remaining = bits - shift;
+            if remaining < 7 && payload >> remaining != 0 {
+                return Err((i, DecodeErrorKind::IntegerTooLarge));
+            }
+        }
+        result |= payload << shift;
+        if byte & 0x80 == 0 {
+            return Ok((result, i + 1));
+        }
+    }
+    unreachable!("the last byte always terminates the loop")
+}
+
+pub(crate) fn read_signed(bytes: &[u8], bits: u32) -> LebResult<i64> {
+    let max_len = bits.div_ceil(7) as usize;
+    let mut result = 0_i64;
+    for i in 0..max_len {
+        let byte = *bytes.get(i).ok_or((i, DecodeErrorKind::UnexpectedEnd))?;
+        let shift = 7 * i as u32;
+        let payload = byte & 0x7f;
+        if i == max_len - 1 {
+            if byte & 0x80 != 0 {
+                return Err((i, DecodeErrorKind::IntegerTooLong));
+            }
+            let remaining = bits - shift;
+            if remaining < 7 {
+                let sign_and_unused = payload >> (remaining - 1);
+                if sign_and_unused != 0 && sign_and_unused != 0x7f >> (remaining - 1) {
+                    return Err((i, DecodeErrorKind::IntegerTooLarge));
+                }
+            }
+        }
+        result |= i64::from(payload) << shift;
+        if byte & 0x80 == 0 {
+            let shift = shift + 7;
+            if shift < 64 && payload & 0x40 != 0 {
+                result |= -1_i64 << shift;
+            }
+            return Ok((result, i + 1));
+        }
+    }
+    unreachable!("the last byte always terminates the loop")
+}
+
+pub(crate) fn write_unsigned(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+pub(crate) fn write_signed(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_unsigned(value: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_unsigned(&mut bytes, value);
+        bytes
+    }
+
+    fn encode_signed(value: i64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_signed(&mut bytes, value);
+        bytes
+    }
+
+    #[test]
+    fn encodings_are_canonical() {
+        assert_eq!(encode_unsigned(0), [0x00]);
+        assert_eq!(encode_unsigned(127), [0x7f]);
+        assert_eq!(encode_unsigned(128), [0x80, 0x01]);
+        assert_eq!(
+            encode_unsigned(u32::MAX.into()),
+            [0xff, 0xff, 0xff, 0xff, 0x0f]
+        );
+        assert_eq!(encode_signed(-1), [0x7f]);
+        assert_eq!(encode_signed(63), [0x3f]);
+        assert_eq!(encode_signed(64), [0xc0, 0x00]);
+        assert_eq!(encode_signed(-65), [0xbf, 0x7f]);
+        assert_eq!(
+            encode_signed(i32::MIN.into()),
+            [0x80, 0x80, 0x80, 0x80, 0x78]
+        );
+    }
+
+    fn random_values(count: usize) -> impl Iterator<Item = u64> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        (0..count).map(move |_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state >> (state % 64)
+        })
+    }
+
+    #[test]
+    fn unsigned_round_trip() {
+        for value in random_values(10_000) {
+            let bytes = encode_unsigned(value);
+            assert_eq!(read_unsigned(&bytes, 64), Ok((value, bytes.len())));
+            let value = value as u32 as u64;
+            let bytes = encode_unsigned(value);
+            assert_eq!(read_unsigned(&bytes, 32), Ok((value, bytes.len())));
+        }
+    }
+
+    #[test]
+    fn signed_round_trip() {
+        for value in random_values(10_000) {
+            for value in [value as i64, -(value as i64)] {
+                let bytes = encode_signed(value);
+                assert_eq!(read_signed(&bytes, 64), Ok((value, bytes.len())));
+                let value = value as i32 as i64;
+                let bytes = encode_signed(value);
+                assert_eq!(read_signed(&bytes, 32), Ok((value, bytes.len())));
+                let value = value << 31 >> 31;
+                let bytes = encode_signed(value);
+                assert_eq!(read_signed(&bytes, 33), Ok((value, bytes.len())));
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        for value in random_values(10_000).filter(|value| *value > u32::MAX as u64) {
+            let bytes = encode_unsigned(value);
+            assert!(read_unsigned(&bytes, 32).is_err());
+        }
+        for value in random_values(10_000).map(|value| value as i64) {
+            let bytes = encode_signed(value);
+            let fits = i32::try_from(value).is_ok();
+            assert_eq!(read_signed(&bytes, 32).is_ok(), fits);
+        }
+    }
+
+    #[test]
+    fn boundaries() {
+        assert_eq!(
+            read_unsigned(&[0xff, 0xff, 0xff, 0xff, 0x0f], 32),
+            Ok((u32::MAX as u64, 5))
+        );
+        assert_eq!(
+            read_signed(&[0x80, 0x80, 0x80, 0x80, 0x78], 32),
+            Ok((i32::MIN as i64, 5))
+        );
+        assert_eq!(
+            read_signed(&[0xff, 0xff, 0xff, 0xff, 0x07], 32),
+            Ok((i32::MAX as i64, 5))
+        );
+        assert_eq!(read_signed(&[0x7f], 33), Ok((-1, 1)));
+        assert_eq!(
+            read_signed(&[0xff, 0xff, 0xff, 0xff, 0x0f], 33),
+            Ok(((1 << 32) - 1, 5))
+        );
+        assert_eq!(
+            read_signed(&[0x80, 0x80, 0x80, 0x80, 0x70], 33),
+            Ok((-(1 << 32), 5))
+        );
+        assert_eq!(
+            read_signed(&[0x80, 0x80, 0x80, 0x80, 0x10], 33),
+            Err((4, DecodeErrorKind::IntegerTooLarge))
+        );
+        let min = [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7f];
+        assert_eq!(read_signed(&min, 64), Ok((i64::MIN, 10)));
+        let max = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+        assert_eq!(read_signed(&max, 64), Ok((i64::MAX, 10)));
+    }
+
+    #[test]
+    fn overlong_encodings_are_rejected() {
+        assert_eq!(
+            read_unsigned(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x00], 32),
+            Err((4, DecodeErrorKind::IntegerTooLong))
+        );
+        assert_eq!(
+            read_signed(&[0xff, 0xff, 0xff, 0xff, 0xff, 0x7f], 32),
+            Err((4, DecodeErrorKind::IntegerTooLong))
+        );
+        assert_eq!(read_unsigned(&[0x80, 0x00], 32), Ok((0, 2)));
+    }
+
+    #[test]
+    fn unused_bits_must_match() {
+        assert_eq!(
+            read_unsigned(&[0x80, 0x80, 0x80, 0x80, 0x10], 32),
+            Err((4, DecodeErrorKind::IntegerTooLarge))
+        );
+        assert_eq!(
+            read_signed(&[0x80, 0x80, 0x80, 0x80, 0x70], 32),
+            Err((4, DecodeErrorKind::IntegerTooLarge))
+        );
+        assert_eq!(
+            read_signed(&[0xff, 0xff, 0xff, 0xff, 0x0f], 32),
+            Err((4, DecodeErrorKind::IntegerTooLarge))
+        );
+        let bytes = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        assert_eq!(
+            read_signed(&bytes, 64),
+            Err((9, DecodeErrorKind::IntegerTooLarge))
+        );
+    }
+
+    #[test]
+    fn truncated_input_is_rejected() {
+        assert_eq!(
+            read_unsigned(&[0x80, 0x80], 32),
+            Err((2, DecodeErrorKind::UnexpectedEnd))
+        );
+        assert_eq!(
+            read_signed(&[], 64),
+            Err((0, DecodeErrorKind::UnexpectedEnd))
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use crate::error::{ParseError, ParseErrorKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenKind<'a> {
+    LParen,
+    RParen,
+    Atom(&'a str),
+    Id(&'a str),
+    String(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Token<'a> {
+    pub kind: TokenKind<'a>,
+    pub offset: usize,
+}
+
+pub(crate) struct Lexer<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn tokenize(source: &'a str) -> Result<Vec<Token<'a>>, ParseError> {
+        let mut lexer = Lexer {
+            source,
+            position: 0,
+        };
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token()? {
+            tokens.push(token);
+        }
+        Ok(tokens)
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token<'a>>, ParseError> {
+        self.skip_whitespace_and_comments()?;
+        let offset = self.position;
+        let Some(byte) = self.peek() else {
+            return Ok(None);
+        };
+        let kind = match byte {
+            b'(' => {
+                self.position += 1;
+                TokenKind::LParen
+            }
+            b')' => {
+                self.position += 1;
+                TokenKind::RParen
+            }
+            b'"' => TokenKind::String(self.string()?),
+            b'$' => {
+                self.position += 1;
+                let name = self.idchars();
+                if name.is_empty() {
+                    return Err(self.error(offset, ParseErrorKind::EmptyId));
+                }
+                TokenKind::Id(name)
+            }
+            byte if is_idchar(byte) => TokenKind::Atom(self.idchars()),
+            _ => {
+                let c = self.source[offset..].chars().next().unwrap_or_default();
+                return Err(self.error(offset, ParseErrorKind::UnexpectedChar(c)));
+            }
+        };
+        Ok(Some(Token { kind, offset }))
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), ParseError> {
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(b' ' | b'\t' | b'\n' | b'\r'), _) => self.position += 1,
+                (Some(b';'), Some(b';')) => {
+                    while !matches!(self.peek(), None | Some(b'\n')) {
+                        self.position += 1;
+                    }
+                }
+                (Some(b'('), Some(b';')) => self.block_comment()?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn block_comment(&mut self) -> Result<(), ParseError> {
+        let start = self.position;
+        let mut depth = 0;
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(b'('), Some(b';')) => {
+                    depth += 1;
+                    self.position += 2;
+                }
+                (Some(b';'), Some(b')')) => {
+                    depth -= 1;
+                    self.position += 2;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                (Some(_), _) => self.position += 1,
+                (None, _) => return Err(self.error(start, ParseErrorKind::UnterminatedComment)),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<Vec<u8>, ParseError> {
+        let start = self.position;
+        self.position += 1;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(byte) = self.peek() else {
+                return Err(self.error(start, ParseErrorKind::UnterminatedString));
+            };
+            self.position += 1;
+            match byte {
+                b'"' => return Ok(bytes),
+                b'\\' => self.escape(&mut bytes)?,
+                b'\n' => return Err(self.error(start, ParseErrorKind::UnterminatedString)),
+                byte => bytes.push(byte),
+            }
+        }
+    }
+
+    fn escape(&mut self, bytes: &mut Vec<u8>) -> Result<(), ParseError> {
+        let start = self.position - 1;
+        let invalid = |lexer: &Self| lexer.error(start, ParseErrorKind::InvalidEscape);
+        let byte = self.peek().ok_or_else(|| invalid(self))?;
+        self.position += 1;
+        match byte {
+            b't' => bytes.push(b'\t'),
+            b'n' => bytes.push(b'\n'),
+            b'r' => bytes.push(b'\r'),
+            b'"' => bytes.push(b'"'),
+            b'\'' => bytes.push(b'\''),
+            b'\\' => bytes.push(b'\\'),
+            b'u' => {
+                if self.peek() != Some(b'{') {
+                    return Err(invalid(self));
+                }
+                let end = self.source[self.position..]
+                    .find('}')
+                    .ok_or_else(|| invalid(self))?;
+                let digits = &self.source[self.position + 1..self.position + end];
+                let c = u32::from_str_radix(&digits.replace('_', ""), 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| invalid(self))?;
+                bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                self.position += end + 1;
+            }
+            high => {
+                let low = self.peek().ok_or_else(|| invalid(self))?;
+                let (Some(high), Some(low)) = (hex_digit(high), hex_digit(low)) else {
+                    return Err(invalid(self));
+                };
+                self.position += 1;
+                bytes.push(high << 4 | low);
+            }
+        }
+        Ok(())
+    }
+
+    fn idchars(&mut self) -> &'a str {
+        let start = self.position;
+        while self.peek().is_some_and(is_idchar) {
+            self.position += 1;
+        }
+        &self.source[start..self.position]
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, n: usize) -> Option<u8> {
+        self.source.as_bytes().get(self.position + n).copied()
+    }
+
+    fn error(&self, offset: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(self.source, offset, kind)
+    }
+}
+
+pub(crate) fn is_idchar(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$%&'*+-./:<=>?@\\^_`|~".contains(&byte)
+}
+
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
@@ -1,6 +1,32 @@
-pub mod interpreter;
+mod codes;
 pub mod config;
 mod decoder;
+mod encoder;
+mod error;
+mod executor;
+mod instance;
+pub mod interpreter;
+mod leb128;
+mod lexer;
+mod linker;
+pub mod module;
+mod parser;
+mod printer;
+mod store;
+mod trap;
 mod validator;
-mod module;
-mod codes;
+mod value;
+pub mod wasi;
+
+pub use decoder::StreamingDecoder;
+pub use encoder::Encoder;
+pub use error::{
+    DecodeError, DecodeErrorKind, Error, InvokeError, LinkError, ParseError, ParseErrorKind,
+    ValidationError, ValidationErrorKind,
+};
+pub use instance::{Caller, Extern, ExternRef, Func, Global, Instance, Memory, Table};
+pub use linker::Linker;
+pub use module::Module;
+pub use store::Store;
+pub use trap::{FrameInfo, Trap, TrapKind};
+pub use value::Value;
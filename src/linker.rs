@@ -0,0 +1,64 @@
+use crate::error::LinkError;
+use crate::instance::{Caller, Extern, Func, Instance};
+use crate::module::{FuncType, Module};
+use crate::store::Store;
+use crate::trap::Trap;
+use crate::value::Value;
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct Linker {
+    definitions: HashMap<(String, String), Extern>,
+}
+
+impl Linker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn define(&mut self, module: &str, name: &str, item: impl Into<Extern>) -> &mut Self {
+        self.definitions
+            .insert((module.to_string(), name.to_string()), item.into());
+        self
+    }
+
+    pub fn func(
+        &mut self,
+        store: &mut Store,
+        module: &str,
+        name: &str,
+        typ: FuncType,
+        func: impl Fn(&mut Caller, &[Value]) -> Result<Vec<Value>, Trap> + 'static,
+    ) -> &mut Self {
+        let func = Func::new(store, typ, func);
+        self.define(module, name, func)
+    }
+
+    pub fn instance(&mut self, store: &Store, module: &str, instance: Instance) -> &mut Self {
+        for export in &store.instances[instance.0].exports {
+            self.define(module, &export.name, export.value);
+        }
+        self
+    }
+
+    pub fn get(&self, module: &str, name: &str) -> Option<Extern> {
+        self.definitions
+            .get(&(module.to_string(), name.to_string()))
+            .copied()
+    }
+
+    pub fn instantiate(&self, store: &mut Store, module: &Module) -> Result<Instance, LinkError> {
+        let imports = module
+            .imports
+            .iter()
+            .map(|import| {
+                self.get(&import.module, &import.name)
+                    .ok_or_else(|| LinkError::UnknownImport {
+                        module: import.module.clone(),
+                        name: import.name.clone(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Instance::new(store, module, &imports)
+    }
+}
@@ -7,8 +7,11 @@ fn main() {
         println!("Problem parsing arguments: {err}");
         process::exit(1);
     });
-    Interpreter::run(config).unwrap_or_else(|err| {
-        println!("Problem interpreting binary {err}");
-        process::exit(1);
+    let code = Interpreter::run(config).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(err.exit_code());
     });
+    if code != 0 {
+        process::exit(code);
+    }
 }
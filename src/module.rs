@@ -1,4 +1,19 @@
-#[derive(Default, Debug)]
+use crate::{
+    decoder::Decoder,
+    encoder::Encoder,
+    error::{Error, ValidationError},
+    parser::Parser,
+    printer::Printer,
+    validator::Validator,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+// Instruction bodies are trees, deeper nesting than this is rejected by the
+// decoder and the text parser so that walking them can't exhaust the stack.
+pub const MAX_NESTING_DEPTH: usize = 1024;
+
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct Module {
     pub version: i32,
     pub types: TypesComponent,
@@ -8,9 +23,56 @@ pub struct Module {
     pub globals: GlobalsComponent,
     pub elem: ElemComponent,
     pub data: DataComponent,
+    pub data_count: Option<u32>,
     pub start: StartComponent,
     pub imports: ImportsComponent,
     pub exports: ExportsComponent,
+    pub customs: CustomsComponent,
+    pub names: Names,
+}
+
+impl Module {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let module = Decoder::decode(bytes)?;
+        Validator::validate(&module)?;
+        Ok(module)
+    }
+
+    pub fn from_wat(text: &str) -> Result<Self, Error> {
+        let module = Parser::parse(text)?;
+        Validator::validate(&module)?;
+        Ok(module)
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        Validator::validate(self)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        Encoder::encode(self)
+    }
+
+    pub fn to_wat(&self) -> String {
+        Printer::print(self)
+    }
+
+    pub fn func_type(&self, idx: u32) -> Option<&FuncType> {
+        let imported = self.imports.iter().filter_map(|import| match &import.desc {
+            ImpExportDesc::Func(typ) => Some(typ),
+            _ => None,
+        });
+        let TypeIdx(typ) = imported
+            .chain(self.funcs.iter().map(|func| &func.typ))
+            .nth(idx as usize)?;
+        self.types.get(*typ as usize)
+    }
+
+    pub fn custom_sections<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a [u8]> + 'a {
+        self.customs
+            .iter()
+            .filter(move |custom| custom.name == name)
+            .map(|custom| custom.bytes.as_slice())
+    }
 }
 
 pub type TypesComponent = Vec<FuncType>;
@@ -23,104 +85,178 @@ pub type DataComponent = Vec<Data>;
 pub type StartComponent = Option<Start>;
 pub type ImportsComponent = Vec<Import>;
 pub type ExportsComponent = Vec<Export>;
+pub type CustomsComponent = Vec<Custom>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TypeIdx(pub u32);
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FuncIdx(pub u32);
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TableIdx(pub u32);
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MemIdx(pub u32);
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GlobalIdx(pub u32);
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LocalIdx(pub u32);
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LabelIdx(pub u32);
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElemIdx(pub u32);
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataIdx(pub u32);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FuncType {
     pub params: Vec<ValType>,
     pub results: Vec<ValType>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Function {
     pub typ: TypeIdx,
     pub locals: Vec<ValType>,
     pub body: Expr,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Table {
     pub typ: TableType,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TableType(pub Limits, pub ElemType);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Limits {
     pub min: u32,
     pub max: Option<u32>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ElemType {
     FuncRef,
+    ExternRef,
 }
 
-#[derive(Debug)]
+impl From<ElemType> for ValType {
+    fn from(typ: ElemType) -> Self {
+        match typ {
+            ElemType::FuncRef => ValType::FuncRef,
+            ElemType::ExternRef => ValType::ExternRef,
+        }
+    }
+}
+
+impl fmt::Display for ElemType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        ValType::from(*self).fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Mem {
     pub typ: MemType,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MemType(pub Limits);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Global {
     pub typ: GlobalType,
     pub init: Expr,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GlobalType(pub Mut, pub ValType);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mut {
     Var,
     Const,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Elem {
-    pub table: TableIdx,
-    pub offset: Expr,
-    pub init: Vec<FuncIdx>,
+    pub typ: ElemType,
+    pub mode: ElemMode,
+    pub init: Vec<Expr>,
 }
 
-#[derive(Debug)]
+impl Elem {
+    // The function indices of a segment that only holds `ref.func` items,
+    // which the binary and text formats can write in a compact form.
+    pub(crate) fn func_indices(&self) -> Option<Vec<u32>> {
+        if self.typ != ElemType::FuncRef {
+            return None;
+        }
+        self.init
+            .iter()
+            .map(|Expr(instrs, _)| match instrs[..] {
+                [Instr::RefFunc(FuncIdx(idx))] => Some(idx),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ElemMode {
+    Passive,
+    Active { table: TableIdx, offset: Expr },
+    Declarative,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Data {
-    pub data: MemIdx,
-    pub offset: Expr,
+    pub mode: DataMode,
     pub init: Vec<u8>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataMode {
+    Passive,
+    Active { memory: MemIdx, offset: Expr },
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Start {
     pub func: FuncIdx,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Custom {
+    pub name: String,
+    pub bytes: Vec<u8>,
+    pub after_section: Option<u8>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Names {
+    pub module: Option<String>,
+    pub funcs: HashMap<u32, String>,
+    pub locals: HashMap<u32, HashMap<u32, String>>,
+}
+
+impl Names {
+    pub fn func(&self, idx: u32) -> Option<&str> {
+        self.funcs.get(&idx).map(String::as_str)
+    }
+
+    pub fn local(&self, func: u32, idx: u32) -> Option<&str> {
+        self.locals.get(&func)?.get(&idx).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Import {
     pub module: String,
     pub name: String,
     pub desc: ImpExportDesc,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ImpExportDesc {
     Func(TypeIdx),
     Table(TableType),
@@ -128,52 +264,88 @@ pub enum ImpExportDesc {
     Global(GlobalType),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Export {
     pub name: String,
-    pub desc: ImpExportDesc,
+    pub desc: ExportDesc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportDesc {
+    Func(FuncIdx),
+    Table(TableIdx),
+    Mem(MemIdx),
+    Global(GlobalIdx),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ValType {
     I32,
     I64,
     F32,
     F64,
+    FuncRef,
+    ExternRef,
 }
 
-#[derive(Debug)]
-pub struct ResultType(pub Option<ValType>);
+impl ValType {
+    pub fn is_ref(&self) -> bool {
+        matches!(self, ValType::FuncRef | ValType::ExternRef)
+    }
+}
+
+impl fmt::Display for ValType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValType::I32 => write!(f, "i32"),
+            ValType::I64 => write!(f, "i64"),
+            ValType::F32 => write!(f, "f32"),
+            ValType::F64 => write!(f, "f64"),
+            ValType::FuncRef => write!(f, "funcref"),
+            ValType::ExternRef => write!(f, "externref"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockType {
+    Empty,
+    Value(ValType),
+    Type(TypeIdx),
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MemArg {
     pub offset: u32,
     pub align: u32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Expr(pub Vec<Instr>, pub End);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Instr {
     Unreachable,
     Nop,
-    Block(ResultType, Vec<Instr>, End),
-    Loop(ResultType, Vec<Instr>, End),
-    If(ResultType, Vec<Instr>, Else, Vec<Instr>, End),
+    Block(BlockType, Vec<Instr>, End),
+    Loop(BlockType, Vec<Instr>, End),
+    If(BlockType, Vec<Instr>, Else, Vec<Instr>, End),
     Br(LabelIdx),
     BrIf(LabelIdx),
     BrTable(Vec<LabelIdx>, LabelIdx),
     Return,
     Call(FuncIdx),
-    CallIndirect(TypeIdx),
+    CallIndirect(TypeIdx, TableIdx),
     Drop,
     Select,
+    TypedSelect(Vec<ValType>),
     LocalGet(LocalIdx),
     LocalSet(LocalIdx),
     LocalTee(LocalIdx),
     GlobalGet(GlobalIdx),
     GlobalSet(GlobalIdx),
+    TableGet(TableIdx),
+    TableSet(TableIdx),
     I32Load(MemArg),
     I64Load(MemArg),
     F32Load(MemArg),
@@ -199,6 +371,19 @@ pub enum Instr {
     I64Store32(MemArg),
     MemorySize,
     MemoryGrow,
+    MemoryInit(DataIdx),
+    DataDrop(DataIdx),
+    MemoryCopy,
+    MemoryFill,
+    TableInit(ElemIdx, TableIdx),
+    ElemDrop(ElemIdx),
+    TableCopy(TableIdx, TableIdx),
+    TableGrow(TableIdx),
+    TableSize(TableIdx),
+    TableFill(TableIdx),
+    RefNull(ElemType),
+    RefIsNull,
+    RefFunc(FuncIdx),
     I32Const(i32),
     I64Const(i64),
     F32Const(f32),
@@ -326,10 +511,23 @@ pub enum Instr {
     I64ReinterpretF64,
     F32ReinterpretI32,
     F64ReinterpretI64,
+    I32Extend8S,
+    I32Extend16S,
+    I64Extend8S,
+    I64Extend16S,
+    I64Extend32S,
+    I32TruncSatF32S,
+    I32TruncSatF32U,
+    I32TruncSatF64S,
+    I32TruncSatF64U,
+    I64TruncSatF32S,
+    I64TruncSatF32U,
+    I64TruncSatF64S,
+    I64TruncSatF64U,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Else;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct End;
@@ -0,0 +1,1696 @@
+use crate::error::{ParseError, ParseErrorKind};
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::module::*;
+use crate::store::PAGE_SIZE;
+use crate::value::Value;
+use std::collections::HashMap;
+use std::mem;
+
+pub(crate) struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token<'a>>,
+    position: usize,
+    types: Space<'a>,
+    funcs: Space<'a>,
+    tables: Space<'a>,
+    mems: Space<'a>,
+    globals: Space<'a>,
+    elems: Space<'a>,
+    datas: Space<'a>,
+    module: Module,
+    uses_data_count: bool,
+}
+
+struct Space<'a> {
+    kind: &'static str,
+    names: HashMap<&'a str, u32>,
+    count: u32,
+    next: u32,
+}
+
+type MemoryInstr = fn(MemArg) -> Instr;
+
+type SpaceOf<'a> = for<'p> fn(&'p mut Parser<'a>) -> &'p mut Space<'a>;
+
+#[derive(Default)]
+struct Body<'a> {
+    locals: HashMap<&'a str, u32>,
+    labels: Vec<Option<&'a str>>,
+}
+
+// A block or folded instruction whose closing token hasn't been reached yet.
+// The ones that start a new body hold on to the enclosing instructions in
+// `outer`; a folded `if` only does so once its condition has been read.
+enum Open<'a> {
+    Block {
+        name: &'a str,
+        label: Option<&'a str>,
+        typ: BlockType,
+        outer: Vec<Instr>,
+        consequent: Option<Vec<Instr>>,
+    },
+    FoldedBlock {
+        name: &'a str,
+        typ: BlockType,
+        outer: Vec<Instr>,
+    },
+    FoldedIf {
+        label: Option<&'a str>,
+        typ: BlockType,
+        outer: Option<Vec<Instr>>,
+        consequent: Option<Vec<Instr>>,
+    },
+    Operands(Instr),
+}
+
+impl<'a> Space<'a> {
+    fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            names: HashMap::new(),
+            count: 0,
+            next: 0,
+        }
+    }
+
+    fn define(&mut self, id: Option<&'a str>) -> Result<u32, ParseErrorKind> {
+        let idx = self.count;
+        self.count += 1;
+        if let Some(id) = id {
+            if self.names.insert(id, idx).is_some() {
+                return Err(ParseErrorKind::DuplicateId(self.kind, id.to_owned()));
+            }
+        }
+        Ok(idx)
+    }
+
+    fn next(&mut self) -> u32 {
+        let idx = self.next;
+        self.next += 1;
+        idx
+    }
+}
+
+impl<'a> Parser<'a> {
+    pub fn parse(source: &'a str) -> Result<Module, ParseError> {
+        let mut parser = Parser {
+            source,
+            tokens: Lexer::tokenize(source)?,
+            position: 0,
+            types: Space::new("type"),
+            funcs: Space::new("function"),
+            tables: Space::new("table"),
+            mems: Space::new("memory"),
+            globals: Space::new("global"),
+            elems: Space::new("elem segment"),
+            datas: Space::new("data segment"),
+            module: Module {
+                version: 1,
+                ..Module::default()
+            },
+            uses_data_count: false,
+        };
+        parser.parse_module()?;
+        // Like other tools, only emit a data count section when the code needs
+        // one to be validated.
+        if parser.uses_data_count {
+            parser.module.data_count = Some(parser.module.data.len() as u32);
+        }
+        Ok(parser.module)
+    }
+
+    fn parse_module(&mut self) -> Result<(), ParseError> {
+        let wrapped = self.peek_list("module");
+        if wrapped {
+            self.expect_lparen()?;
+            self.expect_keyword("module")?;
+            self.module.names.module = self.take_id().map(str::to_owned);
+        }
+        let start = self.position;
+        self.scan_fields()?;
+        self.position = start;
+        while self.peek_is_lparen() {
+            self.field()?;
+        }
+        if wrapped {
+            self.expect_rparen()?;
+        }
+        match self.tokens.get(self.position) {
+            Some(token) => Err(self.unexpected("end of input", token)),
+            None => Ok(()),
+        }
+    }
+
+    // Assigns indices to every definition up front so that fields can refer
+    // to each other by name regardless of the order they appear in.
+    fn scan_fields(&mut self) -> Result<(), ParseError> {
+        let mut definition = None;
+        while self.peek_is_lparen() {
+            self.expect_lparen()?;
+            let (field, offset) = self.atom()?;
+            match field {
+                "type" => {
+                    let id = self.take_id();
+                    self.expect_lparen()?;
+                    self.expect_keyword("func")?;
+                    let (params, _) = self.params()?;
+                    let results = self.results()?;
+                    self.expect_rparen()?;
+                    self.expect_rparen()?;
+                    self.define(|p| &mut p.types, id)?;
+                    self.module.types.push(FuncType { params, results });
+                }
+                "import" => {
+                    self.name()?;
+                    self.name()?;
+                    self.expect_lparen()?;
+                    let (kind, offset) = self.atom()?;
+                    let id = self.take_id();
+                    let (_, kind) = self.space_for(kind, offset)?;
+                    if let Some(definition) = definition {
+                        let kind = ParseErrorKind::ImportAfterDefinition(definition);
+                        return Err(self.error(offset, kind));
+                    }
+                    self.define(kind, id)?;
+                    self.skip_list()?;
+                    self.skip_list()?;
+                }
+                "func" | "table" | "memory" | "global" => {
+                    let id = self.take_id();
+                    let (name, kind) = self.space_for(field, offset)?;
+                    while self.peek_list("export") {
+                        self.expect_lparen()?;
+                        self.skip_list()?;
+                    }
+                    if !self.peek_list("import") {
+                        definition.get_or_insert(name);
+                    } else if let Some(definition) = definition {
+                        let kind = ParseErrorKind::ImportAfterDefinition(definition);
+                        return Err(self.error(offset, kind));
+                    }
+                    self.define(kind, id)?;
+                    // Inline `(elem ...)` and `(data ...)` abbreviations also
+                    // define a segment in the order the fields appear.
+                    let inline = !self.peek_list("import");
+                    if field == "table" && inline && !self.peek_is_index() {
+                        self.define(|p| &mut p.elems, None)?;
+                    } else if field == "memory" && inline && self.peek_list("data") {
+                        self.define(|p| &mut p.datas, None)?;
+                    }
+                    self.skip_list()?;
+                }
+                "elem" | "data" => {
+                    let id = self.take_id();
+                    match field {
+                        "elem" => self.define(|p| &mut p.elems, id)?,
+                        _ => self.define(|p| &mut p.datas, id)?,
+                    };
+                    self.skip_list()?;
+                }
+                "export" | "start" => self.skip_list()?,
+                _ => {
+                    let kind = ParseErrorKind::UnknownField(field.to_owned());
+                    return Err(self.error(offset, kind));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn space_for(
+        &self,
+        kind: &str,
+        offset: usize,
+    ) -> Result<(&'static str, SpaceOf<'a>), ParseError> {
+        Ok(match kind {
+            "func" => ("function", |p| &mut p.funcs),
+            "table" => ("table", |p| &mut p.tables),
+            "memory" => ("memory", |p| &mut p.mems),
+            "global" => ("global", |p| &mut p.globals),
+            _ => return Err(self.unknown_kind(kind, offset)),
+        })
+    }
+
+    fn unknown_kind(&self, kind: &str, offset: usize) -> ParseError {
+        let kind = ParseErrorKind::UnexpectedToken {
+            expected: "external kind",
+            found: kind.to_owned(),
+        };
+        self.error(offset, kind)
+    }
+
+    fn define(&mut self, space: SpaceOf<'a>, id: Option<&'a str>) -> Result<u32, ParseError> {
+        space(self).define(id).map_err(|kind| self.error_here(kind))
+    }
+
+    fn field(&mut self) -> Result<(), ParseError> {
+        self.expect_lparen()?;
+        let (field, _) = self.atom()?;
+        match field {
+            "type" => self.skip_list(),
+            "import" => self.import(),
+            "func" => self.func(),
+            "table" => self.table(),
+            "memory" => self.memory(),
+            "global" => self.global(),
+            "export" => self.export(),
+            "start" => self.start(),
+            "elem" => self.elem(),
+            _ => self.data(),
+        }
+    }
+
+    fn import(&mut self) -> Result<(), ParseError> {
+        let module = self.name()?;
+        let name = self.name()?;
+        self.expect_lparen()?;
+        let (kind, _) = self.atom()?;
+        let id = self.take_id();
+        let desc = match kind {
+            "func" => {
+                let idx = self.funcs.next();
+                if let Some(id) = id {
+                    self.module.names.funcs.insert(idx, id.to_owned());
+                }
+                ImpExportDesc::Func(TypeIdx(self.type_use()?.0))
+            }
+            "table" => {
+                self.tables.next();
+                ImpExportDesc::Table(self.table_type()?)
+            }
+            "memory" => {
+                self.mems.next();
+                ImpExportDesc::Mem(MemType(self.limits()?))
+            }
+            _ => {
+                self.globals.next();
+                ImpExportDesc::Global(self.global_type()?)
+            }
+        };
+        self.expect_rparen()?;
+        self.expect_rparen()?;
+        self.module.imports.push(Import { module, name, desc });
+        Ok(())
+    }
+
+    fn func(&mut self) -> Result<(), ParseError> {
+        let id = self.take_id();
+        let idx = self.funcs.next();
+        if let Some(id) = id {
+            self.module.names.funcs.insert(idx, id.to_owned());
+        }
+        self.inline_exports(ExportDesc::Func(FuncIdx(idx)))?;
+        if let Some((module, name)) = self.inline_import()? {
+            let desc = ImpExportDesc::Func(TypeIdx(self.type_use()?.0));
+            self.module.imports.push(Import { module, name, desc });
+            return self.expect_rparen();
+        }
+        let (typ, params) = self.type_use()?;
+        let mut locals = Space::new("local");
+        for param in params {
+            locals.define(param).map_err(|kind| self.error_here(kind))?;
+        }
+        let mut local_types = Vec::new();
+        while self.peek_list("local") {
+            self.expect_lparen()?;
+            self.expect_keyword("local")?;
+            if let Some(id) = self.take_id() {
+                local_types.push(self.val_type()?);
+                locals
+                    .define(Some(id))
+                    .map_err(|kind| self.error_here(kind))?;
+            } else {
+                while !self.peek_is_rparen() {
+                    local_types.push(self.val_type()?);
+                    locals.define(None).map_err(|kind| self.error_here(kind))?;
+                }
+            }
+            self.expect_rparen()?;
+        }
+        if !locals.names.is_empty() {
+            let names = locals
+                .names
+                .iter()
+                .map(|(name, idx)| (*idx, (*name).to_owned()))
+                .collect();
+            self.module.names.locals.insert(idx, names);
+        }
+        let mut body = Body {
+            locals: locals.names,
+            labels: Vec::new(),
+        };
+        let instrs = self.instrs(&mut body)?;
+        self.expect_rparen()?;
+        self.module.funcs.push(Function {
+            typ: TypeIdx(typ),
+            locals: local_types,
+            body: Expr(instrs, End),
+        });
+        Ok(())
+    }
+
+    fn table(&mut self) -> Result<(), ParseError> {
+        self.take_id();
+        let idx = self.tables.next();
+        self.inline_exports(ExportDesc::Table(TableIdx(idx)))?;
+        if let Some((module, name)) = self.inline_import()? {
+            let desc = ImpExportDesc::Table(self.table_type()?);
+            self.module.imports.push(Import { module, name, desc });
+            return self.expect_rparen();
+        }
+        if self.peek_is_index() {
+            let typ = self.table_type()?;
+            self.module.table.push(Table { typ });
+            return self.expect_rparen();
+        }
+        let elem_type = self.elem_type()?;
+        self.expect_lparen()?;
+        self.expect_keyword("elem")?;
+        let init = match self.peek_is_lparen() {
+            true => self.elem_exprs()?,
+            false => self.elem_funcs()?,
+        };
+        self.expect_rparen()?;
+        self.expect_rparen()?;
+        let size = init.len() as u32;
+        let limits = Limits {
+            min: size,
+            max: Some(size),
+        };
+        self.module.table.push(Table {
+            typ: TableType(limits, elem_type),
+        });
+        self.elems.next();
+        self.module.elem.push(Elem {
+            typ: elem_type,
+            mode: ElemMode::Active {
+                table: TableIdx(idx),
+                offset: Expr(vec![Instr::I32Const(0)], End),
+            },
+            init,
+        });
+        Ok(())
+    }
+
+    fn memory(&mut self) -> Result<(), ParseError> {
+        self.take_id();
+        let idx = self.mems.next();
+        self.inline_exports(ExportDesc::Mem(MemIdx(idx)))?;
+        if let Some((module, name)) = self.inline_import()? {
+            let desc = ImpExportDesc::Mem(MemType(self.limits()?));
+            self.module.imports.push(Import { module, name, desc });
+            return self.expect_rparen();
+        }
+        if !self.peek_list("data") {
+            let typ = MemType(self.limits()?);
+            self.module.memory.push(Mem { typ });
+            return self.expect_rparen();
+        }
+        self.expect_lparen()?;
+        self.expect_keyword("data")?;
+        let init = self.data_strings()?;
+        self.expect_rparen()?;
+        self.expect_rparen()?;
+        let pages = init.len().div_ceil(PAGE_SIZE) as u32;
+        let limits = Limits {
+            min: pages,
+            max: Some(pages),
+        };
+        self.module.memory.push(Mem {
+            typ: MemType(limits),
+        });
+        self.datas.next();
+        self.module.data.push(Data {
+            mode: DataMode::Active {
+                memory: MemIdx(idx),
+                offset: Expr(vec![Instr::I32Const(0)], End),
+            },
+            init,
+        });
+        Ok(())
+    }
+
+    fn global(&mut self) -> Result<(), ParseError> {
+        self.take_id();
+        let idx = self.globals.next();
+        self.inline_exports(ExportDesc::Global(GlobalIdx(idx)))?;
+        if let Some((module, name)) = self.inline_import()? {
+            let desc = ImpExportDesc::Global(self.global_type()?);
+            self.module.imports.push(Import { module, name, desc });
+            return self.expect_rparen();
+        }
+        let typ = self.global_type()?;
+        let init = self.instrs(&mut Body::default())?;
+        self.expect_rparen()?;
+        self.module.globals.push(Global {
+            typ,
+            init: Expr(init, End),
+        });
+        Ok(())
+    }
+
+    fn export(&mut self) -> Result<(), ParseError> {
+        let name = self.name()?;
+        self.expect_lparen()?;
+        let (kind, offset) = self.atom()?;
+        let desc = match kind {
+            "func" => ExportDesc::Func(FuncIdx(self.index_of(|p| &p.funcs)?)),
+            "table" => ExportDesc::Table(TableIdx(self.index_of(|p| &p.tables)?)),
+            "memory" => ExportDesc::Mem(MemIdx(self.index_of(|p| &p.mems)?)),
+            "global" => ExportDesc::Global(GlobalIdx(self.index_of(|p| &p.globals)?)),
+            _ => return Err(self.unknown_kind(kind, offset)),
+        };
+        self.expect_rparen()?;
+        self.expect_rparen()?;
+        self.module.exports.push(Export { name, desc });
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<(), ParseError> {
+        if self.module.start.is_some() {
+            return Err(self.error_here(ParseErrorKind::MultipleStart));
+        }
+        let func = FuncIdx(self.index_of(|p| &p.funcs)?);
+        self.module.start = Some(Start { func });
+        self.expect_rparen()
+    }
+
+    fn elem(&mut self) -> Result<(), ParseError> {
+        self.take_id();
+        self.elems.next();
+        let mode = if self.peek_atom() == Some("declare") {
+            self.position += 1;
+            ElemMode::Declarative
+        } else if self.peek_is_lparen() || self.peek_is_index() {
+            let table = if self.peek_list("table") {
+                self.expect_lparen()?;
+                self.expect_keyword("table")?;
+                let idx = self.index_of(|p| &p.tables)?;
+                self.expect_rparen()?;
+                idx
+            } else if self.peek_is_index() {
+                self.index_of(|p| &p.tables)?
+            } else {
+                0
+            };
+            ElemMode::Active {
+                table: TableIdx(table),
+                offset: self.offset()?,
+            }
+        } else {
+            ElemMode::Passive
+        };
+        // Without an element type the segment is a list of function
+        // indices, optionally introduced by `func`.
+        let (typ, init) = match self.peek_atom() {
+            Some("func") => {
+                self.position += 1;
+                (ElemType::FuncRef, self.elem_funcs()?)
+            }
+            Some(_) if !self.peek_is_index() => (self.elem_type()?, self.elem_exprs()?),
+            _ => (ElemType::FuncRef, self.elem_funcs()?),
+        };
+        self.expect_rparen()?;
+        self.module.elem.push(Elem { typ, mode, init });
+        Ok(())
+    }
+
+    fn elem_funcs(&mut self) -> Result<Vec<Expr>, ParseError> {
+        let mut init = Vec::new();
+        while !self.peek_is_rparen() {
+            let func = FuncIdx(self.index_of(|p| &p.funcs)?);
+            init.push(Expr(vec![Instr::RefFunc(func)], End));
+        }
+        Ok(init)
+    }
+
+    fn elem_exprs(&mut self) -> Result<Vec<Expr>, ParseError> {
+        let mut init = Vec::new();
+        while !self.peek_is_rparen() {
+            let instrs = if self.peek_list("item") {
+                self.expect_lparen()?;
+                self.expect_keyword("item")?;
+                let instrs = self.instrs(&mut Body::default())?;
+                self.expect_rparen()?;
+                instrs
+            } else {
+                self.folded_instr(&mut Body::default())?
+            };
+            init.push(Expr(instrs, End));
+        }
+        Ok(init)
+    }
+
+    fn data(&mut self) -> Result<(), ParseError> {
+        self.take_id();
+        self.datas.next();
+        let mode = if self.peek_is_lparen() || self.peek_is_index() {
+            let memory = if self.peek_list("memory") {
+                self.expect_lparen()?;
+                self.expect_keyword("memory")?;
+                let idx = self.index_of(|p| &p.mems)?;
+                self.expect_rparen()?;
+                idx
+            } else if self.peek_is_index() {
+                self.index_of(|p| &p.mems)?
+            } else {
+                0
+            };
+            DataMode::Active {
+                memory: MemIdx(memory),
+                offset: self.offset()?,
+            }
+        } else {
+            DataMode::Passive
+        };
+        let init = self.data_strings()?;
+        self.expect_rparen()?;
+        self.module.data.push(Data { mode, init });
+        Ok(())
+    }
+
+    fn offset(&mut self) -> Result<Expr, ParseError> {
+        let instrs = if self.peek_list("offset") {
+            self.expect_lparen()?;
+            self.expect_keyword("offset")?;
+            let instrs = self.instrs(&mut Body::default())?;
+            self.expect_rparen()?;
+            instrs
+        } else {
+            self.folded_instr(&mut Body::default())?
+        };
+        Ok(Expr(instrs, End))
+    }
+
+    fn data_strings(&mut self) -> Result<Vec<u8>, ParseError> {
+        let mut bytes = Vec::new();
+        while !self.peek_is_rparen() {
+            bytes.extend(self.string()?);
+        }
+        Ok(bytes)
+    }
+
+    fn inline_exports(&mut self, desc: ExportDesc) -> Result<(), ParseError> {
+        while self.peek_list("export") {
+            self.expect_lparen()?;
+            self.expect_keyword("export")?;
+            let name = self.name()?;
+            self.expect_rparen()?;
+            let desc = desc.clone();
+            self.module.exports.push(Export { name, desc });
+        }
+        Ok(())
+    }
+
+    fn inline_import(&mut self) -> Result<Option<(String, String)>, ParseError> {
+        if !self.peek_list("import") {
+            return Ok(None);
+        }
+        self.expect_lparen()?;
+        self.expect_keyword("import")?;
+        let module = self.name()?;
+        let name = self.name()?;
+        self.expect_rparen()?;
+        Ok(Some((module, name)))
+    }
+
+    fn type_use(&mut self) -> Result<(u32, Vec<Option<&'a str>>), ParseError> {
+        let explicit = self.explicit_type()?;
+        let (params, names) = self.params()?;
+        let results = self.results()?;
+        self.resolve_type_use(explicit, FuncType { params, results }, names)
+    }
+
+    fn explicit_type(&mut self) -> Result<Option<u32>, ParseError> {
+        if !self.peek_list("type") {
+            return Ok(None);
+        }
+        self.expect_lparen()?;
+        self.expect_keyword("type")?;
+        let idx = self.index_of(|p| &p.types)?;
+        self.expect_rparen()?;
+        Ok(Some(idx))
+    }
+
+    fn resolve_type_use(
+        &mut self,
+        explicit: Option<u32>,
+        typ: FuncType,
+        names: Vec<Option<&'a str>>,
+    ) -> Result<(u32, Vec<Option<&'a str>>), ParseError> {
+        let Some(idx) = explicit else {
+            let types = &mut self.module.types;
+            let idx = types.iter().position(|t| *t == typ).unwrap_or_else(|| {
+                types.push(typ);
+                types.len() - 1
+            });
+            return Ok((idx as u32, names));
+        };
+        let inline = !typ.params.is_empty() || !typ.results.is_empty();
+        match self.module.types.get(idx as usize) {
+            Some(declared) if inline && *declared != typ => {
+                Err(self.error_here(ParseErrorKind::InlineTypeMismatch))
+            }
+            Some(declared) if !inline => Ok((idx, vec![None; declared.params.len()])),
+            _ => Ok((idx, names)),
+        }
+    }
+
+    fn params(&mut self) -> Result<(Vec<ValType>, Vec<Option<&'a str>>), ParseError> {
+        let mut types = Vec::new();
+        let mut names = Vec::new();
+        while self.peek_list("param") {
+            self.expect_lparen()?;
+            self.expect_keyword("param")?;
+            if let Some(id) = self.take_id() {
+                types.push(self.val_type()?);
+                names.push(Some(id));
+            } else {
+                while !self.peek_is_rparen() {
+                    types.push(self.val_type()?);
+                    names.push(None);
+                }
+            }
+            self.expect_rparen()?;
+        }
+        Ok((types, names))
+    }
+
+    fn results(&mut self) -> Result<Vec<ValType>, ParseError> {
+        let mut types = Vec::new();
+        while self.peek_list("result") {
+            self.expect_lparen()?;
+            self.expect_keyword("result")?;
+            while !self.peek_is_rparen() {
+                types.push(self.val_type()?);
+            }
+            self.expect_rparen()?;
+        }
+        Ok(types)
+    }
+
+    fn val_type(&mut self) -> Result<ValType, ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Atom("i32") => Ok(ValType::I32),
+            TokenKind::Atom("i64") => Ok(ValType::I64),
+            TokenKind::Atom("f32") => Ok(ValType::F32),
+            TokenKind::Atom("f64") => Ok(ValType::F64),
+            TokenKind::Atom("funcref") => Ok(ValType::FuncRef),
+            TokenKind::Atom("externref") => Ok(ValType::ExternRef),
+            _ => Err(self.unexpected("value type", &token)),
+        }
+    }
+
+    fn elem_type(&mut self) -> Result<ElemType, ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Atom("funcref" | "anyfunc") => Ok(ElemType::FuncRef),
+            TokenKind::Atom("externref") => Ok(ElemType::ExternRef),
+            _ => Err(self.unexpected("element type", &token)),
+        }
+    }
+
+    fn heap_type(&mut self) -> Result<ElemType, ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Atom("func") => Ok(ElemType::FuncRef),
+            TokenKind::Atom("extern") => Ok(ElemType::ExternRef),
+            _ => Err(self.unexpected("heap type", &token)),
+        }
+    }
+
+    fn limits(&mut self) -> Result<Limits, ParseError> {
+        let min = self.u32()?;
+        let max = if self.peek_is_index() {
+            Some(self.u32()?)
+        } else {
+            None
+        };
+        Ok(Limits { min, max })
+    }
+
+    fn table_type(&mut self) -> Result<TableType, ParseError> {
+        let limits = self.limits()?;
+        Ok(TableType(limits, self.elem_type()?))
+    }
+
+    fn global_type(&mut self) -> Result<GlobalType, ParseError> {
+        if !self.peek_list("mut") {
+            return Ok(GlobalType(Mut::Const, self.val_type()?));
+        }
+        self.expect_lparen()?;
+        self.expect_keyword("mut")?;
+        let typ = self.val_type()?;
+        self.expect_rparen()?;
+        Ok(GlobalType(Mut::Var, typ))
+    }
+
+    fn block_type(&mut self) -> Result<BlockType, ParseError> {
+        let explicit = self.explicit_type()?;
+        let (params, names) = self.params()?;
+        let results = self.results()?;
+        if explicit.is_none() && params.is_empty() {
+            match results[..] {
+                [] => return Ok(BlockType::Empty),
+                [typ] => return Ok(BlockType::Value(typ)),
+                _ => {}
+            }
+        }
+        let (idx, _) = self.resolve_type_use(explicit, FuncType { params, results }, names)?;
+        Ok(BlockType::Type(TypeIdx(idx)))
+    }
+
+    fn instrs(&mut self, body: &mut Body<'a>) -> Result<Vec<Instr>, ParseError> {
+        self.instr_sequence(body, false)
+    }
+
+    fn folded_instr(&mut self, body: &mut Body<'a>) -> Result<Vec<Instr>, ParseError> {
+        self.instr_sequence(body, true)
+    }
+
+    // Blocks and folded operands nest arbitrarily deep, so the ones still
+    // open are kept on an explicit stack instead of the call stack. With
+    // `single` set only one folded instruction is read.
+    fn instr_sequence(
+        &mut self,
+        body: &mut Body<'a>,
+        single: bool,
+    ) -> Result<Vec<Instr>, ParseError> {
+        let mut instrs = Vec::new();
+        let mut open: Vec<Open<'a>> = Vec::new();
+        loop {
+            if single && open.is_empty() && !instrs.is_empty() {
+                return Ok(instrs);
+            }
+            let folded = self.peek_is_lparen() || (single && open.is_empty());
+            let flat = matches!(
+                self.peek(),
+                Some(TokenKind::Atom(name)) if !matches!(*name, "end" | "else")
+            );
+            match open.pop() {
+                Some(Open::Operands(instr)) if !folded => {
+                    self.expect_rparen()?;
+                    instrs.push(instr);
+                }
+                Some(Open::FoldedIf {
+                    label,
+                    typ,
+                    outer: None,
+                    ..
+                }) if !folded || self.peek_list("then") => {
+                    body.labels.push(label);
+                    self.expect_lparen()?;
+                    self.expect_keyword("then")?;
+                    open.push(Open::FoldedIf {
+                        label,
+                        typ,
+                        outer: Some(mem::take(&mut instrs)),
+                        consequent: None,
+                    });
+                }
+                top if folded || flat => {
+                    open.extend(top);
+                    match folded {
+                        true => self.open_folded(body, &mut open, &mut instrs)?,
+                        false => self.open_flat(body, &mut open, &mut instrs)?,
+                    }
+                }
+                Some(block) => self.close(body, block, &mut open, &mut instrs)?,
+                None => return Ok(instrs),
+            }
+        }
+    }
+
+    fn open_flat(
+        &mut self,
+        body: &mut Body<'a>,
+        open: &mut Vec<Open<'a>>,
+        instrs: &mut Vec<Instr>,
+    ) -> Result<(), ParseError> {
+        let (name, offset) = self.atom()?;
+        if !matches!(name, "block" | "loop" | "if") {
+            let instr = self.plain_instr(name, offset, body)?;
+            instrs.push(instr);
+            return Ok(());
+        }
+        self.check_depth(open)?;
+        let label = self.take_id();
+        let typ = self.block_type()?;
+        body.labels.push(label);
+        open.push(Open::Block {
+            name,
+            label,
+            typ,
+            outer: mem::take(instrs),
+            consequent: None,
+        });
+        Ok(())
+    }
+
+    fn open_folded(
+        &mut self,
+        body: &mut Body<'a>,
+        open: &mut Vec<Open<'a>>,
+        instrs: &mut Vec<Instr>,
+    ) -> Result<(), ParseError> {
+        self.expect_lparen()?;
+        self.check_depth(open)?;
+        let (name, offset) = self.atom()?;
+        match name {
+            "block" | "loop" => {
+                let label = self.take_id();
+                let typ = self.block_type()?;
+                body.labels.push(label);
+                open.push(Open::FoldedBlock {
+                    name,
+                    typ,
+                    outer: mem::take(instrs),
+                });
+            }
+            "if" => {
+                let label = self.take_id();
+                let typ = self.block_type()?;
+                open.push(Open::FoldedIf {
+                    label,
+                    typ,
+                    outer: None,
+                    consequent: None,
+                });
+            }
+            _ => {
+                let instr = self.plain_instr(name, offset, body)?;
+                open.push(Open::Operands(instr));
+            }
+        }
+        Ok(())
+    }
+
+    fn close(
+        &mut self,
+        body: &mut Body<'a>,
+        block: Open<'a>,
+        open: &mut Vec<Open<'a>>,
+        instrs: &mut Vec<Instr>,
+    ) -> Result<(), ParseError> {
+        match block {
+            Open::Block {
+                name,
+                label,
+                typ,
+                outer,
+                consequent: None,
+            } if name == "if" && self.peek_atom() == Some("else") => {
+                self.position += 1;
+                self.end_label(label)?;
+                open.push(Open::Block {
+                    name,
+                    label,
+                    typ,
+                    outer,
+                    consequent: Some(mem::take(instrs)),
+                });
+            }
+            Open::Block {
+                name,
+                label,
+                typ,
+                outer,
+                consequent,
+            } => {
+                self.expect_keyword("end")?;
+                self.end_label(label)?;
+                body.labels.pop();
+                let inner = mem::replace(instrs, outer);
+                instrs.push(match (name, consequent) {
+                    ("block", _) => Instr::Block(typ, inner, End),
+                    ("loop", _) => Instr::Loop(typ, inner, End),
+                    (_, Some(consequent)) => Instr::If(typ, consequent, Else, inner, End),
+                    (_, None) => Instr::If(typ, inner, Else, Vec::new(), End),
+                });
+            }
+            Open::FoldedBlock { name, typ, outer } => {
+                body.labels.pop();
+                let inner = mem::replace(instrs, outer);
+                instrs.push(match name {
+                    "block" => Instr::Block(typ, inner, End),
+                    _ => Instr::Loop(typ, inner, End),
+                });
+                self.expect_rparen()?;
+            }
+            Open::FoldedIf {
+                label,
+                typ,
+                outer: Some(outer),
+                consequent,
+            } => {
+                self.expect_rparen()?;
+                if consequent.is_none() && self.peek_list("else") {
+                    self.expect_lparen()?;
+                    self.expect_keyword("else")?;
+                    open.push(Open::FoldedIf {
+                        label,
+                        typ,
+                        outer: Some(outer),
+                        consequent: Some(mem::take(instrs)),
+                    });
+                    return Ok(());
+                }
+                body.labels.pop();
+                let inner = mem::replace(instrs, outer);
+                instrs.push(match consequent {
+                    Some(consequent) => Instr::If(typ, consequent, Else, inner, End),
+                    None => Instr::If(typ, inner, Else, Vec::new(), End),
+                });
+                self.expect_rparen()?;
+            }
+            Open::FoldedIf { outer: None, .. } | Open::Operands(_) => {
+                unreachable!("handled before closing")
+            }
+        }
+        Ok(())
+    }
+
+    fn check_depth(&self, open: &[Open<'a>]) -> Result<(), ParseError> {
+        if open.len() >= MAX_NESTING_DEPTH {
+            return Err(self.error_here(ParseErrorKind::NestingTooDeep));
+        }
+        Ok(())
+    }
+
+    fn plain_instr(
+        &mut self,
+        name: &'a str,
+        offset: usize,
+        body: &mut Body<'a>,
+    ) -> Result<Instr, ParseError> {
+        if let Some(instr) = simple_instr(name) {
+            return Ok(instr);
+        }
+        if let Some((instr, natural_align)) = memory_instr(name) {
+            return Ok(instr(self.mem_arg(natural_align)?));
+        }
+        Ok(match name {
+            "br" => Instr::Br(self.label(body)?),
+            "br_if" => Instr::BrIf(self.label(body)?),
+            "br_table" => {
+                let mut labels = vec![self.label(body)?];
+                while self.peek_is_index() {
+                    labels.push(self.label(body)?);
+                }
+                let default = labels.pop().expect("at least one label");
+                Instr::BrTable(labels, default)
+            }
+            "call" => Instr::Call(FuncIdx(self.index_of(|p| &p.funcs)?)),
+            "call_indirect" => {
+                let table = self.table_index()?;
+                Instr::CallIndirect(TypeIdx(self.type_use()?.0), table)
+            }
+            "select" => match self.peek_list("result") {
+                true => Instr::TypedSelect(self.results()?),
+                false => Instr::Select,
+            },
+            "local.get" => Instr::LocalGet(self.local(body)?),
+            "local.set" => Instr::LocalSet(self.local(body)?),
+            "local.tee" => Instr::LocalTee(self.local(body)?),
+            "global.get" => Instr::GlobalGet(GlobalIdx(self.index_of(|p| &p.globals)?)),
+            "global.set" => Instr::GlobalSet(GlobalIdx(self.index_of(|p| &p.globals)?)),
+            "table.get" => Instr::TableGet(self.table_index()?),
+            "table.set" => Instr::TableSet(self.table_index()?),
+            "table.grow" => Instr::TableGrow(self.table_index()?),
+            "table.size" => Instr::TableSize(self.table_index()?),
+            "table.fill" => Instr::TableFill(self.table_index()?),
+            "ref.null" => Instr::RefNull(self.heap_type()?),
+            "ref.func" => Instr::RefFunc(FuncIdx(self.index_of(|p| &p.funcs)?)),
+            "memory.init" => {
+                self.uses_data_count = true;
+                Instr::MemoryInit(DataIdx(self.index_of(|p| &p.datas)?))
+            }
+            "data.drop" => {
+                self.uses_data_count = true;
+                Instr::DataDrop(DataIdx(self.index_of(|p| &p.datas)?))
+            }
+            "table.init" => {
+                // The table index is optional and comes first when present.
+                let start = self.position;
+                self.next()?;
+                let explicit = self.peek_is_index();
+                self.position = start;
+                let table = match explicit {
+                    true => self.index_of(|p| &p.tables)?,
+                    false => 0,
+                };
+                let elem = self.index_of(|p| &p.elems)?;
+                Instr::TableInit(ElemIdx(elem), TableIdx(table))
+            }
+            "elem.drop" => Instr::ElemDrop(ElemIdx(self.index_of(|p| &p.elems)?)),
+            "table.copy" => match self.peek_is_index() {
+                true => Instr::TableCopy(
+                    TableIdx(self.index_of(|p| &p.tables)?),
+                    TableIdx(self.index_of(|p| &p.tables)?),
+                ),
+                false => Instr::TableCopy(TableIdx(0), TableIdx(0)),
+            },
+            "i32.const" => Instr::I32Const(self.integer(32)? as i32),
+            "i64.const" => Instr::I64Const(self.integer(64)?),
+            "f32.const" => Instr::F32Const(f32::from_bits(self.float(23, 8)? as u32)),
+            "f64.const" => Instr::F64Const(f64::from_bits(self.float(52, 11)?)),
+            _ => {
+                let kind = ParseErrorKind::UnknownInstruction(name.to_owned());
+                return Err(self.error(offset, kind));
+            }
+        })
+    }
+
+    fn table_index(&mut self) -> Result<TableIdx, ParseError> {
+        match self.peek_is_index() {
+            true => Ok(TableIdx(self.index_of(|p| &p.tables)?)),
+            false => Ok(TableIdx(0)),
+        }
+    }
+
+    fn end_label(&mut self, label: Option<&'a str>) -> Result<(), ParseError> {
+        if let Some(TokenKind::Id(id)) = self.peek() {
+            let id = *id;
+            self.position += 1;
+            if label != Some(id) {
+                return Err(self.error_here(ParseErrorKind::MismatchedLabel(id.to_owned())));
+            }
+        }
+        Ok(())
+    }
+
+    fn label(&mut self, body: &Body<'a>) -> Result<LabelIdx, ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Id(id) => body
+                .labels
+                .iter()
+                .rev()
+                .position(|label| *label == Some(id))
+                .map(|depth| LabelIdx(depth as u32))
+                .ok_or_else(|| {
+                    let kind = ParseErrorKind::UnknownId("label", id.to_owned());
+                    self.error(token.offset, kind)
+                }),
+            TokenKind::Atom(text) => Ok(LabelIdx(self.parse_u32(text, token.offset)?)),
+            _ => Err(self.unexpected("label", &token)),
+        }
+    }
+
+    fn local(&mut self, body: &Body<'a>) -> Result<LocalIdx, ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Id(id) => body
+                .locals
+                .get(id)
+                .map(|idx| LocalIdx(*idx))
+                .ok_or_else(|| {
+                    let kind = ParseErrorKind::UnknownId("local", id.to_owned());
+                    self.error(token.offset, kind)
+                }),
+            TokenKind::Atom(text) => Ok(LocalIdx(self.parse_u32(text, token.offset)?)),
+            _ => Err(self.unexpected("local", &token)),
+        }
+    }
+
+    fn mem_arg(&mut self, natural_align: u32) -> Result<MemArg, ParseError> {
+        let mut mem_arg = MemArg {
+            offset: 0,
+            align: natural_align,
+        };
+        if let Some(text) = self
+            .peek_atom()
+            .and_then(|atom| atom.strip_prefix("offset="))
+        {
+            let offset = self.tokens[self.position].offset;
+            mem_arg.offset = self.parse_u32(text, offset)?;
+            self.position += 1;
+        }
+        if let Some(text) = self
+            .peek_atom()
+            .and_then(|atom| atom.strip_prefix("align="))
+        {
+            let offset = self.tokens[self.position].offset;
+            let align = self.parse_u32(text, offset)?;
+            if !align.is_power_of_two() {
+                return Err(self.error(offset, ParseErrorKind::InvalidAlignment(align)));
+            }
+            mem_arg.align = align.trailing_zeros();
+            self.position += 1;
+        }
+        Ok(mem_arg)
+    }
+
+    fn index_of(&mut self, space: fn(&Self) -> &Space<'a>) -> Result<u32, ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Id(id) => {
+                let space = space(self);
+                space.names.get(id).copied().ok_or_else(|| {
+                    let kind = ParseErrorKind::UnknownId(space.kind, id.to_owned());
+                    self.error(token.offset, kind)
+                })
+            }
+            TokenKind::Atom(text) => self.parse_u32(text, token.offset),
+            _ => Err(self.unexpected("index", &token)),
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, ParseError> {
+        let (text, offset) = self.atom()?;
+        self.parse_u32(text, offset)
+    }
+
+    fn parse_u32(&self, text: &str, offset: usize) -> Result<u32, ParseError> {
+        let value = unsigned(text)
+            .ok_or_else(|| self.error(offset, ParseErrorKind::InvalidNumber(text.to_owned())))?;
+        u32::try_from(value)
+            .map_err(|_| self.error(offset, ParseErrorKind::ConstantOutOfRange(text.to_owned())))
+    }
+
+    fn integer(&mut self, bits: u32) -> Result<i64, ParseError> {
+        let (text, offset) = self.atom()?;
+        integer(text, bits).map_err(|kind| self.error(offset, kind))
+    }
+
+    fn float(&mut self, mantissa_bits: u32, exponent_bits: u32) -> Result<u64, ParseError> {
+        let (text, offset) = self.atom()?;
+        float(text, mantissa_bits, exponent_bits)
+            .ok_or_else(|| self.error(offset, ParseErrorKind::InvalidNumber(text.to_owned())))
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        let bytes = self.string()?;
+        String::from_utf8(bytes).map_err(|_| self.error_here(ParseErrorKind::MalformedUtf8))
+    }
+
+    fn string(&mut self) -> Result<Vec<u8>, ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::String(bytes) => Ok(bytes),
+            _ => Err(self.unexpected("string", &token)),
+        }
+    }
+
+    fn atom(&mut self) -> Result<(&'a str, usize), ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Atom(atom) => Ok((atom, token.offset)),
+            _ => Err(self.unexpected("keyword", &token)),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &'static str) -> Result<(), ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Atom(atom) if atom == keyword => Ok(()),
+            _ => Err(self.unexpected(keyword, &token)),
+        }
+    }
+
+    fn expect_lparen(&mut self) -> Result<(), ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::LParen => Ok(()),
+            _ => Err(self.unexpected("(", &token)),
+        }
+    }
+
+    fn expect_rparen(&mut self) -> Result<(), ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::RParen => Ok(()),
+            _ => Err(self.unexpected(")", &token)),
+        }
+    }
+
+    // Consumes the rest of the current list, including its closing paren.
+    fn skip_list(&mut self) -> Result<(), ParseError> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next()?.kind {
+                TokenKind::LParen => depth += 1,
+                TokenKind::RParen => depth -= 1,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn take_id(&mut self) -> Option<&'a str> {
+        match self.peek() {
+            Some(TokenKind::Id(id)) => {
+                let id = *id;
+                self.position += 1;
+                Some(id)
+            }
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Result<Token<'a>, ParseError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| self.error(self.source.len(), ParseErrorKind::UnexpectedEnd))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn peek(&self) -> Option<&TokenKind<'a>> {
+        self.tokens.get(self.position).map(|token| &token.kind)
+    }
+
+    fn peek_atom(&self) -> Option<&'a str> {
+        match self.peek() {
+            Some(TokenKind::Atom(atom)) => Some(atom),
+            _ => None,
+        }
+    }
+
+    fn peek_list(&self, keyword: &str) -> bool {
+        self.peek_is_lparen()
+            && matches!(
+                self.tokens.get(self.position + 1).map(|token| &token.kind),
+                Some(TokenKind::Atom(atom)) if *atom == keyword
+            )
+    }
+
+    fn peek_is_lparen(&self) -> bool {
+        matches!(self.peek(), Some(TokenKind::LParen))
+    }
+
+    fn peek_is_rparen(&self) -> bool {
+        matches!(self.peek(), Some(TokenKind::RParen) | None)
+    }
+
+    fn peek_is_index(&self) -> bool {
+        match self.peek() {
+            Some(TokenKind::Id(_)) => true,
+            Some(TokenKind::Atom(atom)) => atom.starts_with(|c: char| c.is_ascii_digit()),
+            _ => false,
+        }
+    }
+
+    fn unexpected(&self, expected: &'static str, token: &Token<'a>) -> ParseError {
+        let found = match &token.kind {
+            TokenKind::LParen => "(".to_owned(),
+            TokenKind::RParen => ")".to_owned(),
+            TokenKind::Atom(atom) => (*atom).to_owned(),
+            TokenKind::Id(id) => format!("${id}"),
+            TokenKind::String(_) => "string".to_owned(),
+        };
+        self.error(
+            token.offset,
+            ParseErrorKind::UnexpectedToken { expected, found },
+        )
+    }
+
+    fn error_here(&self, kind: ParseErrorKind) -> ParseError {
+        let offset = match self.position.checked_sub(1) {
+            Some(previous) => self.tokens[previous].offset,
+            None => 0,
+        };
+        self.error(offset, kind)
+    }
+
+    fn error(&self, offset: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(self.source, offset, kind)
+    }
+}
+
+pub(crate) fn value(text: &str, typ: ValType) -> Option<Value> {
+    Some(match typ {
+        ValType::I32 => Value::I32(integer(text, 32).ok()? as i32),
+        ValType::I64 => Value::I64(integer(text, 64).ok()?),
+        ValType::F32 => Value::F32(f32::from_bits(float(text, 23, 8)? as u32)),
+        ValType::F64 => Value::F64(f64::from_bits(float(text, 52, 11)?)),
+        ValType::FuncRef if text == "ref.null func" => Value::FuncRef(None),
+        ValType::ExternRef if text == "ref.null extern" => Value::ExternRef(None),
+        ValType::FuncRef | ValType::ExternRef => return None,
+    })
+}
+
+fn integer(text: &str, bits: u32) -> Result<i64, ParseErrorKind> {
+    let (negative, magnitude) = match text.strip_prefix('-') {
+        Some(magnitude) => (true, magnitude),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let value =
+        unsigned(magnitude).ok_or_else(|| ParseErrorKind::InvalidNumber(text.to_owned()))?;
+    let limit = if negative {
+        1 << (bits - 1)
+    } else {
+        (1 << bits) - 1
+    };
+    if value > limit {
+        return Err(ParseErrorKind::ConstantOutOfRange(text.to_owned()));
+    }
+    let value = value as i128;
+    Ok(if negative { -value } else { value } as i64)
+}
+
+fn unsigned(text: &str) -> Option<u128> {
+    match text.strip_prefix("0x") {
+        Some(hex) => digits(hex, 16),
+        None => digits(text, 10),
+    }
+}
+
+fn digits(text: &str, radix: u32) -> Option<u128> {
+    if text.is_empty() || !separated(text, radix) {
+        return None;
+    }
+    text.chars()
+        .filter(|c| *c != '_')
+        .try_fold(0_u128, |value, c| {
+            let digit = c.to_digit(radix)?;
+            value.checked_mul(radix.into())?.checked_add(digit.into())
+        })
+}
+
+// Underscores may only appear between two digits.
+fn separated(text: &str, radix: u32) -> bool {
+    let bytes = text.as_bytes();
+    let is_digit = |i: Option<usize>| {
+        i.and_then(|i| bytes.get(i))
+            .is_some_and(|byte| (*byte as char).is_digit(radix))
+    };
+    (0..bytes.len())
+        .filter(|i| bytes[*i] == b'_')
+        .all(|i| is_digit(i.checked_sub(1)) && is_digit(Some(i + 1)))
+}
+
+fn float(text: &str, mantissa_bits: u32, exponent_bits: u32) -> Option<u64> {
+    let (negative, body) = match text.strip_prefix('-') {
+        Some(body) => (true, body),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let sign = u64::from(negative) << (mantissa_bits + exponent_bits);
+    let infinity = ((1 << exponent_bits) - 1) << mantissa_bits;
+    let bits = if body == "inf" {
+        infinity
+    } else if body == "nan" {
+        infinity | 1 << (mantissa_bits - 1)
+    } else if let Some(payload) = body.strip_prefix("nan:0x") {
+        let payload = digits(payload, 16)?;
+        if payload == 0 || payload >> mantissa_bits != 0 {
+            return None;
+        }
+        infinity | payload as u64
+    } else if let Some(hex) = body.strip_prefix("0x") {
+        hex_float(hex, mantissa_bits, exponent_bits)?
+    } else {
+        decimal_float(body, mantissa_bits)?
+    };
+    Some(sign | bits)
+}
+
+fn decimal_float(text: &str, mantissa_bits: u32) -> Option<u64> {
+    let valid = text.starts_with(|c: char| c.is_ascii_digit())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_digit() || "._eE+-".contains(c))
+        && separated(text, 10);
+    if !valid {
+        return None;
+    }
+    let text = text.replace('_', "");
+    if mantissa_bits == 23 {
+        let value = text.parse::<f32>().ok()?;
+        value.is_finite().then(|| value.to_bits().into())
+    } else {
+        let value = text.parse::<f64>().ok()?;
+        value.is_finite().then(|| value.to_bits())
+    }
+}
+
+// Converts a hexadecimal float literal to the bits of an IEEE 754 value with
+// the given field widths, rounding to nearest with ties to even.
+fn hex_float(text: &str, mantissa_bits: u32, exponent_bits: u32) -> Option<u64> {
+    let (significand, exponent) = match text.split_once(['p', 'P']) {
+        Some((significand, exponent)) => (significand, decimal_exponent(exponent)?),
+        None => (text, 0),
+    };
+    let (integral, fraction) = significand.split_once('.').unwrap_or((significand, ""));
+    if integral.is_empty() || !separated(integral, 16) || !separated(fraction, 16) {
+        return None;
+    }
+    let mut mantissa = 0_u128;
+    let mut exponent = exponent;
+    let mut sticky = false;
+    for (i, c) in integral.chars().chain(fraction.chars()).enumerate() {
+        if c == '_' {
+            continue;
+        }
+        let digit = c.to_digit(16)?;
+        let fractional = i >= integral.len();
+        if mantissa >> 120 == 0 {
+            mantissa = mantissa << 4 | u128::from(digit);
+            exponent -= if fractional { 4 } else { 0 };
+        } else {
+            exponent += if fractional { 0 } else { 4 };
+            sticky |= digit != 0;
+        }
+    }
+    if mantissa == 0 {
+        return Some(0);
+    }
+    let precision = i64::from(mantissa_bits);
+    let bias = (1 << (exponent_bits - 1)) - 1;
+    let top = 127 - i64::from(mantissa.leading_zeros()) + exponent;
+    if top > bias {
+        return None;
+    }
+    let mut ulp = (top - precision).max(1 - bias - precision);
+    let shift = ulp - exponent;
+    let mut significand = if shift <= 0 {
+        mantissa << -shift
+    } else if shift >= 128 {
+        0
+    } else {
+        let kept = mantissa >> shift;
+        let rest = mantissa & ((1 << shift) - 1);
+        let half = 1 << (shift - 1);
+        if rest > half || (rest == half && (sticky || kept & 1 == 1)) {
+            kept + 1
+        } else {
+            kept
+        }
+    };
+    if significand >> (mantissa_bits + 1) != 0 {
+        significand >>= 1;
+        ulp += 1;
+    }
+    let biased = if significand >> mantissa_bits == 0 {
+        0
+    } else {
+        ulp + precision + bias
+    };
+    if biased >= (1 << exponent_bits) - 1 {
+        return None;
+    }
+    let fraction = significand as u64 & ((1 << mantissa_bits) - 1);
+    Some((biased as u64) << mantissa_bits | fraction)
+}
+
+fn decimal_exponent(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if digits.is_empty() || !separated(digits, 10) {
+        return None;
+    }
+    let magnitude = digits
+        .chars()
+        .filter(|c| *c != '_')
+        .try_fold(0_i64, |value, c| {
+            Some((value * 10 + i64::from(c.to_digit(10)?)).min(1 << 20))
+        })?;
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+fn simple_instr(name: &str) -> Option<Instr> {
+    use Instr::*;
+    Some(match name {
+        "unreachable" => Unreachable,
+        "nop" => Nop,
+        "return" => Return,
+        "drop" => Drop,
+        "ref.is_null" => RefIsNull,
+        "memory.size" => MemorySize,
+        "memory.grow" => MemoryGrow,
+        "memory.copy" => MemoryCopy,
+        "memory.fill" => MemoryFill,
+        "i32.eqz" => I32Eqz,
+        "i32.eq" => I32Eq,
+        "i32.ne" => I32Ne,
+        "i32.lt_s" => I32LtS,
+        "i32.lt_u" => I32LtU,
+        "i32.gt_s" => I32GtS,
+        "i32.gt_u" => I32GtU,
+        "i32.le_s" => I32LeS,
+        "i32.le_u" => I32LeU,
+        "i32.ge_s" => I32GeS,
+        "i32.ge_u" => I32GeU,
+        "i64.eqz" => I64Eqz,
+        "i64.eq" => I64Eq,
+        "i64.ne" => I64Ne,
+        "i64.lt_s" => I64LtS,
+        "i64.lt_u" => I64LtU,
+        "i64.gt_s" => I64GtS,
+        "i64.gt_u" => I64GtU,
+        "i64.le_s" => I64LeS,
+        "i64.le_u" => I64LeU,
+        "i64.ge_s" => I64GeS,
+        "i64.ge_u" => I64GeU,
+        "f32.eq" => F32Eq,
+        "f32.ne" => F32Ne,
+        "f32.lt" => F32Lt,
+        "f32.gt" => F32Gt,
+        "f32.le" => F32Le,
+        "f32.ge" => F32Ge,
+        "f64.eq" => F64Eq,
+        "f64.ne" => F64Ne,
+        "f64.lt" => F64Lt,
+        "f64.gt" => F64Gt,
+        "f64.le" => F64Le,
+        "f64.ge" => F64Ge,
+        "i32.clz" => I32Clz,
+        "i32.ctz" => I32Ctz,
+        "i32.popcnt" => I32Popcnt,
+        "i32.add" => I32Add,
+        "i32.sub" => I32Sub,
+        "i32.mul" => I32Mul,
+        "i32.div_s" => I32DivS,
+        "i32.div_u" => I32DivU,
+        "i32.rem_s" => I32RemS,
+        "i32.rem_u" => I32RemU,
+        "i32.and" => I32And,
+        "i32.or" => I32Or,
+        "i32.xor" => I32Xor,
+        "i32.shl" => I32Shl,
+        "i32.shr_s" => I32ShrS,
+        "i32.shr_u" => I32ShrU,
+        "i32.rotl" => I32Rotl,
+        "i32.rotr" => I32Rotr,
+        "i64.clz" => I64Clz,
+        "i64.ctz" => I64Ctz,
+        "i64.popcnt" => I64Popcnt,
+        "i64.add" => I64Add,
+        "i64.sub" => I64Sub,
+        "i64.mul" => I64Mul,
+        "i64.div_s" => I64DivS,
+        "i64.div_u" => I64DivU,
+        "i64.rem_s" => I64RemS,
+        "i64.rem_u" => I64RemU,
+        "i64.and" => I64And,
+        "i64.or" => I64Or,
+        "i64.xor" => I64Xor,
+        "i64.shl" => I64Shl,
+        "i64.shr_s" => I64ShrS,
+        "i64.shr_u" => I64ShrU,
+        "i64.rotl" => I64Rotl,
+        "i64.rotr" => I64Rotr,
+        "f32.abs" => F32Abs,
+        "f32.neg" => F32Neg,
+        "f32.ceil" => F32Ceil,
+        "f32.floor" => F32Floor,
+        "f32.trunc" => F32Trunc,
+        "f32.nearest" => F32Nearest,
+        "f32.sqrt" => F32Sqrt,
+        "f32.add" => F32Add,
+        "f32.sub" => F32Sub,
+        "f32.mul" => F32Mul,
+        "f32.div" => F32Div,
+        "f32.min" => F32Min,
+        "f32.max" => F32Max,
+        "f32.copysign" => F32Copysign,
+        "f64.abs" => F64Abs,
+        "f64.neg" => F64Neg,
+        "f64.ceil" => F64Ceil,
+        "f64.floor" => F64Floor,
+        "f64.trunc" => F64Trunc,
+        "f64.nearest" => F64Nearest,
+        "f64.sqrt" => F64Sqrt,
+        "f64.add" => F64Add,
+        "f64.sub" => F64Sub,
+        "f64.mul" => F64Mul,
+        "f64.div" => F64Div,
+        "f64.min" => F64Min,
+        "f64.max" => F64Max,
+        "f64.copysign" => F64Copysign,
+        "i32.wrap_i64" => I32WrapI64,
+        "i32.trunc_f32_s" => I32TruncF32S,
+        "i32.trunc_f32_u" => I32TruncF32U,
+        "i32.trunc_f64_s" => I32TruncF64S,
+        "i32.trunc_f64_u" => I32TruncF64U,
+        "i64.extend_i32_s" => I64ExtendI32S,
+        "i64.extend_i32_u" => I64ExtendI32U,
+        "i64.trunc_f32_s" => I64TruncF32S,
+        "i64.trunc_f32_u" => I64TruncF32U,
+        "i64.trunc_f64_s" => I64TruncF64S,
+        "i64.trunc_f64_u" => I64TruncF64U,
+        "f32.convert_i32_s" => F32ConvertI32S,
+        "f32.convert_i32_u" => F32ConvertI32U,
+        "f32.convert_i64_s" => F32ConvertI64S,
+        "f32.convert_i64_u" => F32ConvertI64U,
+        "f32.demote_f64" => F32DemoteF64,
+        "f64.convert_i32_s" => F64ConvertI32S,
+        "f64.convert_i32_u" => F64ConvertI32U,
+        "f64.convert_i64_s" => F64ConvertI64S,
+        "f64.convert_i64_u" => F64ConvertI64U,
+        "f64.promote_f32" => F64PromoteF32,
+        "i32.reinterpret_f32" => I32ReinterpretF32,
+        "i64.reinterpret_f64" => I64ReinterpretF64,
+        "f32.reinterpret_i32" => F32ReinterpretI32,
+        "f64.reinterpret_i64" => F64ReinterpretI64,
+        "i32.extend8_s" => I32Extend8S,
+        "i32.extend16_s" => I32Extend16S,
+        "i64.extend8_s" => I64Extend8S,
+        "i64.extend16_s" => I64Extend16S,
+        "i64.extend32_s" => I64Extend32S,
+        "i32.trunc_sat_f32_s" => I32TruncSatF32S,
+        "i32.trunc_sat_f32_u" => I32TruncSatF32U,
+        "i32.trunc_sat_f64_s" => I32TruncSatF64S,
+        "i32.trunc_sat_f64_u" => I32TruncSatF64U,
+        "i64.trunc_sat_f32_s" => I64TruncSatF32S,
+        "i64.trunc_sat_f32_u" => I64TruncSatF32U,
+        "i64.trunc_sat_f64_s" => I64TruncSatF64S,
+        "i64.trunc_sat_f64_u" => I64TruncSatF64U,
+        _ => return None,
+    })
+}
+
+fn memory_instr(name: &str) -> Option<(MemoryInstr, u32)> {
+    use Instr::*;
+    Some(match name {
+        "i32.load" => (I32Load, 2),
+        "i64.load" => (I64Load, 3),
+        "f32.load" => (F32Load, 2),
+        "f64.load" => (F64Load, 3),
+        "i32.load8_s" => (I32Load8S, 0),
+        "i32.load8_u" => (I32Load8U, 0),
+        "i32.load16_s" => (I32Load16S, 1),
+        "i32.load16_u" => (I32Load16U, 1),
+        "i64.load8_s" => (I64Load8S, 0),
+        "i64.load8_u" => (I64Load8U, 0),
+        "i64.load16_s" => (I64Load16S, 1),
+        "i64.load16_u" => (I64Load16U, 1),
+        "i64.load32_s" => (I64Load32S, 2),
+        "i64.load32_u" => (I64Load32U, 2),
+        "i32.store" => (I32Store, 2),
+        "i64.store" => (I64Store, 3),
+        "f32.store" => (F32Store, 2),
+        "f64.store" => (F64Store, 3),
+        "i32.store8" => (I32Store8, 0),
+        "i32.store16" => (I32Store16, 1),
+        "i64.store8" => (I64Store8, 0),
+        "i64.store16" => (I64Store16, 1),
+        "i64.store32" => (I64Store32, 2),
+        _ => return None,
+    })
+}
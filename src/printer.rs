@@ -0,0 +1,648 @@
+use crate::lexer::is_idchar;
+use crate::module::*;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+pub(crate) struct Printer<'a> {
+    module: &'a Module,
+    out: String,
+    func_ids: Vec<Option<String>>,
+}
+
+impl<'a> Printer<'a> {
+    pub fn print(module: &'a Module) -> String {
+        let imported_funcs = module
+            .imports
+            .iter()
+            .filter(|import| matches!(import.desc, ImpExportDesc::Func(_)))
+            .count();
+        let func_count = (imported_funcs + module.funcs.len()) as u32;
+        let mut printer = Printer {
+            module,
+            out: String::new(),
+            func_ids: ids((0..func_count).map(|idx| module.names.func(idx))),
+        };
+        printer.print_module();
+        printer.out
+    }
+
+    pub fn instr(module: &'a Module, instr: &Instr) -> String {
+        let printer = Self::bare(module);
+        match instr {
+            Instr::Block(typ, ..) => format!("block{}", printer.block_type(typ)),
+            Instr::Loop(typ, ..) => format!("loop{}", printer.block_type(typ)),
+            Instr::If(typ, ..) => format!("if{}", printer.block_type(typ)),
+            _ => printer.plain_instr(instr, &[]),
+        }
+    }
+
+    pub fn import_desc(module: &'a Module, desc: &ImpExportDesc) -> String {
+        let kind = match desc {
+            ImpExportDesc::Func(_) => "func",
+            ImpExportDesc::Table(_) => "table",
+            ImpExportDesc::Mem(_) => "memory",
+            ImpExportDesc::Global(_) => "global",
+        };
+        format!("{kind}{}", Self::bare(module).import_type(desc))
+    }
+
+    fn bare(module: &'a Module) -> Self {
+        Printer {
+            module,
+            out: String::new(),
+            func_ids: Vec::new(),
+        }
+    }
+
+    fn print_module(&mut self) {
+        let module = self.module;
+        self.out.push_str("(module");
+        if let Some(name) = module.names.module.as_deref().filter(|name| is_id(name)) {
+            write!(self.out, " ${name}").unwrap();
+        }
+        self.out.push('\n');
+        for (idx, typ) in module.types.iter().enumerate() {
+            let signature = signature(typ, &[]);
+            self.line(1, &format!("(type (;{idx};) (func{signature}))"));
+        }
+        let mut counts = [0; 4];
+        for import in &module.imports {
+            let (kind, counter) = match import.desc {
+                ImpExportDesc::Func(_) => ("func", 0),
+                ImpExportDesc::Table(_) => ("table", 1),
+                ImpExportDesc::Mem(_) => ("memory", 2),
+                ImpExportDesc::Global(_) => ("global", 3),
+            };
+            let idx = counts[counter];
+            counts[counter] += 1;
+            let id = match kind {
+                "func" => self.func_id(idx),
+                _ => String::new(),
+            };
+            let desc = format!("{kind}{id} (;{idx};){}", self.import_type(&import.desc));
+            let module_name = string(import.module.as_bytes());
+            let name = string(import.name.as_bytes());
+            self.line(1, &format!("(import {module_name} {name} ({desc}))"));
+        }
+        for (offset, func) in module.funcs.iter().enumerate() {
+            self.print_func(counts[0] + offset as u32, func);
+        }
+        for (offset, table) in module.table.iter().enumerate() {
+            let idx = counts[1] + offset as u32;
+            self.line(1, &format!("(table (;{idx};) {})", table_type(&table.typ)));
+        }
+        for (offset, mem) in module.memory.iter().enumerate() {
+            let idx = counts[2] + offset as u32;
+            self.line(1, &format!("(memory (;{idx};) {})", limits(&mem.typ.0)));
+        }
+        for (offset, global) in module.globals.iter().enumerate() {
+            let idx = counts[3] + offset as u32;
+            let typ = global_type(&global.typ);
+            let init = self.inline_expr(&global.init);
+            self.line(1, &format!("(global (;{idx};) {typ} {init})"));
+        }
+        for export in &module.exports {
+            let desc = match &export.desc {
+                ExportDesc::Func(idx) => format!("func {}", self.func_ref(idx.0)),
+                ExportDesc::Table(idx) => format!("table {}", idx.0),
+                ExportDesc::Mem(idx) => format!("memory {}", idx.0),
+                ExportDesc::Global(idx) => format!("global {}", idx.0),
+            };
+            let name = string(export.name.as_bytes());
+            self.line(1, &format!("(export {name} ({desc}))"));
+        }
+        if let Some(start) = &module.start {
+            self.line(1, &format!("(start {})", self.func_ref(start.func.0)));
+        }
+        for (idx, elem) in module.elem.iter().enumerate() {
+            let mut text = format!("(elem (;{idx};)");
+            match &elem.mode {
+                ElemMode::Active { table, offset } => {
+                    if table.0 != 0 {
+                        write!(text, " (table {})", table.0).unwrap();
+                    }
+                    write!(text, " {}", self.offset(offset)).unwrap();
+                }
+                ElemMode::Passive => {}
+                ElemMode::Declarative => text.push_str(" declare"),
+            }
+            match elem.func_indices() {
+                Some(funcs) => {
+                    text.push_str(" func");
+                    for func in funcs {
+                        write!(text, " {}", self.func_ref(func)).unwrap();
+                    }
+                }
+                None => {
+                    write!(text, " {}", elem.typ).unwrap();
+                    for item in &elem.init {
+                        write!(text, " {}", self.folded(item, "item")).unwrap();
+                    }
+                }
+            }
+            self.line(1, &format!("{text})"));
+        }
+        for (idx, data) in module.data.iter().enumerate() {
+            let mut text = format!("(data (;{idx};)");
+            if let DataMode::Active { memory, offset } = &data.mode {
+                if memory.0 != 0 {
+                    write!(text, " (memory {})", memory.0).unwrap();
+                }
+                write!(text, " {}", self.offset(offset)).unwrap();
+            }
+            self.line(1, &format!("{text} {})", string(&data.init)));
+        }
+        self.out.push_str(")\n");
+    }
+
+    fn print_func(&mut self, idx: u32, func: &Function) {
+        let params = self
+            .module
+            .types
+            .get(func.typ.0 as usize)
+            .map_or(0, |typ| typ.params.len());
+        let local_count = (params + func.locals.len()) as u32;
+        let local_ids = ids((0..local_count).map(|local| self.module.names.local(idx, local)));
+        let signature = self.type_use(func.typ.0, &local_ids);
+        let id = self.func_id(idx);
+        self.line(1, &format!("(func{id} (;{idx};){signature}"));
+        for (typ, id) in func.locals.iter().zip(&local_ids[params..]) {
+            match id {
+                Some(id) => self.line(2, &format!("(local ${id} {})", val_type(typ))),
+                None => self.line(2, &format!("(local {})", val_type(typ))),
+            }
+        }
+        let mut lines = Vec::new();
+        self.instr_lines(&func.body.0, &local_ids, 2, &mut lines);
+        for (depth, text) in lines {
+            self.line(depth, &text);
+        }
+        self.line(1, ")");
+    }
+
+    fn import_type(&self, desc: &ImpExportDesc) -> String {
+        match desc {
+            ImpExportDesc::Func(typ) => self.type_use(typ.0, &[]),
+            ImpExportDesc::Table(typ) => format!(" {}", table_type(typ)),
+            ImpExportDesc::Mem(typ) => format!(" {}", limits(&typ.0)),
+            ImpExportDesc::Global(typ) => format!(" {}", global_type(typ)),
+        }
+    }
+
+    fn block_type(&self, typ: &BlockType) -> String {
+        match typ {
+            BlockType::Empty => String::new(),
+            BlockType::Value(typ) => format!(" (result {})", val_type(typ)),
+            BlockType::Type(idx) => self.type_use(idx.0, &[]),
+        }
+    }
+
+    fn type_use(&self, typ: u32, param_ids: &[Option<String>]) -> String {
+        match self.module.types.get(typ as usize) {
+            Some(func_type) => format!(" (type {typ}){}", signature(func_type, param_ids)),
+            None => format!(" (type {typ})"),
+        }
+    }
+
+    fn offset(&self, expr: &Expr) -> String {
+        self.folded(expr, "offset")
+    }
+
+    // A single instruction is written folded, anything longer is wrapped in
+    // the given keyword.
+    fn folded(&self, expr: &Expr, keyword: &str) -> String {
+        let mut lines = Vec::new();
+        self.instr_lines(&expr.0, &[], 0, &mut lines);
+        match &lines[..] {
+            [(_, instr)] => format!("({instr})"),
+            _ => format!("({keyword} {})", self.inline_expr(expr)),
+        }
+    }
+
+    fn inline_expr(&self, expr: &Expr) -> String {
+        let mut lines = Vec::new();
+        self.instr_lines(&expr.0, &[], 0, &mut lines);
+        let texts = lines.into_iter().map(|(_, text)| text).collect::<Vec<_>>();
+        texts.join(" ")
+    }
+
+    fn instr_lines(
+        &self,
+        instrs: &[Instr],
+        locals: &[Option<String>],
+        depth: usize,
+        lines: &mut Vec<(usize, String)>,
+    ) {
+        for instr in instrs {
+            match instr {
+                Instr::Block(typ, body, End) | Instr::Loop(typ, body, End) => {
+                    let name = match instr {
+                        Instr::Block(..) => "block",
+                        _ => "loop",
+                    };
+                    lines.push((depth, format!("{name}{}", self.block_type(typ))));
+                    self.instr_lines(body, locals, depth + 1, lines);
+                    lines.push((depth, "end".to_owned()));
+                }
+                Instr::If(typ, if_instrs, Else, else_instrs, End) => {
+                    lines.push((depth, format!("if{}", self.block_type(typ))));
+                    self.instr_lines(if_instrs, locals, depth + 1, lines);
+                    if !else_instrs.is_empty() {
+                        lines.push((depth, "else".to_owned()));
+                        self.instr_lines(else_instrs, locals, depth + 1, lines);
+                    }
+                    lines.push((depth, "end".to_owned()));
+                }
+                _ => lines.push((depth, self.plain_instr(instr, locals))),
+            }
+        }
+    }
+
+    fn plain_instr(&self, instr: &Instr, locals: &[Option<String>]) -> String {
+        let local = |idx: &LocalIdx| match locals.get(idx.0 as usize) {
+            Some(Some(id)) => format!("${id}"),
+            _ => idx.0.to_string(),
+        };
+        match instr {
+            Instr::Br(label) => format!("br {}", label.0),
+            Instr::BrIf(label) => format!("br_if {}", label.0),
+            Instr::BrTable(labels, default) => {
+                let mut text = String::from("br_table");
+                for label in labels.iter().chain([default]) {
+                    write!(text, " {}", label.0).unwrap();
+                }
+                text
+            }
+            Instr::Call(func) => format!("call {}", self.func_ref(func.0)),
+            Instr::CallIndirect(typ, TableIdx(0)) => {
+                format!("call_indirect{}", self.type_use(typ.0, &[]))
+            }
+            Instr::CallIndirect(typ, table) => {
+                format!("call_indirect {}{}", table.0, self.type_use(typ.0, &[]))
+            }
+            Instr::TypedSelect(types) => {
+                let mut text = String::from("select (result");
+                for typ in types {
+                    write!(text, " {}", val_type(typ)).unwrap();
+                }
+                text + ")"
+            }
+            Instr::LocalGet(idx) => format!("local.get {}", local(idx)),
+            Instr::LocalSet(idx) => format!("local.set {}", local(idx)),
+            Instr::LocalTee(idx) => format!("local.tee {}", local(idx)),
+            Instr::GlobalGet(idx) => format!("global.get {}", idx.0),
+            Instr::GlobalSet(idx) => format!("global.set {}", idx.0),
+            Instr::TableGet(idx) => format!("table.get {}", idx.0),
+            Instr::TableSet(idx) => format!("table.set {}", idx.0),
+            Instr::MemoryInit(idx) => format!("memory.init {}", idx.0),
+            Instr::DataDrop(idx) => format!("data.drop {}", idx.0),
+            Instr::TableInit(elem, TableIdx(0)) => format!("table.init {}", elem.0),
+            Instr::TableInit(elem, table) => format!("table.init {} {}", table.0, elem.0),
+            Instr::ElemDrop(idx) => format!("elem.drop {}", idx.0),
+            Instr::TableCopy(TableIdx(0), TableIdx(0)) => "table.copy".to_owned(),
+            Instr::TableCopy(dst, src) => format!("table.copy {} {}", dst.0, src.0),
+            Instr::TableGrow(idx) => format!("table.grow {}", idx.0),
+            Instr::TableSize(idx) => format!("table.size {}", idx.0),
+            Instr::TableFill(idx) => format!("table.fill {}", idx.0),
+            Instr::RefNull(ElemType::FuncRef) => "ref.null func".to_owned(),
+            Instr::RefNull(ElemType::ExternRef) => "ref.null extern".to_owned(),
+            Instr::RefFunc(func) => format!("ref.func {}", self.func_ref(func.0)),
+            Instr::I32Const(value) => format!("i32.const {value}"),
+            Instr::I64Const(value) => format!("i64.const {value}"),
+            Instr::F32Const(value) => format!("f32.const {}", f32_literal(*value)),
+            Instr::F64Const(value) => format!("f64.const {}", f64_literal(*value)),
+            _ => match memory_instr(instr) {
+                Some((name, mem_arg, natural_align)) => {
+                    let mut text = name.to_owned();
+                    if mem_arg.offset != 0 {
+                        write!(text, " offset={}", mem_arg.offset).unwrap();
+                    }
+                    if mem_arg.align != natural_align {
+                        let align = 1_u64.checked_shl(mem_arg.align).unwrap_or(0);
+                        write!(text, " align={align}").unwrap();
+                    }
+                    text
+                }
+                None => instr_name(instr).to_owned(),
+            },
+        }
+    }
+
+    fn func_id(&self, idx: u32) -> String {
+        match self.func_ids.get(idx as usize) {
+            Some(Some(id)) => format!(" ${id}"),
+            _ => String::new(),
+        }
+    }
+
+    fn func_ref(&self, idx: u32) -> String {
+        match self.func_ids.get(idx as usize) {
+            Some(Some(id)) => format!("${id}"),
+            _ => idx.to_string(),
+        }
+    }
+
+    fn line(&mut self, depth: usize, text: &str) {
+        for _ in 0..depth {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+}
+
+// Names become identifiers only if they are valid and unique; anything else
+// is printed by index so that the output always parses back.
+fn ids<'n>(names: impl Iterator<Item = Option<&'n str>>) -> Vec<Option<String>> {
+    let names = names.collect::<Vec<_>>();
+    let mut seen = HashSet::new();
+    let duplicates = names
+        .iter()
+        .flatten()
+        .filter(|name| !seen.insert(**name))
+        .copied()
+        .collect::<HashSet<_>>();
+    names
+        .into_iter()
+        .map(|name| {
+            name.filter(|name| is_id(name) && !duplicates.contains(name))
+                .map(str::to_owned)
+        })
+        .collect()
+}
+
+fn is_id(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(is_idchar)
+}
+
+pub(crate) fn signature(typ: &FuncType, param_ids: &[Option<String>]) -> String {
+    let mut text = String::new();
+    let named = typ.params.iter().zip(param_ids).any(|(_, id)| id.is_some());
+    if named {
+        for (idx, param) in typ.params.iter().enumerate() {
+            match param_ids.get(idx) {
+                Some(Some(id)) => write!(text, " (param ${id} {})", val_type(param)).unwrap(),
+                _ => write!(text, " (param {})", val_type(param)).unwrap(),
+            }
+        }
+    } else if !typ.params.is_empty() {
+        write!(text, " (param {})", val_types(&typ.params)).unwrap();
+    }
+    if !typ.results.is_empty() {
+        write!(text, " (result {})", val_types(&typ.results)).unwrap();
+    }
+    text
+}
+
+fn val_types(types: &[ValType]) -> String {
+    types.iter().map(val_type).collect::<Vec<_>>().join(" ")
+}
+
+fn val_type(typ: &ValType) -> &'static str {
+    match typ {
+        ValType::I32 => "i32",
+        ValType::I64 => "i64",
+        ValType::F32 => "f32",
+        ValType::F64 => "f64",
+        ValType::FuncRef => "funcref",
+        ValType::ExternRef => "externref",
+    }
+}
+
+fn table_type(typ: &TableType) -> String {
+    format!("{} {}", limits(&typ.0), typ.1)
+}
+
+fn global_type(typ: &GlobalType) -> String {
+    match typ.0 {
+        Mut::Const => val_type(&typ.1).to_owned(),
+        Mut::Var => format!("(mut {})", val_type(&typ.1)),
+    }
+}
+
+fn limits(limits: &Limits) -> String {
+    match limits.max {
+        Some(max) => format!("{} {max}", limits.min),
+        None => limits.min.to_string(),
+    }
+}
+
+fn string(bytes: &[u8]) -> String {
+    let mut text = String::from("\"");
+    for byte in bytes {
+        match byte {
+            b'"' | b'\\' => write!(text, "\\{}", *byte as char).unwrap(),
+            0x20..=0x7e => text.push(*byte as char),
+            _ => write!(text, "\\{byte:02x}").unwrap(),
+        }
+    }
+    text.push('"');
+    text
+}
+
+pub(crate) fn f32_literal(value: f32) -> String {
+    match value.is_nan() {
+        true => nan_literal(value.is_sign_negative(), value.to_bits().into(), 23),
+        false => format!("{value:?}"),
+    }
+}
+
+pub(crate) fn f64_literal(value: f64) -> String {
+    match value.is_nan() {
+        true => nan_literal(value.is_sign_negative(), value.to_bits(), 52),
+        false => format!("{value:?}"),
+    }
+}
+
+fn nan_literal(negative: bool, bits: u64, mantissa_bits: u32) -> String {
+    let sign = if negative { "-" } else { "" };
+    let payload = bits & ((1 << mantissa_bits) - 1);
+    if payload == 1 << (mantissa_bits - 1) {
+        format!("{sign}nan")
+    } else {
+        format!("{sign}nan:{payload:#x}")
+    }
+}
+
+fn memory_instr(instr: &Instr) -> Option<(&'static str, &MemArg, u32)> {
+    use Instr::*;
+    Some(match instr {
+        I32Load(mem_arg) => ("i32.load", mem_arg, 2),
+        I64Load(mem_arg) => ("i64.load", mem_arg, 3),
+        F32Load(mem_arg) => ("f32.load", mem_arg, 2),
+        F64Load(mem_arg) => ("f64.load", mem_arg, 3),
+        I32Load8S(mem_arg) => ("i32.load8_s", mem_arg, 0),
+        I32Load8U(mem_arg) => ("i32.load8_u", mem_arg, 0),
+        I32Load16S(mem_arg) => ("i32.load16_s", mem_arg, 1),
+        I32Load16U(mem_arg) => ("i32.load16_u", mem_arg, 1),
+        I64Load8S(mem_arg) => ("i64.load8_s", mem_arg, 0),
+        I64Load8U(mem_arg) => ("i64.load8_u", mem_arg, 0),
+        I64Load16S(mem_arg) => ("i64.load16_s", mem_arg, 1),
+        I64Load16U(mem_arg) => ("i64.load16_u", mem_arg, 1),
+        I64Load32S(mem_arg) => ("i64.load32_s", mem_arg, 2),
+        I64Load32U(mem_arg) => ("i64.load32_u", mem_arg, 2),
+        I32Store(mem_arg) => ("i32.store", mem_arg, 2),
+        I64Store(mem_arg) => ("i64.store", mem_arg, 3),
+        F32Store(mem_arg) => ("f32.store", mem_arg, 2),
+        F64Store(mem_arg) => ("f64.store", mem_arg, 3),
+        I32Store8(mem_arg) => ("i32.store8", mem_arg, 0),
+        I32Store16(mem_arg) => ("i32.store16", mem_arg, 1),
+        I64Store8(mem_arg) => ("i64.store8", mem_arg, 0),
+        I64Store16(mem_arg) => ("i64.store16", mem_arg, 1),
+        I64Store32(mem_arg) => ("i64.store32", mem_arg, 2),
+        _ => return None,
+    })
+}
+
+fn instr_name(instr: &Instr) -> &'static str {
+    use Instr::*;
+    match instr {
+        Unreachable => "unreachable",
+        Nop => "nop",
+        Return => "return",
+        Drop => "drop",
+        Select => "select",
+        RefIsNull => "ref.is_null",
+        MemorySize => "memory.size",
+        MemoryGrow => "memory.grow",
+        MemoryCopy => "memory.copy",
+        MemoryFill => "memory.fill",
+        I32Eqz => "i32.eqz",
+        I32Eq => "i32.eq",
+        I32Ne => "i32.ne",
+        I32LtS => "i32.lt_s",
+        I32LtU => "i32.lt_u",
+        I32GtS => "i32.gt_s",
+        I32GtU => "i32.gt_u",
+        I32LeS => "i32.le_s",
+        I32LeU => "i32.le_u",
+        I32GeS => "i32.ge_s",
+        I32GeU => "i32.ge_u",
+        I64Eqz => "i64.eqz",
+        I64Eq => "i64.eq",
+        I64Ne => "i64.ne",
+        I64LtS => "i64.lt_s",
+        I64LtU => "i64.lt_u",
+        I64GtS => "i64.gt_s",
+        I64GtU => "i64.gt_u",
+        I64LeS => "i64.le_s",
+        I64LeU => "i64.le_u",
+        I64GeS => "i64.ge_s",
+        I64GeU => "i64.ge_u",
+        F32Eq => "f32.eq",
+        F32Ne => "f32.ne",
+        F32Lt => "f32.lt",
+        F32Gt => "f32.gt",
+        F32Le => "f32.le",
+        F32Ge => "f32.ge",
+        F64Eq => "f64.eq",
+        F64Ne => "f64.ne",
+        F64Lt => "f64.lt",
+        F64Gt => "f64.gt",
+        F64Le => "f64.le",
+        F64Ge => "f64.ge",
+        I32Clz => "i32.clz",
+        I32Ctz => "i32.ctz",
+        I32Popcnt => "i32.popcnt",
+        I32Add => "i32.add",
+        I32Sub => "i32.sub",
+        I32Mul => "i32.mul",
+        I32DivS => "i32.div_s",
+        I32DivU => "i32.div_u",
+        I32RemS => "i32.rem_s",
+        I32RemU => "i32.rem_u",
+        I32And => "i32.and",
+        I32Or => "i32.or",
+        I32Xor => "i32.xor",
+        I32Shl => "i32.shl",
+        I32ShrS => "i32.shr_s",
+        I32ShrU => "i32.shr_u",
+        I32Rotl => "i32.rotl",
+        I32Rotr => "i32.rotr",
+        I64Clz => "i64.clz",
+        I64Ctz => "i64.ctz",
+        I64Popcnt => "i64.popcnt",
+        I64Add => "i64.add",
+        I64Sub => "i64.sub",
+        I64Mul => "i64.mul",
+        I64DivS => "i64.div_s",
+        I64DivU => "i64.div_u",
+        I64RemS => "i64.rem_s",
+        I64RemU => "i64.rem_u",
+        I64And => "i64.and",
+        I64Or => "i64.or",
+        I64Xor => "i64.xor",
+        I64Shl => "i64.shl",
+        I64ShrS => "i64.shr_s",
+        I64ShrU => "i64.shr_u",
+        I64Rotl => "i64.rotl",
+        I64Rotr => "i64.rotr",
+        F32Abs => "f32.abs",
+        F32Neg => "f32.neg",
+        F32Ceil => "f32.ceil",
+        F32Floor => "f32.floor",
+        F32Trunc => "f32.trunc",
+        F32Nearest => "f32.nearest",
+        F32Sqrt => "f32.sqrt",
+        F32Add => "f32.add",
+        F32Sub => "f32.sub",
+        F32Mul => "f32.mul",
+        F32Div => "f32.div",
+        F32Min => "f32.min",
+        F32Max => "f32.max",
+        F32Copysign => "f32.copysign",
+        F64Abs => "f64.abs",
+        F64Neg => "f64.neg",
+        F64Ceil => "f64.ceil",
+        F64Floor => "f64.floor",
+        F64Trunc => "f64.trunc",
+        F64Nearest => "f64.nearest",
+        F64Sqrt => "f64.sqrt",
+        F64Add => "f64.add",
+        F64Sub => "f64.sub",
+        F64Mul => "f64.mul",
+        F64Div => "f64.div",
+        F64Min => "f64.min",
+        F64Max => "f64.max",
+        F64Copysign => "f64.copysign",
+        I32WrapI64 => "i32.wrap_i64",
+        I32TruncF32S => "i32.trunc_f32_s",
+        I32TruncF32U => "i32.trunc_f32_u",
+        I32TruncF64S => "i32.trunc_f64_s",
+        I32TruncF64U => "i32.trunc_f64_u",
+        I64ExtendI32S => "i64.extend_i32_s",
+        I64ExtendI32U => "i64.extend_i32_u",
+        I64TruncF32S => "i64.trunc_f32_s",
+        I64TruncF32U => "i64.trunc_f32_u",
+        I64TruncF64S => "i64.trunc_f64_s",
+        I64TruncF64U => "i64.trunc_f64_u",
+        F32ConvertI32S => "f32.convert_i32_s",
+        F32ConvertI32U => "f32.convert_i32_u",
+        F32ConvertI64S => "f32.convert_i64_s",
+        F32ConvertI64U => "f32.convert_i64_u",
+        F32DemoteF64 => "f32.demote_f64",
+        F64ConvertI32S => "f64.convert_i32_s",
+        F64ConvertI32U => "f64.convert_i32_u",
+        F64ConvertI64S => "f64.convert_i64_s",
+        F64ConvertI64U => "f64.convert_i64_u",
+        F64PromoteF32 => "f64.promote_f32",
+        I32ReinterpretF32 => "i32.reinterpret_f32",
+        I64ReinterpretF64 => "i64.reinterpret_f64",
+        F32ReinterpretI32 => "f32.reinterpret_i32",
+        F64ReinterpretI64 => "f64.reinterpret_i64",
+        I32Extend8S => "i32.extend8_s",
+        I32Extend16S => "i32.extend16_s",
+        I64Extend8S => "i64.extend8_s",
+        I64Extend16S => "i64.extend16_s",
+        I64Extend32S => "i64.extend32_s",
+        I32TruncSatF32S => "i32.trunc_sat_f32_s",
+        I32TruncSatF32U => "i32.trunc_sat_f32_u",
+        I32TruncSatF64S => "i32.trunc_sat_f64_s",
+        I32TruncSatF64U => "i32.trunc_sat_f64_u",
+        I64TruncSatF32S => "i64.trunc_sat_f32_s",
+        I64TruncSatF32U => "i64.trunc_sat_f32_u",
+        I64TruncSatF64S => "i64.trunc_sat_f64_s",
+        I64TruncSatF64U => "i64.trunc_sat_f64_u",
+        _ => unreachable!("instructions with immediates are printed separately"),
+    }
+}
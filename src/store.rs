@@ -0,0 +1,558 @@
+use crate::error::LinkError;
+use crate::executor::Executor;
+use crate::instance::{Caller, Extern, Func, Global, Memory, Table};
+use crate::module::*;
+use crate::trap::{Trap, TrapKind};
+use crate::validator::Validator;
+use crate::value::Value;
+use std::any::Any;
+use std::ops::Range;
+use std::rc::Rc;
+
+pub const PAGE_SIZE: usize = 65536;
+pub const MAX_PAGES: u32 = 65536;
+// Tables have no natural size limit, so growth is capped to keep a runaway
+// table.grow from exhausting host memory.
+pub const MAX_TABLE_ELEMENTS: u32 = 10_000_000;
+
+pub type FuncAddr = usize;
+pub type TableAddr = usize;
+pub type MemAddr = usize;
+pub type GlobalAddr = usize;
+pub type ElemAddr = usize;
+pub type DataAddr = usize;
+pub type ExternAddr = usize;
+pub type ModuleAddr = usize;
+
+#[derive(Default)]
+pub struct Store {
+    pub(crate) funcs: Vec<FuncInst>,
+    pub(crate) tables: Vec<TableInst>,
+    pub(crate) mems: Vec<MemInst>,
+    pub(crate) globals: Vec<GlobalInst>,
+    pub(crate) elems: Vec<ElemInst>,
+    pub(crate) datas: Vec<DataInst>,
+    pub(crate) externs: Vec<Box<dyn Any>>,
+    pub(crate) instances: Vec<ModuleInst>,
+    pub(crate) fuel: Option<u64>,
+    pub(crate) trace: bool,
+    pub(crate) memory_limit: Option<u32>,
+}
+
+pub(crate) type HostFunc = Box<dyn Fn(&mut Caller, &[Value]) -> Result<Vec<Value>, Trap>>;
+
+pub(crate) enum FuncInst {
+    Wasm {
+        typ: FuncType,
+        instance: ModuleAddr,
+        module: Rc<Module>,
+        code: usize,
+    },
+    Host {
+        typ: FuncType,
+        func: HostFunc,
+    },
+}
+
+pub(crate) struct TableInst {
+    pub elements: Vec<Value>,
+    pub max: Option<u32>,
+    pub typ: ElemType,
+}
+
+pub(crate) struct MemInst {
+    pub data: Vec<u8>,
+    pub max: Option<u32>,
+    pub limit: u32,
+}
+
+pub(crate) struct GlobalInst {
+    pub value: Value,
+    pub mutability: Mut,
+}
+
+pub(crate) struct ElemInst {
+    pub elements: Vec<Value>,
+}
+
+pub(crate) struct DataInst {
+    pub data: Vec<u8>,
+}
+
+#[derive(Default)]
+pub(crate) struct ModuleInst {
+    pub types: Vec<FuncType>,
+    pub func_addrs: Vec<FuncAddr>,
+    pub table_addrs: Vec<TableAddr>,
+    pub mem_addrs: Vec<MemAddr>,
+    pub global_addrs: Vec<GlobalAddr>,
+    pub elem_addrs: Vec<ElemAddr>,
+    pub data_addrs: Vec<DataAddr>,
+    pub exports: Vec<ExportInst>,
+}
+
+pub(crate) struct ExportInst {
+    pub name: String,
+    pub value: Extern,
+}
+
+impl FuncInst {
+    pub fn typ(&self) -> &FuncType {
+        match self {
+            FuncInst::Wasm { typ, .. } | FuncInst::Host { typ, .. } => typ,
+        }
+    }
+}
+
+impl TableInst {
+    pub(crate) fn new(typ: &TableType) -> Self {
+        let TableType(limits, elem_type) = typ;
+        Self {
+            elements: vec![Value::default_for((*elem_type).into()); limits.min as usize],
+            max: limits.max,
+            typ: *elem_type,
+        }
+    }
+
+    pub fn grow(&mut self, delta: u32, init: Value) -> Option<u32> {
+        let old_size = self.elements.len() as u32;
+        let new_size = old_size.checked_add(delta)?;
+        if new_size > self.max.unwrap_or(u32::MAX).min(MAX_TABLE_ELEMENTS) {
+            return None;
+        }
+        self.elements.resize(new_size as usize, init);
+        Some(old_size)
+    }
+}
+
+impl MemInst {
+    pub(crate) fn new(typ: &MemType, limit: Option<u32>) -> Self {
+        let MemType(limits) = typ;
+        let max = limits.max.unwrap_or(MAX_PAGES).min(MAX_PAGES);
+        Self {
+            data: vec![0; limits.min as usize * PAGE_SIZE],
+            max: limits.max,
+            limit: limit.map_or(max, |limit| limit.min(max)),
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        (self.data.len() / PAGE_SIZE) as u32
+    }
+
+    pub fn grow(&mut self, delta: u32) -> Option<u32> {
+        let old_size = self.size();
+        let new_size = old_size.checked_add(delta)?;
+        if new_size > self.limit {
+            return None;
+        }
+        self.data.resize(new_size as usize * PAGE_SIZE, 0);
+        Some(old_size)
+    }
+}
+
+// Returns the range `offset..offset + len` if it lies within `size`, the
+// bounds check shared by segment initialization and the bulk instructions.
+pub(crate) fn range(offset: u32, len: u32, size: usize) -> Option<Range<usize>> {
+    let end = offset as usize + len as usize;
+    (end <= size).then_some(offset as usize..end)
+}
+
+impl ModuleInst {
+    pub fn export(&self, name: &str) -> Option<Extern> {
+        self.exports
+            .iter()
+            .find(|export| export.name == name)
+            .map(|export| export.value)
+    }
+}
+
+impl Store {
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+
+    pub fn set_memory_limit(&mut self, pages: Option<u32>) {
+        self.memory_limit = pages;
+    }
+
+    pub(crate) fn instantiate(
+        &mut self,
+        module: &Module,
+        externs: &[Extern],
+    ) -> Result<ModuleAddr, LinkError> {
+        // Every field of a module is public, so one that didn't come from
+        // the decoder or the parser is checked here before anything indexes
+        // into it.
+        Validator::validate(module).map_err(LinkError::InvalidModule)?;
+        if externs.len() != module.imports.len() {
+            return Err(LinkError::ImportCountMismatch {
+                expected: module.imports.len(),
+                actual: externs.len(),
+            });
+        }
+        let module = Rc::new(module.clone());
+        let addr = self.instances.len();
+        // Everything allocated for an instance that fails before it is
+        // registered is released again, so that no function is left pointing
+        // at an instance address the next instantiation would reuse.
+        let sizes = self.sizes();
+        let instance = self
+            .allocate(&module, addr, externs)
+            .inspect_err(|_| self.truncate(sizes))?;
+        let start = module
+            .start
+            .as_ref()
+            .map(|start| instance.func_addrs[start.func.0 as usize]);
+        // The instance is registered before its segments are applied: a
+        // segment that does not fit aborts instantiation, but the writes of
+        // earlier segments stay visible in shared tables and memories and may
+        // refer to this instance's functions.
+        self.instances.push(instance);
+        self.init_segments(&module, addr)?;
+        if let Some(start) = start {
+            self.invoke(start, Vec::new())
+                .map_err(LinkError::StartTrap)?;
+        }
+        Ok(addr)
+    }
+
+    fn allocate(
+        &mut self,
+        module: &Rc<Module>,
+        addr: ModuleAddr,
+        externs: &[Extern],
+    ) -> Result<ModuleInst, LinkError> {
+        let mut instance = ModuleInst {
+            types: module.types.clone(),
+            ..Default::default()
+        };
+        for (import, external) in module.imports.iter().zip(externs) {
+            self.check_import(module, import, external)
+                .map_err(|reason| LinkError::IncompatibleImport {
+                    module: import.module.clone(),
+                    name: import.name.clone(),
+                    reason,
+                })?;
+            match *external {
+                Extern::Func(Func(a)) => instance.func_addrs.push(a),
+                Extern::Table(Table(a)) => instance.table_addrs.push(a),
+                Extern::Memory(Memory(a)) => instance.mem_addrs.push(a),
+                Extern::Global(Global(a)) => instance.global_addrs.push(a),
+            }
+        }
+        for (code, func) in module.funcs.iter().enumerate() {
+            instance.func_addrs.push(self.funcs.len());
+            self.funcs.push(FuncInst::Wasm {
+                typ: module.types[func.typ.0 as usize].clone(),
+                instance: addr,
+                module: Rc::clone(module),
+                code,
+            });
+        }
+        for table in &module.table {
+            let TableType(limits, _) = &table.typ;
+            if limits.min > MAX_TABLE_ELEMENTS {
+                return Err(LinkError::TableLimitExceeded {
+                    elements: limits.min,
+                    limit: MAX_TABLE_ELEMENTS,
+                });
+            }
+            instance.table_addrs.push(self.tables.len());
+            self.tables.push(TableInst::new(&table.typ));
+        }
+        for mem in &module.memory {
+            let MemType(limits) = &mem.typ;
+            if let Some(limit) = self.memory_limit.filter(|limit| limits.min > *limit) {
+                return Err(LinkError::MemoryLimitExceeded {
+                    pages: limits.min,
+                    limit,
+                });
+            }
+            instance.mem_addrs.push(self.mems.len());
+            self.mems.push(MemInst::new(&mem.typ, self.memory_limit));
+        }
+        let mut globals = Vec::new();
+        for global in &module.globals {
+            let GlobalType(mutability, _) = global.typ;
+            let value = self.eval_const_expr(&global.init, &instance)?;
+            globals.push(GlobalInst { value, mutability });
+        }
+        for global in globals {
+            instance.global_addrs.push(self.globals.len());
+            self.globals.push(global);
+        }
+        for export in &module.exports {
+            let value = match &export.desc {
+                ExportDesc::Func(FuncIdx(idx)) => {
+                    Extern::Func(Func(instance.func_addrs[*idx as usize]))
+                }
+                ExportDesc::Table(TableIdx(idx)) => {
+                    Extern::Table(Table(instance.table_addrs[*idx as usize]))
+                }
+                ExportDesc::Mem(MemIdx(idx)) => {
+                    Extern::Memory(Memory(instance.mem_addrs[*idx as usize]))
+                }
+                ExportDesc::Global(GlobalIdx(idx)) => {
+                    Extern::Global(Global(instance.global_addrs[*idx as usize]))
+                }
+            };
+            instance.exports.push(ExportInst {
+                name: export.name.clone(),
+                value,
+            });
+        }
+        for elem in &module.elem {
+            let elements = elem
+                .init
+                .iter()
+                .map(|expr| self.eval_const_expr(expr, &instance))
+                .collect::<Result<_, _>>()?;
+            instance.elem_addrs.push(self.elems.len());
+            self.elems.push(ElemInst { elements });
+        }
+        for data in &module.data {
+            instance.data_addrs.push(self.datas.len());
+            self.datas.push(DataInst {
+                data: data.init.clone(),
+            });
+        }
+        Ok(instance)
+    }
+
+    fn sizes(&self) -> [usize; 6] {
+        [
+            self.funcs.len(),
+            self.tables.len(),
+            self.mems.len(),
+            self.globals.len(),
+            self.elems.len(),
+            self.datas.len(),
+        ]
+    }
+
+    fn truncate(&mut self, [funcs, tables, mems, globals, elems, datas]: [usize; 6]) {
+        self.funcs.truncate(funcs);
+        self.tables.truncate(tables);
+        self.mems.truncate(mems);
+        self.globals.truncate(globals);
+        self.elems.truncate(elems);
+        self.datas.truncate(datas);
+    }
+
+    fn init_segments(&mut self, module: &Module, addr: ModuleAddr) -> Result<(), LinkError> {
+        let instance = &self.instances[addr];
+        for (idx, elem) in module.elem.iter().enumerate() {
+            let elem_addr = instance.elem_addrs[idx];
+            if let ElemMode::Active { table, offset } = &elem.mode {
+                let offset = self.eval_offset(offset, instance)?;
+                let table = &mut self.tables[instance.table_addrs[table.0 as usize]];
+                let elements = &self.elems[elem_addr].elements;
+                let range = range(offset, elements.len() as u32, table.elements.len())
+                    .ok_or(LinkError::ElementSegmentDoesNotFit(idx as u32))?;
+                table.elements[range].copy_from_slice(elements);
+            }
+            if elem.mode != ElemMode::Passive {
+                self.elems[elem_addr].elements = Vec::new();
+            }
+        }
+        for (idx, data) in module.data.iter().enumerate() {
+            let DataMode::Active { memory, offset } = &data.mode else {
+                continue;
+            };
+            let data_addr = instance.data_addrs[idx];
+            let offset = self.eval_offset(offset, instance)?;
+            let mem = &mut self.mems[instance.mem_addrs[memory.0 as usize]];
+            let bytes = &self.datas[data_addr].data;
+            let range = range(offset, bytes.len() as u32, mem.data.len())
+                .ok_or(LinkError::DataSegmentDoesNotFit(idx as u32))?;
+            mem.data[range].copy_from_slice(bytes);
+            self.datas[data_addr].data = Vec::new();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn invoke(&mut self, addr: FuncAddr, args: Vec<Value>) -> Result<Vec<Value>, Trap> {
+        let typ = self.funcs[addr].typ();
+        if args.len() != typ.params.len()
+            || args
+                .iter()
+                .zip(&typ.params)
+                .any(|(arg, typ)| arg.typ() != *typ)
+        {
+            return Err(Trap::from(TrapKind::ArgumentMismatch {
+                expected: typ.params.clone(),
+                actual: args.iter().map(Value::typ).collect(),
+            }));
+        }
+        Executor::new(self).invoke(addr, args)
+    }
+
+    fn check_import(
+        &self,
+        module: &Module,
+        import: &Import,
+        external: &Extern,
+    ) -> Result<(), String> {
+        match (&import.desc, external) {
+            (ImpExportDesc::Func(TypeIdx(idx)), Extern::Func(Func(addr))) => {
+                let expected = &module.types[*idx as usize];
+                let actual = self.funcs[*addr].typ();
+                if expected != actual {
+                    return Err(format!(
+                        "expected function {expected:?} but found function {actual:?}"
+                    ));
+                }
+            }
+            (ImpExportDesc::Table(TableType(limits, typ)), Extern::Table(Table(addr))) => {
+                let table = &self.tables[*addr];
+                if table.typ != *typ {
+                    return Err(format!(
+                        "expected table of {typ} but found table of {}",
+                        table.typ
+                    ));
+                }
+                Self::check_limits(limits, table.elements.len() as u32, table.max)?;
+            }
+            (ImpExportDesc::Mem(MemType(limits)), Extern::Memory(Memory(addr))) => {
+                let mem = &self.mems[*addr];
+                Self::check_limits(limits, mem.size(), mem.max)?;
+            }
+            (ImpExportDesc::Global(GlobalType(mutability, typ)), Extern::Global(Global(addr))) => {
+                let global = &self.globals[*addr];
+                if global.mutability != *mutability || global.value.typ() != *typ {
+                    return Err(format!(
+                        "expected global {mutability:?} {typ:?} but found global {:?} {:?}",
+                        global.mutability,
+                        global.value.typ()
+                    ));
+                }
+            }
+            (desc, external) => {
+                return Err(format!(
+                    "expected {} but found {}",
+                    Self::desc_kind(desc),
+                    Self::extern_kind(external)
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn check_limits(expected: &Limits, size: u32, max: Option<u32>) -> Result<(), String> {
+        if size < expected.min {
+            return Err(format!(
+                "expected a minimum size of {} but found {size}",
+                expected.min
+            ));
+        }
+        match (expected.max, max) {
+            (Some(expected), None) => Err(format!(
+                "expected a maximum size of {expected} but found no maximum"
+            )),
+            (Some(expected), Some(max)) if max > expected => Err(format!(
+                "expected a maximum size of {expected} but found {max}"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn desc_kind(desc: &ImpExportDesc) -> &'static str {
+        match desc {
+            ImpExportDesc::Func(_) => "function",
+            ImpExportDesc::Table(_) => "table",
+            ImpExportDesc::Mem(_) => "memory",
+            ImpExportDesc::Global(_) => "global",
+        }
+    }
+
+    fn extern_kind(external: &Extern) -> &'static str {
+        match external {
+            Extern::Func(_) => "function",
+            Extern::Table(_) => "table",
+            Extern::Memory(_) => "memory",
+            Extern::Global(_) => "global",
+        }
+    }
+
+    fn eval_offset(&self, expr: &Expr, instance: &ModuleInst) -> Result<u32, LinkError> {
+        match self.eval_const_expr(expr, instance)? {
+            Value::I32(offset) => Ok(offset as u32),
+            _ => Err(LinkError::InvalidConstExpr),
+        }
+    }
+
+    fn eval_const_expr(&self, expr: &Expr, instance: &ModuleInst) -> Result<Value, LinkError> {
+        match expr.0.as_slice() {
+            [Instr::I32Const(v)] => Ok(Value::I32(*v)),
+            [Instr::I64Const(v)] => Ok(Value::I64(*v)),
+            [Instr::F32Const(v)] => Ok(Value::F32(*v)),
+            [Instr::F64Const(v)] => Ok(Value::F64(*v)),
+            [Instr::GlobalGet(GlobalIdx(idx))] => instance
+                .global_addrs
+                .get(*idx as usize)
+                .map(|addr| self.globals[*addr].value)
+                .ok_or(LinkError::InvalidConstExpr),
+            [Instr::RefNull(typ)] => Ok(Value::default_for((*typ).into())),
+            [Instr::RefFunc(FuncIdx(idx))] => instance
+                .func_addrs
+                .get(*idx as usize)
+                .map(|addr| Value::from(Func(*addr)))
+                .ok_or(LinkError::InvalidConstExpr),
+            _ => Err(LinkError::InvalidConstExpr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_instantiation_releases_its_allocations() {
+        let module = Module::from_wat(
+            r#"(module
+              (func $f)
+              (table 1 funcref)
+              (global funcref (ref.func $f))
+              (memory 4))"#,
+        )
+        .unwrap();
+        let mut store = Store::default();
+        store.set_memory_limit(Some(1));
+        assert_eq!(
+            store.instantiate(&module, &[]),
+            Err(LinkError::MemoryLimitExceeded { pages: 4, limit: 1 })
+        );
+        assert_eq!(store.sizes(), [0; 6]);
+        store.set_memory_limit(None);
+        assert_eq!(store.instantiate(&module, &[]), Ok(0));
+        let FuncInst::Wasm { instance, .. } = &store.funcs[0] else {
+            panic!("expected a wasm function");
+        };
+        assert_eq!(*instance, 0);
+        assert_eq!(store.sizes(), [1, 1, 1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn huge_tables_are_refused() {
+        let module = Module::from_wat("(module (func) (table 4294967295 funcref))").unwrap();
+        let mut store = Store::default();
+        assert_eq!(
+            store.instantiate(&module, &[]),
+            Err(LinkError::TableLimitExceeded {
+                elements: u32::MAX,
+                limit: MAX_TABLE_ELEMENTS,
+            })
+        );
+        assert_eq!(store.sizes(), [0; 6]);
+    }
+}
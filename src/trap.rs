@@ -0,0 +1,137 @@
+use crate::module::ValType;
+use std::{error::Error, fmt};
+
+const MAX_DISPLAYED_FRAMES: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trap {
+    kind: TrapKind,
+    backtrace: Vec<FrameInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameInfo {
+    pub func_idx: u32,
+    pub func_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrapKind {
+    Unreachable,
+    IntegerDivideByZero,
+    IntegerOverflow,
+    InvalidConversionToInteger,
+    OutOfBoundsMemoryAccess,
+    OutOfBoundsTableAccess,
+    UndefinedElement,
+    UninitializedElement,
+    IndirectCallTypeMismatch,
+    CallStackExhausted,
+    OutOfFuel,
+    UnknownExport(String),
+    ArgumentMismatch {
+        expected: Vec<ValType>,
+        actual: Vec<ValType>,
+    },
+    ResultMismatch {
+        expected: Vec<ValType>,
+        actual: Vec<ValType>,
+    },
+    Exit(i32),
+    Host(String),
+}
+
+impl Trap {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self::from(TrapKind::Host(message.into()))
+    }
+
+    pub fn exit(code: i32) -> Self {
+        Self::from(TrapKind::Exit(code))
+    }
+
+    pub fn kind(&self) -> &TrapKind {
+        &self.kind
+    }
+
+    pub fn backtrace(&self) -> &[FrameInfo] {
+        &self.backtrace
+    }
+
+    pub(crate) fn push_frame(&mut self, frame: FrameInfo) {
+        self.backtrace.push(frame);
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        match self.kind {
+            TrapKind::Exit(code) => Some(code),
+            _ => None,
+        }
+    }
+}
+
+impl From<TrapKind> for Trap {
+    fn from(kind: TrapKind) -> Self {
+        Self {
+            kind,
+            backtrace: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for Trap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if !self.backtrace.is_empty() {
+            write!(f, "\nwasm backtrace:")?;
+            for (i, frame) in self.backtrace.iter().take(MAX_DISPLAYED_FRAMES).enumerate() {
+                write!(f, "\n  {i:>3}: {frame}")?;
+            }
+            if self.backtrace.len() > MAX_DISPLAYED_FRAMES {
+                let omitted = self.backtrace.len() - MAX_DISPLAYED_FRAMES;
+                write!(f, "\n  ... {omitted} more frames")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FrameInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.func_name {
+            Some(name) => write!(f, "${name}"),
+            None => write!(f, "func {}", self.func_idx),
+        }
+    }
+}
+
+impl fmt::Display for TrapKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreachable => write!(f, "unreachable"),
+            Self::IntegerDivideByZero => write!(f, "integer divide by zero"),
+            Self::IntegerOverflow => write!(f, "integer overflow"),
+            Self::InvalidConversionToInteger => write!(f, "invalid conversion to integer"),
+            Self::OutOfBoundsMemoryAccess => write!(f, "out of bounds memory access"),
+            Self::OutOfBoundsTableAccess => write!(f, "out of bounds table access"),
+            Self::UndefinedElement => write!(f, "undefined element"),
+            Self::UninitializedElement => write!(f, "uninitialized element"),
+            Self::IndirectCallTypeMismatch => write!(f, "indirect call type mismatch"),
+            Self::CallStackExhausted => write!(f, "call stack exhausted"),
+            Self::OutOfFuel => write!(f, "all fuel consumed"),
+            Self::UnknownExport(name) => write!(f, "no exported function named {name}"),
+            Self::ArgumentMismatch { expected, actual } => write!(
+                f,
+                "function expects arguments {expected:?} but got {actual:?}"
+            ),
+            Self::ResultMismatch { expected, actual } => write!(
+                f,
+                "host function returned {actual:?} but its type requires {expected:?}"
+            ),
+            Self::Exit(code) => write!(f, "exit with code {code}"),
+            Self::Host(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl Error for Trap {}
@@ -1,9 +1,928 @@
-use crate::module::Module;
+use crate::error::{ValidationError, ValidationErrorKind};
+use crate::module::*;
+use std::collections::HashSet;
+
+const MAX_PAGES: u32 = 65536;
 
 pub struct Validator;
 
+struct Context<'a> {
+    types: &'a [FuncType],
+    funcs: Vec<&'a FuncType>,
+    tables: Vec<&'a TableType>,
+    mems: Vec<&'a MemType>,
+    globals: Vec<&'a GlobalType>,
+    elems: Vec<ElemType>,
+    datas: Option<u32>,
+    refs: HashSet<u32>,
+}
+
+struct CtrlFrame {
+    label_types: Vec<ValType>,
+    end_types: Vec<ValType>,
+    height: usize,
+    unreachable: bool,
+}
+
+// A block body whose instructions are still being validated. The bodies of
+// nested blocks are kept on an explicit stack rather than the native one, so
+// deeply nested code cannot overflow it.
+struct Body<'i> {
+    instrs: &'i [Instr],
+    pc: usize,
+    else_branch: Option<(&'i [Instr], Vec<ValType>)>,
+}
+
+struct FuncValidator<'a> {
+    context: &'a Context<'a>,
+    locals: Vec<ValType>,
+    results: Vec<ValType>,
+    vals: Vec<Option<ValType>>,
+    ctrls: Vec<CtrlFrame>,
+    instr_count: usize,
+}
+
 impl Validator {
-    pub fn validate(_module: &Module) -> Result<(), String> {
-        todo!()
+    pub fn validate(module: &Module) -> Result<(), ValidationError> {
+        let mut context = Context {
+            types: &module.types,
+            funcs: Vec::new(),
+            tables: Vec::new(),
+            mems: Vec::new(),
+            globals: Vec::new(),
+            elems: module.elem.iter().map(|elem| elem.typ).collect(),
+            datas: module.data_count,
+            refs: Self::func_refs(module),
+        };
+        for import in &module.imports {
+            match &import.desc {
+                ImpExportDesc::Func(typ) => context.funcs.push(Self::func_type(&context, typ)?),
+                ImpExportDesc::Table(typ) => {
+                    Self::validate_table_type(typ)?;
+                    context.tables.push(typ);
+                }
+                ImpExportDesc::Mem(typ) => {
+                    Self::validate_mem_type(typ)?;
+                    context.mems.push(typ);
+                }
+                ImpExportDesc::Global(typ) => context.globals.push(typ),
+            }
+        }
+        let imported_globals = context.globals.len();
+        for func in &module.funcs {
+            context.funcs.push(Self::func_type(&context, &func.typ)?);
+        }
+        for table in &module.table {
+            Self::validate_table_type(&table.typ)?;
+            context.tables.push(&table.typ);
+        }
+        for mem in &module.memory {
+            Self::validate_mem_type(&mem.typ)?;
+            context.mems.push(&mem.typ);
+        }
+        if context.mems.len() > 1 {
+            return Err(ValidationError::from(ValidationErrorKind::MultipleMemories));
+        }
+        for (idx, global) in module.globals.iter().enumerate() {
+            let GlobalType(_, typ) = global.typ;
+            Self::validate_const_expr(&context, &global.init, typ, imported_globals).map_err(
+                |kind| {
+                    ValidationError::from(ValidationErrorKind::Global(idx as u32, Box::new(kind)))
+                },
+            )?;
+        }
+        for global in &module.globals {
+            context.globals.push(&global.typ);
+        }
+        for (idx, elem) in module.elem.iter().enumerate() {
+            Self::validate_elem(&context, elem, imported_globals).map_err(|kind| {
+                ValidationError::from(ValidationErrorKind::ElementSegment(
+                    idx as u32,
+                    Box::new(kind),
+                ))
+            })?;
+        }
+        for (idx, data) in module.data.iter().enumerate() {
+            Self::validate_data(&context, data, imported_globals).map_err(|kind| {
+                ValidationError::from(ValidationErrorKind::DataSegment(idx as u32, Box::new(kind)))
+            })?;
+        }
+        if let Some(Start { func: FuncIdx(idx) }) = &module.start {
+            let typ = context
+                .funcs
+                .get(*idx as usize)
+                .ok_or(ValidationErrorKind::UnknownFunction(*idx))
+                .map_err(ValidationError::from)?;
+            if !typ.params.is_empty() || !typ.results.is_empty() {
+                return Err(ValidationError::from(
+                    ValidationErrorKind::InvalidStartFunction,
+                ));
+            }
+        }
+        Self::validate_exports(&context, &module.exports).map_err(ValidationError::from)?;
+        let imported_funcs = context.funcs.len() - module.funcs.len();
+        for (idx, func) in module.funcs.iter().enumerate() {
+            FuncValidator::new(&context, func)
+                .validate(&func.body)
+                .map_err(|(instr, kind)| {
+                    let func_idx = (imported_funcs + idx) as u32;
+                    ValidationError {
+                        func_idx: Some(func_idx),
+                        func_name: module.names.func(func_idx).map(String::from),
+                        instr_offset: Some(instr),
+                        kind,
+                    }
+                })?;
+        }
+        Ok(())
+    }
+
+    // Functions referenced outside of function bodies are the only ones that
+    // code is allowed to take a reference to with ref.func.
+    fn func_refs(module: &Module) -> HashSet<u32> {
+        let exprs = module
+            .elem
+            .iter()
+            .flat_map(|elem| &elem.init)
+            .chain(module.globals.iter().map(|global| &global.init));
+        let mut refs = exprs
+            .flat_map(|Expr(instrs, _)| instrs)
+            .filter_map(|instr| match instr {
+                Instr::RefFunc(FuncIdx(idx)) => Some(*idx),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        refs.extend(
+            module
+                .exports
+                .iter()
+                .filter_map(|export| match export.desc {
+                    ExportDesc::Func(FuncIdx(idx)) => Some(idx),
+                    _ => None,
+                }),
+        );
+        refs
+    }
+
+    fn validate_elem(
+        context: &Context,
+        elem: &Elem,
+        imported_globals: usize,
+    ) -> Result<(), ValidationErrorKind> {
+        if let ElemMode::Active { table, offset } = &elem.mode {
+            let TableType(_, typ) = context
+                .tables
+                .get(table.0 as usize)
+                .ok_or(ValidationErrorKind::UnknownTable(table.0))?;
+            if *typ != elem.typ {
+                return Err(ValidationErrorKind::TypeMismatch {
+                    expected: (*typ).into(),
+                    actual: elem.typ.into(),
+                });
+            }
+            Self::validate_const_expr(context, offset, ValType::I32, imported_globals)?;
+        }
+        for expr in &elem.init {
+            Self::validate_const_expr(context, expr, elem.typ.into(), imported_globals)?;
+        }
+        Ok(())
+    }
+
+    fn validate_data(
+        context: &Context,
+        data: &Data,
+        imported_globals: usize,
+    ) -> Result<(), ValidationErrorKind> {
+        match &data.mode {
+            DataMode::Active { memory, offset } => {
+                if memory.0 as usize >= context.mems.len() {
+                    return Err(ValidationErrorKind::UnknownMemory(memory.0));
+                }
+                Self::validate_const_expr(context, offset, ValType::I32, imported_globals)
+            }
+            DataMode::Passive => Ok(()),
+        }
+    }
+
+    fn func_type<'a>(
+        context: &Context<'a>,
+        TypeIdx(idx): &TypeIdx,
+    ) -> Result<&'a FuncType, ValidationErrorKind> {
+        context
+            .types
+            .get(*idx as usize)
+            .ok_or(ValidationErrorKind::UnknownType(*idx))
+    }
+
+    fn validate_limits(limits: &Limits, range: u32) -> Result<(), ValidationErrorKind> {
+        if limits.min > range {
+            return Err(ValidationErrorKind::LimitsMinTooLarge(range));
+        }
+        if let Some(max) = limits.max {
+            if max > range {
+                return Err(ValidationErrorKind::LimitsMaxTooLarge(range));
+            }
+            if limits.min > max {
+                return Err(ValidationErrorKind::LimitsMinExceedsMax);
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_table_type(TableType(limits, _): &TableType) -> Result<(), ValidationErrorKind> {
+        Self::validate_limits(limits, u32::MAX)
+    }
+
+    fn validate_mem_type(MemType(limits): &MemType) -> Result<(), ValidationErrorKind> {
+        Self::validate_limits(limits, MAX_PAGES)
+    }
+
+    fn validate_const_expr(
+        context: &Context,
+        Expr(instrs, _): &Expr,
+        expected: ValType,
+        imported_globals: usize,
+    ) -> Result<(), ValidationErrorKind> {
+        let typ = match instrs.as_slice() {
+            [Instr::I32Const(_)] => ValType::I32,
+            [Instr::I64Const(_)] => ValType::I64,
+            [Instr::F32Const(_)] => ValType::F32,
+            [Instr::F64Const(_)] => ValType::F64,
+            [Instr::GlobalGet(GlobalIdx(idx))] => {
+                if *idx as usize >= imported_globals {
+                    return Err(ValidationErrorKind::UnknownGlobal(*idx));
+                }
+                let GlobalType(mutability, typ) = context.globals[*idx as usize];
+                if *mutability == Mut::Var {
+                    return Err(ValidationErrorKind::ConstExprMutableGlobal(*idx));
+                }
+                *typ
+            }
+            [Instr::RefNull(typ)] => (*typ).into(),
+            [Instr::RefFunc(FuncIdx(idx))] => {
+                if *idx as usize >= context.funcs.len() {
+                    return Err(ValidationErrorKind::UnknownFunction(*idx));
+                }
+                ValType::FuncRef
+            }
+            _ => return Err(ValidationErrorKind::ConstExprRequired),
+        };
+        if typ != expected {
+            return Err(ValidationErrorKind::TypeMismatch {
+                expected,
+                actual: typ,
+            });
+        }
+        Ok(())
+    }
+
+    fn validate_exports(context: &Context, exports: &[Export]) -> Result<(), ValidationErrorKind> {
+        let mut names = HashSet::new();
+        for export in exports {
+            if !names.insert(export.name.as_str()) {
+                return Err(ValidationErrorKind::DuplicateExport(export.name.clone()));
+            }
+            let (idx, count, unknown): (_, _, fn(u32) -> ValidationErrorKind) = match &export.desc {
+                ExportDesc::Func(FuncIdx(idx)) => (
+                    *idx,
+                    context.funcs.len(),
+                    ValidationErrorKind::UnknownFunction,
+                ),
+                ExportDesc::Table(TableIdx(idx)) => (
+                    *idx,
+                    context.tables.len(),
+                    ValidationErrorKind::UnknownTable,
+                ),
+                ExportDesc::Mem(MemIdx(idx)) => {
+                    (*idx, context.mems.len(), ValidationErrorKind::UnknownMemory)
+                }
+                ExportDesc::Global(GlobalIdx(idx)) => (
+                    *idx,
+                    context.globals.len(),
+                    ValidationErrorKind::UnknownGlobal,
+                ),
+            };
+            if idx as usize >= count {
+                return Err(ValidationErrorKind::Export(
+                    export.name.clone(),
+                    Box::new(unknown(idx)),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> FuncValidator<'a> {
+    fn new(context: &'a Context<'a>, func: &Function) -> Self {
+        let typ = context.types[func.typ.0 as usize].clone();
+        let mut locals = typ.params;
+        locals.extend(func.locals.iter().copied());
+        Self {
+            context,
+            locals,
+            results: typ.results,
+            vals: Vec::new(),
+            ctrls: Vec::new(),
+            instr_count: 0,
+        }
+    }
+
+    fn validate(mut self, Expr(instrs, _): &Expr) -> Result<(), (usize, ValidationErrorKind)> {
+        let results = self.results.clone();
+        self.push_ctrl(results.clone(), results);
+        self.validate_body(instrs)
+            .map_err(|err| (self.instr_count.saturating_sub(1), err))
+    }
+
+    fn validate_body(&mut self, instrs: &[Instr]) -> Result<(), ValidationErrorKind> {
+        let mut bodies = vec![Body {
+            instrs,
+            pc: 0,
+            else_branch: None,
+        }];
+        while let Some(body) = bodies.last_mut() {
+            if let Some(instr) = body.instrs.get(body.pc) {
+                body.pc += 1;
+                self.instr_count += 1;
+                if let Some(body) = self.validate_instr(instr)? {
+                    bodies.push(body);
+                }
+                continue;
+            }
+            let body = bodies.pop().expect("checked above");
+            let results = self.pop_ctrl()?;
+            if let Some((instrs, params)) = body.else_branch {
+                bodies.push(self.enter_block(&params, results.clone(), results, instrs, None));
+            } else if !bodies.is_empty() {
+                self.push_vals(&results);
+            }
+        }
+        Ok(())
+    }
+
+    fn push_val(&mut self, typ: ValType) {
+        self.vals.push(Some(typ));
+    }
+
+    fn pop_val(&mut self) -> Result<Option<ValType>, ValidationErrorKind> {
+        let frame = self.ctrls.last().expect("control stack is never empty");
+        if self.vals.len() == frame.height {
+            if frame.unreachable {
+                return Ok(None);
+            }
+            return Err(ValidationErrorKind::StackUnderflow);
+        }
+        Ok(self.vals.pop().expect("checked above"))
+    }
+
+    fn pop_expect(&mut self, expected: ValType) -> Result<Option<ValType>, ValidationErrorKind> {
+        let actual = self.pop_val()?;
+        match actual {
+            Some(actual) if actual != expected => {
+                Err(ValidationErrorKind::TypeMismatch { expected, actual })
+            }
+            _ => Ok(actual),
+        }
+    }
+
+    fn pop_vals(&mut self, types: &[ValType]) -> Result<(), ValidationErrorKind> {
+        for typ in types.iter().rev() {
+            self.pop_expect(*typ)?;
+        }
+        Ok(())
+    }
+
+    fn push_vals(&mut self, types: &[ValType]) {
+        for typ in types {
+            self.push_val(*typ);
+        }
+    }
+
+    fn push_ctrl(&mut self, label_types: Vec<ValType>, end_types: Vec<ValType>) {
+        self.ctrls.push(CtrlFrame {
+            label_types,
+            end_types,
+            height: self.vals.len(),
+            unreachable: false,
+        });
+    }
+
+    fn pop_ctrl(&mut self) -> Result<Vec<ValType>, ValidationErrorKind> {
+        let end_types = self
+            .ctrls
+            .last()
+            .expect("control stack is never empty")
+            .end_types
+            .clone();
+        self.pop_vals(&end_types)?;
+        let frame = self.ctrls.pop().expect("control stack is never empty");
+        if self.vals.len() != frame.height {
+            return Err(ValidationErrorKind::ValuesRemaining);
+        }
+        Ok(frame.end_types)
+    }
+
+    fn set_unreachable(&mut self) {
+        let frame = self.ctrls.last_mut().expect("control stack is never empty");
+        self.vals.truncate(frame.height);
+        frame.unreachable = true;
+    }
+
+    fn label_types(&self, LabelIdx(idx): &LabelIdx) -> Result<Vec<ValType>, ValidationErrorKind> {
+        let idx = *idx as usize;
+        if idx >= self.ctrls.len() {
+            return Err(ValidationErrorKind::UnknownLabel(idx as u32));
+        }
+        Ok(self.ctrls[self.ctrls.len() - 1 - idx].label_types.clone())
+    }
+
+    fn block_type(
+        &self,
+        block_type: &BlockType,
+    ) -> Result<(Vec<ValType>, Vec<ValType>), ValidationErrorKind> {
+        match block_type {
+            BlockType::Empty => Ok((Vec::new(), Vec::new())),
+            BlockType::Value(typ) => Ok((Vec::new(), vec![*typ])),
+            BlockType::Type(TypeIdx(idx)) => {
+                let typ = self
+                    .context
+                    .types
+                    .get(*idx as usize)
+                    .ok_or(ValidationErrorKind::UnknownType(*idx))?;
+                Ok((typ.params.clone(), typ.results.clone()))
+            }
+        }
+    }
+
+    fn local(&self, LocalIdx(idx): &LocalIdx) -> Result<ValType, ValidationErrorKind> {
+        self.locals
+            .get(*idx as usize)
+            .copied()
+            .ok_or(ValidationErrorKind::UnknownLocal(*idx))
+    }
+
+    fn global(&self, GlobalIdx(idx): &GlobalIdx) -> Result<&'a GlobalType, ValidationErrorKind> {
+        self.context
+            .globals
+            .get(*idx as usize)
+            .copied()
+            .ok_or(ValidationErrorKind::UnknownGlobal(*idx))
+    }
+
+    fn check_memory(&self) -> Result<(), ValidationErrorKind> {
+        if self.context.mems.is_empty() {
+            return Err(ValidationErrorKind::UnknownMemory(0));
+        }
+        Ok(())
+    }
+
+    fn table(&self, TableIdx(idx): &TableIdx) -> Result<ValType, ValidationErrorKind> {
+        let TableType(_, typ) = self
+            .context
+            .tables
+            .get(*idx as usize)
+            .ok_or(ValidationErrorKind::UnknownTable(*idx))?;
+        Ok((*typ).into())
+    }
+
+    fn elem(&self, ElemIdx(idx): &ElemIdx) -> Result<ValType, ValidationErrorKind> {
+        self.context
+            .elems
+            .get(*idx as usize)
+            .map(|typ| (*typ).into())
+            .ok_or(ValidationErrorKind::UnknownElem(*idx))
+    }
+
+    fn check_same(expected: ValType, actual: ValType) -> Result<(), ValidationErrorKind> {
+        if expected != actual {
+            return Err(ValidationErrorKind::TypeMismatch { expected, actual });
+        }
+        Ok(())
+    }
+
+    // Data indices are checked against the data count section so that code
+    // can be validated in a single pass before the data section is seen.
+    fn check_data(&self, DataIdx(idx): &DataIdx) -> Result<(), ValidationErrorKind> {
+        let count = self
+            .context
+            .datas
+            .ok_or(ValidationErrorKind::DataCountRequired)?;
+        if *idx >= count {
+            return Err(ValidationErrorKind::UnknownData(*idx));
+        }
+        Ok(())
+    }
+
+    fn check_mem_arg(&self, mem_arg: &MemArg, width: u32) -> Result<(), ValidationErrorKind> {
+        self.check_memory()?;
+        if 1_u64.checked_shl(mem_arg.align).unwrap_or(u64::MAX) > width as u64 {
+            return Err(ValidationErrorKind::AlignmentTooLarge);
+        }
+        Ok(())
+    }
+
+    fn load(
+        &mut self,
+        mem_arg: &MemArg,
+        width: u32,
+        typ: ValType,
+    ) -> Result<(), ValidationErrorKind> {
+        self.check_mem_arg(mem_arg, width)?;
+        self.pop_expect(ValType::I32)?;
+        self.push_val(typ);
+        Ok(())
+    }
+
+    fn store(
+        &mut self,
+        mem_arg: &MemArg,
+        width: u32,
+        typ: ValType,
+    ) -> Result<(), ValidationErrorKind> {
+        self.check_mem_arg(mem_arg, width)?;
+        self.pop_expect(typ)?;
+        self.pop_expect(ValType::I32)?;
+        Ok(())
+    }
+
+    fn unop(&mut self, operand: ValType, result: ValType) -> Result<(), ValidationErrorKind> {
+        self.pop_expect(operand)?;
+        self.push_val(result);
+        Ok(())
+    }
+
+    fn binop(&mut self, operand: ValType, result: ValType) -> Result<(), ValidationErrorKind> {
+        self.pop_expect(operand)?;
+        self.pop_expect(operand)?;
+        self.push_val(result);
+        Ok(())
+    }
+
+    fn enter_block<'i>(
+        &mut self,
+        params: &[ValType],
+        label_types: Vec<ValType>,
+        end_types: Vec<ValType>,
+        instrs: &'i [Instr],
+        else_branch: Option<(&'i [Instr], Vec<ValType>)>,
+    ) -> Body<'i> {
+        self.push_ctrl(label_types, end_types);
+        self.push_vals(params);
+        Body {
+            instrs,
+            pc: 0,
+            else_branch,
+        }
+    }
+
+    // Returns the body of a block instruction, which the caller validates
+    // before continuing with the instructions that follow it.
+    fn validate_instr<'i>(
+        &mut self,
+        instr: &'i Instr,
+    ) -> Result<Option<Body<'i>>, ValidationErrorKind> {
+        use ValType::*;
+        match instr {
+            Instr::Unreachable => self.set_unreachable(),
+            Instr::Nop => {}
+            Instr::Block(block_type, instrs, _) => {
+                let (params, results) = self.block_type(block_type)?;
+                self.pop_vals(&params)?;
+                let body = self.enter_block(&params, results.clone(), results, instrs, None);
+                return Ok(Some(body));
+            }
+            Instr::Loop(block_type, instrs, _) => {
+                let (params, results) = self.block_type(block_type)?;
+                self.pop_vals(&params)?;
+                let body = self.enter_block(&params, params.clone(), results, instrs, None);
+                return Ok(Some(body));
+            }
+            Instr::If(block_type, if_instrs, _, else_instrs, _) => {
+                self.pop_expect(I32)?;
+                let (params, results) = self.block_type(block_type)?;
+                self.pop_vals(&params)?;
+                let else_branch = Some((else_instrs.as_slice(), params.clone()));
+                let body =
+                    self.enter_block(&params, results.clone(), results, if_instrs, else_branch);
+                return Ok(Some(body));
+            }
+            Instr::Br(label) => {
+                let types = self.label_types(label)?;
+                self.pop_vals(&types)?;
+                self.set_unreachable();
+            }
+            Instr::BrIf(label) => {
+                let types = self.label_types(label)?;
+                self.pop_expect(I32)?;
+                self.pop_vals(&types)?;
+                self.push_vals(&types);
+            }
+            Instr::BrTable(labels, default) => {
+                self.pop_expect(I32)?;
+                let types = self.label_types(default)?;
+                for label in labels {
+                    let label_types = self.label_types(label)?;
+                    if label_types.len() != types.len() {
+                        return Err(ValidationErrorKind::BrTableArityMismatch);
+                    }
+                    // Each target is checked against the same operands, which
+                    // stay unknown in unreachable code.
+                    let operands = label_types
+                        .iter()
+                        .rev()
+                        .map(|typ| self.pop_expect(*typ))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    self.vals.extend(operands.into_iter().rev());
+                }
+                self.pop_vals(&types)?;
+                self.set_unreachable();
+            }
+            Instr::Return => {
+                let results = self.results.clone();
+                self.pop_vals(&results)?;
+                self.set_unreachable();
+            }
+            Instr::Call(FuncIdx(idx)) => {
+                let typ = self
+                    .context
+                    .funcs
+                    .get(*idx as usize)
+                    .ok_or(ValidationErrorKind::UnknownFunction(*idx))?;
+                self.pop_vals(&typ.params)?;
+                self.push_vals(&typ.results);
+            }
+            Instr::CallIndirect(TypeIdx(idx), table) => {
+                Self::check_same(FuncRef, self.table(table)?)?;
+                let typ = self
+                    .context
+                    .types
+                    .get(*idx as usize)
+                    .ok_or(ValidationErrorKind::UnknownType(*idx))?;
+                self.pop_expect(I32)?;
+                self.pop_vals(&typ.params)?;
+                self.push_vals(&typ.results);
+            }
+            Instr::Drop => {
+                self.pop_val()?;
+            }
+            Instr::Select => {
+                self.pop_expect(I32)?;
+                let t1 = self.pop_val()?;
+                let t2 = self.pop_val()?;
+                if let Some(typ) = t1.or(t2).filter(ValType::is_ref) {
+                    return Err(ValidationErrorKind::SelectRequiresType(typ));
+                }
+                match (t1, t2) {
+                    (Some(t1), Some(t2)) if t1 != t2 => {
+                        return Err(ValidationErrorKind::TypeMismatch {
+                            expected: t2,
+                            actual: t1,
+                        })
+                    }
+                    (Some(t), _) | (_, Some(t)) => self.push_val(t),
+                    (None, None) => self.vals.push(None),
+                }
+            }
+            Instr::TypedSelect(types) => {
+                let [typ] = types[..] else {
+                    return Err(ValidationErrorKind::InvalidResultArity);
+                };
+                self.pop_expect(I32)?;
+                self.pop_vals(&[typ, typ])?;
+                self.push_val(typ);
+            }
+            Instr::LocalGet(idx) => {
+                let typ = self.local(idx)?;
+                self.push_val(typ);
+            }
+            Instr::LocalSet(idx) => {
+                let typ = self.local(idx)?;
+                self.pop_expect(typ)?;
+            }
+            Instr::LocalTee(idx) => {
+                let typ = self.local(idx)?;
+                self.pop_expect(typ)?;
+                self.push_val(typ);
+            }
+            Instr::GlobalGet(idx) => {
+                let GlobalType(_, typ) = self.global(idx)?;
+                self.push_val(*typ);
+            }
+            Instr::GlobalSet(idx) => {
+                let GlobalType(mutability, typ) = self.global(idx)?;
+                if *mutability == Mut::Const {
+                    return Err(ValidationErrorKind::ImmutableGlobal(idx.0));
+                }
+                self.pop_expect(*typ)?;
+            }
+            Instr::TableGet(table) => {
+                let typ = self.table(table)?;
+                self.unop(I32, typ)?;
+            }
+            Instr::TableSet(table) => {
+                let typ = self.table(table)?;
+                self.pop_vals(&[I32, typ])?;
+            }
+            Instr::I32Load(m) => self.load(m, 4, I32)?,
+            Instr::I64Load(m) => self.load(m, 8, I64)?,
+            Instr::F32Load(m) => self.load(m, 4, F32)?,
+            Instr::F64Load(m) => self.load(m, 8, F64)?,
+            Instr::I32Load8S(m) | Instr::I32Load8U(m) => self.load(m, 1, I32)?,
+            Instr::I32Load16S(m) | Instr::I32Load16U(m) => self.load(m, 2, I32)?,
+            Instr::I64Load8S(m) | Instr::I64Load8U(m) => self.load(m, 1, I64)?,
+            Instr::I64Load16S(m) | Instr::I64Load16U(m) => self.load(m, 2, I64)?,
+            Instr::I64Load32S(m) | Instr::I64Load32U(m) => self.load(m, 4, I64)?,
+            Instr::I32Store(m) => self.store(m, 4, I32)?,
+            Instr::I64Store(m) => self.store(m, 8, I64)?,
+            Instr::F32Store(m) => self.store(m, 4, F32)?,
+            Instr::F64Store(m) => self.store(m, 8, F64)?,
+            Instr::I32Store8(m) => self.store(m, 1, I32)?,
+            Instr::I32Store16(m) => self.store(m, 2, I32)?,
+            Instr::I64Store8(m) => self.store(m, 1, I64)?,
+            Instr::I64Store16(m) => self.store(m, 2, I64)?,
+            Instr::I64Store32(m) => self.store(m, 4, I64)?,
+            Instr::MemorySize => {
+                self.check_memory()?;
+                self.push_val(I32);
+            }
+            Instr::MemoryGrow => {
+                self.check_memory()?;
+                self.unop(I32, I32)?;
+            }
+            Instr::MemoryInit(data) => {
+                self.check_memory()?;
+                self.check_data(data)?;
+                self.pop_vals(&[I32, I32, I32])?;
+            }
+            Instr::DataDrop(data) => self.check_data(data)?,
+            Instr::MemoryCopy | Instr::MemoryFill => {
+                self.check_memory()?;
+                self.pop_vals(&[I32, I32, I32])?;
+            }
+            Instr::TableInit(elem, table) => {
+                Self::check_same(self.table(table)?, self.elem(elem)?)?;
+                self.pop_vals(&[I32, I32, I32])?;
+            }
+            Instr::ElemDrop(elem) => {
+                self.elem(elem)?;
+            }
+            Instr::TableCopy(dst, src) => {
+                Self::check_same(self.table(dst)?, self.table(src)?)?;
+                self.pop_vals(&[I32, I32, I32])?;
+            }
+            Instr::TableGrow(table) => {
+                let typ = self.table(table)?;
+                self.pop_vals(&[typ, I32])?;
+                self.push_val(I32);
+            }
+            Instr::TableSize(table) => {
+                self.table(table)?;
+                self.push_val(I32);
+            }
+            Instr::TableFill(table) => {
+                let typ = self.table(table)?;
+                self.pop_vals(&[I32, typ, I32])?;
+            }
+            Instr::RefNull(typ) => self.push_val((*typ).into()),
+            Instr::RefIsNull => {
+                if let Some(typ) = self.pop_val()?.filter(|typ| !typ.is_ref()) {
+                    return Err(ValidationErrorKind::ExpectedReference(typ));
+                }
+                self.push_val(I32);
+            }
+            Instr::RefFunc(FuncIdx(idx)) => {
+                if *idx as usize >= self.context.funcs.len() {
+                    return Err(ValidationErrorKind::UnknownFunction(*idx));
+                }
+                if !self.context.refs.contains(idx) {
+                    return Err(ValidationErrorKind::UndeclaredFunctionReference(*idx));
+                }
+                self.push_val(FuncRef);
+            }
+            Instr::I32Const(_) => self.push_val(I32),
+            Instr::I64Const(_) => self.push_val(I64),
+            Instr::F32Const(_) => self.push_val(F32),
+            Instr::F64Const(_) => self.push_val(F64),
+            Instr::I32Eqz => self.unop(I32, I32)?,
+            Instr::I32Eq
+            | Instr::I32Ne
+            | Instr::I32LtS
+            | Instr::I32LtU
+            | Instr::I32GtS
+            | Instr::I32GtU
+            | Instr::I32LeS
+            | Instr::I32LeU
+            | Instr::I32GeS
+            | Instr::I32GeU => self.binop(I32, I32)?,
+            Instr::I64Eqz => self.unop(I64, I32)?,
+            Instr::I64Eq
+            | Instr::I64Ne
+            | Instr::I64LtS
+            | Instr::I64LtU
+            | Instr::I64GtS
+            | Instr::I64GtU
+            | Instr::I64LeS
+            | Instr::I64LeU
+            | Instr::I64GeS
+            | Instr::I64GeU => self.binop(I64, I32)?,
+            Instr::F32Eq
+            | Instr::F32Ne
+            | Instr::F32Lt
+            | Instr::F32Gt
+            | Instr::F32Le
+            | Instr::F32Ge => self.binop(F32, I32)?,
+            Instr::F64Eq
+            | Instr::F64Ne
+            | Instr::F64Lt
+            | Instr::F64Gt
+            | Instr::F64Le
+            | Instr::F64Ge => self.binop(F64, I32)?,
+            Instr::I32Clz | Instr::I32Ctz | Instr::I32Popcnt => self.unop(I32, I32)?,
+            Instr::I32Add
+            | Instr::I32Sub
+            | Instr::I32Mul
+            | Instr::I32DivS
+            | Instr::I32DivU
+            | Instr::I32RemS
+            | Instr::I32RemU
+            | Instr::I32And
+            | Instr::I32Or
+            | Instr::I32Xor
+            | Instr::I32Shl
+            | Instr::I32ShrS
+            | Instr::I32ShrU
+            | Instr::I32Rotl
+            | Instr::I32Rotr => self.binop(I32, I32)?,
+            Instr::I64Clz | Instr::I64Ctz | Instr::I64Popcnt => self.unop(I64, I64)?,
+            Instr::I64Add
+            | Instr::I64Sub
+            | Instr::I64Mul
+            | Instr::I64DivS
+            | Instr::I64DivU
+            | Instr::I64RemS
+            | Instr::I64RemU
+            | Instr::I64And
+            | Instr::I64Or
+            | Instr::I64Xor
+            | Instr::I64Shl
+            | Instr::I64ShrS
+            | Instr::I64ShrU
+            | Instr::I64Rotl
+            | Instr::I64Rotr => self.binop(I64, I64)?,
+            Instr::F32Abs
+            | Instr::F32Neg
+            | Instr::F32Ceil
+            | Instr::F32Floor
+            | Instr::F32Trunc
+            | Instr::F32Nearest
+            | Instr::F32Sqrt => self.unop(F32, F32)?,
+            Instr::F32Add
+            | Instr::F32Sub
+            | Instr::F32Mul
+            | Instr::F32Div
+            | Instr::F32Min
+            | Instr::F32Max
+            | Instr::F32Copysign => self.binop(F32, F32)?,
+            Instr::F64Abs
+            | Instr::F64Neg
+            | Instr::F64Ceil
+            | Instr::F64Floor
+            | Instr::F64Trunc
+            | Instr::F64Nearest
+            | Instr::F64Sqrt => self.unop(F64, F64)?,
+            Instr::F64Add
+            | Instr::F64Sub
+            | Instr::F64Mul
+            | Instr::F64Div
+            | Instr::F64Min
+            | Instr::F64Max
+            | Instr::F64Copysign => self.binop(F64, F64)?,
+            Instr::I32WrapI64 => self.unop(I64, I32)?,
+            Instr::I32TruncF32S | Instr::I32TruncF32U => self.unop(F32, I32)?,
+            Instr::I32TruncF64S | Instr::I32TruncF64U => self.unop(F64, I32)?,
+            Instr::I64ExtendI32S | Instr::I64ExtendI32U => self.unop(I32, I64)?,
+            Instr::I64TruncF32S | Instr::I64TruncF32U => self.unop(F32, I64)?,
+            Instr::I64TruncF64S | Instr::I64TruncF64U => self.unop(F64, I64)?,
+            Instr::F32ConvertI32S | Instr::F32ConvertI32U => self.unop(I32, F32)?,
+            Instr::F32ConvertI64S | Instr::F32ConvertI64U => self.unop(I64, F32)?,
+            Instr::F32DemoteF64 => self.unop(F64, F32)?,
+            Instr::F64ConvertI32S | Instr::F64ConvertI32U => self.unop(I32, F64)?,
+            Instr::F64ConvertI64S | Instr::F64ConvertI64U => self.unop(I64, F64)?,
+            Instr::F64PromoteF32 => self.unop(F32, F64)?,
+            Instr::I32ReinterpretF32 => self.unop(F32, I32)?,
+            Instr::I64ReinterpretF64 => self.unop(F64, I64)?,
+            Instr::F32ReinterpretI32 => self.unop(I32, F32)?,
+            Instr::F64ReinterpretI64 => self.unop(I64, F64)?,
+            Instr::I32Extend8S | Instr::I32Extend16S => self.unop(I32, I32)?,
+            Instr::I64Extend8S | Instr::I64Extend16S | Instr::I64Extend32S => {
+                self.unop(I64, I64)?
+            }
+            Instr::I32TruncSatF32S | Instr::I32TruncSatF32U => self.unop(F32, I32)?,
+            Instr::I32TruncSatF64S | Instr::I32TruncSatF64U => self.unop(F64, I32)?,
+            Instr::I64TruncSatF32S | Instr::I64TruncSatF32U => self.unop(F32, I64)?,
+            Instr::I64TruncSatF64S | Instr::I64TruncSatF64U => self.unop(F64, I64)?,
+        }
+        Ok(None)
     }
 }
@@ -0,0 +1,99 @@
+use crate::instance::{ExternRef, Func};
+use crate::module::ValType;
+use crate::printer::{f32_literal, f64_literal};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    FuncRef(Option<Func>),
+    ExternRef(Option<ExternRef>),
+}
+
+impl Value {
+    pub fn default_for(typ: ValType) -> Self {
+        match typ {
+            ValType::I32 => Value::I32(0),
+            ValType::I64 => Value::I64(0),
+            ValType::F32 => Value::F32(0.0),
+            ValType::F64 => Value::F64(0.0),
+            ValType::FuncRef => Value::FuncRef(None),
+            ValType::ExternRef => Value::ExternRef(None),
+        }
+    }
+
+    pub fn typ(&self) -> ValType {
+        match self {
+            Value::I32(_) => ValType::I32,
+            Value::I64(_) => ValType::I64,
+            Value::F32(_) => ValType::F32,
+            Value::F64(_) => ValType::F64,
+            Value::FuncRef(_) => ValType::FuncRef,
+            Value::ExternRef(_) => ValType::ExternRef,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::FuncRef(None) | Value::ExternRef(None))
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::I32(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::I64(value)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::F32(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::F64(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::I32(value as i32)
+    }
+}
+
+impl From<Func> for Value {
+    fn from(func: Func) -> Self {
+        Value::FuncRef(Some(func))
+    }
+}
+
+impl From<ExternRef> for Value {
+    fn from(extern_ref: ExternRef) -> Self {
+        Value::ExternRef(Some(extern_ref))
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::I32(v) => write!(f, "{v}"),
+            Value::I64(v) => write!(f, "{v}"),
+            Value::F32(v) => write!(f, "{}", f32_literal(*v)),
+            Value::F64(v) => write!(f, "{}", f64_literal(*v)),
+            Value::FuncRef(None) => write!(f, "ref.null func"),
+            Value::FuncRef(Some(_)) => write!(f, "ref.func"),
+            Value::ExternRef(None) => write!(f, "ref.null extern"),
+            Value::ExternRef(Some(ExternRef(addr))) => write!(f, "ref.extern {addr}"),
+        }
+    }
+}
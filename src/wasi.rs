@@ -0,0 +1,554 @@
+use crate::instance::{Caller, Extern};
+use crate::linker::Linker;
+use crate::module::{FuncType, ImpExportDesc, Module, ValType};
+use crate::store::Store;
+use crate::trap::Trap;
+use crate::value::Value;
+use std::cell::RefCell;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
+type Errno = i32;
+
+const ESUCCESS: Errno = 0;
+const EBADF: Errno = 8;
+const EEXIST: Errno = 20;
+const EFAULT: Errno = 21;
+const EINVAL: Errno = 28;
+const EIO: Errno = 29;
+const EISDIR: Errno = 31;
+const ENOENT: Errno = 44;
+const ENOSYS: Errno = 52;
+const ENOTDIR: Errno = 54;
+const ESPIPE: Errno = 70;
+const ENOTCAPABLE: Errno = 76;
+
+const FILETYPE_UNKNOWN: u8 = 0;
+const FILETYPE_CHARACTER_DEVICE: u8 = 2;
+const FILETYPE_DIRECTORY: u8 = 3;
+const FILETYPE_REGULAR_FILE: u8 = 4;
+
+const OFLAGS_CREAT: u32 = 1;
+const OFLAGS_DIRECTORY: u32 = 2;
+const OFLAGS_EXCL: u32 = 4;
+const OFLAGS_TRUNC: u32 = 8;
+const FDFLAGS_APPEND: u32 = 1;
+const RIGHTS_FD_READ: u64 = 1 << 1;
+const RIGHTS_FD_WRITE: u64 = 1 << 6;
+
+type WasiFunc = fn(&mut WasiCtx, &mut [u8], &[Value]) -> Result<(), Errno>;
+
+enum Descriptor {
+    Stdin,
+    Stdout,
+    Stderr,
+    PreopenDir { host: PathBuf, guest: String },
+    Dir(PathBuf),
+    File(File),
+}
+
+pub struct WasiCtx {
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    fds: Vec<Option<Descriptor>>,
+    start: Instant,
+    rng: u64,
+}
+
+impl WasiCtx {
+    pub fn new(args: Vec<String>, env: Vec<(String, String)>) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or(0);
+        Self {
+            args,
+            env,
+            fds: vec![
+                Some(Descriptor::Stdin),
+                Some(Descriptor::Stdout),
+                Some(Descriptor::Stderr),
+            ],
+            start: Instant::now(),
+            rng: seed | 1,
+        }
+    }
+
+    pub fn preopen_dir(&mut self, host: impl Into<PathBuf>, guest: &str) -> &mut Self {
+        self.fds.push(Some(Descriptor::PreopenDir {
+            host: host.into(),
+            guest: guest.to_string(),
+        }));
+        self
+    }
+
+    pub fn add_to_linker(self, linker: &mut Linker, store: &mut Store) {
+        let ctx = Rc::new(RefCell::new(self));
+        for (name, params, func) in Self::functions() {
+            let ctx = Rc::clone(&ctx);
+            let typ = FuncType {
+                params: params.to_vec(),
+                results: vec![ValType::I32],
+            };
+            linker.func(store, WASI_MODULE, name, typ, move |caller, args| {
+                let mut ctx = ctx.borrow_mut();
+                let errno = match func(&mut ctx, memory(caller)?, args) {
+                    Ok(()) => ESUCCESS,
+                    Err(errno) => errno,
+                };
+                Ok(vec![Value::I32(errno)])
+            });
+        }
+        let typ = FuncType {
+            params: vec![ValType::I32],
+            results: vec![],
+        };
+        linker.func(store, WASI_MODULE, "proc_exit", typ, |_, args| {
+            Err(Trap::exit(i32_arg(args, 0) as i32))
+        });
+    }
+
+    pub fn add_stubs(linker: &mut Linker, store: &mut Store, module: &Module) {
+        for import in &module.imports {
+            let ImpExportDesc::Func(idx) = &import.desc else {
+                continue;
+            };
+            if import.module != WASI_MODULE || linker.get(&import.module, &import.name).is_some() {
+                continue;
+            }
+            let typ = module.types[idx.0 as usize].clone();
+            if typ.results != [ValType::I32] {
+                continue;
+            }
+            linker.func(store, &import.module, &import.name, typ, |_, _| {
+                Ok(vec![Value::I32(ENOSYS)])
+            });
+        }
+    }
+
+    fn functions() -> Vec<(&'static str, &'static [ValType], WasiFunc)> {
+        use ValType::{I32, I64};
+        vec![
+            ("args_get", &[I32, I32], Self::args_get),
+            ("args_sizes_get", &[I32, I32], Self::args_sizes_get),
+            ("environ_get", &[I32, I32], Self::environ_get),
+            ("environ_sizes_get", &[I32, I32], Self::environ_sizes_get),
+            ("clock_res_get", &[I32, I32], Self::clock_res_get),
+            ("clock_time_get", &[I32, I64, I32], Self::clock_time_get),
+            ("fd_close", &[I32], Self::fd_close),
+            ("fd_fdstat_get", &[I32, I32], Self::fd_fdstat_get),
+            ("fd_filestat_get", &[I32, I32], Self::fd_filestat_get),
+            ("fd_prestat_get", &[I32, I32], Self::fd_prestat_get),
+            (
+                "fd_prestat_dir_name",
+                &[I32, I32, I32],
+                Self::fd_prestat_dir_name,
+            ),
+            ("fd_read", &[I32, I32, I32, I32], Self::fd_read),
+            ("fd_seek", &[I32, I64, I32, I32], Self::fd_seek),
+            ("fd_write", &[I32, I32, I32, I32], Self::fd_write),
+            (
+                "path_open",
+                &[I32, I32, I32, I32, I32, I64, I64, I32, I32],
+                Self::path_open,
+            ),
+            ("random_get", &[I32, I32], Self::random_get),
+            ("sched_yield", &[], Self::sched_yield),
+        ]
+    }
+
+    fn args_get(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let strings: Vec<&str> = self.args.iter().map(String::as_str).collect();
+        write_strings(mem, &strings, i32_arg(args, 0), i32_arg(args, 1))
+    }
+
+    fn args_sizes_get(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let size = self.args.iter().map(|arg| arg.len() + 1).sum::<usize>();
+        write_u32(mem, i32_arg(args, 0), self.args.len() as u32)?;
+        write_u32(mem, i32_arg(args, 1), size as u32)
+    }
+
+    fn environ_get(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let pairs: Vec<String> = self
+            .env
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect();
+        let strings: Vec<&str> = pairs.iter().map(String::as_str).collect();
+        write_strings(mem, &strings, i32_arg(args, 0), i32_arg(args, 1))
+    }
+
+    fn environ_sizes_get(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let size = self
+            .env
+            .iter()
+            .map(|(name, value)| name.len() + value.len() + 2)
+            .sum::<usize>();
+        write_u32(mem, i32_arg(args, 0), self.env.len() as u32)?;
+        write_u32(mem, i32_arg(args, 1), size as u32)
+    }
+
+    fn clock_res_get(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        if i32_arg(args, 0) > 3 {
+            return Err(EINVAL);
+        }
+        write_u64(mem, i32_arg(args, 1), 1_000)
+    }
+
+    fn clock_time_get(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let nanos = match i32_arg(args, 0) {
+            0 => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|_| EIO)?
+                .as_nanos(),
+            1..=3 => self.start.elapsed().as_nanos(),
+            _ => return Err(EINVAL),
+        };
+        write_u64(mem, i32_arg(args, 2), nanos as u64)
+    }
+
+    fn fd_close(&mut self, _: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let fd = i32_arg(args, 0) as usize;
+        match self.fds.get(fd) {
+            Some(Some(Descriptor::PreopenDir { .. })) => Err(ENOTCAPABLE),
+            Some(Some(_)) => {
+                self.fds[fd] = None;
+                Ok(())
+            }
+            _ => Err(EBADF),
+        }
+    }
+
+    fn fd_fdstat_get(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let filetype = self.descriptor(i32_arg(args, 0)).map(filetype)?;
+        let ptr = i32_arg(args, 1);
+        let stat = slice(mem, ptr, 24)?;
+        stat.fill(0);
+        stat[0] = filetype;
+        stat[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        stat[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        Ok(())
+    }
+
+    fn fd_filestat_get(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let descriptor = self.descriptor(i32_arg(args, 0))?;
+        let metadata = match descriptor {
+            Descriptor::File(file) => Some(file.metadata().map_err(io_errno)?),
+            Descriptor::Dir(path) | Descriptor::PreopenDir { host: path, .. } => {
+                Some(fs::metadata(path).map_err(io_errno)?)
+            }
+            _ => None,
+        };
+        let filetype = filetype(descriptor);
+        let stat = slice(mem, i32_arg(args, 1), 64)?;
+        stat.fill(0);
+        stat[16] = filetype;
+        stat[24..32].copy_from_slice(&1_u64.to_le_bytes());
+        if let Some(metadata) = metadata {
+            stat[32..40].copy_from_slice(&metadata.len().to_le_bytes());
+            let times = [metadata.accessed(), metadata.modified(), metadata.created()];
+            for (i, time) in times.into_iter().enumerate() {
+                let nanos = time
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |time| time.as_nanos() as u64);
+                stat[40 + i * 8..48 + i * 8].copy_from_slice(&nanos.to_le_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    fn fd_prestat_get(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let Descriptor::PreopenDir { guest, .. } = self.descriptor(i32_arg(args, 0))? else {
+            return Err(EBADF);
+        };
+        let len = guest.len() as u32;
+        let stat = slice(mem, i32_arg(args, 1), 8)?;
+        stat.fill(0);
+        stat[4..8].copy_from_slice(&len.to_le_bytes());
+        Ok(())
+    }
+
+    fn fd_prestat_dir_name(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let Descriptor::PreopenDir { guest, .. } = self.descriptor(i32_arg(args, 0))? else {
+            return Err(EBADF);
+        };
+        let len = i32_arg(args, 2) as usize;
+        if len < guest.len() {
+            return Err(EINVAL);
+        }
+        slice(mem, i32_arg(args, 1), guest.len())?.copy_from_slice(guest.as_bytes());
+        Ok(())
+    }
+
+    fn fd_read(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let (fd, iovs, iovs_len, nread_ptr) = (
+            i32_arg(args, 0),
+            i32_arg(args, 1),
+            i32_arg(args, 2),
+            i32_arg(args, 3),
+        );
+        let descriptor = self.descriptor(fd)?;
+        let mut nread = 0_u32;
+        for (ptr, len) in iovecs(mem, iovs, iovs_len)? {
+            let buf = slice(mem, ptr, len as usize)?;
+            let n = match descriptor {
+                Descriptor::Stdin => io::stdin().read(buf),
+                Descriptor::File(file) => file.read(buf),
+                Descriptor::PreopenDir { .. } | Descriptor::Dir(_) => return Err(EISDIR),
+                _ => return Err(EBADF),
+            }
+            .map_err(io_errno)?;
+            nread = nread.checked_add(n as u32).ok_or(EINVAL)?;
+            if n < len as usize {
+                break;
+            }
+        }
+        write_u32(mem, nread_ptr, nread)
+    }
+
+    fn fd_seek(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let offset = i64_arg(args, 1);
+        let Descriptor::File(file) = self.descriptor(i32_arg(args, 0))? else {
+            return Err(ESPIPE);
+        };
+        let pos = match i32_arg(args, 2) {
+            0 => SeekFrom::Start(u64::try_from(offset).map_err(|_| EINVAL)?),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => return Err(EINVAL),
+        };
+        let new_offset = file.seek(pos).map_err(io_errno)?;
+        write_u64(mem, i32_arg(args, 3), new_offset)
+    }
+
+    fn fd_write(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let (fd, iovs, iovs_len, nwritten_ptr) = (
+            i32_arg(args, 0),
+            i32_arg(args, 1),
+            i32_arg(args, 2),
+            i32_arg(args, 3),
+        );
+        let descriptor = self.descriptor(fd)?;
+        let mut nwritten = 0_u32;
+        for (ptr, len) in iovecs(mem, iovs, iovs_len)? {
+            // Overlapping iovecs can add up to more than fits in the result.
+            nwritten = nwritten.checked_add(len).ok_or(EINVAL)?;
+            let buf = slice(mem, ptr, len as usize)?;
+            match descriptor {
+                Descriptor::Stdout => io::stdout().write_all(buf),
+                Descriptor::Stderr => io::stderr().write_all(buf),
+                Descriptor::File(file) => file.write_all(buf),
+                Descriptor::PreopenDir { .. } | Descriptor::Dir(_) => return Err(EISDIR),
+                Descriptor::Stdin => return Err(EBADF),
+            }
+            .map_err(io_errno)?;
+        }
+        if let Descriptor::Stdout = descriptor {
+            io::stdout().flush().map_err(io_errno)?;
+        }
+        write_u32(mem, nwritten_ptr, nwritten)
+    }
+
+    fn path_open(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let base = match self.descriptor(i32_arg(args, 0))? {
+            Descriptor::PreopenDir { host, .. } | Descriptor::Dir(host) => host.clone(),
+            _ => return Err(ENOTDIR),
+        };
+        let path = slice(mem, i32_arg(args, 2), i32_arg(args, 3) as usize)?;
+        let path = std::str::from_utf8(path).map_err(|_| EINVAL)?;
+        let path = resolve(&base, path)?;
+        let oflags = i32_arg(args, 4);
+        let rights = i64_arg(args, 5) as u64;
+        let fdflags = i32_arg(args, 7);
+        let descriptor = if oflags & OFLAGS_DIRECTORY != 0 || path.is_dir() {
+            if !path.is_dir() {
+                return Err(if path.exists() { ENOTDIR } else { ENOENT });
+            }
+            Descriptor::Dir(path)
+        } else {
+            let write = rights & RIGHTS_FD_WRITE != 0
+                || oflags & (OFLAGS_CREAT | OFLAGS_TRUNC) != 0
+                || fdflags & FDFLAGS_APPEND != 0;
+            let file = OpenOptions::new()
+                .read(rights & RIGHTS_FD_READ != 0 || !write)
+                .write(write)
+                .append(fdflags & FDFLAGS_APPEND != 0)
+                .create(oflags & OFLAGS_CREAT != 0 && oflags & OFLAGS_EXCL == 0)
+                .create_new(oflags & OFLAGS_CREAT != 0 && oflags & OFLAGS_EXCL != 0)
+                .truncate(oflags & OFLAGS_TRUNC != 0)
+                .open(&path)
+                .map_err(io_errno)?;
+            Descriptor::File(file)
+        };
+        let fd = match self.fds.iter().position(Option::is_none) {
+            Some(fd) => {
+                self.fds[fd] = Some(descriptor);
+                fd
+            }
+            None => {
+                self.fds.push(Some(descriptor));
+                self.fds.len() - 1
+            }
+        };
+        write_u32(mem, i32_arg(args, 8), fd as u32)
+    }
+
+    fn random_get(&mut self, mem: &mut [u8], args: &[Value]) -> Result<(), Errno> {
+        let buf = slice(mem, i32_arg(args, 0), i32_arg(args, 1) as usize)?;
+        for byte in buf {
+            self.rng ^= self.rng << 13;
+            self.rng ^= self.rng >> 7;
+            self.rng ^= self.rng << 17;
+            *byte = self.rng as u8;
+        }
+        Ok(())
+    }
+
+    fn sched_yield(&mut self, _: &mut [u8], _: &[Value]) -> Result<(), Errno> {
+        Ok(())
+    }
+
+    fn descriptor(&mut self, fd: u32) -> Result<&mut Descriptor, Errno> {
+        self.fds
+            .get_mut(fd as usize)
+            .and_then(Option::as_mut)
+            .ok_or(EBADF)
+    }
+}
+
+fn memory<'a>(caller: &'a mut Caller) -> Result<&'a mut [u8], Trap> {
+    match caller.get_export("memory") {
+        Some(Extern::Memory(memory)) => Ok(caller.memory_data_mut(memory)),
+        _ => Err(Trap::new("WASI functions require an exported memory")),
+    }
+}
+
+fn filetype(descriptor: &mut Descriptor) -> u8 {
+    match descriptor {
+        Descriptor::Stdin | Descriptor::Stdout | Descriptor::Stderr => FILETYPE_CHARACTER_DEVICE,
+        Descriptor::PreopenDir { .. } | Descriptor::Dir(_) => FILETYPE_DIRECTORY,
+        Descriptor::File(file) => match file.metadata() {
+            Ok(metadata) if metadata.is_file() => FILETYPE_REGULAR_FILE,
+            _ => FILETYPE_UNKNOWN,
+        },
+    }
+}
+
+fn resolve(base: &Path, path: &str) -> Result<PathBuf, Errno> {
+    let mut resolved = base.to_path_buf();
+    let mut depth = 0_usize;
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => {
+                resolved.push(part);
+                depth += 1;
+            }
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => {
+                resolved.pop();
+                depth -= 1;
+            }
+            _ => return Err(ENOTCAPABLE),
+        }
+    }
+    // Symlinks are followed by the host, so the real path has to stay inside
+    // the preopened directory too. A file that doesn't exist yet is checked
+    // through its parent; a dangling symlink is refused outright.
+    let root = base.canonicalize().map_err(io_errno)?;
+    let real = match resolved.canonicalize() {
+        Ok(real) => real,
+        Err(_) if resolved.symlink_metadata().is_err() => {
+            let (Some(parent), Some(name)) = (resolved.parent(), resolved.file_name()) else {
+                return Err(ENOENT);
+            };
+            parent.canonicalize().map_err(io_errno)?.join(name)
+        }
+        Err(_) => return Err(ENOTCAPABLE),
+    };
+    if !real.starts_with(&root) {
+        return Err(ENOTCAPABLE);
+    }
+    Ok(real)
+}
+
+fn io_errno(err: io::Error) -> Errno {
+    match err.kind() {
+        io::ErrorKind::NotFound => ENOENT,
+        io::ErrorKind::AlreadyExists => EEXIST,
+        io::ErrorKind::PermissionDenied => ENOTCAPABLE,
+        io::ErrorKind::InvalidInput => EINVAL,
+        _ => EIO,
+    }
+}
+
+fn i32_arg(args: &[Value], idx: usize) -> u32 {
+    match args[idx] {
+        Value::I32(v) => v as u32,
+        _ => unreachable!("WASI host functions are called with their declared types"),
+    }
+}
+
+fn i64_arg(args: &[Value], idx: usize) -> i64 {
+    match args[idx] {
+        Value::I64(v) => v,
+        _ => unreachable!("WASI host functions are called with their declared types"),
+    }
+}
+
+fn slice(mem: &mut [u8], ptr: u32, len: usize) -> Result<&mut [u8], Errno> {
+    let start = ptr as usize;
+    mem.get_mut(start..start.checked_add(len).ok_or(EFAULT)?)
+        .ok_or(EFAULT)
+}
+
+fn write_u32(mem: &mut [u8], ptr: u32, value: u32) -> Result<(), Errno> {
+    slice(mem, ptr, 4)?.copy_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+fn write_u64(mem: &mut [u8], ptr: u32, value: u64) -> Result<(), Errno> {
+    slice(mem, ptr, 8)?.copy_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+fn read_u32(mem: &mut [u8], ptr: u32) -> Result<u32, Errno> {
+    let bytes = slice(mem, ptr, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn iovecs(mem: &mut [u8], iovs: u32, iovs_len: u32) -> Result<Vec<(u32, u32)>, Errno> {
+    (0..iovs_len)
+        .map(|i| {
+            let ptr = i
+                .checked_mul(8)
+                .and_then(|offset| iovs.checked_add(offset))
+                .ok_or(EFAULT)?;
+            let len_ptr = ptr.checked_add(4).ok_or(EFAULT)?;
+            Ok((read_u32(mem, ptr)?, read_u32(mem, len_ptr)?))
+        })
+        .collect()
+}
+
+fn write_strings(mem: &mut [u8], strings: &[&str], ptrs: u32, buf: u32) -> Result<(), Errno> {
+    let mut offset = buf as usize;
+    for (i, string) in strings.iter().enumerate() {
+        let ptr = u32::try_from(i)
+            .ok()
+            .and_then(|i| i.checked_mul(4))
+            .and_then(|i| ptrs.checked_add(i))
+            .ok_or(EFAULT)?;
+        let start = u32::try_from(offset).map_err(|_| EFAULT)?;
+        write_u32(mem, ptr, start)?;
+        let dest = slice(mem, start, string.len() + 1)?;
+        dest[..string.len()].copy_from_slice(string.as_bytes());
+        dest[string.len()] = 0;
+        offset += dest.len();
+    }
+    Ok(())
+}
@@ -0,0 +1,280 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command as Process, Output};
+use wasm_interpreter::config::{Command, Config};
+
+fn build(args: &[&str]) -> Result<Config, String> {
+    let args = std::iter::once("wasm-interpreter")
+        .chain(args.iter().copied())
+        .map(String::from)
+        .collect::<Vec<_>>();
+    Config::build(&args)
+}
+
+fn cli(args: &[&str]) -> Output {
+    Process::new(env!("CARGO_BIN_EXE_wasm-interpreter"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+fn scratch(name: &str, contents: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wasm-interpreter-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn subcommands_are_parsed() {
+    let config = build(&["prog.wasm", "a", "--b"]).unwrap();
+    assert_eq!(config.command, Command::Run);
+    assert_eq!(config.args, ["prog.wasm", "a", "--b"]);
+    let config = build(&["--env", "A=1", "run", "prog.wasm", "a"]).unwrap();
+    assert_eq!(config.command, Command::Run);
+    assert_eq!(config.env, [("A".to_string(), "1".to_string())]);
+    assert_eq!(config.args, ["prog.wasm", "a"]);
+    let config = build(&["validate", "m.wasm"]).unwrap();
+    assert_eq!(config.command, Command::Validate);
+    let config = build(&["inspect", "m.wat"]).unwrap();
+    assert_eq!(config.command, Command::Inspect);
+    let config = build(&["invoke", "m.wasm", "add", "1", "-2"]).unwrap();
+    assert_eq!(config.command, Command::Invoke);
+    assert_eq!(config.invoke.as_deref(), Some("add"));
+    assert_eq!(config.invoke_args, ["1", "-2"]);
+    assert_eq!(config.args, ["m.wasm"]);
+}
+
+#[test]
+fn runtime_flags_are_parsed() {
+    let config = build(&[
+        "--trace",
+        "--fuel",
+        "1000",
+        "--max-memory",
+        "16",
+        "--dir",
+        "/tmp::/sandbox",
+        "run",
+        "prog.wasm",
+    ])
+    .unwrap();
+    assert!(config.trace);
+    assert_eq!(config.fuel, Some(1000));
+    assert_eq!(config.max_memory, Some(16));
+    assert_eq!(config.dirs, [("/tmp".to_string(), "/sandbox".to_string())]);
+    let config = build(&["prog.wasm"]).unwrap();
+    assert!(!config.trace);
+    assert_eq!((config.fuel, config.max_memory), (None, None));
+}
+
+#[test]
+fn bad_arguments_are_rejected() {
+    assert_eq!(build(&[]).err().unwrap(), "Not enough params");
+    assert_eq!(build(&["invoke"]).err().unwrap(), "Not enough params");
+    assert_eq!(
+        build(&["invoke", "m.wasm"]).err().unwrap(),
+        "Missing export name to invoke"
+    );
+    assert_eq!(
+        build(&["validate", "m.wasm", "extra"]).err().unwrap(),
+        "Unexpected argument extra"
+    );
+    assert_eq!(
+        build(&["--fuel", "lots", "m.wasm"]).err().unwrap(),
+        "Invalid value lots for --fuel"
+    );
+    assert_eq!(
+        build(&["--max-memory"]).err().unwrap(),
+        "Missing value for --max-memory"
+    );
+    assert_eq!(
+        build(&["--verbose", "m.wasm"]).err().unwrap(),
+        "Unknown option --verbose"
+    );
+}
+
+#[test]
+fn validate_and_inspect_report_on_the_module() {
+    let output = cli(&["validate", "examples/simple01.wasm"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "examples/simple01.wasm: valid\n");
+    let output = cli(&["inspect", "examples/math.wat"]);
+    assert!(output.status.success());
+    let text = stdout(&output);
+    assert!(text.starts_with("version 1, "), "{text}");
+    assert!(text.contains("\n  type "), "{text}");
+    assert!(text.contains("\n  code "), "{text}");
+    assert!(text.contains("\nimports:\nexports:\n"), "{text}");
+    assert!(
+        text.contains("\n  add: func 0 (param i32 i32) (result i32)\n"),
+        "{text}"
+    );
+    assert!(text.contains("\n  memory: memory 0\n"), "{text}");
+    let path = scratch(
+        "bad-export.wat",
+        br#"(module (func) (export "f" (func 5)))"#,
+    );
+    let output = cli(&["inspect", path.to_str().unwrap()]);
+    fs::remove_file(path).unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert!(
+        stderr(&output).starts_with("Error validating module: "),
+        "{}",
+        stderr(&output)
+    );
+}
+
+fn invoke(args: &[&str]) -> Result<String, String> {
+    let args = ["invoke", "examples/math.wat"].iter().chain(args);
+    let output = cli(&args.copied().collect::<Vec<_>>());
+    match output.status.code() {
+        Some(0) => Ok(stdout(&output)),
+        _ => Err(String::from_utf8(output.stderr).unwrap()),
+    }
+}
+
+#[test]
+fn invoke_prints_results() {
+    assert_eq!(invoke(&["add", "1", "2"]).unwrap(), "3\n");
+    assert_eq!(invoke(&["add", "0xffff_ffff", "-2"]).unwrap(), "-3\n");
+    assert_eq!(
+        invoke(&["mul", "-0x8000_0000_0000_0000", "1"]).unwrap(),
+        "-9223372036854775808\n"
+    );
+    assert_eq!(invoke(&["div", "1", "4"]).unwrap(), "0.25\n");
+    assert_eq!(invoke(&["div", "0x1p-1", "-0"]).unwrap(), "-inf\n");
+    assert_eq!(invoke(&["div", "1e300", "1e-10"]).unwrap(), "inf\n");
+    assert_eq!(invoke(&["div", "1e300", "10"]).unwrap(), "1e299\n");
+    assert_eq!(invoke(&["neg", "nan"]).unwrap(), "-nan\n");
+    assert_eq!(invoke(&["neg", "-nan:0x1"]).unwrap(), "nan:0x1\n");
+    assert_eq!(invoke(&["neg", "1.5"]).unwrap(), "-1.5\n");
+}
+
+#[test]
+fn invoke_rejects_bad_arguments() {
+    assert_eq!(
+        invoke(&["add", "1"]).unwrap_err(),
+        "Error invoking function: expected 2 arguments but got 1\n"
+    );
+    assert_eq!(
+        invoke(&["add", "1", "0x1_0000_0000"]).unwrap_err(),
+        "Error invoking function: invalid i32 argument 0x1_0000_0000\n"
+    );
+    assert_eq!(
+        invoke(&["neg", "nan:0x800000"]).unwrap_err(),
+        "Error invoking function: invalid f32 argument nan:0x800000\n"
+    );
+    assert_eq!(
+        invoke(&["sub", "1", "2"]).unwrap_err(),
+        "Error invoking function: unknown export sub\n"
+    );
+    assert_eq!(
+        invoke(&["memory"]).unwrap_err(),
+        "Error invoking function: export memory is not a function\n"
+    );
+}
+
+#[test]
+fn runtime_limits_apply_from_the_command_line() {
+    let output = cli(&["--fuel", "100", "invoke", "examples/math.wat", "spin"]);
+    assert_eq!(output.status.code(), Some(6));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("all fuel consumed"), "{stderr}");
+    let output = cli(&[
+        "--max-memory",
+        "2",
+        "invoke",
+        "examples/math.wat",
+        "grow",
+        "2",
+    ]);
+    assert_eq!(stdout(&output), "-1\n");
+    let output = cli(&["--trace", "invoke", "examples/math.wat", "add", "1", "2"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "$add: local.get 0\n$add: local.get 1\n$add: i32.add\n"
+    );
+}
+
+#[test]
+fn each_stage_fails_with_its_own_exit_code() {
+    let run = |name: &str, contents: &[u8]| {
+        let path = scratch(name, contents);
+        let output = cli(&[path.to_str().unwrap()]);
+        fs::remove_file(path).unwrap();
+        (output.status.code(), stderr(&output))
+    };
+    let output = cli(&["examples/missing.wasm"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("Error reading file examples/missing.wasm: "));
+    let (code, message) = run("magic.wasm", b"\0wasm\x01\0\0\0");
+    assert_eq!(code, Some(3));
+    assert_eq!(
+        message,
+        "Error decoding binary: Byte address: 3, wrong binary magic\n"
+    );
+    let (code, message) = run("syntax.wat", b"(module (func (i32.frobnicate)))");
+    assert_eq!(code, Some(3));
+    assert_eq!(
+        message,
+        "Error parsing text: 1:16: unknown instruction i32.frobnicate\n"
+    );
+    let (code, message) = run("invalid.wat", b"(module (func $f (result i32)))");
+    assert_eq!(code, Some(4));
+    assert!(
+        message.starts_with("Error validating module: in $f, "),
+        "{message}"
+    );
+    let (code, message) = run("import.wat", br#"(module (import "env" "f" (func)))"#);
+    assert_eq!(code, Some(5));
+    assert_eq!(message, "Error linking module: unknown import env.f\n");
+    let huge_table = br#"(module (table 4294967295 funcref) (func (export "_start")))"#;
+    let (code, message) = run("table.wat", huge_table);
+    assert_eq!(code, Some(5));
+    assert_eq!(
+        message,
+        "Error linking module: table requires 4294967295 elements but the limit is 10000000 elements\n"
+    );
+    let (code, message) = run(
+        "start.wat",
+        b"(module (func $boom unreachable) (start $boom))",
+    );
+    assert_eq!(code, Some(6));
+    assert!(
+        message.starts_with("Error linking module: start function trapped: unreachable"),
+        "{message}"
+    );
+    let trapping = br#"(module
+      (func $divide (param i32) (result i32) (i32.div_u (i32.const 1) (local.get 0)))
+      (func (export "_start") (drop (call $divide (i32.const 0)))))"#;
+    let (code, message) = run("trap.wat", trapping);
+    assert_eq!(code, Some(6));
+    assert_eq!(
+        message,
+        "Error interpreting binary: integer divide by zero\nwasm backtrace:\n    0: $divide\n    1: func 1\n"
+    );
+    let (code, message) = run("library.wat", br#"(module (func (export "f")))"#);
+    assert_eq!(code, Some(1));
+    assert_eq!(
+        message,
+        "Error invoking function: module has no _start export, use --invoke\n"
+    );
+    let (code, message) = run("start-only.wat", b"(module (func $s) (start $s))");
+    assert_eq!((code, message.as_str()), (Some(0), ""));
+    let output = cli(&["--fuel"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        "Problem parsing arguments: Missing value for --fuel\n"
+    );
+}
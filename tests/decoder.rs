@@ -0,0 +1,522 @@
+use std::io::{self, Read};
+use wasm_interpreter::module::{BlockType, Custom, Else, End, Instr, MAX_NESTING_DEPTH};
+use wasm_interpreter::{
+    DecodeError, DecodeErrorKind, Error, Instance, Module, Store, StreamingDecoder,
+    ValidationErrorKind, Value,
+};
+
+fn leb(mut value: u32, out: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![id];
+    leb(contents.len() as u32, &mut bytes);
+    bytes.extend_from_slice(contents);
+    bytes
+}
+
+fn custom(name: &str, payload: &[u8]) -> Vec<u8> {
+    let mut contents = Vec::new();
+    leb(name.len() as u32, &mut contents);
+    contents.extend_from_slice(name.as_bytes());
+    contents.extend_from_slice(payload);
+    section(0, &contents)
+}
+
+fn binary(sections: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = b"\0asm\x01\0\0\0".to_vec();
+    for section in sections {
+        bytes.extend_from_slice(section);
+    }
+    bytes
+}
+
+fn decode_error(bytes: &[u8]) -> DecodeError {
+    match Module::from_bytes(bytes) {
+        Err(Error::Decode(err)) => err,
+        result => panic!("expected a decode error, got {result:?}"),
+    }
+}
+
+const TYPE: &[u8] = &[1, 0x60, 0, 0];
+const FUNCTION: &[u8] = &[1, 0];
+const CODE: &[u8] = &[1, 2, 0, 0x0b];
+
+#[test]
+fn custom_sections_may_appear_anywhere() {
+    let bytes = binary(&[
+        custom("first", b"\x01\x02"),
+        section(1, TYPE),
+        custom("producers", b"not wasm at all \xff"),
+        section(3, FUNCTION),
+        section(10, CODE),
+        custom("first", b""),
+    ]);
+    let module = Module::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        module.customs,
+        [
+            Custom {
+                name: "first".into(),
+                bytes: vec![1, 2],
+                after_section: None,
+            },
+            Custom {
+                name: "producers".into(),
+                bytes: b"not wasm at all \xff".to_vec(),
+                after_section: Some(1),
+            },
+            Custom {
+                name: "first".into(),
+                bytes: Vec::new(),
+                after_section: Some(10),
+            },
+        ]
+    );
+    let firsts: Vec<&[u8]> = module.custom_sections("first").collect();
+    assert_eq!(firsts, [&[1, 2][..], &[]]);
+    assert_eq!(module.custom_sections("missing").count(), 0);
+    assert_eq!(module.funcs.len(), 1);
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn custom_section_names_must_fit_the_section() {
+    let err = decode_error(&binary(&[section(0, &[])]));
+    assert_eq!(err.kind, DecodeErrorKind::UnexpectedEnd);
+    let err = decode_error(&binary(&[section(0, &[5, b'a', b'b'])]));
+    assert_eq!(err.kind, DecodeErrorKind::UnexpectedEnd);
+    let mut truncated = binary(&[custom("name", b"payload")]);
+    truncated.pop();
+    assert_eq!(
+        decode_error(&truncated).kind,
+        DecodeErrorKind::UnexpectedEnd
+    );
+}
+
+fn name_section(func: &str, local: &str) -> Vec<u8> {
+    let string = |text: &str| [&[text.len() as u8], text.as_bytes()].concat();
+    let mut contents = Vec::new();
+    contents.extend(section(0, &string("demo")));
+    contents.extend(section(1, &[&[1, 0][..], &string(func)].concat()));
+    contents.extend(section(2, &[&[1, 0, 1, 0][..], &string(local)].concat()));
+    custom("name", &contents)
+}
+
+#[test]
+fn the_name_section_names_functions_and_locals() {
+    let body = [1, 5, 0, 0x20, 0, 0x1a, 0x0b];
+    let bytes = binary(&[
+        section(1, &[1, 0x60, 1, 0x7f, 0]),
+        section(3, FUNCTION),
+        section(7, &[1, 4, b'b', b'o', b'o', b'm', 0, 0]),
+        section(10, &body),
+        name_section("boom", "x"),
+    ]);
+    let module = Module::from_bytes(&bytes).unwrap();
+    assert_eq!(module.names.module.as_deref(), Some("demo"));
+    assert_eq!(module.names.func(0), Some("boom"));
+    assert_eq!(module.names.local(0, 0), Some("x"));
+    assert_eq!(module.names.local(0, 1), None);
+    let text = module.to_wat();
+    assert!(text.starts_with("(module $demo\n"), "{text}");
+    assert!(
+        text.contains("(func $boom (;0;) (type 0) (param $x i32)"),
+        "{text}"
+    );
+    assert!(text.contains("local.get $x"), "{text}");
+    assert_eq!(module.to_bytes(), bytes);
+}
+
+#[test]
+fn function_names_appear_in_errors() {
+    let trapping = [1, 3, 0, 0x00, 0x0b];
+    let bytes = binary(&[
+        section(1, &[1, 0x60, 1, 0x7f, 0]),
+        section(3, FUNCTION),
+        section(7, &[1, 4, b'b', b'o', b'o', b'm', 0, 0]),
+        section(10, &trapping),
+        name_section("boom", "x"),
+    ]);
+    let module = Module::from_bytes(&bytes).unwrap();
+    let mut store = Store::default();
+    let instance = Instance::new(&mut store, &module, &[]).unwrap();
+    let trap = instance
+        .invoke(&mut store, "boom", &[Value::I32(0)])
+        .unwrap_err();
+    assert_eq!(
+        trap.to_string(),
+        "unreachable\nwasm backtrace:\n    0: $boom"
+    );
+    let invalid = [1, 4, 0, 0x42, 0, 0x0b];
+    let bytes = binary(&[
+        section(1, &[1, 0x60, 1, 0x7f, 0]),
+        section(3, FUNCTION),
+        section(10, &invalid),
+        name_section("bad", "x"),
+    ]);
+    match Module::from_bytes(&bytes) {
+        Err(Error::Validation(err)) => {
+            assert_eq!(err.func_name.as_deref(), Some("bad"));
+            assert!(
+                err.to_string().starts_with("in $bad, instruction 0: "),
+                "{err}"
+            );
+        }
+        result => panic!("expected a validation error, got {result:?}"),
+    }
+}
+
+#[test]
+fn a_malformed_name_section_is_ignored() {
+    let mut names = name_section("boom", "x");
+    let len = names.len();
+    names[len - 2] = 5;
+    let bytes = binary(&[
+        section(1, TYPE),
+        section(3, FUNCTION),
+        section(10, CODE),
+        names,
+    ]);
+    let module = Module::from_bytes(&bytes).unwrap();
+    assert_eq!(module.names, Default::default());
+    assert_eq!(module.custom_sections("name").count(), 1);
+}
+
+fn with_body(body: &[u8]) -> Vec<u8> {
+    let mut code = vec![1];
+    leb(body.len() as u32, &mut code);
+    code.extend_from_slice(body);
+    binary(&[section(1, TYPE), section(3, FUNCTION), section(10, &code)])
+}
+
+#[test]
+fn local_counts_are_capped_before_allocating() {
+    let mut body = vec![2];
+    for _ in 0..2 {
+        leb(u32::MAX, &mut body);
+        body.push(0x7e);
+    }
+    body.push(0x0b);
+    let err = decode_error(&with_body(&body));
+    assert_eq!(err.kind, DecodeErrorKind::TooManyLocals);
+    assert_eq!(
+        err.to_string(),
+        format!("Byte address: {:x}, too many locals", err.offset)
+    );
+    let locals = |count| {
+        let mut body = vec![2, 0, 0x7f];
+        leb(count, &mut body);
+        body.extend_from_slice(&[0x7f, 0x0b]);
+        Module::from_bytes(&with_body(&body))
+    };
+    assert_eq!(locals(50_000).unwrap().funcs[0].locals.len(), 50_000);
+    assert!(matches!(
+        locals(50_001),
+        Err(Error::Decode(DecodeError {
+            kind: DecodeErrorKind::TooManyLocals,
+            ..
+        }))
+    ));
+}
+
+fn nested_blocks(depth: usize) -> Vec<u8> {
+    let mut body = vec![0];
+    for _ in 0..depth {
+        body.extend_from_slice(&[0x02, 0x40]);
+    }
+    body.extend(std::iter::repeat_n(0x0b, depth + 1));
+    with_body(&body)
+}
+
+#[test]
+fn nesting_depth_is_limited() {
+    let module = Module::from_bytes(&nested_blocks(MAX_NESTING_DEPTH)).unwrap();
+    assert_eq!(module.to_bytes(), nested_blocks(MAX_NESTING_DEPTH));
+    let err = decode_error(&nested_blocks(MAX_NESTING_DEPTH + 1));
+    assert_eq!(err.kind, DecodeErrorKind::NestingTooDeep);
+    let err = decode_error(&nested_blocks(100_000));
+    assert_eq!(err.kind, DecodeErrorKind::NestingTooDeep);
+}
+
+#[test]
+fn if_else_is_decoded_without_recursion() {
+    let body = [
+        0, 0x41, 1, 0x04, 0x40, 0x41, 0, 0x04, 0x40, 0x01, 0x05, 0x01, 0x0b, 0x05, 0x01, 0x0b, 0x0b,
+    ];
+    let module = Module::from_bytes(&with_body(&body)).unwrap();
+    let inner = Instr::If(
+        BlockType::Empty,
+        vec![Instr::Nop],
+        Else,
+        vec![Instr::Nop],
+        End,
+    );
+    assert_eq!(
+        module.funcs[0].body.0,
+        [
+            Instr::I32Const(1),
+            Instr::If(
+                BlockType::Empty,
+                vec![Instr::I32Const(0), inner],
+                Else,
+                vec![Instr::Nop],
+                End
+            )
+        ]
+    );
+    let stray_else = [0, 0x05, 0x0b];
+    assert_eq!(
+        decode_error(&with_body(&stray_else)).kind,
+        DecodeErrorKind::UnknownOpcode(0x05)
+    );
+    let second_else = [0, 0x41, 1, 0x04, 0x40, 0x05, 0x05, 0x0b, 0x0b];
+    assert_eq!(
+        decode_error(&with_body(&second_else)).kind,
+        DecodeErrorKind::UnknownOpcode(0x05)
+    );
+}
+
+#[test]
+fn streamed_bytes_are_decoded_section_by_section() {
+    let bytes = with_body(&[0, 0x0b]);
+    let mut decoder = StreamingDecoder::new();
+    let mut sections = Vec::new();
+    for byte in &bytes {
+        sections.push(decoder.feed(&[*byte]).unwrap());
+    }
+    assert_eq!(sections.iter().sum::<usize>(), 3);
+    assert_eq!(sections.iter().filter(|count| **count == 1).count(), 3);
+    assert_eq!(
+        decoder.finish().unwrap(),
+        Module::from_bytes(&bytes).unwrap()
+    );
+
+    let mut decoder = StreamingDecoder::new();
+    decoder.feed(&bytes[..bytes.len() - 1]).unwrap();
+    assert_eq!(decoder.module().types.len(), 1);
+    assert!(decoder.module().funcs.is_empty());
+    let Err(Error::Decode(err)) = decoder.finish() else {
+        panic!("expected a decode error");
+    };
+    assert_eq!(
+        err,
+        DecodeError {
+            offset: bytes.len() - 1,
+            kind: DecodeErrorKind::UnexpectedEnd,
+        }
+    );
+}
+
+#[test]
+fn errors_after_drained_input_report_absolute_offsets() {
+    let prefix = binary(&[section(1, TYPE)]);
+    let mut decoder = StreamingDecoder::new();
+    assert_eq!(decoder.feed(&prefix), Ok(1));
+    assert_eq!(
+        decoder.feed(&section(13, &[])),
+        Err(DecodeError {
+            offset: prefix.len(),
+            kind: DecodeErrorKind::UnsupportedSection(13),
+        })
+    );
+    let mut decoder = StreamingDecoder::new();
+    decoder
+        .feed(&binary(&[section(1, TYPE), section(3, FUNCTION)]))
+        .unwrap();
+    let Err(Error::Decode(err)) = decoder.finish() else {
+        panic!("expected a decode error");
+    };
+    assert_eq!(err.kind, DecodeErrorKind::FunctionCodeCountMismatch);
+}
+
+#[test]
+fn modules_can_be_validated_before_the_code_arrives() {
+    let exports = [1, 1, b'f', 0, 0];
+    let bytes = binary(&[
+        section(1, TYPE),
+        section(3, FUNCTION),
+        section(7, &exports),
+        section(10, CODE),
+    ]);
+    let mut decoder = StreamingDecoder::new();
+    decoder
+        .feed(&bytes[..bytes.len() - CODE.len() - 2])
+        .unwrap();
+    assert!(decoder.module().funcs.is_empty());
+    assert_eq!(decoder.validate(), Ok(()));
+    let mut decoder = StreamingDecoder::new();
+    let unknown = [1, 1, b'f', 0, 1];
+    decoder
+        .feed(&binary(&[
+            section(1, TYPE),
+            section(3, FUNCTION),
+            section(7, &unknown),
+        ]))
+        .unwrap();
+    assert_eq!(
+        decoder.validate().unwrap_err().kind,
+        ValidationErrorKind::Export(
+            "f".into(),
+            Box::new(ValidationErrorKind::UnknownFunction(1))
+        )
+    );
+}
+
+struct Chunked<'a>(&'a [u8]);
+
+impl Read for Chunked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.0.len()).min(3);
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn modules_can_be_decoded_from_a_reader() {
+    let bytes = std::fs::read("examples/simple01.wasm").unwrap();
+    let module = StreamingDecoder::decode_from(Chunked(&bytes)).unwrap();
+    assert_eq!(module, Module::from_bytes(&bytes).unwrap());
+    let err = StreamingDecoder::decode_from(Chunked(b"\0asm\x02\0\0\0")).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let err = err.into_inner().unwrap().downcast::<Error>().unwrap();
+    assert!(
+        matches!(
+            *err,
+            Error::Decode(DecodeError {
+                kind: DecodeErrorKind::UnsupportedVersion(2),
+                ..
+            })
+        ),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn streamed_modules_are_validated() {
+    // A function declared to return an i32 whose body leaves an i64.
+    let bytes = binary(&[
+        section(1, &[1, 0x60, 0, 1, 0x7f]),
+        section(3, FUNCTION),
+        section(10, &[1, 4, 0, 0x42, 1, 0x0b]),
+    ]);
+    let mut decoder = StreamingDecoder::new();
+    decoder.feed(&bytes).unwrap();
+    assert!(matches!(decoder.finish(), Err(Error::Validation(_))));
+    let err = StreamingDecoder::decode_from(Chunked(&bytes)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let err = err.into_inner().unwrap().downcast::<Error>().unwrap();
+    assert!(matches!(*err, Error::Validation(_)), "{err:?}");
+}
+
+#[test]
+fn sections_must_be_in_order_and_unique() {
+    let err = decode_error(&binary(&[section(3, FUNCTION), section(1, TYPE)]));
+    assert_eq!(err.kind, DecodeErrorKind::SectionOutOfOrder(1));
+    assert_eq!(err.offset, 8 + FUNCTION.len() + 2);
+    let err = decode_error(&binary(&[section(1, TYPE), section(1, TYPE)]));
+    assert_eq!(err.kind, DecodeErrorKind::DuplicateSection(1));
+    let err = decode_error(&binary(&[
+        section(1, TYPE),
+        custom("x", b""),
+        section(1, TYPE),
+    ]));
+    assert_eq!(err.kind, DecodeErrorKind::DuplicateSection(1));
+    let err = decode_error(&binary(&[section(11, &[0]), section(12, &[0])]));
+    assert_eq!(err.kind, DecodeErrorKind::SectionOutOfOrder(12));
+    let err = decode_error(&binary(&[section(10, &[0]), section(12, &[0])]));
+    assert_eq!(err.kind, DecodeErrorKind::SectionOutOfOrder(12));
+    let err = decode_error(&binary(&[section(14, &[])]));
+    assert_eq!(err.kind, DecodeErrorKind::UnsupportedSection(14));
+    Module::from_bytes(&binary(&[
+        section(12, &[0]),
+        section(10, &[0]),
+        section(11, &[0]),
+    ]))
+    .unwrap();
+}
+
+#[test]
+fn function_and_code_sections_must_agree() {
+    let err = decode_error(&binary(&[section(1, TYPE), section(3, FUNCTION)]));
+    assert_eq!(err.kind, DecodeErrorKind::FunctionCodeCountMismatch);
+    let err = decode_error(&binary(&[section(1, TYPE), section(10, CODE)]));
+    assert_eq!(err.kind, DecodeErrorKind::FunctionCodeCountMismatch);
+    let err = decode_error(&binary(&[
+        section(1, TYPE),
+        section(3, &[2, 0, 0]),
+        section(10, CODE),
+    ]));
+    assert_eq!(err.kind, DecodeErrorKind::FunctionCodeCountMismatch);
+    let module = Module::from_bytes(&binary(&[section(3, &[0]), section(10, &[0])])).unwrap();
+    assert!(module.funcs.is_empty());
+}
+
+#[test]
+fn sections_must_match_their_declared_size() {
+    let mut padded = TYPE.to_vec();
+    padded.push(0);
+    let err = decode_error(&binary(&[section(1, &padded)]));
+    assert_eq!(err.kind, DecodeErrorKind::SectionSizeMismatch);
+    let err = decode_error(&binary(&[section(1, &TYPE[..3])]));
+    assert_eq!(err.kind, DecodeErrorKind::UnexpectedEnd);
+    let err = decode_error(&binary(&[section(11, &[1])]));
+    assert_eq!(err.kind, DecodeErrorKind::UnexpectedEnd);
+    let err = decode_error(&binary(&[section(12, &[1]), section(11, &[0])]));
+    assert_eq!(err.kind, DecodeErrorKind::DataCountMismatch);
+}
+
+fn export_named(name: &[u8]) -> Vec<u8> {
+    let mut exports = vec![1];
+    leb(name.len() as u32, &mut exports);
+    exports.extend_from_slice(name);
+    exports.extend_from_slice(&[0, 0]);
+    binary(&[
+        section(1, TYPE),
+        section(3, FUNCTION),
+        section(7, &exports),
+        section(10, CODE),
+    ])
+}
+
+#[test]
+fn names_must_be_valid_utf8() {
+    for name in [&b"\xff"[..], b"ok\xc0\x80", b"\xed\xa0\x80", b"\xe6\x97"] {
+        let err = decode_error(&export_named(name));
+        assert_eq!(err.kind, DecodeErrorKind::MalformedUtf8, "{name:?}");
+    }
+    let err = decode_error(&export_named(b"ok\xc0\x80"));
+    assert_eq!(err.offset, 24);
+    let err = decode_error(&binary(&[section(2, b"\x01\x02\xce\x00\x01f\x00\x00")]));
+    assert_eq!(err.kind, DecodeErrorKind::MalformedUtf8);
+    let err = decode_error(&binary(&[section(0, b"\x02\xff\xfepayload")]));
+    assert_eq!(err.kind, DecodeErrorKind::MalformedUtf8);
+}
+
+#[test]
+fn non_ascii_names_round_trip() {
+    let name = "héllo, 日本 🦀";
+    let bytes = export_named(name.as_bytes());
+    let module = Module::from_bytes(&bytes).unwrap();
+    assert_eq!(module.exports[0].name, name);
+    assert_eq!(module.to_bytes(), bytes);
+    let text = module.to_wat();
+    assert_eq!(Module::from_wat(&text).unwrap().exports, module.exports);
+    let module = Module::from_wat(r#"(module (func (export "\u{65e5}\e6\9c\ac")))"#).unwrap();
+    assert_eq!(module.exports[0].name, "日本");
+    assert!(matches!(
+        Module::from_wat(r#"(module (func (export "\ff")))"#),
+        Err(Error::Parse(err)) if err.kind == wasm_interpreter::ParseErrorKind::MalformedUtf8
+    ));
+}
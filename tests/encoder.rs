@@ -0,0 +1,82 @@
+use std::fs;
+use wasm_interpreter::module::Custom;
+use wasm_interpreter::{Encoder, Module};
+
+fn examples(extension: &str) -> Vec<String> {
+    let mut paths = fs::read_dir("examples")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn binary_examples_round_trip() {
+    for path in examples("wasm") {
+        let bytes = fs::read(&path).unwrap();
+        let module = Module::from_bytes(&bytes).unwrap();
+        let encoded = Encoder::encode(&module);
+        assert_eq!(Module::from_bytes(&encoded).unwrap(), module, "{path}");
+        assert_eq!(encoded, bytes, "{path}");
+    }
+}
+
+#[test]
+fn text_examples_round_trip() {
+    for path in examples("wat") {
+        let module = Module::from_wat(&fs::read_to_string(&path).unwrap()).unwrap();
+        let decoded = Module::from_bytes(&module.to_bytes()).unwrap();
+        assert_eq!(decoded, module, "{path}");
+    }
+}
+
+#[test]
+fn custom_sections_keep_their_position() {
+    let mut module = Module::from_wat(
+        r#"(module
+          (memory 1)
+          (func $f (export "f") (param $x i32) (result i32)
+            (block (result i32)
+              (if (result i32) (local.get $x)
+                (then (i32.const -129))
+                (else (i32.load offset=4 (i32.const 0)))))))"#,
+    )
+    .unwrap();
+    let custom = |name: &str, after_section| Custom {
+        name: name.to_owned(),
+        bytes: name.bytes().rev().collect(),
+        after_section,
+    };
+    module.customs = vec![
+        custom("first", None),
+        custom("after-type", Some(1)),
+        custom("after-data", Some(11)),
+    ];
+    let decoded = Module::from_bytes(&module.to_bytes()).unwrap();
+    assert_eq!(decoded, module);
+}
+
+#[test]
+fn names_are_encoded_in_a_name_section() {
+    let module = Module::from_wat(
+        r#"(module $demo
+          (func $first (param $a i32) (param i32) (local $c i64))
+          (func)
+          (func $third (local $z f32)))"#,
+    )
+    .unwrap();
+    let bytes = module.to_bytes();
+    let decoded = Module::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.names, module.names);
+    assert_eq!(decoded.names.func(2), Some("third"));
+    assert_eq!(decoded.names.local(0, 2), Some("c"));
+    assert_eq!(decoded.custom_sections("name").count(), 0);
+    assert_eq!(decoded.to_bytes(), bytes);
+    let mut unnamed = module.clone();
+    unnamed.names = Default::default();
+    assert!(unnamed.to_bytes().len() < bytes.len());
+    assert_eq!(Module::from_bytes(&unnamed.to_bytes()).unwrap(), unnamed);
+}
@@ -0,0 +1,135 @@
+use std::error::Error as _;
+use std::io;
+use wasm_interpreter::module::{FuncType, ValType};
+use wasm_interpreter::{
+    DecodeError, DecodeErrorKind, Error, Func, Instance, LinkError, Module, ParseError,
+    ParseErrorKind, Store, TrapKind,
+};
+
+fn decode_error(bytes: &[u8]) -> DecodeError {
+    match Module::from_bytes(bytes) {
+        Err(Error::Decode(err)) => err,
+        result => panic!("expected a decode error, got {result:?}"),
+    }
+}
+
+fn parse_error(text: &str) -> ParseError {
+    match Module::from_wat(text) {
+        Err(Error::Parse(err)) => err,
+        result => panic!("expected a parse error, got {result:?}"),
+    }
+}
+
+#[test]
+fn decode_errors_carry_the_offset_and_kind() {
+    assert_eq!(
+        decode_error(b"\0wasm\x01\0\0\0").kind,
+        DecodeErrorKind::BadMagic
+    );
+    let err = decode_error(b"\0asm\x02\0\0\0");
+    assert_eq!(
+        err,
+        DecodeError {
+            offset: 7,
+            kind: DecodeErrorKind::UnsupportedVersion(2),
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "Byte address: 7, version 2 unsupported, currently only version 1 is supported"
+    );
+    let err = decode_error(b"\0asm\x01\0\0\0\x01\x05\x01\x60");
+    assert_eq!(err.kind, DecodeErrorKind::UnexpectedEnd);
+    let err = Module::from_bytes(b"\0asm\x01\0\0\0\x0d\0").unwrap_err();
+    assert_eq!(err.exit_code(), 3);
+    assert_eq!(
+        err.to_string(),
+        "Error decoding binary: Byte address: 8, unsupported section id 13"
+    );
+    let source = err.source().unwrap().downcast_ref::<DecodeError>();
+    assert_eq!(
+        source.map(|err| &err.kind),
+        Some(&DecodeErrorKind::UnsupportedSection(13))
+    );
+}
+
+#[test]
+fn parse_errors_carry_the_line_and_column() {
+    let err = parse_error("(module\n  (func\n    (i32.frobnicate)))");
+    assert_eq!((err.line, err.column), (3, 6));
+    assert_eq!(
+        err.kind,
+        ParseErrorKind::UnknownInstruction("i32.frobnicate".into())
+    );
+    assert_eq!(err.to_string(), "3:6: unknown instruction i32.frobnicate");
+    let err = parse_error("(module (func $f) (func $f))");
+    assert_eq!(
+        err.kind,
+        ParseErrorKind::DuplicateId("function", "f".into())
+    );
+    assert_eq!(parse_error("(module").kind, ParseErrorKind::UnexpectedEnd);
+}
+
+#[test]
+fn validation_and_link_errors_have_their_own_exit_codes() {
+    let err = Module::from_wat("(module (func (result i32)))").unwrap_err();
+    assert!(matches!(err, Error::Validation(_)), "{err:?}");
+    assert_eq!(err.exit_code(), 4);
+    let module = Module::from_wat(r#"(module (import "env" "f" (func)))"#).unwrap();
+    let err = Error::from(Instance::new(&mut Store::default(), &module, &[]).unwrap_err());
+    assert_eq!(err.exit_code(), 5);
+    assert_eq!(
+        err.to_string(),
+        "Error linking module: module requires 1 imports but 0 were provided"
+    );
+    let err = Error::Io {
+        path: "missing.wasm".into(),
+        source: io::Error::from(io::ErrorKind::NotFound),
+    };
+    assert_eq!(err.exit_code(), 2);
+    assert!(err.source().unwrap().is::<io::Error>());
+}
+
+#[test]
+fn start_traps_are_chained_as_sources() {
+    let module = Module::from_wat("(module (func $boom unreachable) (start $boom))").unwrap();
+    let err = Instance::new(&mut Store::default(), &module, &[]).unwrap_err();
+    let LinkError::StartTrap(trap) = &err else {
+        panic!("expected a start trap, got {err:?}");
+    };
+    assert_eq!(*trap.kind(), TrapKind::Unreachable);
+    assert_eq!(
+        err.to_string(),
+        "start function trapped: unreachable\nwasm backtrace:\n    0: $boom"
+    );
+    let err = Error::from(err);
+    assert_eq!(err.exit_code(), 6);
+    let link = err.source().unwrap();
+    assert!(link
+        .source()
+        .unwrap()
+        .to_string()
+        .starts_with("unreachable"));
+}
+
+#[test]
+fn host_results_are_checked_against_the_function_type() {
+    let mut store = Store::default();
+    let typ = FuncType {
+        params: Vec::new(),
+        results: vec![ValType::I32],
+    };
+    let func = Func::new(&mut store, typ, |_, _| Ok(Vec::new()));
+    let trap = func.call(&mut store, &[]).unwrap_err();
+    assert_eq!(
+        *trap.kind(),
+        TrapKind::ResultMismatch {
+            expected: vec![ValType::I32],
+            actual: Vec::new(),
+        }
+    );
+    assert_eq!(
+        trap.to_string(),
+        "host function returned [] but its type requires [I32]"
+    );
+}
@@ -0,0 +1,368 @@
+use wasm_interpreter::{Extern, ExternRef, Linker, Module, Store, TrapKind, Value};
+
+fn run(text: &str, name: &str, args: &[Value]) -> Vec<Value> {
+    let module = Module::from_wat(text).unwrap_or_else(|err| panic!("{err}"));
+    let mut store = Store::default();
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    instance.invoke(&mut store, name, args).unwrap()
+}
+
+#[test]
+fn recursive_calls() {
+    let text = r#"(module
+      (func $fib (export "fib") (param $n i64) (result i64)
+        (if (result i64) (i64.lt_u (local.get $n) (i64.const 2))
+          (then (local.get $n))
+          (else
+            (i64.add
+              (call $fib (i64.sub (local.get $n) (i64.const 1)))
+              (call $fib (i64.sub (local.get $n) (i64.const 2))))))))"#;
+    assert_eq!(run(text, "fib", &[Value::I64(20)]), vec![Value::I64(6765)]);
+}
+
+#[test]
+fn branches_unwind_the_operand_stack() {
+    let text = r#"(module
+      (func (export "classify") (param i32) (result i32)
+        (block $default
+          (block $two
+            (block $one
+              (block $zero
+                (br_table $zero $one $two $default (local.get 0)))
+              (return (i32.const 100)))
+            (return (i32.const 101)))
+          (return (i32.const 102)))
+        (i32.const -1))
+      (func (export "early") (result i32)
+        (i32.const 1)
+        (block (result i32)
+          (i32.const 2)
+          (i32.const 3)
+          (br 0))
+        (i32.add))
+      (func (export "count") (param $n i32) (result i32)
+        (local $sum i32)
+        (loop $next
+          (local.set $sum (i32.add (local.get $sum) (local.get $n)))
+          (local.tee $n (i32.sub (local.get $n) (i32.const 1)))
+          (br_if $next))
+        (local.get $sum)))"#;
+    let classify = |n| run(text, "classify", &[Value::I32(n)]);
+    assert_eq!(classify(0), vec![Value::I32(100)]);
+    assert_eq!(classify(2), vec![Value::I32(102)]);
+    assert_eq!(classify(7), vec![Value::I32(-1)]);
+    assert_eq!(classify(-1), vec![Value::I32(-1)]);
+    assert_eq!(run(text, "early", &[]), vec![Value::I32(4)]);
+    assert_eq!(
+        run(text, "count", &[Value::I32(100)]),
+        vec![Value::I32(5050)]
+    );
+}
+
+#[test]
+fn memory_loads_and_stores() {
+    let text = r#"(module
+      (memory (export "mem") 1)
+      (data (i32.const 16) "\ff\7f")
+      (func (export "load8_s") (result i32) (i32.load8_s (i32.const 16)))
+      (func (export "load16_u") (result i32) (i32.load16_u (i32.const 16)))
+      (func (export "store") (param i64)
+        (i64.store offset=4 (i32.const 0) (local.get 0)))
+      (func (export "load32_s") (result i64) (i64.load32_s offset=8 (i32.const 0))))"#;
+    assert_eq!(run(text, "load8_s", &[]), vec![Value::I32(-1)]);
+    assert_eq!(run(text, "load16_u", &[]), vec![Value::I32(0x7fff)]);
+    let module = Module::from_wat(text).unwrap();
+    let mut store = Store::default();
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    instance
+        .invoke(&mut store, "store", &[Value::I64(-0x1234_5678_9abc)])
+        .unwrap();
+    assert_eq!(
+        instance.invoke(&mut store, "load32_s", &[]).unwrap(),
+        vec![Value::I64(-0x1235)]
+    );
+    let Some(Extern::Memory(memory)) = instance.get_export(&store, "mem") else {
+        panic!("expected a memory export");
+    };
+    assert_eq!(
+        memory.data(&store)[4..8],
+        0x5678_9abc_u32.wrapping_neg().to_le_bytes()
+    );
+}
+
+#[test]
+fn globals_and_the_start_function() {
+    let text = r#"(module
+      (global $counter (export "counter") (mut i32) (i32.const 10))
+      (func $bump (global.set $counter (i32.add (global.get $counter) (i32.const 1))))
+      (func (export "bump") (result i32) (call $bump) (global.get $counter))
+      (start $bump))"#;
+    let module = Module::from_wat(text).unwrap();
+    let mut store = Store::default();
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    let Some(Extern::Global(counter)) = instance.get_export(&store, "counter") else {
+        panic!("expected a global export");
+    };
+    assert_eq!(counter.get(&store), Value::I32(11));
+    assert_eq!(
+        instance.invoke(&mut store, "bump", &[]).unwrap(),
+        vec![Value::I32(12)]
+    );
+}
+
+#[test]
+fn numeric_edge_cases() {
+    let text = r#"(module
+      (func (export "rotl") (param i32 i32) (result i32) (i32.rotl (local.get 0) (local.get 1)))
+      (func (export "shr_s") (param i64 i64) (result i64) (i64.shr_s (local.get 0) (local.get 1)))
+      (func (export "rem_s") (param i32 i32) (result i32) (i32.rem_s (local.get 0) (local.get 1)))
+      (func (export "min") (param f32 f32) (result f32) (f32.min (local.get 0) (local.get 1)))
+      (func (export "nearest") (param f64) (result f64) (f64.nearest (local.get 0))))"#;
+    let call = |name, args: &[Value]| run(text, name, args);
+    assert_eq!(
+        call(
+            "rotl",
+            &[Value::I32(0x8000_0001_u32 as i32), Value::I32(33)]
+        ),
+        vec![Value::I32(3)]
+    );
+    assert_eq!(
+        call("shr_s", &[Value::I64(-8), Value::I64(65)]),
+        vec![Value::I64(-4)]
+    );
+    assert_eq!(
+        call("rem_s", &[Value::I32(i32::MIN), Value::I32(-1)]),
+        vec![Value::I32(0)]
+    );
+    let [Value::F32(min)] = call("min", &[Value::F32(0.0), Value::F32(-0.0)])[..] else {
+        panic!("expected an f32");
+    };
+    assert!(min.is_sign_negative());
+    assert_eq!(call("nearest", &[Value::F64(2.5)]), vec![Value::F64(2.0)]);
+    assert_eq!(call("nearest", &[Value::F64(-3.5)]), vec![Value::F64(-4.0)]);
+}
+
+#[test]
+fn bulk_memory_operations() {
+    let text = r#"(module
+      (memory (export "mem") 1)
+      (data $hello "hello")
+      (func (export "init") (param i32 i32 i32)
+        (memory.init $hello (local.get 0) (local.get 1) (local.get 2)))
+      (func (export "drop") (data.drop $hello))
+      (func (export "copy") (param i32 i32 i32)
+        (memory.copy (local.get 0) (local.get 1) (local.get 2)))
+      (func (export "fill") (param i32 i32 i32)
+        (memory.fill (local.get 0) (local.get 1) (local.get 2))))"#;
+    let module = Module::from_wat(text).unwrap();
+    let mut store = Store::default();
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    let Some(Extern::Memory(memory)) = instance.get_export(&store, "mem") else {
+        panic!("expected a memory export");
+    };
+    let call = |store: &mut Store, name: &str, args: [i32; 3]| {
+        instance
+            .invoke(store, name, &args.map(Value::I32))
+            .map_err(|trap| trap.kind().clone())
+    };
+    call(&mut store, "init", [0, 0, 5]).unwrap();
+    call(&mut store, "init", [10, 1, 3]).unwrap();
+    assert_eq!(memory.data(&store)[..13], *b"hello\0\0\0\0\0ell");
+    call(&mut store, "copy", [1, 0, 5]).unwrap();
+    assert_eq!(memory.data(&store)[..6], *b"hhello");
+    call(&mut store, "copy", [0, 1, 5]).unwrap();
+    assert_eq!(memory.data(&store)[..6], *b"helloo");
+    call(&mut store, "fill", [2, 0x2a, 3]).unwrap();
+    assert_eq!(memory.data(&store)[..6], *b"he***o");
+    call(&mut store, "fill", [65536, 0, 0]).unwrap();
+    let oob = Err(TrapKind::OutOfBoundsMemoryAccess);
+    assert_eq!(call(&mut store, "fill", [65535, 0, 2]), oob);
+    assert_eq!(call(&mut store, "copy", [65535, 0, 2]), oob);
+    assert_eq!(call(&mut store, "init", [0, 3, 3]), oob);
+    instance.invoke(&mut store, "drop", &[]).unwrap();
+    call(&mut store, "init", [0, 0, 0]).unwrap();
+    assert_eq!(call(&mut store, "init", [0, 0, 1]), oob);
+    assert_eq!(memory.data(&store)[..6], *b"he***o");
+}
+
+#[test]
+fn reference_types_operations() {
+    let text = r#"(module
+      (type $unary (func (param i32) (result i32)))
+      (table $funcs (export "funcs") 2 funcref)
+      (table $externs 0 externref)
+      (func $double (type $unary) (i32.mul (local.get 0) (i32.const 2)))
+      (elem declare func $double)
+      (func (export "install") (param i32)
+        (table.set $funcs (local.get 0) (ref.func $double)))
+      (func (export "call") (param i32 i32) (result i32)
+        (call_indirect $funcs (type $unary) (local.get 1) (local.get 0)))
+      (func (export "is_null") (param i32) (result i32)
+        (ref.is_null (table.get $funcs (local.get 0))))
+      (func (export "grow") (param externref i32) (result i32)
+        (table.grow $externs (local.get 0) (local.get 1)))
+      (func (export "size") (result i32) (table.size $externs))
+      (func (export "get") (param i32) (result externref) (table.get $externs (local.get 0)))
+      (func (export "fill") (param i32 externref i32)
+        (table.fill $externs (local.get 0) (local.get 1) (local.get 2))))"#;
+    let module = Module::from_wat(text).unwrap();
+    let mut store = Store::default();
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    let call = |store: &mut Store, name: &str, args: &[Value]| {
+        instance
+            .invoke(store, name, args)
+            .map_err(|trap| trap.kind().clone())
+    };
+    let i32 = |value: i32| vec![Value::I32(value)];
+    assert_eq!(call(&mut store, "is_null", &i32(1)), Ok(i32(1)));
+    assert_eq!(
+        call(&mut store, "call", &[Value::I32(1), Value::I32(21)]),
+        Err(TrapKind::UninitializedElement)
+    );
+    call(&mut store, "install", &i32(1)).unwrap();
+    assert_eq!(call(&mut store, "is_null", &i32(1)), Ok(i32(0)));
+    assert_eq!(
+        call(&mut store, "call", &[Value::I32(1), Value::I32(21)]),
+        Ok(i32(42))
+    );
+    let Some(Extern::Table(funcs)) = instance.get_export(&store, "funcs") else {
+        panic!("expected a table export");
+    };
+    assert!(matches!(
+        funcs.get(&store, 1),
+        Some(Value::FuncRef(Some(_)))
+    ));
+
+    let host = ExternRef::new(&mut store, "host data");
+    let host = Value::ExternRef(Some(host));
+    let null = Value::ExternRef(None);
+    assert_eq!(call(&mut store, "grow", &[host, Value::I32(2)]), Ok(i32(0)));
+    assert_eq!(call(&mut store, "grow", &[null, Value::I32(1)]), Ok(i32(2)));
+    assert_eq!(call(&mut store, "size", &[]), Ok(i32(3)));
+    assert_eq!(call(&mut store, "get", &i32(1)), Ok(vec![host]));
+    assert_eq!(call(&mut store, "get", &i32(2)), Ok(vec![null]));
+    let Ok(results) = call(&mut store, "get", &i32(0)) else {
+        panic!("expected table.get to succeed");
+    };
+    let [Value::ExternRef(Some(data))] = results[..] else {
+        panic!("expected an extern reference, got {results:?}");
+    };
+    assert_eq!(data.data(&store).downcast_ref(), Some(&"host data"));
+    call(&mut store, "fill", &[Value::I32(1), null, Value::I32(2)]).unwrap();
+    assert_eq!(call(&mut store, "get", &i32(1)), Ok(vec![null]));
+    assert_eq!(
+        call(&mut store, "fill", &[Value::I32(2), null, Value::I32(2)]),
+        Err(TrapKind::OutOfBoundsTableAccess)
+    );
+    assert_eq!(
+        call(&mut store, "get", &i32(3)),
+        Err(TrapKind::OutOfBoundsTableAccess)
+    );
+    assert_eq!(
+        call(&mut store, "grow", &[null, Value::I32(-1)]),
+        Ok(i32(-1))
+    );
+}
+
+#[test]
+fn saturating_conversions_clamp() {
+    let text = r#"(module
+      (func (export "i32.f32_s") (param f32) (result i32) (i32.trunc_sat_f32_s (local.get 0)))
+      (func (export "i32.f32_u") (param f32) (result i32) (i32.trunc_sat_f32_u (local.get 0)))
+      (func (export "i32.f64_s") (param f64) (result i32) (i32.trunc_sat_f64_s (local.get 0)))
+      (func (export "i32.f64_u") (param f64) (result i32) (i32.trunc_sat_f64_u (local.get 0)))
+      (func (export "i64.f32_s") (param f32) (result i64) (i64.trunc_sat_f32_s (local.get 0)))
+      (func (export "i64.f32_u") (param f32) (result i64) (i64.trunc_sat_f32_u (local.get 0)))
+      (func (export "i64.f64_s") (param f64) (result i64) (i64.trunc_sat_f64_s (local.get 0)))
+      (func (export "i64.f64_u") (param f64) (result i64) (i64.trunc_sat_f64_u (local.get 0))))"#;
+    let module = Module::from_wat(text).unwrap();
+    let mut store = Store::default();
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    let mut sat = |name: &str, arg: f64| {
+        let arg = match name.ends_with("f32_s") || name.ends_with("f32_u") {
+            true => Value::F32(arg as f32),
+            false => Value::F64(arg),
+        };
+        match instance.invoke(&mut store, name, &[arg]).unwrap()[..] {
+            [Value::I32(result)] => i64::from(result),
+            [Value::I64(result)] => result,
+            ref results => panic!("unexpected results {results:?}"),
+        }
+    };
+    let (nan, inf) = (f64::NAN, f64::INFINITY);
+    let cases: &[(&str, [i64; 6])] = &[
+        // NaN, +inf, -inf, too large, too small, in range
+        (
+            "i32.f32_s",
+            [0, 2147483647, -2147483648, 2147483647, -2147483648, -1],
+        ),
+        ("i32.f32_u", [0, -1, 0, -1, 0, 0]),
+        (
+            "i32.f64_s",
+            [0, 2147483647, -2147483648, 2147483647, -2147483648, -1],
+        ),
+        ("i32.f64_u", [0, -1, 0, -1, 0, 0]),
+        ("i64.f32_s", [0, i64::MAX, i64::MIN, i64::MAX, i64::MIN, -1]),
+        ("i64.f32_u", [0, -1, 0, -1, 0, 0]),
+        ("i64.f64_s", [0, i64::MAX, i64::MIN, i64::MAX, i64::MIN, -1]),
+        ("i64.f64_u", [0, -1, 0, -1, 0, 0]),
+    ];
+    for (name, expected) in cases {
+        let args = [nan, inf, -inf, 1e20, -1e20, -1.9];
+        let actual = args.map(|arg| sat(name, arg));
+        assert_eq!(actual, *expected, "{name}");
+    }
+    assert_eq!(sat("i32.f64_s", 2147483647.9), 2147483647);
+    assert_eq!(sat("i32.f64_s", -2147483648.9), -2147483648);
+    assert_eq!(sat("i32.f64_u", 4294967295.9), -1);
+    assert_eq!(sat("i32.f32_u", 4294967040.0), 4294967040_u32 as i32 as i64);
+    assert_eq!(sat("i64.f64_u", 18446744073709549568.0), -2048);
+    assert_eq!(sat("i64.f64_s", -9223372036854775808.0), i64::MIN);
+}
+
+#[test]
+fn sign_extension_boundaries() {
+    let text = r#"(module
+      (func (export "i32.extend8_s") (param i32) (result i32) (i32.extend8_s (local.get 0)))
+      (func (export "i32.extend16_s") (param i32) (result i32) (i32.extend16_s (local.get 0)))
+      (func (export "i64.extend8_s") (param i64) (result i64) (i64.extend8_s (local.get 0)))
+      (func (export "i64.extend16_s") (param i64) (result i64) (i64.extend16_s (local.get 0)))
+      (func (export "i64.extend32_s") (param i64) (result i64) (i64.extend32_s (local.get 0))))"#;
+    let i32_cases: &[(&str, i32, i32)] = &[
+        ("i32.extend8_s", 0x7f, 127),
+        ("i32.extend8_s", 0x80, -128),
+        ("i32.extend8_s", 0xff, -1),
+        ("i32.extend8_s", 0x1234_5600, 0),
+        ("i32.extend8_s", -0x7f, -127),
+        ("i32.extend16_s", 0x7fff, 32767),
+        ("i32.extend16_s", 0x8000, -32768),
+        ("i32.extend16_s", 0xffff, -1),
+        ("i32.extend16_s", 0x7fff_0000, 0),
+    ];
+    for &(name, arg, expected) in i32_cases {
+        assert_eq!(
+            run(text, name, &[Value::I32(arg)]),
+            vec![Value::I32(expected)],
+            "{name} {arg:#x}"
+        );
+    }
+    let i64_cases: &[(&str, i64, i64)] = &[
+        ("i64.extend8_s", 0x7f, 127),
+        ("i64.extend8_s", 0x80, -128),
+        ("i64.extend8_s", -0x0100, 0),
+        ("i64.extend16_s", 0x7fff, 32767),
+        ("i64.extend16_s", 0x8000, -32768),
+        ("i64.extend16_s", 0x1_ffff, -1),
+        ("i64.extend32_s", 0x7fff_ffff, 2147483647),
+        ("i64.extend32_s", 0x8000_0000, -2147483648),
+        ("i64.extend32_s", 0xffff_ffff, -1),
+        ("i64.extend32_s", 0x1234_5678_0000_0001, 1),
+    ];
+    for &(name, arg, expected) in i64_cases {
+        assert_eq!(
+            run(text, name, &[Value::I64(arg)]),
+            vec![Value::I64(expected)],
+            "{name} {arg:#x}"
+        );
+    }
+}
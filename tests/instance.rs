@@ -0,0 +1,108 @@
+use std::fs;
+use wasm_interpreter::module::{FuncIdx, FuncType, Instr, Start, TypeIdx, ValType};
+use wasm_interpreter::{
+    Error, Extern, Func, Instance, LinkError, Linker, Module, Store, TrapKind, Value,
+};
+
+const ADDER: &str = r#"(module
+  (func $log (import "env" "log") (param i32))
+  (func (export "add") (param i32 i32) (result i32)
+    (call $log (local.get 0))
+    (i32.add (local.get 0) (local.get 1))))"#;
+
+#[test]
+fn binaries_load_and_run() {
+    let bytes = fs::read("examples/only_one_function.wasm").unwrap();
+    let module = Module::from_bytes(&bytes).unwrap();
+    let mut store = Store::default();
+    Instance::new(&mut store, &module, &[]).unwrap();
+    let module = Module::from_wat(&fs::read_to_string("examples/math.wat").unwrap()).unwrap();
+    let instance = Instance::new(&mut store, &module, &[]).unwrap();
+    let add = instance.get_func(&store, "add").unwrap();
+    assert_eq!(
+        add.typ(&store),
+        &FuncType {
+            params: vec![ValType::I32, ValType::I32],
+            results: vec![ValType::I32],
+        }
+    );
+    assert_eq!(
+        add.call(&mut store, &[Value::I32(40), Value::I32(2)]),
+        Ok(vec![Value::I32(42)])
+    );
+    assert!(instance.get_func(&store, "memory").is_none());
+    assert!(matches!(
+        instance.get_export(&store, "memory"),
+        Some(Extern::Memory(_))
+    ));
+}
+
+#[test]
+fn imports_are_passed_in_order() {
+    let module = Module::from_wat(ADDER).unwrap();
+    let mut store = Store::default();
+    assert_eq!(
+        Instance::new(&mut store, &module, &[]).unwrap_err(),
+        LinkError::ImportCountMismatch {
+            expected: 1,
+            actual: 0,
+        }
+    );
+    let typ = FuncType {
+        params: vec![ValType::I32],
+        results: Vec::new(),
+    };
+    let log = Func::new(&mut store, typ, |_, _| Ok(Vec::new()));
+    let instance = Instance::new(&mut store, &module, &[log.into()]).unwrap();
+    assert_eq!(
+        instance.invoke(&mut store, "add", &[Value::I32(1), Value::I32(2)]),
+        Ok(vec![Value::I32(3)])
+    );
+}
+
+#[test]
+fn bad_invocations_are_reported() {
+    let module = Module::from_wat(ADDER).unwrap();
+    let mut store = Store::default();
+    let typ = FuncType {
+        params: vec![ValType::I32],
+        results: Vec::new(),
+    };
+    let log = Func::new(&mut store, typ, |_, _| Ok(Vec::new()));
+    let instance = Instance::new(&mut store, &module, &[log.into()]).unwrap();
+    let trap = instance.invoke(&mut store, "sub", &[]).unwrap_err();
+    assert_eq!(*trap.kind(), TrapKind::UnknownExport("sub".into()));
+    let trap = instance
+        .invoke(&mut store, "add", &[Value::I32(1), Value::I64(2)])
+        .unwrap_err();
+    assert_eq!(
+        *trap.kind(),
+        TrapKind::ArgumentMismatch {
+            expected: vec![ValType::I32, ValType::I32],
+            actual: vec![ValType::I32, ValType::I64],
+        }
+    );
+    let trap = instance.invoke(&mut store, "add", &[]).unwrap_err();
+    assert!(matches!(trap.kind(), TrapKind::ArgumentMismatch { .. }));
+}
+
+#[test]
+fn invalid_modules_are_refused() {
+    let module =
+        Module::from_wat(r#"(module (func (export "f") (result i32) (i32.const 1)))"#).unwrap();
+    let mut wrong_result = module.clone();
+    wrong_result.funcs[0].body.0 = vec![Instr::I64Const(1)];
+    let mut unknown_type = module.clone();
+    unknown_type.funcs[0].typ = TypeIdx(7);
+    let mut unknown_start = module.clone();
+    unknown_start.start = Some(Start { func: FuncIdx(3) });
+    for invalid in [wrong_result, unknown_type, unknown_start] {
+        let mut store = Store::default();
+        let err = Instance::new(&mut store, &invalid, &[]).unwrap_err();
+        assert!(matches!(err, LinkError::InvalidModule(_)), "{err:?}");
+        let err = Linker::new().instantiate(&mut store, &invalid).unwrap_err();
+        let err = Error::from(err);
+        assert!(matches!(err, Error::Validation(_)), "{err:?}");
+        assert_eq!(err.exit_code(), 4);
+    }
+}
@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_interpreter::module::{
+    ElemType, FuncType, GlobalType, Limits, MemType, Mut, TableType, ValType,
+};
+use wasm_interpreter::{
+    Extern, Global, LinkError, Linker, Memory, Module, Store, Table, Trap, Value,
+};
+
+fn link_error(linker: &Linker, store: &mut Store, text: &str) -> LinkError {
+    let module = Module::from_wat(text).unwrap();
+    linker.instantiate(store, &module).unwrap_err()
+}
+
+fn reason(err: LinkError) -> String {
+    match err {
+        LinkError::IncompatibleImport { reason, .. } => reason,
+        err => panic!("expected an incompatible import, got {err:?}"),
+    }
+}
+
+#[test]
+fn host_functions_see_the_calling_instance() {
+    let module = Module::from_wat(
+        r#"(module
+          (import "host" "print" (func $print (param i32 i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 8) "hello")
+          (func (export "main") (call $print (i32.const 8) (i32.const 5))))"#,
+    )
+    .unwrap();
+    let printed = Rc::new(RefCell::new(String::new()));
+    let mut store = Store::default();
+    let mut linker = Linker::new();
+    let sink = Rc::clone(&printed);
+    let typ = FuncType {
+        params: vec![ValType::I32, ValType::I32],
+        results: Vec::new(),
+    };
+    linker.func(&mut store, "host", "print", typ, move |caller, args| {
+        let [Value::I32(ptr), Value::I32(len)] = args[..] else {
+            unreachable!("checked against the function type");
+        };
+        let Some(Extern::Memory(memory)) = caller.get_export("memory") else {
+            return Err(Trap::new("no memory export"));
+        };
+        let bytes = &caller.memory_data(memory)[ptr as usize..(ptr + len) as usize];
+        sink.borrow_mut()
+            .push_str(std::str::from_utf8(bytes).unwrap());
+        Ok(Vec::new())
+    });
+    let instance = linker.instantiate(&mut store, &module).unwrap();
+    instance.invoke(&mut store, "main", &[]).unwrap();
+    assert_eq!(*printed.borrow(), "hello");
+}
+
+#[test]
+fn host_globals_memories_and_tables_are_shared() {
+    let mut store = Store::default();
+    let counter = Global::new(
+        &mut store,
+        GlobalType(Mut::Var, ValType::I64),
+        Value::I64(41),
+    )
+    .unwrap();
+    let memory = Memory::new(
+        &mut store,
+        MemType(Limits {
+            min: 1,
+            max: Some(2),
+        }),
+    );
+    let table = Table::new(
+        &mut store,
+        TableType(Limits { min: 2, max: None }, ElemType::FuncRef),
+    );
+    let mut linker = Linker::new();
+    linker
+        .define("env", "counter", counter)
+        .define("env", "memory", memory)
+        .define("env", "table", table);
+    let module = Module::from_wat(
+        r#"(module
+          (import "env" "counter" (global $counter (mut i64)))
+          (import "env" "memory" (memory 1 2))
+          (import "env" "table" (table 1 funcref))
+          (func $f)
+          (elem (i32.const 1) $f)
+          (func (export "bump")
+            (global.set $counter (i64.add (global.get $counter) (i64.const 1)))
+            (i64.store (i32.const 0) (global.get $counter))))"#,
+    )
+    .unwrap();
+    let instance = linker.instantiate(&mut store, &module).unwrap();
+    instance.invoke(&mut store, "bump", &[]).unwrap();
+    assert_eq!(counter.get(&store), Value::I64(42));
+    assert_eq!(memory.data(&store)[..8], 42_i64.to_le_bytes());
+    assert!(matches!(
+        table.get(&store, 1),
+        Some(Value::FuncRef(Some(_)))
+    ));
+    assert_eq!(table.get(&store, 0), Some(Value::FuncRef(None)));
+}
+
+#[test]
+fn instances_can_be_registered_for_later_modules() {
+    let mut store = Store::default();
+    let mut linker = Linker::new();
+    let provider =
+        Module::from_wat(r#"(module (func (export "seven") (result i32) (i32.const 7)))"#).unwrap();
+    let instance = linker.instantiate(&mut store, &provider).unwrap();
+    linker.instance(&store, "provider", instance);
+    let consumer = Module::from_wat(
+        r#"(module
+          (import "provider" "seven" (func $seven (result i32)))
+          (func (export "fourteen") (result i32) (i32.mul (call $seven) (i32.const 2))))"#,
+    )
+    .unwrap();
+    let instance = linker.instantiate(&mut store, &consumer).unwrap();
+    assert_eq!(
+        instance.invoke(&mut store, "fourteen", &[]),
+        Ok(vec![Value::I32(14)])
+    );
+}
+
+#[test]
+fn import_mismatches_are_reported() {
+    let mut store = Store::default();
+    let mut linker = Linker::new();
+    let typ = FuncType {
+        params: vec![ValType::I32],
+        results: Vec::new(),
+    };
+    linker.func(&mut store, "env", "f", typ, |_, _| Ok(Vec::new()));
+    let global = Global::new(
+        &mut store,
+        GlobalType(Mut::Const, ValType::I32),
+        Value::I32(0),
+    );
+    linker.define("env", "g", global.unwrap());
+    let memory = Memory::new(&mut store, MemType(Limits { min: 1, max: None }));
+    linker.define("env", "m", memory);
+    assert_eq!(
+        link_error(&linker, &mut store, r#"(module (import "env" "h" (func)))"#),
+        LinkError::UnknownImport {
+            module: "env".into(),
+            name: "h".into(),
+        }
+    );
+    let err = link_error(
+        &linker,
+        &mut store,
+        r#"(module (import "env" "f" (func (param i64))))"#,
+    );
+    assert!(reason(err).starts_with("expected function"));
+    let err = link_error(
+        &linker,
+        &mut store,
+        r#"(module (import "env" "f" (global i32)))"#,
+    );
+    assert_eq!(reason(err), "expected global but found function");
+    let err = link_error(
+        &linker,
+        &mut store,
+        r#"(module (import "env" "g" (global (mut i32))))"#,
+    );
+    assert!(reason(err).starts_with("expected global Var I32"));
+    let err = link_error(
+        &linker,
+        &mut store,
+        r#"(module (import "env" "m" (memory 2)))"#,
+    );
+    assert_eq!(reason(err), "expected a minimum size of 2 but found 1");
+    let err = link_error(
+        &linker,
+        &mut store,
+        r#"(module (import "env" "m" (memory 1 3)))"#,
+    );
+    assert_eq!(
+        reason(err),
+        "expected a maximum size of 3 but found no maximum"
+    );
+}
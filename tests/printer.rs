@@ -0,0 +1,209 @@
+use std::fs;
+use wasm_interpreter::Module;
+
+#[test]
+fn examples_print_and_parse_back() {
+    for entry in fs::read_dir("examples").unwrap() {
+        let path = entry.unwrap().path();
+        let module = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("wat") => Module::from_wat(&fs::read_to_string(&path).unwrap()).unwrap(),
+            Some("wasm") => Module::from_bytes(&fs::read(&path).unwrap()).unwrap(),
+            _ => continue,
+        };
+        let text = module.to_wat();
+        let printed = Module::from_wat(&text).unwrap_or_else(|err| panic!("{err}\n{text}"));
+        assert_eq!(printed, module, "{}", path.display());
+    }
+}
+
+#[test]
+fn nested_blocks_are_indented() {
+    let module = Module::from_wat(
+        r#"(module
+          (func $f (export "f") (param $n i32) (result i32)
+            (local $i i64)
+            (block (result i32)
+              (loop
+                (drop (br_if 1 (i32.const 7) (local.get $n)))
+                (if (local.get $n) (then (br 1)) (else (nop))))
+              (i32.const 0)))
+          (memory 1)
+          (global (mut f32) (f32.const -0.5))
+          (data (i32.const 8) "a\"b\00"))"#,
+    )
+    .unwrap();
+    let expected = r#"(module
+  (type (;0;) (func (param i32) (result i32)))
+  (func $f (;0;) (type 0) (param $n i32) (result i32)
+    (local $i i64)
+    block (result i32)
+      loop
+        i32.const 7
+        local.get $n
+        br_if 1
+        drop
+        local.get $n
+        if
+          br 1
+        else
+          nop
+        end
+      end
+      i32.const 0
+    end
+  )
+  (memory (;0;) 1)
+  (global (;0;) (mut f32) f32.const -0.5)
+  (export "f" (func $f))
+  (data (;0;) (i32.const 8) "a\"b\00")
+)
+"#;
+    assert_eq!(module.to_wat(), expected);
+}
+
+#[test]
+fn unusable_names_fall_back_to_indices() {
+    let mut module =
+        Module::from_wat("(module (func $a (call $b)) (func $b (call $a)) (func (call 0)))")
+            .unwrap();
+    module.names.funcs.insert(0, "has space".into());
+    module.names.funcs.insert(2, "b".into());
+    let text = module.to_wat();
+    assert!(
+        text.contains("(func (;0;) (type 0)\n    call 1\n"),
+        "{text}"
+    );
+    assert!(
+        text.contains("(func (;1;) (type 0)\n    call 0\n"),
+        "{text}"
+    );
+    let printed = Module::from_wat(&text).unwrap();
+    assert_eq!(printed.funcs, module.funcs);
+}
+
+// Each module uses the instructions and segment forms a proposal added,
+// along with lines its printed form has to contain.
+const PROPOSALS: &[(&str, &str, &[&str])] = &[
+    (
+        "bulk memory",
+        r#"(module
+          (table 2 funcref)
+          (memory 1)
+          (func $f
+            (memory.init 1 (i32.const 0) (i32.const 0) (i32.const 1))
+            (data.drop 1)
+            (memory.copy (i32.const 0) (i32.const 1) (i32.const 1))
+            (memory.fill (i32.const 0) (i32.const 0) (i32.const 1))
+            (table.init 0 2 (i32.const 0) (i32.const 0) (i32.const 0))
+            (elem.drop 2)
+            (table.copy (i32.const 0) (i32.const 1) (i32.const 1)))
+          (elem (i32.const 0) $f)
+          (elem func $f)
+          (elem declare func $f)
+          (data (i32.const 0) "a")
+          (data "b"))"#,
+        &[
+            "    memory.init 1\n",
+            "    data.drop 1\n",
+            "    memory.copy\n",
+            "    memory.fill\n",
+            "    table.init 2\n",
+            "    elem.drop 2\n",
+            "    table.copy\n",
+            "  (elem (;0;) (i32.const 0) func $f)\n",
+            "  (elem (;1;) func $f)\n",
+            "  (elem (;2;) declare func $f)\n",
+            "  (data (;1;) \"b\")\n",
+        ],
+    ),
+    (
+        "reference types",
+        r#"(module
+          (type $t (func))
+          (table $funcs 1 funcref)
+          (table $externs 2 externref)
+          (table $more 1 funcref)
+          (func $f (param externref) (result externref)
+            (call_indirect $more (type $t) (i32.const 0))
+            (select (result externref) (local.get 0) (ref.null extern) (i32.const 1))
+            (drop (ref.is_null (ref.func $f)))
+            (drop (table.grow $externs (ref.null extern) (i32.const 1)))
+            (table.set $externs (i32.const 0) (table.get $externs (i32.const 1)))
+            (table.fill $externs (i32.const 0) (ref.null extern) (table.size $externs)))
+          (elem (table $externs) (i32.const 0) externref (ref.null extern))
+          (elem funcref (ref.func $f) (ref.null func)))"#,
+        &[
+            "  (table (;1;) 2 externref)\n",
+            "  (func $f (;0;) (type 1) (param externref) (result externref)\n",
+            "    call_indirect 2 (type 0)\n",
+            "    select (result externref)\n",
+            "    ref.func $f\n",
+            "    ref.is_null\n",
+            "    table.grow 1\n",
+            "    table.get 1\n",
+            "    table.set 1\n",
+            "    table.size 1\n",
+            "    table.fill 1\n",
+            "  (elem (;0;) (table 1) (i32.const 0) externref (ref.null extern))\n",
+            "  (elem (;1;) funcref (ref.func $f) (ref.null func))\n",
+        ],
+    ),
+    (
+        "saturating conversions",
+        r#"(module
+          (func (param f32 f64) (result i64)
+            (i64.extend_i32_u (i32.trunc_sat_f32_u (local.get 0)))
+            (i64.trunc_sat_f64_s (local.get 1))
+            (i64.add)
+            (drop (i32.trunc_sat_f32_s (local.get 0)))
+            (drop (i32.trunc_sat_f64_s (local.get 1)))
+            (drop (i32.trunc_sat_f64_u (local.get 1)))
+            (drop (i64.trunc_sat_f32_s (local.get 0)))
+            (drop (i64.trunc_sat_f32_u (local.get 0)))
+            (drop (i64.trunc_sat_f64_u (local.get 1)))))"#,
+        &[
+            "    i32.trunc_sat_f32_s\n",
+            "    i32.trunc_sat_f32_u\n",
+            "    i32.trunc_sat_f64_s\n",
+            "    i32.trunc_sat_f64_u\n",
+            "    i64.trunc_sat_f32_s\n",
+            "    i64.trunc_sat_f32_u\n",
+            "    i64.trunc_sat_f64_s\n",
+            "    i64.trunc_sat_f64_u\n",
+        ],
+    ),
+    (
+        "sign extension",
+        "(module (func (param i32 i64) (result i64)
+          (i64.extend_i32_s (i32.extend16_s (i32.extend8_s (local.get 0))))
+          (i64.extend32_s (i64.extend16_s (i64.extend8_s (local.get 1))))
+          (i64.add)))",
+        &[
+            "    i32.extend8_s\n",
+            "    i32.extend16_s\n",
+            "    i64.extend8_s\n",
+            "    i64.extend16_s\n",
+            "    i64.extend32_s\n",
+        ],
+    ),
+];
+
+#[test]
+fn proposals_round_trip() {
+    for (proposal, source, lines) in PROPOSALS {
+        let module = Module::from_wat(source).unwrap_or_else(|err| panic!("{proposal}: {err}"));
+        let text = module.to_wat();
+        for line in *lines {
+            assert!(
+                text.contains(line),
+                "{proposal}: missing {line:?} in\n{text}"
+            );
+        }
+        assert_eq!(Module::from_wat(&text).unwrap(), module, "{proposal}");
+        assert_eq!(
+            Module::from_bytes(&module.to_bytes()).unwrap(),
+            module,
+            "{proposal}"
+        );
+    }
+}
@@ -0,0 +1,468 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wasm_interpreter::module::{
+    ElemType, FuncType, GlobalType, Limits, MemType, Mut, TableType, ValType,
+};
+use wasm_interpreter::{
+    Error, Extern, ExternRef, Func, Global, Instance, LinkError, Linker, Memory, Module,
+    ParseError, ParseErrorKind, Store, Table, Value,
+};
+use wast::core::{AbstractHeapType, HeapType, NanPattern, WastArgCore, WastRetCore};
+use wast::parser::{self, ParseBuffer};
+use wast::token::Id;
+use wast::{
+    QuoteWat, QuoteWatTest, Wast, WastArg, WastDirective, WastExecute, WastInvoke, WastRet,
+};
+
+const WAST_DIR: &str = "tests/wast";
+const KNOWN_FAILURES: &str = "tests/testsuite-known-failures.txt";
+
+// Runs our own scripts under tests/wast. They are written in the spec's
+// format and exercise each proposal, but are not the official testsuite;
+// pointing WASM_SPEC_TESTSUITE at a checkout of
+// github.com/WebAssembly/testsuite runs that instead, with the directives
+// listed in KNOWN_FAILURES expected to fail.
+#[test]
+fn spec_testsuite() {
+    let (root, known_failures) = match env::var_os("WASM_SPEC_TESTSUITE") {
+        Some(root) => (PathBuf::from(root), known_failures()),
+        None => (PathBuf::from(WAST_DIR), HashSet::new()),
+    };
+    let mut report = Vec::new();
+    let mut failures = Vec::new();
+    for (name, dir) in proposals(&root) {
+        let mut totals = [0; 3];
+        for path in wast_files(&dir) {
+            let result = run_file(&root, &path, &known_failures);
+            totals[0] += result.passed;
+            totals[1] += result.failures.len();
+            totals[2] += result.skipped;
+            failures.extend(result.failures);
+        }
+        report.push((name, totals));
+    }
+
+    println!(
+        "{:<40} {:>8} {:>8} {:>8}",
+        "proposal", "passed", "failed", "skipped"
+    );
+    for (name, [passed, failed, skipped]) in &report {
+        println!("{name:<40} {passed:>8} {failed:>8} {skipped:>8}");
+    }
+    for failure in &failures {
+        println!("FAIL {failure}");
+    }
+    assert!(
+        failures.is_empty(),
+        "{} spec directives failed",
+        failures.len()
+    );
+}
+
+// Each line of the list is a `file.wast:line` relative to the suite root,
+// optionally followed by a `#` comment saying why it fails.
+fn known_failures() -> HashSet<String> {
+    fs::read_to_string(KNOWN_FAILURES)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+// Scripts directly in the root are the core suite, every other directory is
+// a proposal; upstream keeps those under `proposals/`.
+fn proposals(root: &Path) -> Vec<(String, PathBuf)> {
+    let mut proposals = Vec::new();
+    if !wast_files(root).is_empty() {
+        proposals.push((String::from("core"), root.to_path_buf()));
+    }
+    for dir in subdirectories(root) {
+        let name = dir.file_name().unwrap().to_string_lossy().into_owned();
+        if name == "proposals" {
+            proposals.extend(proposals_in(&dir));
+        } else if !wast_files(&dir).is_empty() {
+            proposals.push((name, dir));
+        }
+    }
+    proposals
+}
+
+fn proposals_in(dir: &Path) -> Vec<(String, PathBuf)> {
+    subdirectories(dir)
+        .into_iter()
+        .map(|dir| (dir.file_name().unwrap().to_string_lossy().into_owned(), dir))
+        .collect()
+}
+
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("cannot read {}: {err}", dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    dirs
+}
+
+fn wast_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wast"))
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+struct FileResult {
+    passed: usize,
+    skipped: usize,
+    failures: Vec<String>,
+}
+
+fn run_file(root: &Path, path: &Path, known_failures: &HashSet<String>) -> FileResult {
+    let source = fs::read_to_string(path).unwrap();
+    let relative = path
+        .strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string();
+    let buffer = match ParseBuffer::new(&source) {
+        Ok(buffer) => buffer,
+        Err(err) => return FileResult::error(path, err),
+    };
+    let wast = match parser::parse::<Wast>(&buffer) {
+        Ok(wast) => wast,
+        Err(err) => return FileResult::error(path, err),
+    };
+
+    let mut runner = Runner::new();
+    let mut result = FileResult {
+        passed: 0,
+        skipped: 0,
+        failures: Vec::new(),
+    };
+    for directive in wast.directives {
+        let (line, col) = directive.span().linecol_in(&source);
+        let key = format!("{relative}:{}", line + 1);
+        let known = known_failures.contains(&key);
+        match runner.run(directive) {
+            Ok(()) if known => result
+                .failures
+                .push(format!("{key}: passes but is listed in {KNOWN_FAILURES}")),
+            Ok(()) => result.passed += 1,
+            Err(_) if known => result.skipped += 1,
+            Err(message) => result
+                .failures
+                .push(format!("{key}:{}: {message}", col + 1)),
+        }
+    }
+    result
+}
+
+impl FileResult {
+    fn error(path: &Path, err: impl std::fmt::Display) -> Self {
+        Self {
+            passed: 0,
+            skipped: 0,
+            failures: vec![format!("{}: {err}", path.display())],
+        }
+    }
+}
+
+struct Runner {
+    store: Store,
+    linker: Linker,
+    instances: HashMap<String, Instance>,
+    current: Option<Instance>,
+    // Scripts name host references by number; each number maps to one handle
+    // whose payload is that number.
+    externs: HashMap<u32, ExternRef>,
+}
+
+impl Runner {
+    fn new() -> Self {
+        let mut store = Store::default();
+        let mut linker = Linker::new();
+        define_spectest(&mut store, &mut linker);
+        Self {
+            store,
+            linker,
+            instances: HashMap::new(),
+            current: None,
+            externs: HashMap::new(),
+        }
+    }
+
+    fn run(&mut self, directive: WastDirective) -> Result<(), String> {
+        match directive {
+            WastDirective::Module(mut module) => {
+                let id = module.name();
+                let module = load(&mut module)?.map_err(|err| err.to_string())?;
+                let instance = self.instantiate(&module).map_err(|err| err.to_string())?;
+                if let Some(id) = id {
+                    self.instances.insert(id.name().to_string(), instance);
+                }
+                self.current = Some(instance);
+                Ok(())
+            }
+            WastDirective::Register { name, module, .. } => {
+                let instance = self.instance(module)?;
+                self.linker.instance(&self.store, name, instance);
+                Ok(())
+            }
+            WastDirective::Invoke(invoke) => {
+                self.invoke(invoke)?.map_err(|err| err.to_string())?;
+                Ok(())
+            }
+            WastDirective::AssertReturn { exec, results, .. } => {
+                let actual = self.execute(exec)?.map_err(|err| err.to_string())?;
+                if actual.len() != results.len() {
+                    return Err(format!(
+                        "expected {} results, got {actual:?}",
+                        results.len()
+                    ));
+                }
+                for (actual, expected) in actual.iter().zip(&results) {
+                    if !matches_result(&self.store, actual, expected)? {
+                        return Err(format!("expected {expected:?}, got {actual:?}"));
+                    }
+                }
+                Ok(())
+            }
+            WastDirective::AssertTrap { exec, message, .. } => match self.execute(exec)? {
+                Ok(results) => Err(format!("expected trap `{message}`, got {results:?}")),
+                Err(err) => expect_trap(&err, message),
+            },
+            WastDirective::AssertExhaustion { call, message, .. } => match self.invoke(call)? {
+                Ok(results) => Err(format!("expected trap `{message}`, got {results:?}")),
+                Err(err) => expect_trap(&err, message),
+            },
+            WastDirective::AssertInvalid {
+                mut module,
+                message,
+                ..
+            } => match load(&mut module)? {
+                Ok(_) => Err(format!("expected invalid module `{message}`")),
+                Err(Error::Validation(_) | Error::Decode(_)) => Ok(()),
+                Err(err) => Err(format!("expected invalid module `{message}`, got {err}")),
+            },
+            WastDirective::AssertMalformed {
+                mut module,
+                message,
+                ..
+            } => match load(&mut module)? {
+                Ok(_) => Err(format!("expected malformed module `{message}`")),
+                Err(Error::Decode(_) | Error::Parse(_)) => Ok(()),
+                Err(err) => Err(format!("expected malformed module `{message}`, got {err}")),
+            },
+            WastDirective::AssertUnlinkable {
+                module, message, ..
+            } => {
+                let module = load(&mut QuoteWat::Wat(module))?.map_err(|err| err.to_string())?;
+                match self.instantiate(&module) {
+                    Ok(_) => Err(format!("expected unlinkable module `{message}`")),
+                    Err(Error::Link(_)) => Ok(()),
+                    Err(err) => Err(format!("expected unlinkable module `{message}`, got {err}")),
+                }
+            }
+            directive => Err(format!("unsupported directive {directive:?}")),
+        }
+    }
+
+    fn instantiate(&mut self, module: &Module) -> Result<Instance, Error> {
+        Ok(self.linker.instantiate(&mut self.store, module)?)
+    }
+
+    fn instance(&self, id: Option<Id>) -> Result<Instance, String> {
+        match id {
+            Some(id) => self
+                .instances
+                .get(id.name())
+                .copied()
+                .ok_or_else(|| format!("unknown module ${}", id.name())),
+            None => self
+                .current
+                .ok_or_else(|| String::from("no module instantiated")),
+        }
+    }
+
+    fn execute(&mut self, exec: WastExecute) -> Result<Result<Vec<Value>, Error>, String> {
+        match exec {
+            WastExecute::Invoke(invoke) => self.invoke(invoke),
+            WastExecute::Wat(module) => Ok(load(&mut QuoteWat::Wat(module))?
+                .and_then(|module| self.instantiate(&module))
+                .map(|_| Vec::new())),
+            WastExecute::Get { module, global, .. } => {
+                let instance = self.instance(module)?;
+                match instance.get_export(&self.store, global) {
+                    Some(Extern::Global(global)) => Ok(Ok(vec![global.get(&self.store)])),
+                    _ => Err(format!("no exported global named {global}")),
+                }
+            }
+        }
+    }
+
+    fn invoke(&mut self, invoke: WastInvoke) -> Result<Result<Vec<Value>, Error>, String> {
+        let instance = self.instance(invoke.module)?;
+        let args = invoke
+            .args
+            .iter()
+            .map(|arg| self.argument(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(instance
+            .invoke(&mut self.store, invoke.name, &args)
+            .map_err(Error::Trap))
+    }
+
+    fn argument(&mut self, arg: &WastArg) -> Result<Value, String> {
+        match arg {
+            WastArg::Core(WastArgCore::I32(value)) => Ok(Value::I32(*value)),
+            WastArg::Core(WastArgCore::I64(value)) => Ok(Value::I64(*value)),
+            WastArg::Core(WastArgCore::F32(value)) => Ok(Value::F32(f32::from_bits(value.bits))),
+            WastArg::Core(WastArgCore::F64(value)) => Ok(Value::F64(f64::from_bits(value.bits))),
+            WastArg::Core(WastArgCore::RefNull(heap_type)) => match heap_type {
+                HeapType::Abstract {
+                    ty: AbstractHeapType::Func,
+                    ..
+                } => Ok(Value::FuncRef(None)),
+                HeapType::Abstract {
+                    ty: AbstractHeapType::Extern,
+                    ..
+                } => Ok(Value::ExternRef(None)),
+                _ => Err(format!("unsupported argument {arg:?}")),
+            },
+            WastArg::Core(WastArgCore::RefExtern(n)) => {
+                let store = &mut self.store;
+                let extern_ref = *self
+                    .externs
+                    .entry(*n)
+                    .or_insert_with(|| ExternRef::new(store, *n));
+                Ok(Value::from(extern_ref))
+            }
+            arg => Err(format!("unsupported argument {arg:?}")),
+        }
+    }
+}
+
+// Quoted text goes through our own parser and everything else through our
+// decoder, so a module only counts as malformed if we are the ones who
+// reject it. An error from `wast` itself is a broken script, not a pass.
+fn load(module: &mut QuoteWat) -> Result<Result<Module, Error>, String> {
+    Ok(match module.to_test().map_err(|err| err.to_string())? {
+        QuoteWatTest::Binary(bytes) => Module::from_bytes(&bytes),
+        QuoteWatTest::Text(text) => match String::from_utf8(text) {
+            Ok(text) => Module::from_wat(&text),
+            Err(_) => Err(Error::Parse(ParseError {
+                line: 1,
+                column: 1,
+                kind: ParseErrorKind::MalformedUtf8,
+            })),
+        },
+    })
+}
+
+fn define_spectest(store: &mut Store, linker: &mut Linker) {
+    let print = |params: Vec<ValType>| {
+        let typ = FuncType {
+            params,
+            results: Vec::new(),
+        };
+        move |store: &mut Store| Func::new(store, typ.clone(), |_, _| Ok(Vec::new()))
+    };
+    let funcs = [
+        ("print", vec![]),
+        ("print_i32", vec![ValType::I32]),
+        ("print_i64", vec![ValType::I64]),
+        ("print_f32", vec![ValType::F32]),
+        ("print_f64", vec![ValType::F64]),
+        ("print_i32_f32", vec![ValType::I32, ValType::F32]),
+        ("print_f64_f64", vec![ValType::F64, ValType::F64]),
+    ];
+    for (name, params) in funcs {
+        let func = print(params)(store);
+        linker.define("spectest", name, func);
+    }
+
+    let globals = [
+        ("global_i32", Value::I32(666)),
+        ("global_i64", Value::I64(666)),
+        ("global_f32", Value::F32(666.6)),
+        ("global_f64", Value::F64(666.6)),
+    ];
+    for (name, value) in globals {
+        let global = Global::new(store, GlobalType(Mut::Const, value.typ()), value).unwrap();
+        linker.define("spectest", name, global);
+    }
+
+    let table = Table::new(
+        store,
+        TableType(
+            Limits {
+                min: 10,
+                max: Some(20),
+            },
+            ElemType::FuncRef,
+        ),
+    );
+    linker.define("spectest", "table", table);
+    let memory = Memory::new(
+        store,
+        MemType(Limits {
+            min: 1,
+            max: Some(2),
+        }),
+    );
+    linker.define("spectest", "memory", memory);
+}
+
+fn matches_result(store: &Store, actual: &Value, expected: &WastRet) -> Result<bool, String> {
+    let WastRet::Core(expected) = expected else {
+        return Err(format!("unsupported result {expected:?}"));
+    };
+    Ok(match (actual, expected) {
+        (Value::I32(actual), WastRetCore::I32(expected)) => actual == expected,
+        (Value::I64(actual), WastRetCore::I64(expected)) => actual == expected,
+        (Value::F32(actual), WastRetCore::F32(expected)) => match expected {
+            NanPattern::CanonicalNan => actual.to_bits() & 0x7fff_ffff == 0x7fc0_0000,
+            NanPattern::ArithmeticNan => actual.to_bits() & 0x7fc0_0000 == 0x7fc0_0000,
+            NanPattern::Value(expected) => actual.to_bits() == expected.bits,
+        },
+        (Value::F64(actual), WastRetCore::F64(expected)) => match expected {
+            NanPattern::CanonicalNan => {
+                actual.to_bits() & 0x7fff_ffff_ffff_ffff == 0x7ff8_0000_0000_0000
+            }
+            NanPattern::ArithmeticNan => {
+                actual.to_bits() & 0x7ff8_0000_0000_0000 == 0x7ff8_0000_0000_0000
+            }
+            NanPattern::Value(expected) => actual.to_bits() == expected.bits,
+        },
+        (Value::FuncRef(actual), WastRetCore::RefNull(_)) => actual.is_none(),
+        (Value::ExternRef(actual), WastRetCore::RefNull(_)) => actual.is_none(),
+        (Value::FuncRef(actual), WastRetCore::RefFunc(_)) => actual.is_some(),
+        (Value::ExternRef(actual), WastRetCore::RefExtern(expected)) => match (actual, expected) {
+            (Some(actual), Some(n)) => actual.data(store).downcast_ref::<u32>() == Some(n),
+            (Some(_), None) => true,
+            (None, _) => false,
+        },
+        _ => false,
+    })
+}
+
+fn expect_trap(err: &Error, message: &str) -> Result<(), String> {
+    let trap = match err {
+        Error::Trap(trap) => trap,
+        Error::Link(LinkError::StartTrap(trap)) => trap,
+        err => return Err(format!("expected trap `{message}`, got {err}")),
+    };
+    let actual = trap.kind().to_string();
+    if actual.starts_with(message) {
+        Ok(())
+    } else {
+        Err(format!("expected trap `{message}`, got `{actual}`"))
+    }
+}
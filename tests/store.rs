@@ -0,0 +1,83 @@
+use wasm_interpreter::{Extern, ExternRef, LinkError, Linker, Module, Store, TrapKind, Value};
+
+const TEXT: &str = r#"(module
+  (memory (export "memory") 1)
+  (func (export "count") (param $n i32)
+    (loop $next
+      (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+      (br_if $next (local.get $n))))
+  (func (export "grow") (param i32) (result i32)
+    (memory.grow (local.get 0))))"#;
+
+#[test]
+fn fuel_is_consumed_per_instruction() {
+    let module = Module::from_wat(TEXT).unwrap();
+    let mut store = Store::default();
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    store.set_fuel(Some(100));
+    instance
+        .invoke(&mut store, "count", &[Value::I32(3)])
+        .unwrap();
+    // One loop instruction plus six instructions per iteration.
+    assert_eq!(store.fuel(), Some(100 - 1 - 3 * 6));
+    let trap = instance
+        .invoke(&mut store, "count", &[Value::I32(1000)])
+        .unwrap_err();
+    assert_eq!(*trap.kind(), TrapKind::OutOfFuel);
+    assert_eq!(store.fuel(), Some(0));
+    store.set_fuel(None);
+    instance
+        .invoke(&mut store, "count", &[Value::I32(1000)])
+        .unwrap();
+}
+
+#[test]
+fn memory_limit_caps_instantiation_and_growth() {
+    let module = Module::from_wat(TEXT).unwrap();
+    let mut store = Store::default();
+    store.set_memory_limit(Some(3));
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    let grow = |store: &mut Store, pages| instance.invoke(store, "grow", &[Value::I32(pages)]);
+    assert_eq!(grow(&mut store, 2).unwrap(), vec![Value::I32(1)]);
+    assert_eq!(grow(&mut store, 1).unwrap(), vec![Value::I32(-1)]);
+    let mut store = Store::default();
+    store.set_memory_limit(Some(0));
+    assert_eq!(
+        Linker::new().instantiate(&mut store, &module).unwrap_err(),
+        LinkError::MemoryLimitExceeded { pages: 1, limit: 0 }
+    );
+}
+
+#[test]
+fn extern_refs_pass_through_tables() {
+    let module = Module::from_wat(
+        r#"(module
+          (table (export "table") 1 externref)
+          (func (export "swap") (param externref) (result externref)
+            (table.get (i32.const 0))
+            (table.set (i32.const 0) (local.get 0))))"#,
+    )
+    .unwrap();
+    let mut store = Store::default();
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    let Some(Extern::Table(table)) = instance.get_export(&store, "table") else {
+        panic!("expected a table export");
+    };
+    let greeting = ExternRef::new(&mut store, String::from("hello"));
+    let results = instance
+        .invoke(&mut store, "swap", &[greeting.into()])
+        .unwrap();
+    assert_eq!(results, vec![Value::ExternRef(None)]);
+    let Some(Value::ExternRef(Some(stored))) = table.get(&store, 0) else {
+        panic!("expected a non-null reference");
+    };
+    assert_eq!(stored, greeting);
+    assert_eq!(
+        stored.data(&store).downcast_ref::<String>().unwrap(),
+        "hello"
+    );
+    assert_eq!(table.grow(&mut store, 2, stored.into()), Ok(1));
+    assert_eq!(table.size(&store), 3);
+    assert!(table.set(&mut store, 3, Value::ExternRef(None)).is_err());
+    assert!(table.set(&mut store, 2, Value::FuncRef(None)).is_err());
+}
@@ -0,0 +1,131 @@
+use wasm_interpreter::{FrameInfo, Linker, Module, Store, Trap, TrapKind, Value};
+
+const TRAPS: &str = r#"(module
+  (type $unary (func (param i32) (result i32)))
+  (memory 1)
+  (table 3 funcref)
+  (elem (i32.const 0) $id $nullary)
+  (func $id (type $unary) (local.get 0))
+  (func $nullary)
+  (func (export "unreachable") unreachable)
+  (func (export "div_s") (param i32 i32) (result i32) (i32.div_s (local.get 0) (local.get 1)))
+  (func (export "rem_u") (param i64 i64) (result i64) (i64.rem_u (local.get 0) (local.get 1)))
+  (func (export "trunc") (param f64) (result i32) (i32.trunc_f64_s (local.get 0)))
+  (func (export "load") (param i32) (result i64) (i64.load offset=2 (local.get 0)))
+  (func (export "fill") (param i32 i32) (memory.fill (local.get 0) (i32.const 0) (local.get 1)))
+  (func (export "call_indirect") (param i32) (result i32)
+    (call_indirect (type $unary) (i32.const 5) (local.get 0)))
+  (func (export "table_get") (param i32) (result funcref) (table.get (local.get 0)))
+  (func $recurse (export "recurse") (call $recurse)))"#;
+
+fn trap(name: &str, args: &[Value]) -> Trap {
+    let module = Module::from_wat(TRAPS).unwrap();
+    let mut store = Store::default();
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    instance.invoke(&mut store, name, args).unwrap_err()
+}
+
+fn kind(name: &str, args: &[Value]) -> TrapKind {
+    trap(name, args).kind().clone()
+}
+
+#[test]
+fn numeric_traps() {
+    assert_eq!(kind("unreachable", &[]), TrapKind::Unreachable);
+    assert_eq!(
+        kind("div_s", &[Value::I32(1), Value::I32(0)]),
+        TrapKind::IntegerDivideByZero
+    );
+    assert_eq!(
+        kind("div_s", &[Value::I32(i32::MIN), Value::I32(-1)]),
+        TrapKind::IntegerOverflow
+    );
+    assert_eq!(
+        kind("rem_u", &[Value::I64(1), Value::I64(0)]),
+        TrapKind::IntegerDivideByZero
+    );
+    assert_eq!(
+        kind("trunc", &[Value::F64(f64::NAN)]),
+        TrapKind::InvalidConversionToInteger
+    );
+    assert_eq!(
+        kind("trunc", &[Value::F64(2147483648.0)]),
+        TrapKind::IntegerOverflow
+    );
+    assert_eq!(
+        kind("trunc", &[Value::F64(f64::NEG_INFINITY)]),
+        TrapKind::IntegerOverflow
+    );
+}
+
+#[test]
+fn memory_and_table_traps() {
+    let oob = TrapKind::OutOfBoundsMemoryAccess;
+    assert_eq!(kind("load", &[Value::I32(65527)]), oob);
+    assert_eq!(kind("load", &[Value::I32(-1)]), oob);
+    assert_eq!(kind("fill", &[Value::I32(65535), Value::I32(2)]), oob);
+    assert_eq!(
+        kind("call_indirect", &[Value::I32(1)]),
+        TrapKind::IndirectCallTypeMismatch
+    );
+    assert_eq!(
+        kind("call_indirect", &[Value::I32(2)]),
+        TrapKind::UninitializedElement
+    );
+    assert_eq!(
+        kind("call_indirect", &[Value::I32(3)]),
+        TrapKind::UndefinedElement
+    );
+    assert_eq!(
+        kind("table_get", &[Value::I32(3)]),
+        TrapKind::OutOfBoundsTableAccess
+    );
+}
+
+#[test]
+fn runaway_recursion_exhausts_the_call_stack() {
+    let trap = trap("recurse", &[]);
+    assert_eq!(*trap.kind(), TrapKind::CallStackExhausted);
+    assert_eq!(trap.backtrace().len(), 16384);
+    let message = trap.to_string();
+    let lines: Vec<&str> = message.lines().collect();
+    assert_eq!(
+        lines[..3],
+        ["call stack exhausted", "wasm backtrace:", "    0: $recurse"]
+    );
+    assert_eq!(lines.len(), 2 + 32 + 1);
+    assert_eq!(
+        lines.last().copied(),
+        Some(format!("  ... {} more frames", trap.backtrace().len() - 32).as_str())
+    );
+}
+
+#[test]
+fn backtraces_list_the_innermost_frame_first() {
+    let module = Module::from_wat(
+        r#"(module
+          (func $inner (param i32) (result i32) (i32.div_u (i32.const 1) (local.get 0)))
+          (func (result i32) (call $inner (i32.const 0)))
+          (func $outer (export "outer") (result i32) (call 1)))"#,
+    )
+    .unwrap();
+    let mut store = Store::default();
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    let trap = instance.invoke(&mut store, "outer", &[]).unwrap_err();
+    let frame = |func_idx, name: Option<&str>| FrameInfo {
+        func_idx,
+        func_name: name.map(String::from),
+    };
+    assert_eq!(
+        trap.backtrace(),
+        [
+            frame(0, Some("inner")),
+            frame(1, None),
+            frame(2, Some("outer"))
+        ]
+    );
+    assert_eq!(
+        trap.to_string(),
+        "integer divide by zero\nwasm backtrace:\n    0: $inner\n    1: func 1\n    2: $outer"
+    );
+}
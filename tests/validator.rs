@@ -0,0 +1,130 @@
+use wasm_interpreter::module::{BlockType, End, Instr, ValType};
+use wasm_interpreter::{Error, Module, ValidationError, ValidationErrorKind};
+
+fn validation_error(text: &str) -> ValidationError {
+    match Module::from_wat(text) {
+        Err(Error::Validation(err)) => err,
+        other => panic!("expected a validation error, got {other:?}"),
+    }
+}
+
+#[test]
+fn errors_point_at_the_function_and_instruction() {
+    let err = validation_error(
+        r#"(module
+          (func)
+          (func $add (param i32) (result i32)
+            (local.get 0)
+            (i64.const 1)
+            (i32.add)))"#,
+    );
+    assert_eq!(err.func_idx, Some(1));
+    assert_eq!(err.func_name.as_deref(), Some("add"));
+    assert_eq!(err.instr_offset, Some(2));
+    assert_eq!(
+        err.kind,
+        ValidationErrorKind::TypeMismatch {
+            expected: ValType::I32,
+            actual: ValType::I64,
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "in $add, instruction 2: type mismatch: expected I32, found I64"
+    );
+}
+
+#[test]
+fn unreachable_code_is_polymorphic() {
+    Module::from_wat(
+        r#"(module
+          (func (result i32)
+            (block (result i32)
+              (block (result i64)
+                unreachable
+                (br_table 0 1 (i32.const 0)))
+              (drop)
+              (i32.const 0)))
+          (func (result f64)
+            unreachable
+            (select)
+            (f64.add)))"#,
+    )
+    .unwrap();
+    let err = validation_error(
+        r#"(module
+          (func
+            (block (result i32)
+              (block
+                (br_table 0 1 (i32.const 0) (i32.const 0))))))"#,
+    );
+    assert_eq!(err.kind, ValidationErrorKind::BrTableArityMismatch);
+    let err = validation_error(
+        r#"(module
+          (func (result i32)
+            (block (result i32)
+              (block (result i64)
+                (br_table 0 1 (i64.const 0) (i32.const 0)))
+              (drop)
+              (i32.const 0))))"#,
+    );
+    assert!(
+        matches!(err.kind, ValidationErrorKind::TypeMismatch { .. }),
+        "{err}"
+    );
+}
+
+#[test]
+fn block_results_are_checked() {
+    let err = validation_error("(module (func (block (result i32) (nop))))");
+    assert_eq!(err.kind, ValidationErrorKind::StackUnderflow);
+    let err = validation_error("(module (func (block (i32.const 1))))");
+    assert_eq!(err.kind, ValidationErrorKind::ValuesRemaining);
+    let err =
+        validation_error("(module (func (if (result i32) (i32.const 1) (then (i32.const 1)))))");
+    assert_eq!(err.kind, ValidationErrorKind::StackUnderflow);
+    let err = validation_error("(module (func (br 1)))");
+    assert_eq!(err.kind, ValidationErrorKind::UnknownLabel(1));
+}
+
+#[test]
+fn module_level_rules() {
+    let err =
+        validation_error("(module (global i32 (i32.const 0)) (func (global.set 0 (i32.const 1))))");
+    assert_eq!(err.kind, ValidationErrorKind::ImmutableGlobal(0));
+    let err = validation_error("(module (global i32 (i32.add (i32.const 1) (i32.const 2))))");
+    assert_eq!(
+        err.kind,
+        ValidationErrorKind::Global(0, Box::new(ValidationErrorKind::ConstExprRequired))
+    );
+    let err = validation_error(r#"(module (func (export "f")) (func (export "f")))"#);
+    assert_eq!(err.kind, ValidationErrorKind::DuplicateExport("f".into()));
+    let err = validation_error("(module (func (param i32)) (start 0))");
+    assert_eq!(err.kind, ValidationErrorKind::InvalidStartFunction);
+    let err =
+        validation_error("(module (memory 1) (func (drop (i32.load align=8 (i32.const 0)))))");
+    assert_eq!(err.kind, ValidationErrorKind::AlignmentTooLarge);
+    let err = validation_error("(module (func (call 3)))");
+    assert_eq!(err.kind, ValidationErrorKind::UnknownFunction(3));
+}
+
+// Takes the nested blocks apart one level at a time, since the derived drop
+// recurses once per level.
+fn dismantle(mut body: Vec<Instr>) {
+    while let Some(Instr::Block(_, inner, _)) = body.pop() {
+        body = inner;
+    }
+}
+
+#[test]
+fn deeply_nested_blocks_do_not_overflow_the_stack() {
+    let mut module = Module::from_wat("(module (func (result i32) (i32.const 1)))").unwrap();
+    let mut body = std::mem::take(&mut module.funcs[0].body.0);
+    for _ in 0..100_000 {
+        body = vec![Instr::Block(BlockType::Value(ValType::I32), body, End)];
+    }
+    module.funcs[0].body.0 = body;
+    let result = module.validate();
+    dismantle(std::mem::take(&mut module.funcs[0].body.0));
+    result.unwrap();
+}
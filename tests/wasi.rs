@@ -0,0 +1,255 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use wasm_interpreter::wasi::WasiCtx;
+use wasm_interpreter::{Extern, Instance, Linker, Memory, Module, Store, Value};
+
+const EFAULT: i32 = 21;
+const ENOENT: i32 = 44;
+const ENOTCAPABLE: i32 = 76;
+const OFLAGS_CREAT: i32 = 1;
+const OFLAGS_TRUNC: i32 = 8;
+const RIGHTS_FD_WRITE: i64 = 1 << 6;
+
+const GUEST: &str = r#"(module
+  (import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "args_get" (func $args_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "environ_sizes_get" (func $environ_sizes_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "environ_get" (func $environ_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "path_open"
+    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+  (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+  (memory (export "memory") 1)
+  (func (export "args_sizes_get") (param i32 i32) (result i32)
+    (call $args_sizes_get (local.get 0) (local.get 1)))
+  (func (export "args_get") (param i32 i32) (result i32)
+    (call $args_get (local.get 0) (local.get 1)))
+  (func (export "environ_sizes_get") (param i32 i32) (result i32)
+    (call $environ_sizes_get (local.get 0) (local.get 1)))
+  (func (export "environ_get") (param i32 i32) (result i32)
+    (call $environ_get (local.get 0) (local.get 1)))
+  (func (export "path_open") (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)
+    (call $path_open (local.get 0) (local.get 1) (local.get 2) (local.get 3) (local.get 4)
+      (local.get 5) (local.get 6) (local.get 7) (local.get 8)))
+  (func (export "fd_write") (param i32 i32 i32 i32) (result i32)
+    (call $fd_write (local.get 0) (local.get 1) (local.get 2) (local.get 3)))
+  (func (export "fd_close") (param i32) (result i32)
+    (call $fd_close (local.get 0)))
+  (func (export "proc_exit") (param i32)
+    (call $proc_exit (local.get 0))))"#;
+
+struct Guest {
+    store: Store,
+    instance: Instance,
+    memory: Memory,
+}
+
+impl Guest {
+    fn new(wasi: WasiCtx) -> Self {
+        let module = Module::from_wat(GUEST).unwrap();
+        let mut store = Store::default();
+        let mut linker = Linker::new();
+        wasi.add_to_linker(&mut linker, &mut store);
+        let instance = linker.instantiate(&mut store, &module).unwrap();
+        let Some(Extern::Memory(memory)) = instance.get_export(&store, "memory") else {
+            panic!("expected a memory export");
+        };
+        Self {
+            store,
+            instance,
+            memory,
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[Value]) -> i32 {
+        match self.instance.invoke(&mut self.store, name, args).unwrap()[..] {
+            [Value::I32(errno)] => errno,
+            ref results => panic!("unexpected results {results:?}"),
+        }
+    }
+
+    fn read(&self, ptr: usize, len: usize) -> &[u8] {
+        &self.memory.data(&self.store)[ptr..ptr + len]
+    }
+
+    fn read_u32(&self, ptr: usize) -> u32 {
+        u32::from_le_bytes(self.read(ptr, 4).try_into().unwrap())
+    }
+
+    fn write(&mut self, ptr: usize, bytes: &[u8]) {
+        self.memory.data_mut(&mut self.store)[ptr..ptr + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn open(&mut self, path: &str, oflags: i32, rights: i64) -> Result<u32, i32> {
+        self.write(1000, path.as_bytes());
+        let args = [
+            Value::I32(3),
+            Value::I32(0),
+            Value::I32(1000),
+            Value::I32(path.len() as i32),
+            Value::I32(oflags),
+            Value::I64(rights),
+            Value::I64(0),
+            Value::I32(0),
+            Value::I32(996),
+        ];
+        match self.call("path_open", &args) {
+            0 => Ok(self.read_u32(996)),
+            errno => Err(errno),
+        }
+    }
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wasm-interpreter-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn args_and_environ_are_copied_into_memory() {
+    let args = vec!["prog".to_string(), "a b".to_string()];
+    let env = vec![("A".to_string(), "1".to_string())];
+    let mut guest = Guest::new(WasiCtx::new(args, env));
+    assert_eq!(
+        guest.call("args_sizes_get", &[Value::I32(0), Value::I32(4)]),
+        0
+    );
+    assert_eq!((guest.read_u32(0), guest.read_u32(4)), (2, 9));
+    assert_eq!(guest.call("args_get", &[Value::I32(16), Value::I32(64)]), 0);
+    assert_eq!((guest.read_u32(16), guest.read_u32(20)), (64, 69));
+    assert_eq!(guest.read(64, 9), b"prog\0a b\0");
+    assert_eq!(
+        guest.call("environ_sizes_get", &[Value::I32(0), Value::I32(4)]),
+        0
+    );
+    assert_eq!((guest.read_u32(0), guest.read_u32(4)), (1, 4));
+    assert_eq!(
+        guest.call("environ_get", &[Value::I32(16), Value::I32(64)]),
+        0
+    );
+    assert_eq!(guest.read_u32(16), 64);
+    assert_eq!(guest.read(64, 4), b"A=1\0");
+    assert_eq!(
+        guest.call("args_get", &[Value::I32(16), Value::I32(65534)]),
+        EFAULT
+    );
+}
+
+#[test]
+fn files_are_opened_inside_preopened_directories() {
+    let dir = scratch_dir("open");
+    let mut wasi = WasiCtx::new(Vec::new(), Vec::new());
+    wasi.preopen_dir(&dir, "/sandbox");
+    let mut guest = Guest::new(wasi);
+    let fd = guest
+        .open("out.txt", OFLAGS_CREAT | OFLAGS_TRUNC, RIGHTS_FD_WRITE)
+        .unwrap();
+    assert_eq!(fd, 4);
+    guest.write(400, b"hello");
+    guest.write(300, &[400_u32.to_le_bytes(), 5_u32.to_le_bytes()].concat());
+    let args = [fd as i32, 300, 1, 200].map(Value::I32);
+    assert_eq!(guest.call("fd_write", &args), 0);
+    assert_eq!(guest.read_u32(200), 5);
+    assert_eq!(guest.call("fd_close", &[Value::I32(fd as i32)]), 0);
+    assert_eq!(fs::read_to_string(dir.join("out.txt")).unwrap(), "hello");
+    assert_eq!(guest.open("./sub/../out.txt", 0, 0), Ok(4));
+    assert_eq!(guest.open("missing.txt", 0, 0), Err(ENOENT));
+    assert_eq!(guest.open("../out.txt", 0, 0), Err(ENOTCAPABLE));
+    assert_eq!(guest.open("/etc/passwd", 0, 0), Err(ENOTCAPABLE));
+    assert_eq!(guest.call("fd_close", &[Value::I32(3)]), ENOTCAPABLE);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_cannot_escape_the_sandbox() {
+    use std::os::unix::fs::symlink;
+    let outside = scratch_dir("outside");
+    fs::write(outside.join("secret.txt"), "secret").unwrap();
+    let dir = scratch_dir("links");
+    fs::write(dir.join("inside.txt"), "inside").unwrap();
+    symlink(&outside, dir.join("out")).unwrap();
+    symlink(outside.join("secret.txt"), dir.join("secret.txt")).unwrap();
+    symlink(outside.join("new.txt"), dir.join("dangling.txt")).unwrap();
+    symlink("inside.txt", dir.join("alias.txt")).unwrap();
+    let mut wasi = WasiCtx::new(Vec::new(), Vec::new());
+    wasi.preopen_dir(&dir, ".");
+    let mut guest = Guest::new(wasi);
+    assert_eq!(guest.open("alias.txt", 0, 0), Ok(4));
+    assert_eq!(guest.open("out/secret.txt", 0, 0), Err(ENOTCAPABLE));
+    assert_eq!(guest.open("secret.txt", 0, 0), Err(ENOTCAPABLE));
+    assert_eq!(guest.open("out", 0, 0), Err(ENOTCAPABLE));
+    assert_eq!(
+        guest.open("dangling.txt", OFLAGS_CREAT, RIGHTS_FD_WRITE),
+        Err(ENOTCAPABLE)
+    );
+    assert!(!outside.join("new.txt").exists());
+    fs::remove_dir_all(dir).unwrap();
+    fs::remove_dir_all(outside).unwrap();
+}
+
+#[test]
+fn proc_exit_carries_the_exit_code() {
+    let mut guest = Guest::new(WasiCtx::new(Vec::new(), Vec::new()));
+    let trap = guest
+        .instance
+        .invoke(&mut guest.store, "proc_exit", &[Value::I32(7)])
+        .unwrap_err();
+    assert_eq!(trap.exit_code(), Some(7));
+}
+
+#[test]
+fn programs_write_to_stdout_and_exit() {
+    let dir = scratch_dir("cli");
+    let program = dir.join("hello.wat");
+    fs::write(
+        &program,
+        r#"(module
+          (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "\10\00\00\00\03\00\00\00")
+          (data (i32.const 16) "hi\n")
+          (func (export "_start")
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 32)))
+            (call $proc_exit (i32.const 3))))"#,
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_wasm-interpreter"))
+        .arg(&program)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hi\n");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn pointers_at_the_end_of_the_address_space_fault() {
+    let args = vec!["prog".to_string(), "arg".to_string()];
+    let mut guest = Guest::new(WasiCtx::new(args, Vec::new()));
+    let last = u32::MAX as i32 - 3;
+    for (name, args) in [
+        ("fd_write", [1, last, 1, 0]),
+        ("fd_write", [1, 0, -1, 0]),
+        ("fd_write", [1, 65528, 2, 0]),
+    ] {
+        assert_eq!(guest.call(name, &args.map(Value::I32)), EFAULT, "{args:?}");
+    }
+    for args in [[last, 0], [0, last], [-1, -1]] {
+        assert_eq!(
+            guest.call("args_get", &args.map(Value::I32)),
+            EFAULT,
+            "{args:?}"
+        );
+    }
+    assert_eq!(
+        guest.call("args_get", &[Value::I32(0), Value::I32(65527)]),
+        0
+    );
+    assert_eq!(guest.read(65527, 9), b"prog\0arg\0");
+}
@@ -0,0 +1,133 @@
+;; Segment forms.
+(module
+  (memory $m 1)
+  (data (i32.const 0))
+  (data (i32.const 1) "a" "" "bcd")
+  (data (offset (i32.const 0)))
+  (data 0 (i32.const 0))
+  (data (memory 0) (i32.const 0) "")
+  (data (memory $m) (offset (i32.const 0)) "")
+  (data $d1 (i32.const 0))
+  (data $d2 (memory $m) (i32.const 0) "")
+  (data)
+  (data "passive")
+  (data $d3 "" "passive")
+)
+
+;; Active segments are applied in order and later ones win.
+(module
+  (memory (export "memory") 1)
+  (data (i32.const 0) "\01\02\03")
+  (data (i32.const 1) "\aa")
+  (func (export "load8_u") (param i32) (result i32)
+    (i32.load8_u (local.get 0)))
+)
+(assert_return (invoke "load8_u" (i32.const 0)) (i32.const 1))
+(assert_return (invoke "load8_u" (i32.const 1)) (i32.const 0xaa))
+(assert_return (invoke "load8_u" (i32.const 2)) (i32.const 3))
+(register "M")
+
+;; A segment that does not fit aborts instantiation, but the segments before
+;; it stay written.
+(assert_unlinkable
+  (module
+    (import "M" "memory" (memory 1))
+    (data (i32.const 10) "\bb")
+    (data (i32.const 0xffff) "\cc\dd")
+    (data (i32.const 11) "\ee")
+  )
+  "out of bounds memory access"
+)
+(assert_return (invoke "load8_u" (i32.const 10)) (i32.const 0xbb))
+(assert_return (invoke "load8_u" (i32.const 11)) (i32.const 0))
+(assert_return (invoke "load8_u" (i32.const 0xffff)) (i32.const 0))
+
+;; An empty segment at the end of memory fits.
+(module
+  (memory 1)
+  (data (i32.const 0x10000) "")
+)
+(assert_unlinkable
+  (module
+    (memory 1)
+    (data (i32.const 0x10001) "")
+  )
+  "out of bounds memory access"
+)
+
+;; The data count section must match the data section.
+(module binary
+  "\00asm" "\01\00\00\00"
+  "\05\03\01"                             ;; memory section
+  "\00\01"                                ;; memory 0
+  "\0c\01\01"                             ;; data count section: 1 segment
+  "\0b\04\01"                             ;; data section
+  "\01\01\ff"                             ;; passive data segment
+)
+
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\0c\01\01"                           ;; data count section: 1 segment
+  )
+  "data count and data section have inconsistent lengths"
+)
+
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\05\03\01"                           ;; memory section
+    "\00\01"                              ;; memory 0
+    "\0c\01\02"                           ;; data count section: 2 segments
+    "\0b\04\01"                           ;; data section
+    "\01\01\ff"                           ;; passive data segment
+  )
+  "data count and data section have inconsistent lengths"
+)
+
+;; The data count section comes before the code section.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\01\04\01\60\00\00"                  ;; type section
+    "\03\02\01\00"                        ;; function section
+    "\0a\04\01\02\00\0b"                  ;; code section
+    "\0c\01\00"                           ;; data count section
+  )
+  "unexpected content after last section"
+)
+
+;; memory.init and data.drop need the data count section.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\01\04\01\60\00\00"                  ;; type section
+    "\03\02\01\00"                        ;; function section
+    "\05\03\01\00\01"                     ;; memory section
+    "\0a\07\01\05\00\fc\09\00\0b"         ;; code section: data.drop 0
+    "\0b\03\01\01\00"                     ;; data section
+  )
+  "data count section required"
+)
+
+;; Segment flags above 2 are malformed.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\05\03\01\00\01"                     ;; memory section
+    "\0b\03\01\03\00"                     ;; data section with flags 3
+  )
+  "malformed data segment kind"
+)
+
+(assert_invalid
+  (module (data (i32.const 0) ""))
+  "unknown memory 0")
+
+(assert_invalid
+  (module (memory 1) (data (i64.const 0) ""))
+  "type mismatch")
+
+(assert_invalid
+  (module (memory 1) (data (offset (i32.const 0) (i32.const 0)) ""))
+  "constant expression required")
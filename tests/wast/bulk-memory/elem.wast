@@ -0,0 +1,107 @@
+;; Segment forms.
+(module
+  (table $t 10 funcref)
+  (func $f)
+  (func $g)
+  (elem (i32.const 0))
+  (elem (i32.const 0) $f $g)
+  (elem (offset (i32.const 0)))
+  (elem (offset (i32.const 0)) $f $g)
+  (elem 0 (i32.const 0))
+  (elem 0x0 (i32.const 0) $f $f)
+  (elem (table 0) (i32.const 0) func)
+  (elem (table $t) (offset (i32.const 0)) func $f $g)
+  (elem $a (i32.const 0) func $f)
+  (elem $b func $f $g)
+  (elem func)
+  (elem declare func $f)
+  (elem $c declare func)
+)
+
+;; Inline table elements define a segment in field order.
+(module
+  (elem $first func $f)
+  (table funcref (elem $f $f))
+  (elem $third func $f)
+  (func $f (result i32) (i32.const 7))
+  (func (export "drop-third") (elem.drop $third))
+  (func (export "call") (param i32) (result i32)
+    (call_indirect (result i32) (local.get 0)))
+)
+(assert_return (invoke "call" (i32.const 1)) (i32.const 7))
+(invoke "drop-third")
+
+;; Active segments are applied in order and later ones win.
+(module
+  (table (export "table") 4 funcref)
+  (type $out (func (result i32)))
+  (func $zero (type $out) (i32.const 0))
+  (func $one (type $out) (i32.const 1))
+  (elem (i32.const 0) $zero $zero)
+  (elem (i32.const 1) $one)
+  (func (export "call") (param i32) (result i32)
+    (call_indirect (type $out) (local.get 0)))
+)
+(assert_return (invoke "call" (i32.const 0)) (i32.const 0))
+(assert_return (invoke "call" (i32.const 1)) (i32.const 1))
+(register "M")
+
+;; A segment that does not fit aborts instantiation, but the segments before
+;; it stay written and remain callable.
+(assert_unlinkable
+  (module
+    (import "M" "table" (table 4 funcref))
+    (func $two (result i32) (i32.const 2))
+    (elem (i32.const 2) $two)
+    (elem (i32.const 4) $two)
+    (elem (i32.const 3) $two)
+  )
+  "out of bounds table access"
+)
+(assert_return (invoke "call" (i32.const 2)) (i32.const 2))
+(assert_trap (invoke "call" (i32.const 3)) "uninitialized element")
+
+;; Data segments are not applied when an element segment does not fit.
+(module
+  (memory (export "memory") 1)
+  (func (export "load8_u") (param i32) (result i32)
+    (i32.load8_u (local.get 0)))
+)
+(register "N")
+(assert_unlinkable
+  (module
+    (import "M" "table" (table 4 funcref))
+    (import "N" "memory" (memory 1))
+    (func $f)
+    (elem (i32.const 10) $f)
+    (data (i32.const 0) "\aa")
+  )
+  "out of bounds table access"
+)
+(assert_return (invoke "load8_u" (i32.const 0)) (i32.const 0))
+
+;; Segment flags above 3 need reference types.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\04\04\01\70\00\01"                  ;; table section
+    "\09\04\01\08\00\00"                  ;; elem section with flags 8
+  )
+  "malformed elements segment kind"
+)
+
+(assert_invalid
+  (module (elem (i32.const 0)))
+  "unknown table 0")
+
+(assert_invalid
+  (module (table 1 funcref) (elem (i64.const 0)))
+  "type mismatch")
+
+(assert_invalid
+  (module (table 1 funcref) (elem func 0))
+  "unknown function 0")
+
+(assert_invalid
+  (module (table 1 funcref) (elem declare func 0))
+  "unknown function 0")
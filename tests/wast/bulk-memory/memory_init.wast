@@ -0,0 +1,85 @@
+(module
+  (memory 1)
+  (data $passive "\01\02\03\04\05")
+  (data $active (i32.const 100) "\aa\bb")
+
+  (func (export "init") (param i32 i32 i32)
+    (memory.init $passive (local.get 0) (local.get 1) (local.get 2)))
+
+  (func (export "init_active") (param i32 i32 i32)
+    (memory.init $active (local.get 0) (local.get 1) (local.get 2)))
+
+  (func (export "drop") (data.drop $passive))
+
+  (func (export "load8_u") (param i32) (result i32)
+    (i32.load8_u (local.get 0)))
+)
+
+;; Passive segments are not written at instantiation.
+(assert_return (invoke "load8_u" (i32.const 0)) (i32.const 0))
+(assert_return (invoke "load8_u" (i32.const 100)) (i32.const 0xaa))
+(assert_return (invoke "load8_u" (i32.const 101)) (i32.const 0xbb))
+
+(invoke "init" (i32.const 10) (i32.const 1) (i32.const 3))
+(assert_return (invoke "load8_u" (i32.const 9)) (i32.const 0))
+(assert_return (invoke "load8_u" (i32.const 10)) (i32.const 2))
+(assert_return (invoke "load8_u" (i32.const 11)) (i32.const 3))
+(assert_return (invoke "load8_u" (i32.const 12)) (i32.const 4))
+(assert_return (invoke "load8_u" (i32.const 13)) (i32.const 0))
+
+;; A segment can be used more than once.
+(invoke "init" (i32.const 20) (i32.const 0) (i32.const 5))
+(assert_return (invoke "load8_u" (i32.const 24)) (i32.const 5))
+
+;; Reading past the end of the segment traps without writing.
+(assert_trap (invoke "init" (i32.const 30) (i32.const 2) (i32.const 4))
+    "out of bounds memory access")
+(assert_return (invoke "load8_u" (i32.const 30)) (i32.const 0))
+
+;; Writing past the end of memory traps without writing.
+(assert_trap (invoke "init" (i32.const 0xfffe) (i32.const 0) (i32.const 3))
+    "out of bounds memory access")
+(assert_return (invoke "load8_u" (i32.const 0xfffe)) (i32.const 0))
+
+;; Zero-length accesses at the end of the segment or memory are allowed.
+(invoke "init" (i32.const 0x10000) (i32.const 5) (i32.const 0))
+(assert_trap (invoke "init" (i32.const 0) (i32.const 6) (i32.const 0))
+    "out of bounds memory access")
+(assert_trap (invoke "init" (i32.const 0x10001) (i32.const 0) (i32.const 0))
+    "out of bounds memory access")
+
+;; Active segments are dropped after instantiation.
+(invoke "init_active" (i32.const 0) (i32.const 0) (i32.const 0))
+(assert_trap (invoke "init_active" (i32.const 0) (i32.const 0) (i32.const 1))
+    "out of bounds memory access")
+
+;; A dropped segment behaves like an empty one, and can be dropped again.
+(invoke "drop")
+(invoke "drop")
+(invoke "init" (i32.const 0) (i32.const 0) (i32.const 0))
+(assert_trap (invoke "init" (i32.const 0) (i32.const 0) (i32.const 1))
+    "out of bounds memory access")
+
+(assert_invalid
+  (module
+    (func (memory.init 0 (i32.const 0) (i32.const 0) (i32.const 0)))
+    (data ""))
+  "unknown memory 0")
+
+(assert_invalid
+  (module
+    (memory 1)
+    (func (memory.init 1 (i32.const 0) (i32.const 0) (i32.const 0)))
+    (data ""))
+  "unknown data segment 1")
+
+(assert_invalid
+  (module (func (data.drop 0)))
+  "unknown data segment 0")
+
+(assert_invalid
+  (module
+    (memory 1)
+    (func (memory.init 0 (i32.const 0) (i64.const 0) (i32.const 0)))
+    (data ""))
+  "type mismatch")
@@ -0,0 +1,60 @@
+(module
+  (table 10 funcref)
+  (elem (i32.const 2) $zero $one $two)
+
+  (type $out (func (result i32)))
+  (func $zero (type $out) (i32.const 0))
+  (func $one (type $out) (i32.const 1))
+  (func $two (type $out) (i32.const 2))
+
+  (func (export "copy") (param i32 i32 i32)
+    (table.copy (local.get 0) (local.get 1) (local.get 2)))
+
+  (func (export "copy_explicit") (param i32 i32 i32)
+    (table.copy 0 0 (local.get 0) (local.get 1) (local.get 2)))
+
+  (func (export "call") (param i32) (result i32)
+    (call_indirect (type $out) (local.get 0)))
+)
+
+;; Overlap, source < dest.
+(invoke "copy" (i32.const 3) (i32.const 2) (i32.const 3))
+(assert_return (invoke "call" (i32.const 2)) (i32.const 0))
+(assert_return (invoke "call" (i32.const 3)) (i32.const 0))
+(assert_return (invoke "call" (i32.const 4)) (i32.const 1))
+(assert_return (invoke "call" (i32.const 5)) (i32.const 2))
+(assert_trap (invoke "call" (i32.const 6)) "uninitialized element")
+
+;; Overlap, source > dest; uninitialized elements are copied too.
+(invoke "copy_explicit" (i32.const 0) (i32.const 1) (i32.const 4))
+(assert_trap (invoke "call" (i32.const 0)) "uninitialized element")
+(assert_return (invoke "call" (i32.const 1)) (i32.const 0))
+(assert_return (invoke "call" (i32.const 2)) (i32.const 0))
+(assert_return (invoke "call" (i32.const 3)) (i32.const 1))
+(assert_return (invoke "call" (i32.const 4)) (i32.const 1))
+
+;; Out-of-bounds copies trap without writing.
+(assert_trap (invoke "copy" (i32.const 8) (i32.const 1) (i32.const 3))
+    "out of bounds table access")
+(assert_trap (invoke "call" (i32.const 8)) "uninitialized element")
+(assert_trap (invoke "copy" (i32.const 7) (i32.const 8) (i32.const 3))
+    "out of bounds table access")
+(assert_trap (invoke "call" (i32.const 7)) "uninitialized element")
+
+;; Zero-length copies at the end of the table are allowed.
+(invoke "copy" (i32.const 10) (i32.const 0) (i32.const 0))
+(invoke "copy" (i32.const 0) (i32.const 10) (i32.const 0))
+(assert_trap (invoke "copy" (i32.const 11) (i32.const 0) (i32.const 0))
+    "out of bounds table access")
+(assert_trap (invoke "copy" (i32.const 0) (i32.const 11) (i32.const 0))
+    "out of bounds table access")
+
+(assert_invalid
+  (module (func (table.copy (i32.const 0) (i32.const 0) (i32.const 0))))
+  "unknown table 0")
+
+(assert_invalid
+  (module
+    (table 1 funcref)
+    (func (table.copy (i32.const 0) (i64.const 0) (i32.const 0))))
+  "type mismatch")
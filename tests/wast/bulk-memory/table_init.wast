@@ -0,0 +1,87 @@
+(module
+  (table 8 funcref)
+  (elem $passive func $zero $one $two)
+  (elem $active (i32.const 6) $two)
+  (elem $declared declare func $one)
+
+  (type $out (func (result i32)))
+  (func $zero (type $out) (i32.const 0))
+  (func $one (type $out) (i32.const 1))
+  (func $two (type $out) (i32.const 2))
+
+  (func (export "init") (param i32 i32 i32)
+    (table.init $passive (local.get 0) (local.get 1) (local.get 2)))
+
+  (func (export "init_active") (param i32 i32 i32)
+    (table.init 0 $active (local.get 0) (local.get 1) (local.get 2)))
+
+  (func (export "init_declared") (param i32 i32 i32)
+    (table.init $declared (local.get 0) (local.get 1) (local.get 2)))
+
+  (func (export "drop") (elem.drop $passive))
+
+  (func (export "call") (param i32) (result i32)
+    (call_indirect (type $out) (local.get 0)))
+)
+
+;; Passive segments are not written at instantiation.
+(assert_trap (invoke "call" (i32.const 0)) "uninitialized element")
+(assert_return (invoke "call" (i32.const 6)) (i32.const 2))
+
+(invoke "init" (i32.const 1) (i32.const 1) (i32.const 2))
+(assert_trap (invoke "call" (i32.const 0)) "uninitialized element")
+(assert_return (invoke "call" (i32.const 1)) (i32.const 1))
+(assert_return (invoke "call" (i32.const 2)) (i32.const 2))
+(assert_trap (invoke "call" (i32.const 3)) "uninitialized element")
+
+;; Out-of-bounds reads and writes trap without writing.
+(assert_trap (invoke "init" (i32.const 3) (i32.const 1) (i32.const 3))
+    "out of bounds table access")
+(assert_trap (invoke "call" (i32.const 3)) "uninitialized element")
+(assert_trap (invoke "init" (i32.const 7) (i32.const 0) (i32.const 2))
+    "out of bounds table access")
+(assert_trap (invoke "call" (i32.const 7)) "uninitialized element")
+
+;; Zero-length accesses at the end of the segment or table are allowed.
+(invoke "init" (i32.const 8) (i32.const 3) (i32.const 0))
+(assert_trap (invoke "init" (i32.const 9) (i32.const 0) (i32.const 0))
+    "out of bounds table access")
+(assert_trap (invoke "init" (i32.const 0) (i32.const 4) (i32.const 0))
+    "out of bounds table access")
+
+;; Active and declarative segments are dropped after instantiation.
+(invoke "init_active" (i32.const 0) (i32.const 0) (i32.const 0))
+(assert_trap (invoke "init_active" (i32.const 0) (i32.const 0) (i32.const 1))
+    "out of bounds table access")
+(assert_trap (invoke "init_declared" (i32.const 0) (i32.const 0) (i32.const 1))
+    "out of bounds table access")
+
+;; A dropped segment behaves like an empty one, and can be dropped again.
+(invoke "drop")
+(invoke "drop")
+(invoke "init" (i32.const 0) (i32.const 0) (i32.const 0))
+(assert_trap (invoke "init" (i32.const 0) (i32.const 0) (i32.const 1))
+    "out of bounds table access")
+
+(assert_invalid
+  (module
+    (elem func)
+    (func (table.init 0 (i32.const 0) (i32.const 0) (i32.const 0))))
+  "unknown table 0")
+
+(assert_invalid
+  (module
+    (table 1 funcref)
+    (func (table.init 0 (i32.const 0) (i32.const 0) (i32.const 0))))
+  "unknown elem segment 0")
+
+(assert_invalid
+  (module (func (elem.drop 0)))
+  "unknown elem segment 0")
+
+(assert_invalid
+  (module
+    (table 1 funcref)
+    (elem func)
+    (func (table.init 0 (i32.const 0) (i32.const 0) (f64.const 0))))
+  "type mismatch")
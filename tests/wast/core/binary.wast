@@ -0,0 +1,241 @@
+;; Binary format
+
+(module binary "\00asm" "\01\00\00\00")
+(module binary "\00asm\01\00\00\00")
+(module $M1 binary "\00asm" "\01\00\00\00")
+
+(assert_malformed (module binary "") "unexpected end")
+(assert_malformed (module binary "\01") "unexpected end")
+(assert_malformed (module binary "\00as") "unexpected end")
+(assert_malformed (module binary "asm\00") "magic header not detected")
+(assert_malformed (module binary "msa\00") "magic header not detected")
+(assert_malformed (module binary "msa\00\01\00\00\00") "magic header not detected")
+(assert_malformed (module binary "\00ASM\01\00\00\00") "magic header not detected")
+(assert_malformed (module binary "\00\81\a2\94\01\00\00\00") "magic header not detected")
+(assert_malformed (module binary "\ef\bb\bf\00asm\01\00\00\00") "magic header not detected")
+
+(assert_malformed (module binary "\00asm") "unexpected end")
+(assert_malformed (module binary "\00asm\01") "unexpected end")
+(assert_malformed (module binary "\00asm\01\00\00") "unexpected end")
+(assert_malformed (module binary "\00asm\00\00\00\00") "unknown binary version")
+(assert_malformed (module binary "\00asm\0d\00\00\00") "unknown binary version")
+(assert_malformed (module binary "\00asm\00\00\00\01") "unknown binary version")
+(assert_malformed (module binary "\00asm\00\01\00\00") "unknown binary version")
+
+;; Invalid section id.
+(assert_malformed (module binary "\00asm" "\01\00\00\00" "\0e\01\00") "malformed section id")
+(assert_malformed (module binary "\00asm" "\01\00\00\00" "\7f\01\00") "malformed section id")
+(assert_malformed (module binary "\00asm" "\01\00\00\00" "\80\01\00") "malformed section id")
+
+;; Unsigned LEB128 can have non-minimal length.
+(module binary
+  "\00asm" "\01\00\00\00"
+  "\05\04\01"                          ;; Memory section with 1 entry
+  "\00\82\00"                          ;; no max, minimum 2
+)
+(module binary
+  "\00asm" "\01\00\00\00"
+  "\05\07\01"                          ;; Memory section with 1 entry
+  "\00\82\80\80\80\00"                 ;; no max, minimum 2
+)
+
+;; Signed LEB128 can have non-minimal length.
+(module binary
+  "\00asm" "\01\00\00\00"
+  "\06\07\01"                          ;; Global section with 1 entry
+  "\7f\00"                             ;; i32, immutable
+  "\41\80\00"                          ;; i32.const 0
+  "\0b"                                ;; end
+)
+(module binary
+  "\00asm" "\01\00\00\00"
+  "\06\0a\01"                          ;; Global section with 1 entry
+  "\7f\00"                             ;; i32, immutable
+  "\41\ff\ff\ff\ff\7f"                 ;; i32.const -1
+  "\0b"                                ;; end
+)
+(module binary
+  "\00asm" "\01\00\00\00"
+  "\06\0f\01"                          ;; Global section with 1 entry
+  "\7e\00"                             ;; i64, immutable
+  "\42\80\80\80\80\80\80\80\80\80\00"  ;; i64.const 0
+  "\0b"                                ;; end
+)
+
+;; Unsigned LEB128 must not be overlong.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\05\08\01"                          ;; Memory section with 1 entry
+    "\00\82\80\80\80\80\00"              ;; no max, minimum 2 with one byte too many
+  )
+  "integer representation too long"
+)
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\05\06\01"                          ;; Memory section with 1 entry
+    "\00\82\80\80\80\70"                 ;; no max, minimum 2 with unused bits set
+  )
+  "integer too large"
+)
+
+;; Signed LEB128 must not be overlong.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\06\0b\01"                          ;; Global section with 1 entry
+    "\7f\00"                             ;; i32, immutable
+    "\41\80\80\80\80\80\00"              ;; i32.const 0 with one byte too many
+    "\0b"                                ;; end
+  )
+  "integer representation too long"
+)
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\06\0a\01"                          ;; Global section with 1 entry
+    "\7f\00"                             ;; i32, immutable
+    "\41\80\80\80\80\70"                 ;; i32.const 0 with unused bits set
+    "\0b"                                ;; end
+  )
+  "integer too large"
+)
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\06\0a\01"                          ;; Global section with 1 entry
+    "\7f\00"                             ;; i32, immutable
+    "\41\ff\ff\ff\ff\0f"                 ;; i32.const -1 with unused bits unset
+    "\0b"                                ;; end
+  )
+  "integer too large"
+)
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\06\0f\01"                          ;; Global section with 1 entry
+    "\7e\00"                             ;; i64, immutable
+    "\42\ff\ff\ff\ff\ff\ff\ff\ff\ff\01"  ;; i64.const -1 with unused bits unset
+    "\0b"                                ;; end
+  )
+  "integer too large"
+)
+
+;; Sections out of order or duplicated.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\05\03\01\00\01"                    ;; Memory section
+    "\01\04\01\60\00\00"                 ;; Type section
+  )
+  "unexpected content after last section"
+)
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\01\04\01\60\00\00"                 ;; Type section
+    "\01\04\01\60\00\00"                 ;; Type section
+  )
+  "unexpected content after last section"
+)
+
+;; Function and code section counts must match.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\01\04\01\60\00\00"                 ;; Type section
+    "\03\02\01\00"                       ;; Function section with 1 function
+  )
+  "function and code section have inconsistent lengths"
+)
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\0a\04\01\02\00\0b"                 ;; Code section with 1 empty function
+  )
+  "function and code section have inconsistent lengths"
+)
+(module binary
+  "\00asm" "\01\00\00\00"
+  "\03\01\00"                            ;; Function section with 0 functions
+  "\0a\01\00"                            ;; Code section with 0 functions
+)
+
+;; Section sizes must match their contents.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\01\05\01\60\00\00"                 ;; Type section declared one byte too long
+    "\00"
+    "\03\02\01\00"
+    "\0a\04\01\02\00\0b"
+  )
+  "section size mismatch"
+)
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\01\03\01\60\00\00"                 ;; Type section declared one byte too short
+  )
+  "section size mismatch"
+)
+
+;; Names must be valid UTF-8.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\00\03\01\ff\00"                    ;; Custom section with name "\ff"
+  )
+  "malformed UTF-8 encoding"
+)
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\02\08\01"                          ;; Import section with 1 entry
+    "\02\c3\28"                          ;; module name with invalid continuation byte
+    "\01\66"                             ;; name "f"
+    "\00\00"                             ;; func of type 0
+  )
+  "malformed UTF-8 encoding"
+)
+(module binary
+  "\00asm" "\01\00\00\00"
+  "\00\08\07\e2\82\ac\c3\a9\21\00"      ;; Custom section named "€é!"
+)
+
+;; Unknown opcode and truncated bodies.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\01\04\01\60\00\00"
+    "\03\02\01\00"
+    "\0a\05\01\03\00\ff\0b"              ;; Function body with an invalid opcode
+  )
+  "illegal opcode"
+)
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\01\04\01\60\00\00"
+    "\03\02\01\00"
+    "\0a\03\01\01\00"                    ;; Function body without end
+  )
+  "unexpected end"
+)
+
+;; Local count can be 0 or more, but the total has to stay in a u32.
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\01\04\01\60\00\00"                 ;; Type section
+    "\03\02\01\00"                       ;; Function section
+    "\0a\0c\01"                          ;; Code section
+    ;; function 0
+    "\0a\02"
+    "\ff\ff\ff\ff\0f\7f"                 ;; 0xFFFFFFFF i32
+    "\02\7e"                             ;; 0x00000002 i64
+    "\0b"                                ;; end
+  )
+  "too many locals"
+)
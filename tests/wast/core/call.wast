@@ -0,0 +1,146 @@
+;; Direct and indirect calls
+
+(module
+  (type $proc (func))
+  (type $out-i32 (func (result i32)))
+  (type $over-i32 (func (param i32) (result i32)))
+  (type $over-i64 (func (param i64) (result i64)))
+  (type $f32-i32 (func (param f32 i32) (result i32)))
+
+  (table 10 funcref)
+  (elem (i32.const 0)
+    $const-i32 $const-i64 $id-i32 $id-i64 $f32-i32 $fac $fib $even $odd $runaway
+  )
+
+  (func $const-i32 (type $out-i32) (i32.const 0x132))
+  (func $const-i64 (result i64) (i64.const 0x164))
+  (func $id-i32 (type $over-i32) (local.get 0))
+  (func $id-i64 (type $over-i64) (local.get 0))
+  (func $f32-i32 (type $f32-i32) (local.get 1))
+
+  (func $fac (export "fac") (type $over-i64)
+    (if (result i64) (i64.eqz (local.get 0))
+      (then (i64.const 1))
+      (else
+        (i64.mul
+          (local.get 0)
+          (call_indirect (type $over-i64) (i64.sub (local.get 0) (i64.const 1)) (i32.const 5))
+        )
+      )
+    )
+  )
+  (func $fib (export "fib") (type $over-i64)
+    (if (result i64) (i64.le_u (local.get 0) (i64.const 1))
+      (then (i64.const 1))
+      (else
+        (i64.add
+          (call $fib (i64.sub (local.get 0) (i64.const 2)))
+          (call $fib (i64.sub (local.get 0) (i64.const 1)))
+        )
+      )
+    )
+  )
+  (func $even (export "even") (param i32) (result i32)
+    (if (result i32) (i32.eqz (local.get 0))
+      (then (i32.const 44))
+      (else (call $odd (i32.sub (local.get 0) (i32.const 1))))
+    )
+  )
+  (func $odd (export "odd") (param i32) (result i32)
+    (if (result i32) (i32.eqz (local.get 0))
+      (then (i32.const 99))
+      (else (call $even (i32.sub (local.get 0) (i32.const 1))))
+    )
+  )
+  (func $runaway (export "runaway") (call $runaway))
+  (func $mutual-runaway1 (export "mutual-runaway") (call $mutual-runaway2))
+  (func $mutual-runaway2 (call $mutual-runaway1))
+
+  (func (export "call-const") (result i32) (call $const-i32))
+  (func (export "call-params") (result i32) (call $f32-i32 (f32.const 1.5) (i32.const 7)))
+  (func (export "call-many-args") (result i64)
+    (call $sum (i64.const 1) (i32.const 2) (i64.const 3) (i32.const 4) (i64.const 5))
+  )
+  (func $sum (param i64 i32 i64 i32 i64) (result i64)
+    (i64.add
+      (i64.add (local.get 0) (i64.extend_i32_u (local.get 1)))
+      (i64.add
+        (i64.add (local.get 2) (i64.extend_i32_u (local.get 3)))
+        (local.get 4)
+      )
+    )
+  )
+
+  (func (export "dispatch") (param i32 i64) (result i64)
+    (call_indirect (type $over-i64) (local.get 1) (local.get 0))
+  )
+  (func (export "dispatch-i32") (param i32) (result i32)
+    (call_indirect (type $out-i32) (local.get 0))
+  )
+  (func (export "dispatch-structural") (param i32) (result i64)
+    (call_indirect (param i64) (result i64) (i64.const 9) (local.get 0))
+  )
+  (func (export "dispatch-runaway") (call_indirect (type $proc) (i32.const 9)))
+)
+
+(assert_return (invoke "call-const") (i32.const 0x132))
+(assert_return (invoke "call-params") (i32.const 7))
+(assert_return (invoke "call-many-args") (i64.const 15))
+(assert_return (invoke "fac" (i64.const 0)) (i64.const 1))
+(assert_return (invoke "fac" (i64.const 5)) (i64.const 120))
+(assert_return (invoke "fac" (i64.const 25)) (i64.const 7034535277573963776))
+(assert_return (invoke "fib" (i64.const 0)) (i64.const 1))
+(assert_return (invoke "fib" (i64.const 5)) (i64.const 8))
+(assert_return (invoke "fib" (i64.const 20)) (i64.const 10946))
+(assert_return (invoke "even" (i32.const 0)) (i32.const 44))
+(assert_return (invoke "even" (i32.const 1)) (i32.const 99))
+(assert_return (invoke "even" (i32.const 100)) (i32.const 44))
+(assert_return (invoke "odd" (i32.const 77)) (i32.const 44))
+(assert_return (invoke "odd" (i32.const 200)) (i32.const 99))
+
+(assert_return (invoke "dispatch" (i32.const 3) (i64.const 2)) (i64.const 2))
+(assert_return (invoke "dispatch" (i32.const 5) (i64.const 5)) (i64.const 120))
+(assert_return (invoke "dispatch" (i32.const 6) (i64.const 5)) (i64.const 8))
+(assert_trap (invoke "dispatch" (i32.const 0) (i64.const 2)) "indirect call type mismatch")
+(assert_trap (invoke "dispatch" (i32.const 2) (i64.const 2)) "indirect call type mismatch")
+(assert_trap (invoke "dispatch" (i32.const 10) (i64.const 2)) "undefined element")
+(assert_trap (invoke "dispatch" (i32.const -1) (i64.const 2)) "undefined element")
+(assert_return (invoke "dispatch-i32" (i32.const 0)) (i32.const 0x132))
+(assert_trap (invoke "dispatch-i32" (i32.const 1)) "indirect call type mismatch")
+(assert_return (invoke "dispatch-structural" (i32.const 3)) (i64.const 9))
+(assert_return (invoke "dispatch-structural" (i32.const 5)) (i64.const 362880))
+
+(assert_exhaustion (invoke "runaway") "call stack exhausted")
+(assert_exhaustion (invoke "mutual-runaway") "call stack exhausted")
+(assert_exhaustion (invoke "dispatch-runaway") "call stack exhausted")
+
+(module
+  (type $t (func (result i32)))
+  (table 3 funcref)
+  (elem (i32.const 1) $f)
+  (func $f (result i32) (i32.const 65))
+  (func (export "call") (param i32) (result i32) (call_indirect (type $t) (local.get 0)))
+)
+
+(assert_trap (invoke "call" (i32.const 0)) "uninitialized element")
+(assert_return (invoke "call" (i32.const 1)) (i32.const 65))
+(assert_trap (invoke "call" (i32.const 2)) "uninitialized element")
+(assert_trap (invoke "call" (i32.const 3)) "undefined element")
+
+(assert_invalid (module (func $type-void-vs-num (i32.eqz (call 1))) (func)) "type mismatch")
+(assert_invalid (module (func (call 1 (i64.const 1))) (func (param i32))) "type mismatch")
+(assert_invalid (module (func (call 1)) (func (param i32))) "type mismatch")
+(assert_invalid (module (func (call 1))) "unknown function")
+(assert_invalid (module (func (call 1012321300))) "unknown function")
+(assert_invalid
+  (module (type (func)) (func (call_indirect (type 0) (i32.const 0))))
+  "unknown table"
+)
+(assert_invalid
+  (module (type (func)) (table 0 funcref) (func (call_indirect (type 0) (i64.const 0))))
+  "type mismatch"
+)
+(assert_invalid
+  (module (table 0 funcref) (func (call_indirect (type 1) (i32.const 0))))
+  "unknown type"
+)
@@ -0,0 +1,230 @@
+;; Structured control flow: block, loop, if, br, br_if, br_table, return
+
+(module
+  (func $dummy)
+
+  (func (export "empty-block") (block) (block $l))
+  (func (export "singular-block") (result i32) (block (result i32) (i32.const 7)))
+  (func (export "nested-block") (result i32)
+    (block (result i32) (call $dummy) (block (result i32) (call $dummy) (i32.const 9)))
+  )
+  (func (export "block-br-value") (result i32)
+    (block (result i32) (br 0 (i32.const 18)) (i32.const 19))
+  )
+  (func (export "block-br-nested") (result i32)
+    (block $outer (result i32)
+      (block $inner
+        (br $outer (i32.const 42))
+      )
+      (i32.const 7)
+    )
+  )
+  (func (export "block-unwind") (result i32)
+    (block (result i32) (i32.const 3) (i32.const 4) (br 0 (i32.const 5)))
+  )
+
+  (func (export "loop-sum") (param $n i32) (result i32)
+    (local $acc i32)
+    (block $done
+      (loop $continue
+        (br_if $done (i32.eqz (local.get $n)))
+        (local.set $acc (i32.add (local.get $acc) (local.get $n)))
+        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+        (br $continue)
+      )
+    )
+    (local.get $acc)
+  )
+  (func (export "loop-value") (result i32) (loop (result i32) (i32.const 11)))
+  (func (export "loop-br-exit") (result i32)
+    (block (result i32) (loop (result i32) (br 1 (i32.const 13))))
+  )
+
+  (func (export "fac-iter") (param i64) (result i64)
+    (local i64 i64)
+    (local.set 1 (local.get 0))
+    (local.set 2 (i64.const 1))
+    (block
+      (loop
+        (if
+          (i64.eq (local.get 1) (i64.const 0))
+          (then (br 2))
+          (else
+            (local.set 2 (i64.mul (local.get 1) (local.get 2)))
+            (local.set 1 (i64.sub (local.get 1) (i64.const 1)))
+          )
+        )
+        (br 0)
+      )
+    )
+    (local.get 2)
+  )
+
+  (func (export "if-value") (param i32) (result i32)
+    (if (result i32) (local.get 0) (then (i32.const 1)) (else (i32.const 2)))
+  )
+  (func (export "if-no-else") (param i32) (result i32)
+    (local $r i32)
+    (local.set $r (i32.const 10))
+    (if (local.get 0) (then (local.set $r (i32.const 20))))
+    (local.get $r)
+  )
+  (func (export "if-br") (param i32) (result i32)
+    (block (result i32)
+      (if (local.get 0) (then (br 1 (i32.const 3))) (else (br 1 (i32.const 4))))
+      (i32.const 5)
+    )
+  )
+  (func (export "if-nested") (param i32 i32) (result i32)
+    (if (result i32) (local.get 0)
+      (then (if (result i32) (local.get 1) (then (i32.const 11)) (else (i32.const 10))))
+      (else (if (result i32) (local.get 1) (then (i32.const 1)) (else (i32.const 0))))
+    )
+  )
+
+  (func (export "br_if-value") (param i32) (result i32)
+    (block (result i32) (drop (br_if 0 (i32.const 50) (local.get 0))) (i32.const 51))
+  )
+  (func (export "br_if-not-taken") (param i32) (result i32)
+    (block (br_if 0 (local.get 0)) (return (i32.const 2)))
+    (i32.const 3)
+  )
+
+  (func (export "br_table") (param i32) (result i32)
+    (block
+      (block
+        (block
+          (block
+            (br_table 3 2 1 0 (local.get 0))
+          )
+          (return (i32.const 100))
+        )
+        (return (i32.const 101))
+      )
+      (return (i32.const 102))
+    )
+    (i32.const 103)
+  )
+  (func (export "br_table-value") (param i32) (result i32)
+    (block $default (result i32)
+      (block $one (result i32)
+        (block $zero (result i32)
+          (br_table $zero $one $default (i32.const 5) (local.get 0))
+        )
+        (return (i32.add (i32.const 10)))
+      )
+      (return (i32.add (i32.const 20)))
+    )
+  )
+  (func (export "br_table-large") (param i32) (result i32)
+    (block $d
+      (block $a
+        (br_table $a $a $a $a $a $a $a $a $d $a $a $a $a $a $a $a $a $d (local.get 0))
+      )
+      (return (i32.const 0))
+    )
+    (i32.const 1)
+  )
+
+  (func (export "return-early") (param i32) (result i32)
+    (if (local.get 0) (then (return (i32.const 9))))
+    (i32.const 8)
+  )
+  (func (export "return-from-loop") (result i32)
+    (loop (result i32) (return (i32.const 6)))
+  )
+  (func (export "return-nested") (result i64)
+    (block (block (block (return (i64.const 77)))))
+    (i64.const 0)
+  )
+
+  (func (export "unreachable") (unreachable))
+  (func (export "unreachable-in-block") (result i32)
+    (block (result i32) (i32.const 1) (unreachable))
+  )
+  (func (export "unreachable-after-br") (result i32)
+    (block (result i32) (br 0 (i32.const 9)) (unreachable))
+  )
+
+  (func (export "select-i32") (param i32 i32 i32) (result i32)
+    (select (local.get 0) (local.get 1) (local.get 2))
+  )
+  (func (export "select-f64") (param f64 f64 i32) (result f64)
+    (select (local.get 0) (local.get 1) (local.get 2))
+  )
+  (func (export "nop") (result i32) (nop) (i32.const 1) (nop) (nop))
+)
+
+(assert_return (invoke "empty-block"))
+(assert_return (invoke "singular-block") (i32.const 7))
+(assert_return (invoke "nested-block") (i32.const 9))
+(assert_return (invoke "block-br-value") (i32.const 18))
+(assert_return (invoke "block-br-nested") (i32.const 42))
+(assert_return (invoke "block-unwind") (i32.const 5))
+(assert_return (invoke "loop-sum" (i32.const 0)) (i32.const 0))
+(assert_return (invoke "loop-sum" (i32.const 100)) (i32.const 5050))
+(assert_return (invoke "loop-value") (i32.const 11))
+(assert_return (invoke "loop-br-exit") (i32.const 13))
+(assert_return (invoke "fac-iter" (i64.const 0)) (i64.const 1))
+(assert_return (invoke "fac-iter" (i64.const 5)) (i64.const 120))
+(assert_return (invoke "fac-iter" (i64.const 25)) (i64.const 7034535277573963776))
+(assert_return (invoke "if-value" (i32.const 0)) (i32.const 2))
+(assert_return (invoke "if-value" (i32.const -100)) (i32.const 1))
+(assert_return (invoke "if-no-else" (i32.const 0)) (i32.const 10))
+(assert_return (invoke "if-no-else" (i32.const 1)) (i32.const 20))
+(assert_return (invoke "if-br" (i32.const 1)) (i32.const 3))
+(assert_return (invoke "if-br" (i32.const 0)) (i32.const 4))
+(assert_return (invoke "if-nested" (i32.const 1) (i32.const 1)) (i32.const 11))
+(assert_return (invoke "if-nested" (i32.const 1) (i32.const 0)) (i32.const 10))
+(assert_return (invoke "if-nested" (i32.const 0) (i32.const 1)) (i32.const 1))
+(assert_return (invoke "if-nested" (i32.const 0) (i32.const 0)) (i32.const 0))
+(assert_return (invoke "br_if-value" (i32.const 0)) (i32.const 51))
+(assert_return (invoke "br_if-value" (i32.const 1)) (i32.const 50))
+(assert_return (invoke "br_if-not-taken" (i32.const 0)) (i32.const 2))
+(assert_return (invoke "br_if-not-taken" (i32.const 1)) (i32.const 3))
+(assert_return (invoke "br_table" (i32.const 0)) (i32.const 103))
+(assert_return (invoke "br_table" (i32.const 1)) (i32.const 102))
+(assert_return (invoke "br_table" (i32.const 2)) (i32.const 101))
+(assert_return (invoke "br_table" (i32.const 3)) (i32.const 100))
+(assert_return (invoke "br_table" (i32.const 4)) (i32.const 100))
+(assert_return (invoke "br_table" (i32.const -1)) (i32.const 100))
+(assert_return (invoke "br_table-value" (i32.const 0)) (i32.const 15))
+(assert_return (invoke "br_table-value" (i32.const 1)) (i32.const 25))
+(assert_return (invoke "br_table-value" (i32.const 2)) (i32.const 5))
+(assert_return (invoke "br_table-value" (i32.const 1000)) (i32.const 5))
+(assert_return (invoke "br_table-large" (i32.const 8)) (i32.const 1))
+(assert_return (invoke "br_table-large" (i32.const 9)) (i32.const 0))
+(assert_return (invoke "br_table-large" (i32.const 17)) (i32.const 1))
+(assert_return (invoke "br_table-large" (i32.const 100)) (i32.const 1))
+(assert_return (invoke "return-early" (i32.const 1)) (i32.const 9))
+(assert_return (invoke "return-early" (i32.const 0)) (i32.const 8))
+(assert_return (invoke "return-from-loop") (i32.const 6))
+(assert_return (invoke "return-nested") (i64.const 77))
+(assert_trap (invoke "unreachable") "unreachable")
+(assert_trap (invoke "unreachable-in-block") "unreachable")
+(assert_return (invoke "unreachable-after-br") (i32.const 9))
+(assert_return (invoke "select-i32" (i32.const 1) (i32.const 2) (i32.const 1)) (i32.const 1))
+(assert_return (invoke "select-i32" (i32.const 1) (i32.const 2) (i32.const 0)) (i32.const 2))
+(assert_return (invoke "select-i32" (i32.const 1) (i32.const 2) (i32.const -1)) (i32.const 1))
+(assert_return (invoke "select-f64" (f64.const 1) (f64.const 2) (i32.const 0)) (f64.const 2))
+(assert_return (invoke "nop") (i32.const 1))
+
+(assert_invalid (module (func $type-value-num-vs-void (block (i32.const 1)))) "type mismatch")
+(assert_invalid (module (func $type-value-empty-vs-num (result i32) (block (result i32)))) "type mismatch")
+(assert_invalid (module (func (result i32) (block (result i32) (f32.const 0)))) "type mismatch")
+(assert_invalid (module (func (result i32) (loop (result i32) (i64.const 0)))) "type mismatch")
+(assert_invalid (module (func (result i32) (if (result i32) (i32.const 1) (then (i32.const 0))))) "type mismatch")
+(assert_invalid (module (func (if (f32.const 0) (then)))) "type mismatch")
+(assert_invalid (module (func (result i32) (if (result i32) (i32.const 1) (then (i32.const 0)) (else (i64.const 0))))) "type mismatch")
+(assert_invalid (module (func (block (br 2)))) "unknown label")
+(assert_invalid (module (func (br_if 0 (f32.const 0)))) "type mismatch")
+(assert_invalid (module (func (result i32) (block (result i32) (br 0)))) "type mismatch")
+(assert_invalid (module (func (block (result i32) (block (br_table 0 1 (i32.const 0) (i32.const 0)))) (drop))) "type mismatch")
+(assert_invalid (module (func (br_table 0 (i64.const 0)))) "type mismatch")
+(assert_invalid (module (func (block (br_table 2 1 (i32.const 0))))) "unknown label")
+(assert_invalid (module (func (result i32) (return (i64.const 1)))) "type mismatch")
+(assert_invalid (module (func (result i32) (return))) "type mismatch")
+(assert_invalid (module (func (select (i32.const 1) (i64.const 1) (i32.const 1)) (drop))) "type mismatch")
+(assert_invalid (module (func (select (i32.const 1) (i32.const 1) (i64.const 1)) (drop))) "type mismatch")
+(assert_invalid (module (func (result i32) (unreachable) (i64.const 0))) "type mismatch")
+(assert_invalid (module (func (i32.const 0))) "type mismatch")
@@ -0,0 +1,37 @@
+;; Exports
+
+(module (func) (export "a" (func 0)))
+(module (func) (export "a" (func 0)) (export "b" (func 0)))
+(module (func) (func) (export "a" (func 0)) (export "b" (func 1)))
+(module (global i32 (i32.const 0)) (export "a" (global 0)))
+(module (table 0 funcref) (export "a" (table 0)))
+(module (memory 0) (export "a" (memory 0)))
+
+(module $Func
+  (export "e" (func $f))
+  (func $f (param $n i32) (result i32)
+    (return (i32.add (local.get $n) (i32.const 1)))
+  )
+)
+(assert_return (invoke "e" (i32.const 42)) (i32.const 43))
+(assert_return (invoke $Func "e" (i32.const 42)) (i32.const 43))
+
+(module
+  (func (export "") (result i32) (i32.const 0))
+  (func (export "grüße") (result i32) (i32.const 1))
+  (func (export "日本語") (result i32) (i32.const 2))
+  (func (export "\f0\9f\98\80") (result i32) (i32.const 3))
+  (func (export "a\00b") (result i32) (i32.const 4))
+)
+(assert_return (invoke "") (i32.const 0))
+(assert_return (invoke "grüße") (i32.const 1))
+(assert_return (invoke "日本語") (i32.const 2))
+(assert_return (invoke "😀") (i32.const 3))
+(assert_return (invoke "a\00b") (i32.const 4))
+
+(assert_invalid (module (func) (export "a" (func 1))) "unknown function")
+(assert_invalid (module (func) (export "a" (func 0)) (export "a" (func 0))) "duplicate export name")
+(assert_invalid (module (func) (global i32 (i32.const 0)) (export "a" (func 0)) (export "a" (global 0))) "duplicate export name")
+(assert_invalid (module (global i32 (i32.const 0)) (export "a" (global 1))) "unknown global")
+(assert_invalid (module (table 0 funcref) (export "a" (table 1))) "unknown table")
+(assert_invalid (module (memory 0) (export "a" (memory 1))) "unknown memory")
@@ -0,0 +1,146 @@
+;; f32 and f64 arithmetic, comparison and bitwise operations
+
+(module
+  (func (export "f32.add") (param $x f32) (param $y f32) (result f32) (f32.add (local.get $x) (local.get $y)))
+  (func (export "f32.sub") (param $x f32) (param $y f32) (result f32) (f32.sub (local.get $x) (local.get $y)))
+  (func (export "f32.mul") (param $x f32) (param $y f32) (result f32) (f32.mul (local.get $x) (local.get $y)))
+  (func (export "f32.div") (param $x f32) (param $y f32) (result f32) (f32.div (local.get $x) (local.get $y)))
+  (func (export "f32.sqrt") (param $x f32) (result f32) (f32.sqrt (local.get $x)))
+  (func (export "f32.min") (param $x f32) (param $y f32) (result f32) (f32.min (local.get $x) (local.get $y)))
+  (func (export "f32.max") (param $x f32) (param $y f32) (result f32) (f32.max (local.get $x) (local.get $y)))
+  (func (export "f32.ceil") (param $x f32) (result f32) (f32.ceil (local.get $x)))
+  (func (export "f32.floor") (param $x f32) (result f32) (f32.floor (local.get $x)))
+  (func (export "f32.trunc") (param $x f32) (result f32) (f32.trunc (local.get $x)))
+  (func (export "f32.nearest") (param $x f32) (result f32) (f32.nearest (local.get $x)))
+  (func (export "f32.abs") (param $x f32) (result f32) (f32.abs (local.get $x)))
+  (func (export "f32.neg") (param $x f32) (result f32) (f32.neg (local.get $x)))
+  (func (export "f32.copysign") (param $x f32) (param $y f32) (result f32) (f32.copysign (local.get $x) (local.get $y)))
+  (func (export "f32.eq") (param $x f32) (param $y f32) (result i32) (f32.eq (local.get $x) (local.get $y)))
+  (func (export "f32.ne") (param $x f32) (param $y f32) (result i32) (f32.ne (local.get $x) (local.get $y)))
+  (func (export "f32.lt") (param $x f32) (param $y f32) (result i32) (f32.lt (local.get $x) (local.get $y)))
+  (func (export "f32.ge") (param $x f32) (param $y f32) (result i32) (f32.ge (local.get $x) (local.get $y)))
+
+  (func (export "f64.add") (param $x f64) (param $y f64) (result f64) (f64.add (local.get $x) (local.get $y)))
+  (func (export "f64.sub") (param $x f64) (param $y f64) (result f64) (f64.sub (local.get $x) (local.get $y)))
+  (func (export "f64.mul") (param $x f64) (param $y f64) (result f64) (f64.mul (local.get $x) (local.get $y)))
+  (func (export "f64.div") (param $x f64) (param $y f64) (result f64) (f64.div (local.get $x) (local.get $y)))
+  (func (export "f64.sqrt") (param $x f64) (result f64) (f64.sqrt (local.get $x)))
+  (func (export "f64.min") (param $x f64) (param $y f64) (result f64) (f64.min (local.get $x) (local.get $y)))
+  (func (export "f64.max") (param $x f64) (param $y f64) (result f64) (f64.max (local.get $x) (local.get $y)))
+  (func (export "f64.ceil") (param $x f64) (result f64) (f64.ceil (local.get $x)))
+  (func (export "f64.floor") (param $x f64) (result f64) (f64.floor (local.get $x)))
+  (func (export "f64.trunc") (param $x f64) (result f64) (f64.trunc (local.get $x)))
+  (func (export "f64.nearest") (param $x f64) (result f64) (f64.nearest (local.get $x)))
+  (func (export "f64.abs") (param $x f64) (result f64) (f64.abs (local.get $x)))
+  (func (export "f64.neg") (param $x f64) (result f64) (f64.neg (local.get $x)))
+  (func (export "f64.copysign") (param $x f64) (param $y f64) (result f64) (f64.copysign (local.get $x) (local.get $y)))
+  (func (export "f64.eq") (param $x f64) (param $y f64) (result i32) (f64.eq (local.get $x) (local.get $y)))
+  (func (export "f64.ne") (param $x f64) (param $y f64) (result i32) (f64.ne (local.get $x) (local.get $y)))
+  (func (export "f64.le") (param $x f64) (param $y f64) (result i32) (f64.le (local.get $x) (local.get $y)))
+  (func (export "f64.gt") (param $x f64) (param $y f64) (result i32) (f64.gt (local.get $x) (local.get $y)))
+)
+
+(assert_return (invoke "f32.add" (f32.const 0x1p+0) (f32.const 0x1p+0)) (f32.const 0x1p+1))
+(assert_return (invoke "f32.add" (f32.const -0x0p+0) (f32.const -0x0p+0)) (f32.const -0x0p+0))
+(assert_return (invoke "f32.add" (f32.const -0x0p+0) (f32.const 0x0p+0)) (f32.const 0x0p+0))
+(assert_return (invoke "f32.add" (f32.const 0x1p-149) (f32.const 0x1p-149)) (f32.const 0x1p-148))
+(assert_return (invoke "f32.add" (f32.const 0x1.fffffep+127) (f32.const 0x1.fffffep+127)) (f32.const inf))
+(assert_return (invoke "f32.add" (f32.const inf) (f32.const -inf)) (f32.const nan:canonical))
+(assert_return (invoke "f32.add" (f32.const nan) (f32.const 0x1p+0)) (f32.const nan:canonical))
+(assert_return (invoke "f32.add" (f32.const nan:0x200000) (f32.const 0x1p+0)) (f32.const nan:arithmetic))
+(assert_return (invoke "f32.sub" (f32.const inf) (f32.const inf)) (f32.const nan:canonical))
+(assert_return (invoke "f32.sub" (f32.const 0x1p+0) (f32.const 0x1p-1)) (f32.const 0x1p-1))
+(assert_return (invoke "f32.mul" (f32.const inf) (f32.const 0x0p+0)) (f32.const nan:canonical))
+(assert_return (invoke "f32.mul" (f32.const -0x1p+0) (f32.const 0x0p+0)) (f32.const -0x0p+0))
+(assert_return (invoke "f32.mul" (f32.const 0x1p-126) (f32.const 0x1p-1)) (f32.const 0x1p-127))
+(assert_return (invoke "f32.div" (f32.const 0x1p+0) (f32.const 0x0p+0)) (f32.const inf))
+(assert_return (invoke "f32.div" (f32.const -0x1p+0) (f32.const 0x0p+0)) (f32.const -inf))
+(assert_return (invoke "f32.div" (f32.const 0x0p+0) (f32.const 0x0p+0)) (f32.const nan:canonical))
+(assert_return (invoke "f32.div" (f32.const 0x1p+0) (f32.const 0x1.8p+1)) (f32.const 0x1.555556p-2))
+(assert_return (invoke "f32.sqrt" (f32.const 0x1p+2)) (f32.const 0x1p+1))
+(assert_return (invoke "f32.sqrt" (f32.const -0x0p+0)) (f32.const -0x0p+0))
+(assert_return (invoke "f32.sqrt" (f32.const -0x1p+0)) (f32.const nan:canonical))
+(assert_return (invoke "f32.sqrt" (f32.const 0x1p+1)) (f32.const 0x1.6a09e6p+0))
+
+(assert_return (invoke "f32.min" (f32.const -0x0p+0) (f32.const 0x0p+0)) (f32.const -0x0p+0))
+(assert_return (invoke "f32.min" (f32.const 0x0p+0) (f32.const -0x0p+0)) (f32.const -0x0p+0))
+(assert_return (invoke "f32.max" (f32.const -0x0p+0) (f32.const 0x0p+0)) (f32.const 0x0p+0))
+(assert_return (invoke "f32.max" (f32.const 0x0p+0) (f32.const -0x0p+0)) (f32.const 0x0p+0))
+(assert_return (invoke "f32.min" (f32.const nan) (f32.const 0x1p+0)) (f32.const nan:canonical))
+(assert_return (invoke "f32.min" (f32.const 0x1p+0) (f32.const nan)) (f32.const nan:canonical))
+(assert_return (invoke "f32.max" (f32.const -inf) (f32.const nan)) (f32.const nan:canonical))
+(assert_return (invoke "f32.min" (f32.const -inf) (f32.const inf)) (f32.const -inf))
+(assert_return (invoke "f32.max" (f32.const -0x1p+0) (f32.const -0x1p-1)) (f32.const -0x1p-1))
+
+(assert_return (invoke "f32.ceil" (f32.const -0x1.8p+0)) (f32.const -0x1p+0))
+(assert_return (invoke "f32.ceil" (f32.const -0x1p-1)) (f32.const -0x0p+0))
+(assert_return (invoke "f32.ceil" (f32.const 0x1p-149)) (f32.const 0x1p+0))
+(assert_return (invoke "f32.floor" (f32.const -0x1p-149)) (f32.const -0x1p+0))
+(assert_return (invoke "f32.floor" (f32.const 0x1.8p+0)) (f32.const 0x1p+0))
+(assert_return (invoke "f32.trunc" (f32.const -0x1.8p+0)) (f32.const -0x1p+0))
+(assert_return (invoke "f32.trunc" (f32.const -0x1p-1)) (f32.const -0x0p+0))
+(assert_return (invoke "f32.nearest" (f32.const 0x1p-1)) (f32.const 0x0p+0))
+(assert_return (invoke "f32.nearest" (f32.const -0x1p-1)) (f32.const -0x0p+0))
+(assert_return (invoke "f32.nearest" (f32.const 0x1.8p+0)) (f32.const 0x1p+1))
+(assert_return (invoke "f32.nearest" (f32.const 0x1.4p+1)) (f32.const 0x1p+1))
+(assert_return (invoke "f32.nearest" (f32.const -0x1.cp+1)) (f32.const -0x1p+2))
+(assert_return (invoke "f32.nearest" (f32.const 0x1.fffffep+22)) (f32.const 0x1p+23))
+(assert_return (invoke "f32.nearest" (f32.const 0x1.000002p+23)) (f32.const 0x1.000002p+23))
+(assert_return (invoke "f32.nearest" (f32.const nan)) (f32.const nan:canonical))
+
+(assert_return (invoke "f32.abs" (f32.const -0x0p+0)) (f32.const 0x0p+0))
+(assert_return (invoke "f32.abs" (f32.const -inf)) (f32.const inf))
+(assert_return (invoke "f32.abs" (f32.const -nan)) (f32.const nan))
+(assert_return (invoke "f32.abs" (f32.const -nan:0x0f1e2)) (f32.const nan:0x0f1e2))
+(assert_return (invoke "f32.neg" (f32.const 0x0p+0)) (f32.const -0x0p+0))
+(assert_return (invoke "f32.neg" (f32.const nan)) (f32.const -nan))
+(assert_return (invoke "f32.neg" (f32.const -nan:0x0f1e2)) (f32.const nan:0x0f1e2))
+(assert_return (invoke "f32.copysign" (f32.const 0x1p+0) (f32.const -0x0p+0)) (f32.const -0x1p+0))
+(assert_return (invoke "f32.copysign" (f32.const -inf) (f32.const 0x0p+0)) (f32.const inf))
+(assert_return (invoke "f32.copysign" (f32.const nan) (f32.const -0x1p+0)) (f32.const -nan))
+
+(assert_return (invoke "f32.eq" (f32.const -0x0p+0) (f32.const 0x0p+0)) (i32.const 1))
+(assert_return (invoke "f32.eq" (f32.const nan) (f32.const nan)) (i32.const 0))
+(assert_return (invoke "f32.ne" (f32.const nan) (f32.const nan)) (i32.const 1))
+(assert_return (invoke "f32.lt" (f32.const -inf) (f32.const inf)) (i32.const 1))
+(assert_return (invoke "f32.lt" (f32.const nan) (f32.const inf)) (i32.const 0))
+(assert_return (invoke "f32.ge" (f32.const -0x0p+0) (f32.const 0x0p+0)) (i32.const 1))
+
+(assert_return (invoke "f64.add" (f64.const 0x1p+0) (f64.const 0x1p-52)) (f64.const 0x1.0000000000001p+0))
+(assert_return (invoke "f64.add" (f64.const 0x1p-1074) (f64.const -0x1p-1074)) (f64.const 0x0p+0))
+(assert_return (invoke "f64.add" (f64.const 0x1.fffffffffffffp+1023) (f64.const 0x1.fffffffffffffp+1023)) (f64.const inf))
+(assert_return (invoke "f64.add" (f64.const inf) (f64.const -inf)) (f64.const nan:canonical))
+(assert_return (invoke "f64.add" (f64.const nan:0x4000000000000) (f64.const 0x1p+0)) (f64.const nan:arithmetic))
+(assert_return (invoke "f64.sub" (f64.const 0x1p+0) (f64.const 0x1p+0)) (f64.const 0x0p+0))
+(assert_return (invoke "f64.mul" (f64.const 0x1p+512) (f64.const 0x1p+512)) (f64.const inf))
+(assert_return (invoke "f64.mul" (f64.const -0x0p+0) (f64.const -0x1p+0)) (f64.const 0x0p+0))
+(assert_return (invoke "f64.div" (f64.const 0x1p+0) (f64.const 0x1.8p+1)) (f64.const 0x1.5555555555555p-2))
+(assert_return (invoke "f64.div" (f64.const -0x0p+0) (f64.const 0x0p+0)) (f64.const nan:canonical))
+(assert_return (invoke "f64.sqrt" (f64.const 0x1p+1)) (f64.const 0x1.6a09e667f3bcdp+0))
+(assert_return (invoke "f64.sqrt" (f64.const -inf)) (f64.const nan:canonical))
+(assert_return (invoke "f64.min" (f64.const -0x0p+0) (f64.const 0x0p+0)) (f64.const -0x0p+0))
+(assert_return (invoke "f64.max" (f64.const -0x0p+0) (f64.const 0x0p+0)) (f64.const 0x0p+0))
+(assert_return (invoke "f64.min" (f64.const nan) (f64.const 0x0p+0)) (f64.const nan:canonical))
+(assert_return (invoke "f64.max" (f64.const 0x1p+0) (f64.const nan:0x4000000000000)) (f64.const nan:arithmetic))
+(assert_return (invoke "f64.ceil" (f64.const -0x1p-1)) (f64.const -0x0p+0))
+(assert_return (invoke "f64.ceil" (f64.const 0x1.fffffffffffffp+51)) (f64.const 0x1p+52))
+(assert_return (invoke "f64.floor" (f64.const -0x1p-1074)) (f64.const -0x1p+0))
+(assert_return (invoke "f64.trunc" (f64.const -0x1.fffffffffffffp+51)) (f64.const -0x1.ffffffffffffep+51))
+(assert_return (invoke "f64.nearest" (f64.const 0x1.cp+1)) (f64.const 0x1p+2))
+(assert_return (invoke "f64.nearest" (f64.const 0x1.4p+1)) (f64.const 0x1p+1))
+(assert_return (invoke "f64.nearest" (f64.const -0x1p-1)) (f64.const -0x0p+0))
+(assert_return (invoke "f64.nearest" (f64.const 0x1.fffffffffffffp+51)) (f64.const 0x1p+52))
+(assert_return (invoke "f64.abs" (f64.const -nan:0x0f1e27a6b)) (f64.const nan:0x0f1e27a6b))
+(assert_return (invoke "f64.neg" (f64.const -0x0p+0)) (f64.const 0x0p+0))
+(assert_return (invoke "f64.neg" (f64.const nan:0x0f1e27a6b)) (f64.const -nan:0x0f1e27a6b))
+(assert_return (invoke "f64.copysign" (f64.const 0x1p+0) (f64.const -nan)) (f64.const -0x1p+0))
+(assert_return (invoke "f64.copysign" (f64.const -nan) (f64.const 0x1p+0)) (f64.const nan))
+(assert_return (invoke "f64.eq" (f64.const 0x0p+0) (f64.const -0x0p+0)) (i32.const 1))
+(assert_return (invoke "f64.ne" (f64.const nan) (f64.const 0x1p+0)) (i32.const 1))
+(assert_return (invoke "f64.le" (f64.const nan) (f64.const nan)) (i32.const 0))
+(assert_return (invoke "f64.le" (f64.const -0x0p+0) (f64.const 0x0p+0)) (i32.const 1))
+(assert_return (invoke "f64.gt" (f64.const inf) (f64.const 0x1.fffffffffffffp+1023)) (i32.const 1))
+
+(assert_invalid (module (func (result f32) (f32.add (i64.const 0) (f64.const 0)))) "type mismatch")
+(assert_invalid (module (func (result f64) (f64.sqrt (i64.const 0)))) "type mismatch")
+(assert_invalid (module (func (result f64) (f64.eq (f64.const 0) (f64.const 0)))) "type mismatch")
@@ -0,0 +1,64 @@
+;; Global variables
+
+(module
+  (global (import "spectest" "global_i32") i32)
+  (global (import "spectest" "global_i64") i64)
+  (global $a i32 (i32.const -2))
+  (global $b i64 (i64.const -5))
+  (global $x (mut i32) (i32.const -12))
+  (global $y (mut i64) (i64.const -15))
+  (global $z1 i32 (global.get 0))
+  (global $z2 i64 (global.get 1))
+  (global $r f32 (f32.const -3))
+  (global $s (mut f64) (f64.const -4))
+
+  (global (export "exported-i32") i32 (i32.const 42))
+  (global (export "exported-mut") (mut i64) (i64.const 7))
+
+  (func (export "get-a") (result i32) (global.get $a))
+  (func (export "get-b") (result i64) (global.get $b))
+  (func (export "get-x") (result i32) (global.get $x))
+  (func (export "get-y") (result i64) (global.get $y))
+  (func (export "get-z1") (result i32) (global.get $z1))
+  (func (export "get-z2") (result i64) (global.get $z2))
+  (func (export "get-r") (result f32) (global.get $r))
+  (func (export "get-s") (result f64) (global.get $s))
+  (func (export "get-imported") (result i32) (global.get 0))
+  (func (export "set-x") (param i32) (global.set $x (local.get 0)))
+  (func (export "set-y") (param i64) (global.set $y (local.get 0)))
+  (func (export "set-s") (param f64) (global.set $s (local.get 0)))
+  (func (export "bump-exported") (global.set 11 (i64.add (global.get 11) (i64.const 1))))
+)
+
+(assert_return (invoke "get-a") (i32.const -2))
+(assert_return (invoke "get-b") (i64.const -5))
+(assert_return (invoke "get-x") (i32.const -12))
+(assert_return (invoke "get-y") (i64.const -15))
+(assert_return (invoke "get-z1") (i32.const 666))
+(assert_return (invoke "get-z2") (i64.const 666))
+(assert_return (invoke "get-r") (f32.const -3))
+(assert_return (invoke "get-s") (f64.const -4))
+(assert_return (invoke "get-imported") (i32.const 666))
+(assert_return (invoke "set-x" (i32.const 6)))
+(assert_return (invoke "set-y" (i64.const 7)))
+(assert_return (invoke "set-s" (f64.const 8)))
+(assert_return (invoke "get-x") (i32.const 6))
+(assert_return (invoke "get-y") (i64.const 7))
+(assert_return (invoke "get-s") (f64.const 8))
+(assert_return (get "exported-i32") (i32.const 42))
+(assert_return (get "exported-mut") (i64.const 7))
+(assert_return (invoke "bump-exported"))
+(assert_return (get "exported-mut") (i64.const 8))
+
+(assert_invalid (module (global f32 (f32.const 0)) (func (global.set 0 (f32.const 1)))) "global is immutable")
+(assert_invalid (module (import "spectest" "global_i32" (global i32)) (func (global.set 0 (i32.const 1)))) "global is immutable")
+(assert_invalid (module (global i32 (f32.const 0))) "type mismatch")
+(assert_invalid (module (global i32 (i32.const 0) (i32.const 0))) "type mismatch")
+(assert_invalid (module (global i32 (i32.ctz (i32.const 0)))) "constant expression required")
+(assert_invalid (module (global i32 (nop))) "constant expression required")
+(assert_invalid (module (global i32 (global.get 0))) "unknown global")
+(assert_invalid (module (global i32 (i32.const 0)) (global i32 (global.get 0))) "unknown global")
+(assert_invalid (module (global (import "" "") (mut i32)) (global i32 (global.get 0))) "constant expression required")
+(assert_invalid (module (func (result i32) (global.get 0))) "unknown global")
+(assert_invalid (module (global (mut i32) (i32.const 0)) (func (global.set 0 (i64.const 1)))) "type mismatch")
+(assert_invalid (module (global i64 (i64.const 0)) (func (result i32) (global.get 0))) "type mismatch")
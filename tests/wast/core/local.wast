@@ -0,0 +1,86 @@
+;; Local variables: get, set, tee and default values
+
+(module
+  (func (export "type-local-i32") (result i32) (local i32) (local.get 0))
+  (func (export "type-local-i64") (result i64) (local i64) (local.get 0))
+  (func (export "type-local-f32") (result f32) (local f32) (local.get 0))
+  (func (export "type-local-f64") (result f64) (local f64) (local.get 0))
+  (func (export "type-param-i64") (param i64) (result i64) (local.get 0))
+
+  (func (export "type-mixed") (param i64 f32 f64 i32 i32) (local f32 i64 i64 f64)
+    (drop (i64.eqz (local.get 0)))
+    (drop (f32.neg (local.get 1)))
+    (drop (f64.neg (local.get 2)))
+    (drop (i32.eqz (local.get 3)))
+    (drop (i32.eqz (local.get 4)))
+    (drop (f32.neg (local.get 5)))
+    (drop (i64.eqz (local.get 6)))
+    (drop (i64.eqz (local.get 7)))
+    (drop (f64.neg (local.get 8)))
+  )
+
+  (func (export "read") (param i64 f32 f64 i32 i32) (result f64)
+    (local f32 i64 i64 f64)
+    (local.set 1 (f32.const 5.5))
+    (local.set 5 (f32.const 5.5))
+    (local.set 6 (i64.const 6))
+    (local.set 8 (f64.const 8))
+    (f64.add
+      (f64.convert_i64_u (local.get 0))
+      (f64.add
+        (f64.promote_f32 (local.get 1))
+        (f64.add
+          (local.get 2)
+          (f64.add
+            (f64.convert_i32_u (local.get 3))
+            (f64.add
+              (f64.convert_i32_s (local.get 4))
+              (f64.add
+                (f64.promote_f32 (local.get 5))
+                (f64.add
+                  (f64.convert_i64_u (local.get 6))
+                  (f64.add
+                    (f64.convert_i64_u (local.get 7))
+                    (local.get 8)
+                  )
+                )
+              )
+            )
+          )
+        )
+      )
+    )
+  )
+
+  (func (export "tee") (param i32) (result i32)
+    (local i32)
+    (i32.add (local.tee 1 (i32.mul (local.get 0) (i32.const 2))) (local.get 1))
+  )
+  (func (export "many-locals") (result i64)
+    (local i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64 i64)
+    (local.set 15 (i64.const 15))
+    (local.get 15)
+  )
+)
+
+(assert_return (invoke "type-local-i32") (i32.const 0))
+(assert_return (invoke "type-local-i64") (i64.const 0))
+(assert_return (invoke "type-local-f32") (f32.const 0))
+(assert_return (invoke "type-local-f64") (f64.const 0))
+(assert_return (invoke "type-param-i64" (i64.const 2)) (i64.const 2))
+(assert_return
+  (invoke "type-mixed" (i64.const 1) (f32.const 2.2) (f64.const 3.3) (i32.const 4) (i32.const 5))
+)
+(assert_return
+  (invoke "read" (i64.const 1) (f32.const 2) (f64.const 3.3) (i32.const 4) (i32.const 5))
+  (f64.const 38.3)
+)
+(assert_return (invoke "tee" (i32.const 4)) (i32.const 16))
+(assert_return (invoke "many-locals") (i64.const 15))
+
+(assert_invalid (module (func (result i64) (local i32) (local.get 0))) "type mismatch")
+(assert_invalid (module (func (local f32) (i32.eqz (local.get 0)))) "type mismatch")
+(assert_invalid (module (func (param i32) (local.set 0 (f32.const 0)))) "type mismatch")
+(assert_invalid (module (func (result i32) (local i32) (local.tee 0 (i64.const 0)))) "type mismatch")
+(assert_invalid (module (func (local i32 i64) (drop (local.get 3)))) "unknown local")
+(assert_invalid (module (func (param i32) (local.set 14324343 (i32.const 0)))) "unknown local")
@@ -0,0 +1,185 @@
+;; Linear memory: loads, stores, data segments, size and grow
+
+(module (memory 0))
+(module (memory 1))
+(module (memory 0 0))
+(module (memory 1 256))
+(module (memory 0 65536))
+
+(assert_invalid (module (memory 0) (memory 0)) "multiple memories")
+(assert_invalid (module (memory 65537)) "memory size must be at most 65536 pages (4GiB)")
+(assert_invalid (module (memory 1 0)) "size minimum must not be greater than maximum")
+(assert_invalid (module (func (drop (i32.load (i32.const 0))))) "unknown memory")
+(assert_invalid (module (func (i32.store (i32.const 0) (i32.const 0)))) "unknown memory")
+(assert_invalid (module (func (drop (memory.size)))) "unknown memory")
+(assert_invalid (module (func (drop (memory.grow (i32.const 0))))) "unknown memory")
+(assert_invalid (module (memory 1) (func (drop (i32.load align=8 (i32.const 0))))) "alignment must not be larger than natural")
+(assert_invalid (module (memory 1) (func (drop (i64.load16_s align=4 (i32.const 0))))) "alignment must not be larger than natural")
+(assert_invalid (module (memory 1) (func (f32.store (i32.const 0) (i32.const 0)))) "type mismatch")
+
+(module
+  (memory 1)
+  (data (i32.const 0) "ABC\a7D") (data (i32.const 20) "WASM")
+
+  (func (export "data") (result i32)
+    (i32.and
+      (i32.and
+        (i32.and
+          (i32.eq (i32.load8_u (i32.const 0)) (i32.const 65))
+          (i32.eq (i32.load8_u (i32.const 3)) (i32.const 167))
+        )
+        (i32.and
+          (i32.eq (i32.load8_u (i32.const 6)) (i32.const 0))
+          (i32.eq (i32.load8_u (i32.const 19)) (i32.const 0))
+        )
+      )
+      (i32.and
+        (i32.and
+          (i32.eq (i32.load8_u (i32.const 20)) (i32.const 87))
+          (i32.eq (i32.load8_u (i32.const 23)) (i32.const 77))
+        )
+        (i32.and
+          (i32.eq (i32.load8_u (i32.const 24)) (i32.const 0))
+          (i32.eq (i32.load8_u (i32.const 1023)) (i32.const 0))
+        )
+      )
+    )
+  )
+
+  (func (export "i32_load8_s") (param i32) (result i32)
+    (i32.store8 (i32.const 8) (local.get 0)) (i32.load8_s (i32.const 8))
+  )
+  (func (export "i32_load8_u") (param i32) (result i32)
+    (i32.store8 (i32.const 8) (local.get 0)) (i32.load8_u (i32.const 8))
+  )
+  (func (export "i32_load16_s") (param i32) (result i32)
+    (i32.store16 (i32.const 8) (local.get 0)) (i32.load16_s (i32.const 8))
+  )
+  (func (export "i32_load16_u") (param i32) (result i32)
+    (i32.store16 (i32.const 8) (local.get 0)) (i32.load16_u (i32.const 8))
+  )
+  (func (export "i64_load8_s") (param i64) (result i64)
+    (i64.store8 (i32.const 8) (local.get 0)) (i64.load8_s (i32.const 8))
+  )
+  (func (export "i64_load16_u") (param i64) (result i64)
+    (i64.store16 (i32.const 8) (local.get 0)) (i64.load16_u (i32.const 8))
+  )
+  (func (export "i64_load32_s") (param i64) (result i64)
+    (i64.store32 (i32.const 8) (local.get 0)) (i64.load32_s (i32.const 8))
+  )
+  (func (export "i64_load32_u") (param i64) (result i64)
+    (i64.store32 (i32.const 8) (local.get 0)) (i64.load32_u (i32.const 8))
+  )
+  (func (export "f64_roundtrip") (param f64) (result f64)
+    (f64.store offset=32 (i32.const 0) (local.get 0)) (f64.load (i32.const 32))
+  )
+  (func (export "f32_roundtrip") (param f32) (result f32)
+    (f32.store align=1 (i32.const 33) (local.get 0)) (f32.load offset=1 (i32.const 32))
+  )
+  (func (export "little_endian") (result i32)
+    (i32.store (i32.const 40) (i32.const 0x01020304))
+    (i32.load8_u (i32.const 40))
+  )
+  (func (export "unaligned") (result i64)
+    (i64.store (i32.const 3) (i64.const 0x0102030405060708))
+    (i64.load (i32.const 3))
+  )
+)
+
+(assert_return (invoke "data") (i32.const 1))
+(assert_return (invoke "i32_load8_s" (i32.const -1)) (i32.const -1))
+(assert_return (invoke "i32_load8_u" (i32.const -1)) (i32.const 255))
+(assert_return (invoke "i32_load8_s" (i32.const 100)) (i32.const 100))
+(assert_return (invoke "i32_load8_s" (i32.const 0xfedc6543)) (i32.const 0x43))
+(assert_return (invoke "i32_load8_u" (i32.const 0xfedc6543)) (i32.const 0x43))
+(assert_return (invoke "i32_load16_s" (i32.const -1)) (i32.const -1))
+(assert_return (invoke "i32_load16_u" (i32.const -1)) (i32.const 65535))
+(assert_return (invoke "i32_load16_s" (i32.const 0xfedc6543)) (i32.const 0x6543))
+(assert_return (invoke "i32_load16_u" (i32.const 0x3456fedc)) (i32.const 0xfedc))
+(assert_return (invoke "i64_load8_s" (i64.const -1)) (i64.const -1))
+(assert_return (invoke "i64_load8_s" (i64.const 0xfedcba9856346543)) (i64.const 0x43))
+(assert_return (invoke "i64_load16_u" (i64.const -1)) (i64.const 65535))
+(assert_return (invoke "i64_load32_s" (i64.const -1)) (i64.const -1))
+(assert_return (invoke "i64_load32_s" (i64.const 0x3456436598bacdef)) (i64.const 0xffffffff98bacdef))
+(assert_return (invoke "i64_load32_u" (i64.const -1)) (i64.const 4294967295))
+(assert_return (invoke "i64_load32_u" (i64.const 0xfedcba9856346543)) (i64.const 0x56346543))
+(assert_return (invoke "f64_roundtrip" (f64.const -0x1.23456789abcdep+100)) (f64.const -0x1.23456789abcdep+100))
+(assert_return (invoke "f64_roundtrip" (f64.const nan:0x4000000000001)) (f64.const nan:0x4000000000001))
+(assert_return (invoke "f32_roundtrip" (f32.const 0x1.5p-3)) (f32.const 0x1.5p-3))
+(assert_return (invoke "f32_roundtrip" (f32.const -nan:0x1)) (f32.const -nan:0x1))
+(assert_return (invoke "little_endian") (i32.const 4))
+(assert_return (invoke "unaligned") (i64.const 0x0102030405060708))
+
+(module
+  (memory 1)
+  (data (i32.const 0) "abcdefghijklmnopqrstuvwxyz")
+
+  (func (export "8u_good") (param $i i32) (result i32) (i32.load8_u offset=1 (local.get $i)))
+  (func (export "32_good") (param $i i32) (result i32) (i32.load offset=4 align=1 (local.get $i)))
+  (func (export "32_bad") (param $i i32) (drop (i32.load offset=4294967295 (local.get $i))))
+  (func (export "load") (param $i i32) (result i32) (i32.load (local.get $i)))
+  (func (export "load64") (param $i i32) (result i64) (i64.load (local.get $i)))
+  (func (export "store") (param $i i32) (i32.store (local.get $i) (i32.const 0)))
+  (func (export "store8") (param $i i32) (i32.store8 (local.get $i) (i32.const 0)))
+)
+
+(assert_return (invoke "8u_good" (i32.const 0)) (i32.const 98))
+(assert_return (invoke "8u_good" (i32.const 25)) (i32.const 0))
+(assert_return (invoke "8u_good" (i32.const 65534)) (i32.const 0))
+(assert_trap (invoke "8u_good" (i32.const 65535)) "out of bounds memory access")
+(assert_return (invoke "32_good" (i32.const 0)) (i32.const 1751606885))
+(assert_return (invoke "32_good" (i32.const 65528)) (i32.const 0))
+(assert_trap (invoke "32_good" (i32.const 65529)) "out of bounds memory access")
+(assert_trap (invoke "32_bad" (i32.const 0)) "out of bounds memory access")
+(assert_trap (invoke "32_bad" (i32.const 1)) "out of bounds memory access")
+(assert_return (invoke "load" (i32.const 65532)) (i32.const 0))
+(assert_trap (invoke "load" (i32.const 65533)) "out of bounds memory access")
+(assert_trap (invoke "load" (i32.const -1)) "out of bounds memory access")
+(assert_trap (invoke "load" (i32.const -4)) "out of bounds memory access")
+(assert_trap (invoke "load64" (i32.const 65529)) "out of bounds memory access")
+(assert_trap (invoke "store" (i32.const 65533)) "out of bounds memory access")
+(assert_trap (invoke "store8" (i32.const 65536)) "out of bounds memory access")
+(assert_return (invoke "store8" (i32.const 65535)))
+
+(module
+  (memory 0)
+  (func (export "size") (result i32) (memory.size))
+  (func (export "grow") (param i32) (result i32) (memory.grow (local.get 0)))
+  (func (export "load_at") (param i32) (result i32) (i32.load8_u (local.get 0)))
+)
+
+(assert_return (invoke "size") (i32.const 0))
+(assert_trap (invoke "load_at" (i32.const 0)) "out of bounds memory access")
+(assert_return (invoke "grow" (i32.const 1)) (i32.const 0))
+(assert_return (invoke "size") (i32.const 1))
+(assert_return (invoke "load_at" (i32.const 65535)) (i32.const 0))
+(assert_trap (invoke "load_at" (i32.const 65536)) "out of bounds memory access")
+(assert_return (invoke "grow" (i32.const 4)) (i32.const 1))
+(assert_return (invoke "size") (i32.const 5))
+(assert_return (invoke "grow" (i32.const 0)) (i32.const 5))
+(assert_return (invoke "grow" (i32.const 65536)) (i32.const -1))
+
+(module
+  (memory 0 3)
+  (func (export "grow") (param i32) (result i32) (memory.grow (local.get 0)))
+)
+
+(assert_return (invoke "grow" (i32.const 1)) (i32.const 0))
+(assert_return (invoke "grow" (i32.const 2)) (i32.const 1))
+(assert_return (invoke "grow" (i32.const 1)) (i32.const -1))
+(assert_return (invoke "grow" (i32.const 0)) (i32.const 3))
+
+(module (memory 1) (data (i32.const 65536) ""))
+(module (memory 0) (data (i32.const 0) ""))
+(module (memory 1) (data (i32.const 65535) "b"))
+(assert_unlinkable (module (memory 1) (data (i32.const 65536) "a")) "out of bounds memory access")
+(assert_unlinkable (module (memory 0) (data (i32.const 0) "a")) "out of bounds memory access")
+(assert_unlinkable (module (memory 1) (data (i32.const -1) "a")) "out of bounds memory access")
+
+(module
+  (global (import "spectest" "global_i32") i32)
+  (memory 1)
+  (data (global.get 0) "a")
+  (func (export "at_666") (result i32) (i32.load8_u (i32.const 666)))
+)
+(assert_return (invoke "at_666") (i32.const 97))
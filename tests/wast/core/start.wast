@@ -0,0 +1,60 @@
+;; Start functions
+
+(assert_invalid (module (func) (start 1)) "unknown function")
+(assert_invalid (module (func $main (result i32) (return (i32.const 0))) (start $main)) "start function")
+(assert_invalid (module (func $main (param $a i32)) (start $main)) "start function")
+
+(module
+  (memory (data "A"))
+  (func $inc
+    (i32.store8 (i32.const 0) (i32.add (i32.load8_u (i32.const 0)) (i32.const 1)))
+  )
+  (func $get (result i32) (return (i32.load8_u (i32.const 0))))
+  (func $main (call $inc) (call $inc) (call $inc))
+  (start $main)
+  (export "inc" (func $inc))
+  (export "get" (func $get))
+)
+(assert_return (invoke "get") (i32.const 68))
+(invoke "inc")
+(assert_return (invoke "get") (i32.const 69))
+(invoke "inc")
+(assert_return (invoke "get") (i32.const 70))
+
+(module
+  (func $print_i32 (import "spectest" "print_i32") (param i32))
+  (func $main (call $print_i32 (i32.const 1)))
+  (start $main)
+)
+
+(module
+  (func $print (import "spectest" "print"))
+  (start $print)
+)
+
+(assert_trap (module (func $main (unreachable)) (start $main)) "unreachable")
+
+(module $Ms
+  (type $t (func (result i32)))
+  (memory (export "memory") 1)
+  (table (export "table") 1 funcref)
+  (func (export "get memory[0]") (type $t) (i32.load8_u (i32.const 0)))
+  (func (export "get table[0]") (type $t) (call_indirect (type $t) (i32.const 0)))
+)
+(register "Ms" $Ms)
+
+(assert_trap
+  (module
+    (import "Ms" "memory" (memory 1))
+    (import "Ms" "table" (table 1 funcref))
+    (data (i32.const 0) "hello")
+    (elem (i32.const 0) $f)
+    (func $f (result i32) (i32.const 0xdead))
+    (func $main (unreachable))
+    (start $main)
+  )
+  "unreachable"
+)
+
+(assert_return (invoke $Ms "get memory[0]") (i32.const 104))
+(assert_return (invoke $Ms "get table[0]") (i32.const 0xdead))
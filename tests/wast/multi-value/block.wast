@@ -0,0 +1,184 @@
+;; Blocks with parameters and multiple results.
+
+(module
+  (type $block-sig-1 (func))
+  (type $block-sig-2 (func (result i32)))
+  (type $block-sig-3 (func (param $x i32)))
+  (type $block-sig-4 (func (param i32 f64 i32) (result i32 f64 i32)))
+
+  (func (export "type-use")
+    (block (type $block-sig-1))
+    (block (type $block-sig-2) (i32.const 0)) (drop)
+    (i32.const 0) (block (type $block-sig-3) (drop))
+    (i32.const 0) (f64.const 0) (i32.const 0)
+    (block (type $block-sig-4)) (drop) (drop) (drop)
+    (block (type $block-sig-2) (result i32) (i32.const 0)) (drop)
+    (i32.const 0) (block (type $block-sig-3) (param i32) (drop))
+    (i32.const 0) (f64.const 0) (i32.const 0)
+    (block (type $block-sig-4) (param i32) (param f64 i32) (result i32 f64) (result i32)
+      (drop) (drop) (drop) (i32.const 1) (f64.const 2) (i32.const 3))
+    (drop) (drop) (drop)
+    (i32.const 1) (f64.const 2) (i32.const 3)
+    (block (param i32 f64 i32) (result i32 f64 i32))
+    (drop) (drop) (drop)
+  )
+
+  (func (export "multi") (result i32)
+    (block (call $dummy) (call $dummy) (call $dummy) (call $dummy))
+    (block (result i32)
+      (call $dummy) (call $dummy) (call $dummy) (i32.const 7) (call $dummy)
+    )
+    (drop)
+    (block (result i32 i64 i32)
+      (call $dummy) (call $dummy) (call $dummy) (i32.const 8) (call $dummy)
+      (call $dummy) (call $dummy) (call $dummy) (i64.const 7) (call $dummy)
+      (call $dummy) (call $dummy) (call $dummy) (i32.const 9) (call $dummy)
+    )
+    (drop) (drop)
+  )
+  (func $dummy)
+
+  (func (export "param") (result i32)
+    (i32.const 1)
+    (block (param i32) (result i32)
+      (i32.const 2)
+      (i32.add)
+    )
+  )
+  (func (export "params") (result i32)
+    (i32.const 1)
+    (i32.const 2)
+    (block (param i32 i32) (result i32)
+      (i32.add)
+    )
+  )
+  (func (export "params-id") (result i32)
+    (i32.const 1)
+    (i32.const 2)
+    (block (param i32 i32) (result i32 i32))
+    (i32.add)
+  )
+  (func (export "param-break") (result i32)
+    (i32.const 1)
+    (block (param i32) (result i32)
+      (i32.const 2)
+      (i32.add)
+      (br 0)
+    )
+  )
+  (func (export "params-break") (result i32)
+    (i32.const 1)
+    (i32.const 2)
+    (block (param i32 i32) (result i32)
+      (i32.add)
+      (br 0)
+    )
+  )
+  (func (export "params-id-break") (result i32)
+    (i32.const 1)
+    (i32.const 2)
+    (block (param i32 i32) (result i32 i32) (br 0))
+    (i32.add)
+  )
+  (func (export "break-multi-value") (result i32 i32 i64)
+    (block (result i32 i32 i64)
+      (br 0 (i32.const 18) (i32.const -18) (i64.const 18))
+      (i32.const 19) (i32.const -19) (i64.const 19)
+    )
+  )
+  (func (export "break-inner-multi-value") (result i32 i64)
+    (block (result i32 i64)
+      (block (result i32 i64)
+        (br 1 (i32.const 4) (i64.const 5))
+      )
+      (drop) (drop)
+      (i32.const 0) (i64.const 0)
+    )
+  )
+  (func (export "nested-params") (param i32) (result i32 i32)
+    (i32.const 100)
+    (block (param i32) (result i32 i32)
+      (i32.const 200)
+      (block (param i32 i32) (result i32 i32)
+        (br_if 1 (local.get 0))
+        (i32.add)
+        (i32.const 400)
+      )
+    )
+  )
+  (type $pair (func (param i32 i32) (result i32 i32)))
+  (func (export "swap") (param i32 i32) (result i32 i32)
+    (local.get 0) (local.get 1)
+    (block (type $pair)
+      (local.set 0) (local.set 1)
+      (local.get 0) (local.get 1)
+    )
+  )
+)
+
+(assert_return (invoke "type-use"))
+(assert_return (invoke "multi") (i32.const 8))
+(assert_return (invoke "param") (i32.const 3))
+(assert_return (invoke "params") (i32.const 3))
+(assert_return (invoke "params-id") (i32.const 3))
+(assert_return (invoke "param-break") (i32.const 3))
+(assert_return (invoke "params-break") (i32.const 3))
+(assert_return (invoke "params-id-break") (i32.const 3))
+(assert_return (invoke "break-multi-value")
+  (i32.const 18) (i32.const -18) (i64.const 18)
+)
+(assert_return (invoke "break-inner-multi-value") (i32.const 4) (i64.const 5))
+(assert_return (invoke "nested-params" (i32.const 0)) (i32.const 300) (i32.const 400))
+(assert_return (invoke "nested-params" (i32.const 1)) (i32.const 100) (i32.const 200))
+(assert_return (invoke "swap" (i32.const 1) (i32.const 2)) (i32.const 2) (i32.const 1))
+
+(assert_invalid
+  (module (func $type-param-void-vs-num (block (param i32) (drop))))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-param-num-vs-num (f32.const 0) (block (param i32) (drop))))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-param-nested-void-vs-num
+    (block (block (param i32) (drop)))
+  ))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-value-nums-vs-void
+    (block (i32.const 1) (i32.const 2))
+  ))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-value-empty-vs-nums (result i32 i32)
+    (block (result i32 i32))
+  ))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-value-partial-vs-nums (result i32 i32)
+    (block (result i32 i32) (i32.const 1))
+  ))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-break-partial-vs-nums (result i32 i32)
+    (block (result i32 i32) (br 0 (i32.const 1)) (i32.const 1) (i32.const 2))
+  ))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-params-vs-results (result i32)
+    (i32.const 1)
+    (block (param i32) (result i32 i32))
+    (drop)
+  ))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $unknown-type (block (type 7))))
+  "unknown type"
+)
@@ -0,0 +1,85 @@
+;; Functions and calls with multiple results.
+
+(module
+  (func $f (result i32 i64 f32 f64)
+    (i32.const 1) (i64.const 2) (f32.const 3) (f64.const 4)
+  )
+  (func (export "multi") (result i32 i64 f32 f64)
+    (call $f)
+  )
+  (func $add-all (param i32 i64 f32 f64) (result f64)
+    (f64.add
+      (f64.add (f64.convert_i32_s (local.get 0)) (f64.convert_i64_s (local.get 1)))
+      (f64.add (f64.promote_f32 (local.get 2)) (local.get 3))
+    )
+  )
+  (func (export "sum") (result f64)
+    (call $add-all (call $f))
+  )
+  (func $swap (export "swap") (param i32 i32) (result i32 i32)
+    (local.get 1) (local.get 0)
+  )
+  (func (export "swap-twice") (param i32 i32) (result i32 i32)
+    (call $swap (call $swap (local.get 0) (local.get 1)))
+  )
+  (func (export "return-multi") (param i32) (result i32 i64)
+    (if (local.get 0)
+      (then (return (i32.const 1) (i64.const 2)))
+    )
+    (i32.const 3) (i64.const 4)
+  )
+  (func (export "br-multi") (result f32 f32)
+    (f32.const 1) (f32.const 2) (br 0)
+  )
+  (func $div-rem (param i32 i32) (result i32 i32)
+    (i32.div_u (local.get 0) (local.get 1))
+    (i32.rem_u (local.get 0) (local.get 1))
+  )
+  (type $div-rem (func (param i32 i32) (result i32 i32)))
+  (table 2 funcref)
+  (elem (i32.const 0) $div-rem $swap)
+  (func (export "indirect") (param i32 i32 i32) (result i32 i32)
+    (call_indirect (type $div-rem) (local.get 0) (local.get 1) (local.get 2))
+  )
+  (func (export "many") (result i32 i32 i32 i32 i32 i32 i32 i32 i32 i32)
+    (i32.const 0) (i32.const 1) (i32.const 2) (i32.const 3) (i32.const 4)
+    (i32.const 5) (i32.const 6) (i32.const 7) (i32.const 8) (i32.const 9)
+  )
+)
+
+(assert_return (invoke "multi") (i32.const 1) (i64.const 2) (f32.const 3) (f64.const 4))
+(assert_return (invoke "sum") (f64.const 10))
+(assert_return (invoke "swap" (i32.const 1) (i32.const 2)) (i32.const 2) (i32.const 1))
+(assert_return (invoke "swap-twice" (i32.const 1) (i32.const 2)) (i32.const 1) (i32.const 2))
+(assert_return (invoke "return-multi" (i32.const 0)) (i32.const 3) (i64.const 4))
+(assert_return (invoke "return-multi" (i32.const 1)) (i32.const 1) (i64.const 2))
+(assert_return (invoke "br-multi") (f32.const 1) (f32.const 2))
+(assert_return (invoke "indirect" (i32.const 17) (i32.const 5) (i32.const 0))
+  (i32.const 3) (i32.const 2)
+)
+(assert_return (invoke "indirect" (i32.const 17) (i32.const 5) (i32.const 1))
+  (i32.const 5) (i32.const 17)
+)
+(assert_return (invoke "many")
+  (i32.const 0) (i32.const 1) (i32.const 2) (i32.const 3) (i32.const 4)
+  (i32.const 5) (i32.const 6) (i32.const 7) (i32.const 8) (i32.const 9)
+)
+
+(assert_invalid
+  (module (func $type-empty-vs-nums (result i32 i32)))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-partial-vs-nums (result i32 i32) (i32.const 0)))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-return-partial-vs-nums (result i32 i64)
+    (return (i64.const 1)) (i32.const 0) (i64.const 1)
+  ))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-nums-swapped (result i32 i64) (i64.const 1) (i32.const 0)))
+  "type mismatch"
+)
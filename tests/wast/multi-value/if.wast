@@ -0,0 +1,110 @@
+;; Ifs with parameters and multiple results.
+
+(module
+  (func $dummy)
+
+  (func (export "multi") (param i32) (result i32 i64)
+    (if (result i32 i64 i32) (local.get 0)
+      (then
+        (call $dummy) (i32.const 8) (call $dummy)
+        (i64.const 7) (call $dummy) (i32.const 9)
+      )
+      (else
+        (call $dummy) (i32.const -8) (call $dummy)
+        (i64.const -7) (call $dummy) (i32.const -9)
+      )
+    )
+    (drop)
+  )
+  (func (export "param") (param i32) (result i32)
+    (i32.const 1)
+    (if (param i32) (result i32) (local.get 0)
+      (then (i32.const 2) (i32.add))
+      (else (i32.const -2) (i32.add))
+    )
+  )
+  (func (export "params") (param i32) (result i32)
+    (i32.const 1)
+    (i32.const 2)
+    (if (param i32 i32) (result i32) (local.get 0)
+      (then (i32.add))
+      (else (i32.sub))
+    )
+  )
+  (func (export "params-id") (param i32) (result i32)
+    (i32.const 1)
+    (i32.const 2)
+    (if (param i32 i32) (result i32 i32) (local.get 0) (then))
+    (i32.add)
+  )
+  (func (export "param-break") (param i32) (result i32)
+    (i32.const 1)
+    (if (param i32) (result i32) (local.get 0)
+      (then (i32.const 2) (i32.add) (br 0))
+      (else (i32.const -2) (i32.add) (br 0))
+    )
+  )
+  (func (export "params-break") (param i32) (result i32)
+    (i32.const 1)
+    (i32.const 2)
+    (if (param i32 i32) (result i32) (local.get 0)
+      (then (i32.add) (br 0))
+      (else (i32.sub) (br 0))
+    )
+  )
+  (func (export "flat") (param i32) (result i32 i32)
+    i32.const 10
+    local.get 0
+    if (param i32) (result i32 i32)
+      i32.const 1
+    else
+      i32.const 2
+    end
+  )
+)
+
+(assert_return (invoke "multi" (i32.const 0)) (i32.const -8) (i64.const -7))
+(assert_return (invoke "multi" (i32.const 1)) (i32.const 8) (i64.const 7))
+(assert_return (invoke "param" (i32.const 0)) (i32.const -1))
+(assert_return (invoke "param" (i32.const 1)) (i32.const 3))
+(assert_return (invoke "params" (i32.const 0)) (i32.const -1))
+(assert_return (invoke "params" (i32.const 1)) (i32.const 3))
+(assert_return (invoke "params-id" (i32.const 0)) (i32.const 3))
+(assert_return (invoke "params-id" (i32.const 1)) (i32.const 3))
+(assert_return (invoke "param-break" (i32.const 0)) (i32.const -1))
+(assert_return (invoke "param-break" (i32.const 1)) (i32.const 3))
+(assert_return (invoke "params-break" (i32.const 0)) (i32.const -1))
+(assert_return (invoke "params-break" (i32.const 1)) (i32.const 3))
+(assert_return (invoke "flat" (i32.const 0)) (i32.const 10) (i32.const 2))
+(assert_return (invoke "flat" (i32.const 1)) (i32.const 10) (i32.const 1))
+
+(assert_invalid
+  (module (func $no-else-params-vs-results (result i32)
+    (i32.const 0)
+    (if (param i32) (result i32 i32) (i32.const 1) (then (i32.const 2)))
+    (drop)
+  ))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-else-value-partial-vs-nums (result i32 i32)
+    (if (result i32 i32) (i32.const 1)
+      (then (i32.const 1) (i32.const 2))
+      (else (i32.const 1))
+    )
+  ))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-param-void-vs-num
+    (if (param i32) (i32.const 1) (then (drop)))
+  ))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-condition-under-params (result i32)
+    (i32.const 1)
+    (if (param i32) (result i32) (then))
+  ))
+  "type mismatch"
+)
@@ -0,0 +1,125 @@
+;; Loops with parameters and multiple results.
+
+(module
+  (func $dummy)
+
+  (func (export "multi") (result i32)
+    (loop (call $dummy) (call $dummy) (call $dummy) (call $dummy))
+    (loop (result i32 i64 i32)
+      (call $dummy) (call $dummy) (i32.const 8) (call $dummy)
+      (call $dummy) (call $dummy) (i64.const 7) (call $dummy)
+      (call $dummy) (call $dummy) (i32.const 9) (call $dummy)
+    )
+    (drop) (drop)
+  )
+  (func (export "param") (result i32)
+    (i32.const 1)
+    (loop (param i32) (result i32)
+      (i32.const 2)
+      (i32.add)
+    )
+  )
+  (func (export "params") (result i32)
+    (i32.const 1)
+    (i32.const 2)
+    (loop (param i32 i32) (result i32)
+      (i32.add)
+    )
+  )
+  (func (export "params-id") (result i32)
+    (i32.const 1)
+    (i32.const 2)
+    (loop (param i32 i32) (result i32 i32))
+    (i32.add)
+  )
+  (func (export "param-break") (result i32)
+    (local $x i32)
+    (i32.const 1)
+    (loop (param i32) (result i32)
+      (i32.const 4)
+      (i32.add)
+      (local.tee $x)
+      (local.get $x)
+      (i32.const 10)
+      (i32.lt_u)
+      (br_if 0)
+    )
+  )
+  (func (export "params-break") (result i32)
+    (local $x i32)
+    (i32.const 1)
+    (i32.const 2)
+    (loop (param i32 i32) (result i32)
+      (i32.add)
+      (local.tee $x)
+      (i32.const 3)
+      (local.get $x)
+      (i32.const 10)
+      (i32.lt_u)
+      (br_if 0)
+      (drop)
+    )
+  )
+  (func (export "params-id-break") (result i32)
+    (local $x i32)
+    (local.set $x (i32.const 0))
+    (i32.const 1)
+    (i32.const 2)
+    (loop (param i32 i32) (result i32 i32)
+      (local.set $x (i32.add (local.get $x) (i32.const 1)))
+      (br_if 0 (i32.lt_u (local.get $x) (i32.const 10)))
+    )
+    (i32.add)
+  )
+
+  ;; Computes the nth Fibonacci number with the pair kept on the stack.
+  (func (export "fib") (param $n i64) (result i64)
+    (i64.const 0)
+    (i64.const 1)
+    (loop $next (param i64 i64) (result i64 i64)
+      (if (param i64 i64) (result i64 i64) (i64.eqz (local.get $n))
+        (then)
+        (else
+          (local.set $n (i64.sub (local.get $n) (i64.const 1)))
+          (call $fib-step)
+          (br $next)
+        )
+      )
+    )
+    (drop)
+  )
+  (func $fib-step (param i64 i64) (result i64 i64)
+    (local.get 1)
+    (i64.add (local.get 0) (local.get 1))
+  )
+)
+
+(assert_return (invoke "multi") (i32.const 8))
+(assert_return (invoke "param") (i32.const 3))
+(assert_return (invoke "params") (i32.const 3))
+(assert_return (invoke "params-id") (i32.const 3))
+(assert_return (invoke "param-break") (i32.const 13))
+(assert_return (invoke "params-break") (i32.const 12))
+(assert_return (invoke "params-id-break") (i32.const 3))
+(assert_return (invoke "fib" (i64.const 0)) (i64.const 0))
+(assert_return (invoke "fib" (i64.const 1)) (i64.const 1))
+(assert_return (invoke "fib" (i64.const 10)) (i64.const 55))
+(assert_return (invoke "fib" (i64.const 90)) (i64.const 2880067194370816120))
+
+(assert_invalid
+  (module (func $type-param-void-vs-num (loop (param i32) (drop))))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-break-param-vs-result (result i32)
+    (i32.const 0)
+    (loop (param i32) (result i32) (drop) (br 0))
+  ))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func $type-value-nums-vs-num (result i32)
+    (loop (result i32) (i32.const 1) (i32.const 2))
+  ))
+  "type mismatch"
+)
@@ -0,0 +1,85 @@
+;; Indirect calls through tables other than table 0.
+(module
+  (type $proc (func))
+  (type $out-i32 (func (result i32)))
+  (type $over-i32 (func (param i32) (result i32)))
+
+  (table $t0 1 funcref)
+  (table $t1 3 funcref)
+  (table $t2 funcref (elem $const-i32 $id-i32))
+  (table $ext 1 externref)
+
+  (elem (table $t1) (i32.const 0) func $const-i32 $id-i32 $dummy)
+
+  (func $dummy)
+  (func $const-i32 (type $out-i32) (i32.const 0x132))
+  (func $id-i32 (type $over-i32) (local.get 0))
+
+  (func (export "call-t1") (param i32) (result i32)
+    (call_indirect $t1 (type $out-i32) (local.get 0))
+  )
+  (func (export "call-t1-flat") (param i32) (result i32)
+    local.get 0
+    call_indirect $t1 (type $out-i32)
+  )
+  (func (export "call-t2") (param i32 i32) (result i32)
+    (call_indirect $t2 (type $over-i32) (local.get 1) (local.get 0))
+  )
+  (func (export "call-t0") (result i32)
+    (call_indirect (type $out-i32) (i32.const 0))
+  )
+  (func (export "call-index") (result i32)
+    (call_indirect 2 (type $out-i32) (i32.const 0))
+  )
+)
+
+(assert_return (invoke "call-t1" (i32.const 0)) (i32.const 0x132))
+(assert_return (invoke "call-t1-flat" (i32.const 0)) (i32.const 0x132))
+(assert_trap (invoke "call-t1" (i32.const 1)) "indirect call type mismatch")
+(assert_trap (invoke "call-t1" (i32.const 2)) "indirect call type mismatch")
+(assert_trap (invoke "call-t1" (i32.const 3)) "undefined element")
+(assert_return (invoke "call-t2" (i32.const 1) (i32.const 7)) (i32.const 7))
+(assert_trap (invoke "call-t2" (i32.const 0) (i32.const 7)) "indirect call type mismatch")
+(assert_trap (invoke "call-t2" (i32.const 2) (i32.const 7)) "undefined element")
+(assert_trap (invoke "call-t0") "uninitialized element")
+(assert_return (invoke "call-index") (i32.const 0x132))
+
+;; Functions stored with table.set are callable.
+(module
+  (type $t (func (param i32) (result i32)))
+  (table $fns 2 funcref)
+  (elem declare func $double $square)
+  (func $double (type $t) (i32.add (local.get 0) (local.get 0)))
+  (func $square (type $t) (i32.mul (local.get 0) (local.get 0)))
+  (func (export "install") (param i32)
+    (table.set $fns (i32.const 0)
+      (select (result funcref)
+        (ref.func $double) (ref.func $square) (local.get 0)))
+  )
+  (func (export "apply") (param i32) (result i32)
+    (call_indirect $fns (type $t) (local.get 0) (i32.const 0))
+  )
+)
+
+(assert_trap (invoke "apply" (i32.const 3)) "uninitialized element")
+(invoke "install" (i32.const 1))
+(assert_return (invoke "apply" (i32.const 3)) (i32.const 6))
+(invoke "install" (i32.const 0))
+(assert_return (invoke "apply" (i32.const 3)) (i32.const 9))
+
+(assert_invalid
+  (module
+    (type (func))
+    (table 1 externref)
+    (func (call_indirect (type 0) (i32.const 0)))
+  )
+  "type mismatch"
+)
+(assert_invalid
+  (module
+    (type (func))
+    (table 1 funcref)
+    (func (call_indirect 1 (type 0) (i32.const 0)))
+  )
+  "unknown table"
+)
@@ -0,0 +1,170 @@
+;; Segments with element expressions.
+(module
+  (table $t 10 funcref)
+  (table $e 10 externref)
+  (func $f)
+  (func $g)
+  (elem (i32.const 0) funcref (ref.func $f) (ref.null func))
+  (elem (table $t) (i32.const 2) funcref (item ref.func $g) (item (ref.null func)))
+  (elem (table $e) (offset (i32.const 0)) externref (ref.null extern))
+  (elem funcref (ref.func $f) (ref.func $g))
+  (elem externref (ref.null extern) (item ref.null extern))
+  (elem declare funcref (ref.func $f))
+  (elem $named externref)
+)
+
+(module
+  (type $out-i32 (func (result i32)))
+  (table $t 4 funcref)
+  (table $e 3 externref)
+  (global $g funcref (ref.func $const-a))
+  (elem (table $t) (i32.const 0) funcref
+    (ref.func $const-a) (ref.null func) (ref.func $const-a))
+  (elem $passive funcref (ref.func $const-b) (ref.null func))
+  (elem $externs externref (ref.null extern) (ref.null extern))
+  (func $const-a (type $out-i32) (i32.const 65))
+  (func $const-b (type $out-i32) (i32.const 66))
+
+  (func (export "call") (param i32) (result i32)
+    (call_indirect $t (type $out-i32) (local.get 0))
+  )
+  (func (export "init") (param i32 i32 i32)
+    (table.init $t $passive (local.get 0) (local.get 1) (local.get 2))
+  )
+  (func (export "fill-externs") (param externref)
+    (table.fill $e (i32.const 0) (local.get 0) (i32.const 3))
+  )
+  (func (export "init-externs") (param i32 i32 i32)
+    (table.init $e $externs (local.get 0) (local.get 1) (local.get 2))
+  )
+  (func (export "extern") (param i32) (result externref)
+    (table.get $e (local.get 0))
+  )
+)
+
+(assert_return (invoke "call" (i32.const 0)) (i32.const 65))
+(assert_trap (invoke "call" (i32.const 1)) "uninitialized element")
+(assert_return (invoke "call" (i32.const 2)) (i32.const 65))
+(assert_trap (invoke "call" (i32.const 3)) "uninitialized element")
+(invoke "init" (i32.const 2) (i32.const 0) (i32.const 2))
+(assert_return (invoke "call" (i32.const 2)) (i32.const 66))
+(assert_trap (invoke "call" (i32.const 3)) "uninitialized element")
+(assert_trap (invoke "init" (i32.const 3) (i32.const 0) (i32.const 2)) "out of bounds table access")
+
+(invoke "fill-externs" (ref.extern 9))
+(assert_return (invoke "extern" (i32.const 1)) (ref.extern 9))
+(invoke "init-externs" (i32.const 1) (i32.const 0) (i32.const 2))
+(assert_return (invoke "extern" (i32.const 0)) (ref.extern 9))
+(assert_return (invoke "extern" (i32.const 1)) (ref.null extern))
+(assert_return (invoke "extern" (i32.const 2)) (ref.null extern))
+
+;; Copying between tables of the same type.
+(module
+  (table $a 2 externref)
+  (table $b 2 externref)
+  (func (export "set") (param externref) (table.set $a (i32.const 0) (local.get 0)))
+  (func (export "copy") (table.copy $b $a (i32.const 1) (i32.const 0) (i32.const 1)))
+  (func (export "get") (result externref) (table.get $b (i32.const 1)))
+)
+(invoke "set" (ref.extern 5))
+(invoke "copy")
+(assert_return (invoke "get") (ref.extern 5))
+
+;; Element types must agree with tables and segments.
+(assert_invalid
+  (module
+    (table 1 funcref)
+    (elem (i32.const 0) externref (ref.null extern))
+  )
+  "type mismatch"
+)
+(assert_invalid
+  (module
+    (table 1 externref)
+    (elem (table 0) (i32.const 0) func $f)
+    (func $f)
+  )
+  "type mismatch"
+)
+(assert_invalid
+  (module
+    (elem funcref (ref.null extern))
+  )
+  "type mismatch"
+)
+(assert_invalid
+  (module
+    (elem externref (ref.func 0))
+    (func)
+  )
+  "type mismatch"
+)
+(assert_invalid
+  (module
+    (elem funcref (i32.const 0))
+  )
+  "type mismatch"
+)
+(assert_invalid
+  (module
+    (func $f)
+    (elem funcref (item (ref.func $f) (drop) (ref.func $f)))
+  )
+  "constant expression required"
+)
+(assert_invalid
+  (module
+    (table 1 funcref)
+    (table 1 externref)
+    (elem funcref (ref.null func))
+    (func (table.init 1 0 (i32.const 0) (i32.const 0) (i32.const 0)))
+  )
+  "type mismatch"
+)
+(assert_invalid
+  (module
+    (table 1 funcref)
+    (table 1 externref)
+    (func (table.copy 0 1 (i32.const 0) (i32.const 0) (i32.const 0)))
+  )
+  "type mismatch"
+)
+(assert_invalid
+  (module
+    (elem funcref (ref.func 1))
+    (func)
+  )
+  "unknown function 1"
+)
+
+;; Element segments in every binary encoding.
+(module binary
+  "\00asm" "\01\00\00\00"
+  "\01\04\01\60\00\00"                  ;; type section: [] -> []
+  "\03\02\01\00"                        ;; function section
+  "\04\07\02\70\00\01\6f\00\01"         ;; table section: funcref, externref
+  "\09\35\08"                           ;; element section: 8 segments
+  "\00\41\00\0b\01\00"                  ;; 0: active table 0, func indices
+  "\01\00\01\00"                        ;; 1: passive, func indices
+  "\02\00\41\00\0b\00\01\00"            ;; 2: active explicit table, func indices
+  "\03\00\01\00"                        ;; 3: declarative, func indices
+  "\04\41\00\0b\01\d2\00\0b"            ;; 4: active table 0, exprs
+  "\05\70\01\d0\70\0b"                  ;; 5: passive, funcref exprs
+  "\06\01\41\00\0b\6f\01\d0\6f\0b"      ;; 6: active table 1, externref exprs
+  "\07\70\01\d2\00\0b"                  ;; 7: declarative, funcref exprs
+  "\0a\04\01\02\00\0b"                  ;; code section
+)
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\09\03\01\08\00"                   ;; element section with flags 8
+  )
+  "malformed elements segment kind"
+)
+(assert_malformed
+  (module binary
+    "\00asm" "\01\00\00\00"
+    "\04\04\01\6e\00\00"                ;; table section with an unknown reference type
+  )
+  "malformed reference type"
+)
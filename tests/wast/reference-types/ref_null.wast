@@ -0,0 +1,23 @@
+(module
+  (func (export "anyref") (result externref) (ref.null extern))
+  (func (export "funcref") (result funcref) (ref.null func))
+
+  (global externref (ref.null extern))
+  (global funcref (ref.null func))
+)
+
+(assert_return (invoke "anyref") (ref.null extern))
+(assert_return (invoke "funcref") (ref.null func))
+
+(assert_malformed
+  (module quote "(func (result funcref) (ref.null any))")
+  "unknown type"
+)
+(assert_invalid
+  (module (func (result externref) (ref.null func)))
+  "type mismatch"
+)
+(assert_invalid
+  (module (func (result i32) (ref.null func)))
+  "type mismatch"
+)
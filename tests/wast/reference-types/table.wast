@@ -0,0 +1,66 @@
+;; Table declarations
+
+(module (table 0 funcref))
+(module (table 1 funcref))
+(module (table 0 0 funcref))
+(module (table 0 1 funcref))
+(module (table 1 256 funcref))
+(module (table 0 65536 funcref))
+(module (table 0 0xffff_ffff funcref))
+(module (table 0 funcref) (table 0 funcref))
+(module (table (import "spectest" "table") 0 funcref) (table 0 funcref))
+(module (table 0 externref) (table 3 5 externref) (table 1 funcref))
+
+(assert_invalid (module (elem (i32.const 0))) "unknown table")
+(assert_invalid (module (elem (i32.const 0) $f) (func $f)) "unknown table")
+
+(assert_invalid
+  (module (table 1 0 funcref))
+  "size minimum must not be greater than maximum"
+)
+(assert_invalid
+  (module (table 0xffff_ffff 0 funcref))
+  "size minimum must not be greater than maximum"
+)
+
+;; Duplicate table identifiers
+
+(assert_malformed
+  (module quote "(table $foo 1 funcref)" "(table $foo 1 funcref)")
+  "duplicate table"
+)
+
+;; Tables of each reference type can be exported and used independently.
+(module
+  (table $f (export "funcs") 2 funcref)
+  (table $e (export "externs") 2 externref)
+  (func (export "size-funcs") (result i32) (table.size $f))
+  (func (export "size-externs") (result i32) (table.size $e))
+  (func (export "store") (param externref) (table.set $e (i32.const 1) (local.get 0)))
+  (func (export "load") (result externref) (table.get $e (i32.const 1)))
+)
+(register "tables")
+
+(assert_return (invoke "size-funcs") (i32.const 2))
+(assert_return (invoke "size-externs") (i32.const 2))
+(invoke "store" (ref.extern 7))
+
+(module
+  (import "tables" "externs" (table $e 2 externref))
+  (func (export "load") (result externref) (table.get $e (i32.const 1)))
+)
+(assert_return (invoke "load") (ref.extern 7))
+
+;; Table element types must match exactly.
+(assert_unlinkable
+  (module (import "tables" "externs" (table 2 funcref)))
+  "incompatible import type"
+)
+(assert_unlinkable
+  (module (import "tables" "funcs" (table 2 externref)))
+  "incompatible import type"
+)
+(assert_unlinkable
+  (module (import "spectest" "table" (table 10 externref)))
+  "incompatible import type"
+)
@@ -0,0 +1,55 @@
+;; i64 sign-extension operators.
+(module
+  (func (export "extend8_s") (param $x i64) (result i64) (i64.extend8_s (local.get $x)))
+  (func (export "extend16_s") (param $x i64) (result i64) (i64.extend16_s (local.get $x)))
+  (func (export "extend32_s") (param $x i64) (result i64) (i64.extend32_s (local.get $x)))
+)
+
+(assert_return (invoke "extend8_s" (i64.const 0)) (i64.const 0))
+(assert_return (invoke "extend8_s" (i64.const 0x7f)) (i64.const 127))
+(assert_return (invoke "extend8_s" (i64.const 0x80)) (i64.const -128))
+(assert_return (invoke "extend8_s" (i64.const 0xff)) (i64.const -1))
+(assert_return (invoke "extend8_s" (i64.const 0x01234567_89abcd_00)) (i64.const 0))
+(assert_return (invoke "extend8_s" (i64.const 0xfedcba98_765432_80)) (i64.const -0x80))
+(assert_return (invoke "extend8_s" (i64.const -1)) (i64.const -1))
+
+(assert_return (invoke "extend16_s" (i64.const 0)) (i64.const 0))
+(assert_return (invoke "extend16_s" (i64.const 0x7fff)) (i64.const 32767))
+(assert_return (invoke "extend16_s" (i64.const 0x8000)) (i64.const -32768))
+(assert_return (invoke "extend16_s" (i64.const 0xffff)) (i64.const -1))
+(assert_return (invoke "extend16_s" (i64.const 0x12345678_9abc_0000)) (i64.const 0))
+(assert_return (invoke "extend16_s" (i64.const 0xfedcba98_7654_8000)) (i64.const -0x8000))
+(assert_return (invoke "extend16_s" (i64.const -1)) (i64.const -1))
+
+(assert_return (invoke "extend32_s" (i64.const 0)) (i64.const 0))
+(assert_return (invoke "extend32_s" (i64.const 0x7fff)) (i64.const 32767))
+(assert_return (invoke "extend32_s" (i64.const 0x8000)) (i64.const 32768))
+(assert_return (invoke "extend32_s" (i64.const 0xffff)) (i64.const 65535))
+(assert_return (invoke "extend32_s" (i64.const 0x7fffffff)) (i64.const 0x7fffffff))
+(assert_return (invoke "extend32_s" (i64.const 0x80000000)) (i64.const -0x80000000))
+(assert_return (invoke "extend32_s" (i64.const 0xffffffff)) (i64.const -1))
+(assert_return (invoke "extend32_s" (i64.const 0x01234567_00000000)) (i64.const 0))
+(assert_return (invoke "extend32_s" (i64.const 0xfedcba98_80000000)) (i64.const -0x80000000))
+(assert_return (invoke "extend32_s" (i64.const -1)) (i64.const -1))
+
+(assert_invalid (module (func (result i64) (i64.extend8_s (i32.const 0)))) "type mismatch")
+(assert_invalid (module (func (result i64) (i64.extend16_s (f64.const 0)))) "type mismatch")
+(assert_invalid (module (func (result i32) (i64.extend32_s (i64.const 0)))) "type mismatch")
+(assert_invalid (module (func (result i64) (i64.extend32_s))) "type mismatch")
+
+;; The single-byte opcodes decode from binary.
+(module binary
+  "\00asm" "\01\00\00\00"
+  "\01\05\01\60\00\01\7e"               ;; type section: [] -> [i64]
+  "\03\02\01\00"                        ;; function section
+  "\07\07\01\03\72\75\6e\00\00"         ;; export "run"
+  "\0a\0e\01\0c\00"                     ;; code section
+  "\41\ff\01"                           ;; i32.const 255
+  "\c0"                                 ;; i32.extend8_s
+  "\ad"                                 ;; i64.extend_i32_u
+  "\c4"                                 ;; i64.extend32_s
+  "\42\80\01"                           ;; i64.const 128
+  "\7c"                                 ;; i64.add
+  "\0b"
+)
+(assert_return (invoke "run") (i64.const 127))
@@ -0,0 +1,274 @@
+use std::fs;
+use wasm_interpreter::module::{BlockType, FuncType, Instr, TypeIdx, ValType, MAX_NESTING_DEPTH};
+use wasm_interpreter::{Error, Linker, Module, ParseErrorKind, Store, Value};
+
+fn run(text: &str, name: &str, args: &[Value]) -> Vec<Value> {
+    let module = Module::from_wat(text).unwrap_or_else(|err| panic!("{err}"));
+    let mut store = Store::default();
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    instance.invoke(&mut store, name, args).unwrap()
+}
+
+fn parse_error(text: &str) -> ParseErrorKind {
+    match Module::from_wat(text) {
+        Err(Error::Parse(err)) => err.kind,
+        other => panic!("expected a parse error, got {other:?}"),
+    }
+}
+
+fn without_names(mut module: Module) -> String {
+    module.names = Default::default();
+    module.customs.clear();
+    format!("{module:?}")
+}
+
+#[test]
+fn examples_parse() {
+    for entry in fs::read_dir("examples").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "wat") {
+            let text = fs::read_to_string(&path).unwrap();
+            if let Err(err) = Module::from_wat(&text) {
+                panic!("{}: {err}", path.display());
+            }
+        }
+    }
+}
+
+#[test]
+fn examples_match_their_binaries() {
+    for name in ["only_module", "only_one_function", "simple01"] {
+        let text = fs::read_to_string(format!("examples/{name}.wat")).unwrap();
+        let bytes = fs::read(format!("examples/{name}.wasm")).unwrap();
+        let parsed = Module::from_wat(&text).unwrap();
+        let decoded = Module::from_bytes(&bytes).unwrap();
+        assert_eq!(without_names(parsed), without_names(decoded), "{name}");
+    }
+}
+
+#[test]
+fn folded_and_flat_forms_agree() {
+    let folded = r#"(module
+      (func $max (export "max") (param $a i32) (param $b i32) (result i32)
+        (if (result i32) (i32.gt_s (local.get $a) (local.get $b))
+          (then (local.get $a))
+          (else (local.get $b)))))"#;
+    let flat = r#"(module
+      (func $max (export "max") (param $a i32) (param $b i32) (result i32)
+        local.get $a
+        local.get $b
+        i32.gt_s
+        if (result i32)
+          local.get $a
+        else
+          local.get $b
+        end))"#;
+    assert_eq!(
+        without_names(Module::from_wat(folded).unwrap()),
+        without_names(Module::from_wat(flat).unwrap())
+    );
+    let args = [Value::I32(-4), Value::I32(3)];
+    assert_eq!(run(folded, "max", &args), vec![Value::I32(3)]);
+}
+
+#[test]
+fn names_resolve_across_fields() {
+    let text = r#"(module
+      (func (export "sum") (param $n i32) (result i32)
+        (local $acc i32)
+        (block $done
+          (loop $next
+            (br_if $done (i32.eqz (local.get $n)))
+            (local.set $acc (call $add (local.get $acc) (local.get $n)))
+            (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+            (br $next)))
+        (i32.add (local.get $acc) (global.get $bias)))
+      (func $add (param i32 i32) (result i32)
+        (i32.add (local.get 0) (local.get 1)))
+      (global $bias i32 (i32.const 1_000)))"#;
+    assert_eq!(run(text, "sum", &[Value::I32(10)]), vec![Value::I32(1055)]);
+    let module = Module::from_wat(text).unwrap();
+    assert_eq!(module.names.func(1), Some("add"));
+    assert_eq!(module.names.local(0, 1), Some("acc"));
+}
+
+#[test]
+fn inline_abbreviations() {
+    let text = r#"(module
+      (type $binop (func (param i32 i32) (result i32)))
+      (memory (export "mem") (data "\01\02" "\03\04"))
+      (table funcref (elem $sub $mul))
+      (func $sub (type $binop) (i32.sub (local.get 0) (local.get 1)))
+      (func $mul (type $binop) (i32.mul (local.get 0) (local.get 1)))
+      (func (export "apply") (param i32 i32 i32) (result i32)
+        (call_indirect (type $binop) (local.get 1) (local.get 2) (local.get 0)))
+      (func (export "load") (result i32)
+        (i32.load offset=1 align=1 (i32.const 0))))"#;
+    let module = Module::from_wat(text).unwrap();
+    assert_eq!(module.types.len(), 3);
+    assert_eq!(module.data[0].init, [1, 2, 3, 4]);
+    let apply = |op| {
+        run(
+            text,
+            "apply",
+            &[Value::I32(op), Value::I32(6), Value::I32(7)],
+        )
+    };
+    assert_eq!(apply(0), vec![Value::I32(-1)]);
+    assert_eq!(apply(1), vec![Value::I32(42)]);
+    assert_eq!(run(text, "load", &[]), vec![Value::I32(0x0004_0302)]);
+}
+
+#[test]
+fn inline_imports_come_first() {
+    let text = r#"(module
+      (func $log (import "env" "log") (param i32))
+      (global $g (import "env" "g") i32)
+      (func (export "f") (call $log (global.get $g))))"#;
+    let module = Module::from_wat(text).unwrap();
+    assert_eq!(module.imports.len(), 2);
+    assert_eq!(module.funcs.len(), 1);
+    let text = r#"(module (func) (import "env" "log" (func)))"#;
+    assert_eq!(
+        parse_error(text),
+        ParseErrorKind::ImportAfterDefinition("function")
+    );
+}
+
+#[test]
+fn block_types_with_params_reference_types() {
+    let text = r#"(module
+      (type $pair (func (param i32 i32) (result i32 i32)))
+      (func (export "divmod") (param i32 i32) (result i32 i32)
+        (local.get 0)
+        (local.get 1)
+        (block (type $pair)
+          (local.set 1)
+          (local.set 0)
+          (i32.div_u (local.get 0) (local.get 1))
+          (i32.rem_u (local.get 0) (local.get 1)))
+        (block (param i32 i32) (result i32 i32 i32)
+          (i32.const 7))
+        (block (result i64) (i64.const 0))
+        (drop)
+        (drop)))"#;
+    let module = Module::from_wat(text).unwrap();
+    let Instr::Block(explicit, ..) = &module.funcs[0].body.0[2] else {
+        panic!("expected a block");
+    };
+    let Instr::Block(inline, ..) = &module.funcs[0].body.0[3] else {
+        panic!("expected a block");
+    };
+    let Instr::Block(single, ..) = &module.funcs[0].body.0[4] else {
+        panic!("expected a block");
+    };
+    assert_eq!(*explicit, BlockType::Type(TypeIdx(0)));
+    assert_eq!(*inline, BlockType::Type(TypeIdx(1)));
+    assert_eq!(*single, BlockType::Value(ValType::I64));
+    assert_eq!(
+        module.types[1],
+        FuncType {
+            params: vec![ValType::I32, ValType::I32],
+            results: vec![ValType::I32, ValType::I32, ValType::I32],
+        }
+    );
+    let args = [Value::I32(17), Value::I32(5)];
+    assert_eq!(
+        run(text, "divmod", &args),
+        vec![Value::I32(3), Value::I32(2)]
+    );
+    let text = "(module (func (block (type 0) (param i32))))";
+    assert_eq!(parse_error(text), ParseErrorKind::InlineTypeMismatch);
+}
+
+#[test]
+fn numeric_literals() {
+    let text = r#"(module
+      (func (export "i32") (result i32) (i32.const 0xffff_ffff))
+      (func (export "i64") (result i64) (i64.const -0x8000_0000_0000_0000))
+      (func (export "f32") (result f32) (f32.const 0x1.8p-1))
+      (func (export "f64") (result f64) (f64.const -1.5e3))
+      (func (export "nan") (result f32) (f32.const -nan:0x200000))
+      (func (export "subnormal") (result f64) (f64.const 0x0.0000000000001p-1022)))"#;
+    assert_eq!(run(text, "i32", &[]), vec![Value::I32(-1)]);
+    assert_eq!(run(text, "i64", &[]), vec![Value::I64(i64::MIN)]);
+    assert_eq!(run(text, "f32", &[]), vec![Value::F32(0.75)]);
+    assert_eq!(run(text, "f64", &[]), vec![Value::F64(-1500.0)]);
+    let [Value::F32(nan)] = run(text, "nan", &[])[..] else {
+        panic!("expected an f32");
+    };
+    assert_eq!(nan.to_bits(), 0xffa0_0000);
+    let [Value::F64(subnormal)] = run(text, "subnormal", &[])[..] else {
+        panic!("expected an f64");
+    };
+    assert_eq!(subnormal.to_bits(), 1);
+}
+
+#[test]
+fn malformed_text_is_rejected() {
+    assert_eq!(
+        parse_error("(module (func (i32.const 0x1_0000_0000)))"),
+        ParseErrorKind::ConstantOutOfRange("0x1_0000_0000".into())
+    );
+    assert_eq!(
+        parse_error("(module (func (i32.const 1__0)))"),
+        ParseErrorKind::InvalidNumber("1__0".into())
+    );
+    assert_eq!(
+        parse_error("(module (func (br $missing)))"),
+        ParseErrorKind::UnknownId("label", "missing".into())
+    );
+    assert_eq!(
+        parse_error("(module (func $f) (func $f))"),
+        ParseErrorKind::DuplicateId("function", "f".into())
+    );
+    assert_eq!(
+        parse_error("(module (func i32.frobnicate))"),
+        ParseErrorKind::UnknownInstruction("i32.frobnicate".into())
+    );
+    assert_eq!(
+        parse_error("(module (func block $a end $b))"),
+        ParseErrorKind::MismatchedLabel("b".into())
+    );
+    assert_eq!(
+        parse_error("(module (; unterminated"),
+        ParseErrorKind::UnterminatedComment
+    );
+    let Err(Error::Parse(err)) = Module::from_wat("(module\n  (func (local.get)))") else {
+        panic!("expected a parse error");
+    };
+    assert_eq!((err.line, err.column), (2, 19));
+}
+
+#[test]
+fn nesting_depth_is_limited() {
+    let flat = |depth| {
+        format!(
+            "(module (func {}{}))",
+            "block ".repeat(depth),
+            "end ".repeat(depth)
+        )
+    };
+    let folded = |depth| {
+        format!(
+            "(module (func {}{}))",
+            "(block ".repeat(depth),
+            ")".repeat(depth)
+        )
+    };
+    let operands = |depth| {
+        format!(
+            "(module (func (result i32) {}(i32.const 0){}))",
+            "(i32.eqz ".repeat(depth - 1),
+            ")".repeat(depth - 1)
+        )
+    };
+    for text in [flat, folded, operands] {
+        assert!(Module::from_wat(&text(MAX_NESTING_DEPTH)).is_ok());
+        assert_eq!(
+            parse_error(&text(MAX_NESTING_DEPTH + 1)),
+            ParseErrorKind::NestingTooDeep
+        );
+        assert_eq!(parse_error(&text(100_000)), ParseErrorKind::NestingTooDeep);
+    }
+}